@@ -23,7 +23,7 @@ use std::{env, process};
 
 use clap::{Arg, ArgAction, Command};
 use hime_sdk::errors::{Error, Errors};
-use hime_sdk::{CompilationTask, Input, Mode, Modifier, ParsingMethod, Runtime};
+use hime_sdk::{CompilationTask, Input, LoadedData, Mode, Modifier, ParsingMethod, Runtime};
 use miette::{EyreContext, MietteHandler};
 
 /// The name of this program
@@ -124,7 +124,9 @@ pub fn main() -> miette::Result<()> {
                     "lr1",
                     "lalr1",
                     "rnglr1",
-                    "rnglalr1"
+                    "rnglalr1",
+                    "minlr1",
+                    "rngminlr1"
                 ])
         )
         .arg(
@@ -155,6 +157,13 @@ pub fn main() -> miette::Result<()> {
                 .action(ArgAction::SetTrue)
                 .required(false)
         )
+        .arg(
+            Arg::new("rust_typed_ast")
+                .long("typed-ast")
+                .help("Rust-only, generates strongly typed wrappers for the AST nodes (default to false)")
+                .action(ArgAction::SetTrue)
+                .required(false)
+        )
         .arg(
             Arg::new("grammar_name")
                 .value_name("GRAMMAR")
@@ -164,6 +173,13 @@ pub fn main() -> miette::Result<()> {
                 .action(ArgAction::Set)
                 .required(false)
         )
+        .arg(
+            Arg::new("deny_warnings")
+                .long("deny-warnings")
+                .help("Fails the compilation when warnings are produced for the grammars.")
+                .action(ArgAction::SetTrue)
+                .required(false)
+        )
         .arg(
             Arg::new("test")
                 .long("test")
@@ -213,11 +229,16 @@ pub fn main() -> miette::Result<()> {
         Some("lalr1") => task.method = Some(ParsingMethod::LALR1),
         Some("rnglr1") => task.method = Some(ParsingMethod::RNGLR1),
         Some("rnglalr1") => task.method = Some(ParsingMethod::RNGLALR1),
+        Some("minlr1") => task.method = Some(ParsingMethod::MinLR1),
+        Some("rngminlr1") => task.method = Some(ParsingMethod::RNGMinLR1),
         _ => {}
     }
     if matches.get_flag("debug") {
         task.print_debug_data = Some(true);
     }
+    if matches.get_flag("deny_warnings") {
+        task.deny_warnings = Some(true);
+    }
     if matches.get_flag("rust_no_std") {
         task.rust_use_std = Some(false);
     }
@@ -227,6 +248,9 @@ pub fn main() -> miette::Result<()> {
     if matches.get_flag("rust_compress_automata") {
         task.rust_compress_automata = Some(true);
     }
+    if matches.get_flag("rust_typed_ast") {
+        task.rust_typed_ast = Some(true);
+    }
     task.grammar_name = matches
         .get_one::<String>("grammar_name")
         .map(std::string::ToString::to_string);
@@ -250,7 +274,8 @@ pub fn main() -> miette::Result<()> {
 
 /// Executes the normal operation of the compiler
 fn execute_normal<'a>(task: &CompilationTask<'a>) -> Result<(), Errors<'a>> {
-    task.execute()?;
+    let data = task.execute()?;
+    print!("{}", HimeCcWarnings(&data));
     Ok(())
 }
 
@@ -295,6 +320,9 @@ fn execute_test<'a>(task: &CompilationTask<'a>) -> Result<(), Errors<'a>> {
         }
     }
     let result = parser.parse(&input);
+    for ambiguity in result.get_ambiguities() {
+        eprintln!("warning: ambiguous input: {ambiguity}");
+    }
     match serde_json::to_writer(std::io::stdout(), &result) {
         Ok(r) => r,
         Err(error) => {
@@ -309,6 +337,7 @@ struct HimeCcErrors<'t>(Errors<'t>);
 
 impl<'t> Display for HimeCcErrors<'t> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        HimeCcWarnings(&self.0.context).fmt(f)?;
         let handler = MietteHandler::default();
         for error in &self.0.errors {
             let contextualized = error.with_context(&self.0.context);
@@ -317,3 +346,17 @@ impl<'t> Display for HimeCcErrors<'t> {
         Ok(())
     }
 }
+
+/// Encapsulate SDK warnings to implement Display with specific formatting
+struct HimeCcWarnings<'d, 't>(&'d LoadedData<'t>);
+
+impl Display for HimeCcWarnings<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let handler = MietteHandler::default();
+        for warning in &self.0.warnings {
+            let contextualized = warning.with_context(self.0);
+            handler.debug(&contextualized, f)?;
+        }
+        Ok(())
+    }
+}
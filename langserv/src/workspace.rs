@@ -21,9 +21,10 @@ use std::fs::File;
 use std::io::{self, BufReader, ErrorKind, Read};
 use std::path::{Path, PathBuf};
 
+use hime_redist::budget::ParseBudget;
 use hime_redist::text::TextPosition;
-use hime_sdk::errors::Error;
-use hime_sdk::grammars::{Grammar, RuleBodyElement, Symbol, SymbolRef, OPTION_AXIOM, OPTION_SEPARATOR};
+use hime_sdk::errors::{Error, Warning};
+use hime_sdk::grammars::{Grammar, RuleBodyElement, Symbol, SymbolRef, OPTION_AXIOM, OPTION_ENTRIES, OPTION_SEPARATOR};
 use hime_sdk::{CompilationTask, Input, InputReference, LoadedData, LoadedInput};
 use serde_json::Value;
 use tower_lsp::jsonrpc::Error as JsonRpcError;
@@ -35,6 +36,9 @@ use tower_lsp::lsp_types::{
 
 use crate::symbols::{SymbolRegistry, SymbolRegistryElement};
 
+/// The maximum number of steps when testing an input against a grammar
+const MAX_PARSE_STEPS: usize = 1_000_000;
+
 /// Represents a document in a workspace
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -246,12 +250,17 @@ impl Workspace {
                     if let Err(mut errs) = task.generate_in_memory(grammar, index) {
                         errors.append(&mut errs);
                     }
+                    data.warnings.append(&mut grammar.collect_warnings(index));
                 }
                 for error in &errors {
                     if let Some((index, diag)) = to_diagnostic(&mut self.documents, &data, error) {
                         self.documents[index].diagnostics.push(diag);
                     }
                 }
+                for warning in &data.warnings {
+                    let (index, diag) = warning_to_diagnostic(&data, warning);
+                    self.documents[index].diagnostics.push(diag);
+                }
                 let symbols = SymbolRegistry::from(&data.grammars);
                 self.data = Some(WorkspaceData {
                     inputs: data.inputs,
@@ -506,7 +515,7 @@ impl Workspace {
                     let task = CompilationTask::default();
                     match task.generate_in_memory(&mut grammar, grammar_index) {
                         Ok(parser) => {
-                            let result = parser.parse(input);
+                            let result = parser.parse_with_budget(input, ParseBudget::new().with_max_steps(MAX_PARSE_STEPS));
                             Ok(Some(serde_json::to_value(result).unwrap()))
                         }
                         Err(_) => Ok(None),
@@ -634,6 +643,44 @@ fn to_diagnostic(documents: &mut [Document], data: &LoadedData, error: &Error) -
                 },
             ))
         }
+        Error::EntryNotDefined(grammar_index, name) => {
+            let option = data.grammars[*grammar_index].get_option(OPTION_ENTRIES).unwrap();
+            let input_reference = option.value_input_ref;
+            Some((
+                input_reference.input_index,
+                Diagnostic {
+                    range: WorkspaceData::to_range(&data.inputs, input_reference),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: None,
+                    code_description: None,
+                    source: Some(super::CRATE_NAME.to_string()),
+                    message: format!("Grammar entry `{name}` is not defined"),
+                    related_information: None,
+                    tags: None,
+                    data: None,
+                },
+            ))
+        }
+        Error::VariableNameReserved(grammar_index, name) => {
+            let rule = data.grammars[*grammar_index]
+                .get_variable_for_name(name)
+                .and_then(|variable| variable.rules.first())?;
+            let input_reference = rule.head_input_ref;
+            Some((
+                input_reference.input_index,
+                Diagnostic {
+                    range: WorkspaceData::to_range(&data.inputs, input_reference),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: None,
+                    code_description: None,
+                    source: Some(super::CRATE_NAME.to_string()),
+                    message: format!("Variable `{name}` uses a name reserved for the generated axioms"),
+                    related_information: None,
+                    tags: None,
+                    data: None,
+                },
+            ))
+        }
         Error::SeparatorNotDefined(grammar_index) => {
             let option = data.grammars[*grammar_index].get_option(OPTION_SEPARATOR).unwrap();
             let input_reference = option.value_input_ref;
@@ -734,6 +781,34 @@ fn to_diagnostic(documents: &mut [Document], data: &LoadedData, error: &Error) -
                 data: None,
             },
         )),
+        Error::ExternalTerminalReferenced(input_reference, name) => Some((
+            input_reference.input_index,
+            Diagnostic {
+                range: WorkspaceData::to_range(&data.inputs, *input_reference),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: None,
+                code_description: None,
+                source: Some(super::CRATE_NAME.to_string()),
+                message: format!("External terminal `{name}` cannot be used in the definition of a terminal"),
+                related_information: None,
+                tags: None,
+                data: None,
+            },
+        )),
+        Error::ModeNotDefined(input_reference, name) => Some((
+            input_reference.input_index,
+            Diagnostic {
+                range: WorkspaceData::to_range(&data.inputs, *input_reference),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: None,
+                code_description: None,
+                source: Some(super::CRATE_NAME.to_string()),
+                message: format!("Lexer mode `{name}` is not defined"),
+                related_information: None,
+                tags: None,
+                data: None,
+            },
+        )),
         Error::InvalidCharacterSpan(input_reference) => Some((
             input_reference.input_index,
             Diagnostic {
@@ -776,6 +851,20 @@ fn to_diagnostic(documents: &mut [Document], data: &LoadedData, error: &Error) -
                 data: None,
             },
         )),
+        Error::InvalidAnnotation(input_reference, name) => Some((
+            input_reference.input_index,
+            Diagnostic {
+                range: WorkspaceData::to_range(&data.inputs, *input_reference),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: None,
+                code_description: None,
+                source: Some(super::CRATE_NAME.to_string()),
+                message: format!("Invalid annotation `%{name}`, expected `%prefer`, `%avoid` or `%priority:N`"),
+                related_information: None,
+                tags: None,
+                data: None,
+            },
+        )),
         Error::UnsupportedNonPlane0InCharacterClass(input_reference, c) => Some((
             input_reference.input_index,
             Diagnostic {
@@ -891,10 +980,46 @@ fn to_diagnostic(documents: &mut [Document], data: &LoadedData, error: &Error) -
                 },
             ))
         }
+        Error::PrecedencesNotSupported(grammar_index) => {
+            let input_reference = data.grammars[*grammar_index].precedences[0].input_ref;
+            Some((
+                input_reference.input_index,
+                Diagnostic {
+                    range: WorkspaceData::to_range(&data.inputs, input_reference),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: None,
+                    code_description: None,
+                    source: Some(super::CRATE_NAME.to_string()),
+                    message: String::from("Precedences are not supported by the LR(0) parsing method"),
+                    related_information: None,
+                    tags: None,
+                    data: None,
+                },
+            ))
+        }
         _ => None,
     }
 }
 
+/// Converts a warning to a diagnostic
+fn warning_to_diagnostic(data: &LoadedData, warning: &Warning) -> (usize, Diagnostic) {
+    let input_reference = warning.get_input_ref(data);
+    (
+        input_reference.input_index,
+        Diagnostic {
+            range: WorkspaceData::to_range(&data.inputs, input_reference),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: None,
+            code_description: None,
+            source: Some(super::CRATE_NAME.to_string()),
+            message: warning.with_context(data).to_string(),
+            related_information: None,
+            tags: None,
+            data: None,
+        },
+    )
+}
+
 #[test]
 fn test_scan_workspace_in() -> io::Result<()> {
     let mut workspace = Workspace::default();
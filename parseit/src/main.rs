@@ -69,6 +69,9 @@ fn do_parse(input: &mut dyn io::Read, lib_name: &str, parser_module: &str) {
         let parser: libloading::Symbol<fn(&mut dyn io::Read) -> ParseResultAst> =
             library.get(function_name.as_bytes()).unwrap();
         let result = parser(input);
+        for ambiguity in result.get_ambiguities() {
+            eprintln!("warning: ambiguous input: {ambiguity}");
+        }
         serde_json::to_writer(std::io::stdout(), &result).unwrap();
     }
 }
@@ -17,12 +17,18 @@
 
 //! Module for Abstract-Syntax Trees
 
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt::{Display, Error, Formatter};
 use core::iter::FusedIterator;
 
 use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
 
-use crate::symbols::{SemanticElementTrait, Symbol};
+use crate::parsers::lrk::LRkCheckpoint;
+use crate::parsers::subtree::ReusableSubTree;
+use crate::sppf::get_symbol_for;
+use crate::symbols::{SemanticElementTrait, Symbol, SYMBOL_ERROR};
 use crate::text::{TextContext, TextPosition, TextSpan};
 use crate::tokens::{Token, TokenRepository};
 use crate::utils::biglist::BigList;
@@ -31,6 +37,8 @@ use crate::utils::biglist::BigList;
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum TableType {
     /// Marks as other (used for SPPF nodes)
+    /// The index is then the one of a terminal without a token (epsilon or a terminal inserted by the error recovery),
+    /// or `INDEX_ERROR` for an error node
     None = 0,
     /// Table of tokens
     Token = 1,
@@ -51,6 +59,9 @@ impl From<usize> for TableType {
     }
 }
 
+/// The index of the label for error nodes in the `None` table
+const INDEX_ERROR: usize = 0x3FFF_FFFF;
+
 /// Represents a compact reference to an element in a table
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct TableElemRef {
@@ -67,6 +78,19 @@ impl TableElemRef {
         }
     }
 
+    /// Initializes the label of an error node
+    /// Error nodes are produced by the error recovery and their children are the skipped elements
+    #[must_use]
+    pub fn new_error() -> TableElemRef {
+        TableElemRef::new(TableType::None, INDEX_ERROR)
+    }
+
+    /// Gets whether this is the label of an error node
+    #[must_use]
+    pub fn is_error(self) -> bool {
+        self.table_type() == TableType::None && self.index() == INDEX_ERROR
+    }
+
     /// Gets the element's type
     #[must_use]
     pub fn table_type(self) -> TableType {
@@ -86,6 +110,7 @@ impl TableElemRef {
     /// Propagates the error from `write!`
     pub fn fmt(&self, f: &mut Formatter<'_>, variables: &[Symbol], virtuals: &[Symbol]) -> Result<(), Error> {
         match self.table_type() {
+            TableType::None if self.is_error() => write!(f, "error")?,
             TableType::None => write!(f, "none[{}]", self.index())?,
             TableType::Token => write!(f, "token[{}]", self.index())?,
             TableType::Variable => write!(f, "{}", variables[self.index()].name)?,
@@ -105,6 +130,10 @@ pub struct AstCell {
     pub count: u32,
     /// The index of the first child
     pub first: u32,
+    /// The index of the label given to the node by the rule of its parent, if any
+    pub element_label: Option<u16>,
+    /// The index of the LR production that produced the node, if any
+    pub production: Option<u16>,
 }
 
 impl AstCell {
@@ -115,13 +144,21 @@ impl AstCell {
             label,
             count: 0,
             first: 0,
+            element_label: None,
+            production: None,
         }
     }
 
     /// Initializes this node
     #[must_use]
     pub fn new(label: TableElemRef, count: u32, first: u32) -> AstCell {
-        AstCell { label, count, first }
+        AstCell {
+            label,
+            count,
+            first,
+            element_label: None,
+            production: None,
+        }
     }
 }
 
@@ -134,9 +171,129 @@ pub struct AstImpl {
     nodes: BigList<AstCell>,
     /// The index of the tree's root node
     root: Option<usize>,
+    /// The sub-trees recorded by the parser for their reuse in a later parse, if enabled
+    subtrees: Option<Vec<ReusableSubTree>>,
+    /// The tree produced by a previous parse, whose recorded sub-trees may be reused
+    previous: Option<Box<AstImpl>>,
+    /// The number of sub-trees reused from the previous parse
+    reused: usize,
+    /// The concrete syntax tree built alongside this one, if any
+    concrete: Option<Box<AstImpl>>,
+    /// The names of the labels given to the nodes
+    labels: Vec<String>,
+    /// The ambiguities in the SPPF this tree was built from, if any
+    ambiguities: Vec<AstImplAmbiguity>,
+    /// The state of a LR(k) parser to resume from, when the input is fed piece by piece
+    checkpoint: Option<Box<LRkCheckpoint>>,
 }
 
 impl AstImpl {
+    /// Creates an empty AST for a parse that may reuse the sub-trees of a previous one
+    /// The reusable sub-trees of the new parse are also recorded.
+    pub(crate) fn new_reusing(previous: AstImpl) -> AstImpl {
+        AstImpl {
+            nodes: BigList::default(),
+            root: None,
+            subtrees: Some(Vec::new()),
+            previous: Some(Box::new(previous)),
+            reused: 0,
+            concrete: None,
+            labels: Vec::new(),
+            ambiguities: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// Creates an empty AST for a parse that resumes from the state of the parser recorded in a previous tree
+    /// The nodes of the previous tree are kept, as the sub-trees on the stack of the parser refer to them.
+    /// When no state was recorded, the sub-trees of the previous tree are reused instead if `reuse` is set.
+    pub(crate) fn new_resuming(mut previous: AstImpl, reuse: bool) -> AstImpl {
+        let checkpoint = previous.checkpoint.take().unwrap_or_default();
+        let mut result = if checkpoint.can_resume() {
+            AstImpl {
+                nodes: previous.nodes,
+                ..AstImpl::default()
+            }
+        } else if reuse {
+            AstImpl::new_reusing(previous)
+        } else {
+            AstImpl::default()
+        };
+        result.checkpoint = Some(checkpoint);
+        result
+    }
+
+    /// Sets the names of the labels given to the nodes
+    pub(crate) fn set_labels(&mut self, labels: &[String]) {
+        self.labels = labels.to_vec();
+    }
+
+    /// Gets the concrete syntax tree built alongside this one, if any
+    #[must_use]
+    pub fn get_concrete(&self) -> Option<&AstImpl> {
+        self.concrete.as_deref()
+    }
+
+    /// Gets the ambiguities in the SPPF this tree was built from, if any
+    /// Only the trees built by RNGLR parsers may have ambiguities.
+    #[must_use]
+    pub fn get_ambiguities(&self) -> &[AstImplAmbiguity] {
+        &self.ambiguities
+    }
+
+    /// Sets the ambiguities in the SPPF this tree was built from
+    pub(crate) fn set_ambiguities(&mut self, ambiguities: Vec<AstImplAmbiguity>) {
+        self.ambiguities = ambiguities;
+    }
+
+    /// Gets the concrete syntax tree built alongside this one, creating it if necessary
+    pub(crate) fn get_concrete_mut(&mut self) -> &mut AstImpl {
+        self.concrete.get_or_insert_with(Box::default)
+    }
+
+    /// Takes the tree of the previous parse, if any
+    pub(crate) fn take_previous(&mut self) -> Option<Box<AstImpl>> {
+        self.previous.take()
+    }
+
+    /// Takes the reusable sub-trees recorded by the parser
+    pub(crate) fn take_subtrees(&mut self) -> Vec<ReusableSubTree> {
+        self.subtrees.take().unwrap_or_default()
+    }
+
+    /// Takes the state of the LR(k) parser to resume from, if the state of the parser shall be recorded
+    pub(crate) fn take_checkpoint(&mut self) -> Option<Box<LRkCheckpoint>> {
+        self.checkpoint.take()
+    }
+
+    /// Sets the recorded state of the LR(k) parser
+    pub(crate) fn set_checkpoint(&mut self, checkpoint: LRkCheckpoint) {
+        self.checkpoint = Some(Box::new(checkpoint));
+    }
+
+    /// Gets whether the reusable sub-trees shall be recorded
+    pub(crate) fn is_recording(&self) -> bool {
+        self.subtrees.is_some()
+    }
+
+    /// Gets the number of sub-trees reused from a previous parse, after some edits of the input
+    #[must_use]
+    pub fn get_reused_count(&self) -> usize {
+        self.reused
+    }
+
+    /// Counts a sub-tree reused from the previous parse
+    pub(crate) fn count_reused(&mut self) {
+        self.reused += 1;
+    }
+
+    /// Records a reusable sub-tree
+    pub(crate) fn record(&mut self, subtree: ReusableSubTree) {
+        if let Some(subtrees) = self.subtrees.as_mut() {
+            subtrees.push(subtree);
+        }
+    }
+
     /// Gets whether a root has been defined for this AST
     #[must_use]
     pub fn has_root(&self) -> bool {
@@ -161,6 +318,83 @@ impl AstImpl {
             result
         }
     }
+
+    /// Stores some children nodes of another AST in this one, along with all their descendants
+    /// The labels of the copied nodes are transformed with the given function.
+    pub(crate) fn store_from<F: Fn(TableElemRef) -> TableElemRef>(
+        &mut self,
+        other: &AstImpl,
+        first: usize,
+        count: usize,
+        remap: &F,
+    ) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        let result = self.nodes.len();
+        let mut stack = Vec::new();
+        for i in 0..count {
+            let mut node = other.nodes[first + i];
+            node.label = remap(node.label);
+            stack.push(self.nodes.push(node));
+        }
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index];
+            if node.count > 0 {
+                let copy = self.nodes.len();
+                for i in 0..node.count as usize {
+                    let mut child = other.nodes[node.first as usize + i];
+                    child.label = remap(child.label);
+                    stack.push(self.nodes.push(child));
+                }
+                self.nodes[index].first = copy as u32;
+            }
+        }
+        result
+    }
+}
+
+/// An ambiguity in the SPPF an AST was built from
+/// The SPPF is not kept with the AST, so that only the ambiguous node's label, span and counts are.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AstImplAmbiguity {
+    /// The label of the ambiguous node
+    pub label: TableElemRef,
+    /// The span of the node in the input, if any
+    pub span: Option<TextSpan>,
+    /// The number of competing versions of the node
+    pub versions: usize,
+    /// The number of distinct trees for the node, saturating at `u64::MAX`
+    pub trees: u64,
+}
+
+/// An ambiguity in the SPPF an AST was built from, with the symbol and the position of the ambiguous node
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AstAmbiguity<'s> {
+    /// The grammar symbol of the node
+    pub symbol: Symbol<'s>,
+    /// The span of the node in the input, if any
+    pub span: Option<TextSpan>,
+    /// The position of the node in the input, if any
+    pub position: Option<TextPosition>,
+    /// The number of competing versions of the node
+    pub versions: usize,
+    /// The number of distinct trees for the node, saturating at `u64::MAX`
+    pub trees: u64,
+}
+
+impl Display for AstAmbiguity<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "`{}`", self.symbol.name)?;
+        if let Some(position) = self.position {
+            write!(f, " at {position}")?;
+        }
+        write!(
+            f,
+            " has {} competing versions, for {} distinct trees",
+            self.versions, self.trees
+        )
+    }
 }
 
 /// Represents a simple AST with a tree structure
@@ -205,6 +439,23 @@ impl<'s, 't, 'a> Ast<'s, 't, 'a> {
         self.data.has_root()
     }
 
+    /// Gets the ambiguities in the SPPF this tree was built from, if any
+    /// Only the trees built by RNGLR parsers may have ambiguities.
+    #[must_use]
+    pub fn get_ambiguities(&self) -> Vec<AstAmbiguity<'s>> {
+        self.data
+            .get_ambiguities()
+            .iter()
+            .map(|ambiguity| AstAmbiguity {
+                symbol: get_symbol_for(ambiguity.label, &self.tokens, self.variables, self.virtuals),
+                span: ambiguity.span,
+                position: ambiguity.span.map(|span| self.tokens.text.get_position_at(span.index)),
+                versions: ambiguity.versions,
+                trees: ambiguity.trees,
+            })
+            .collect()
+    }
+
     /// Gets the root node of this tree
     ///
     /// # Panics
@@ -400,6 +651,43 @@ impl<'s, 't, 'a> AstNode<'s, 't, 'a> {
         self.tree.data.nodes[self.index].count as usize
     }
 
+    /// Gets the label given to this node by the rule of its parent, if any
+    #[must_use]
+    pub fn get_label(&self) -> Option<&'a str> {
+        let cell = self.tree.data.nodes[self.index];
+        cell.element_label
+            .and_then(|label| self.tree.data.labels.get(label as usize))
+            .map(String::as_str)
+    }
+
+    /// Gets the index of the LR production that produced this node, if any
+    /// Only the nodes of variables have a production.
+    /// The productions are numbered in the order of the variables and then of their rules in the grammar,
+    /// so that the index identifies the alternative of the rule that matched.
+    #[must_use]
+    pub fn get_production(&self) -> Option<usize> {
+        self.tree.data.nodes[self.index].production.map(usize::from)
+    }
+
+    /// Gets the first child with the specified label, if any
+    #[must_use]
+    pub fn child_by_label(&self, label: &str) -> Option<AstNode<'s, 't, 'a>> {
+        self.children_by_label(label).next()
+    }
+
+    /// Gets the children with the specified label
+    /// Multiple children have the same label when the labelled element is repeated.
+    pub fn children_by_label<'l>(&self, label: &'l str) -> impl Iterator<Item = AstNode<'s, 't, 'a>> + 'l
+    where
+        'a: 'l,
+        's: 'l,
+        't: 'l,
+    {
+        self.children()
+            .into_iter()
+            .filter(move |child| child.get_label() == Some(label))
+    }
+
     /// Gets the total span for the sub-tree at this node
     #[must_use]
     pub fn get_total_span(&self) -> Option<TextSpan> {
@@ -446,9 +734,10 @@ impl<'s, 't, 'a> SemanticElementTrait<'s, 'a> for AstNode<'s, 't, 'a> {
             }
             TableType::Variable => self.tree.variables[cell.label.index()],
             TableType::Virtual => self.tree.virtuals[cell.label.index()],
+            TableType::None if cell.label.is_error() => SYMBOL_ERROR,
             TableType::None => {
-                // terminal epsilon
-                self.tree.tokens.terminals[0]
+                // terminal epsilon, or a terminal inserted by the error recovery
+                self.tree.tokens.terminals[cell.label.index()]
             }
         }
     }
@@ -506,8 +795,9 @@ impl<'s, 't, 'a> Display for AstNode<'s, 't, 'a> {
                 let symbol = self.tree.virtuals[cell.label.index()];
                 write!(f, "{}", symbol.name)
             }
+            TableType::None if cell.label.is_error() => write!(f, "{}", SYMBOL_ERROR.name),
             TableType::None => {
-                let symbol = self.tree.tokens.terminals[0];
+                let symbol = self.tree.tokens.terminals[cell.label.index()];
                 write!(f, "{}", symbol.name)
             }
         }
@@ -519,8 +809,9 @@ impl<'s, 't, 'a> Serialize for AstNode<'s, 't, 'a> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("AstNode", 5)?;
+        let mut state = serializer.serialize_struct("AstNode", 6)?;
         state.serialize_field("symbol", &self.get_symbol())?;
+        state.serialize_field("production", &self.get_production())?;
         state.serialize_field("position", &self.get_position())?;
         state.serialize_field("span", &self.get_span())?;
         state.serialize_field("value", &self.get_value())?;
@@ -0,0 +1,185 @@
+/*******************************************************************************
+ * Copyright (c) 2024 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the budget bounding the work of lexers and parsers on an input
+
+use alloc::sync::Arc;
+use core::fmt::{Display, Formatter};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+/// The number of steps between two checks of the cancellation flag
+const CANCELLATION_CHECK_PERIOD: usize = 256;
+
+/// The reason why the budget of a parsing is exhausted
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum BudgetExhaustion {
+    /// The maximum number of steps has been reached
+    Steps,
+    /// The maximum number of GSS nodes has been reached
+    GssNodes,
+    /// The maximum number of SPPF nodes has been reached
+    SppfNodes,
+    /// The parsing has been cancelled
+    Cancelled,
+}
+
+impl Display for BudgetExhaustion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BudgetExhaustion::Steps => write!(f, "the maximum number of steps has been reached"),
+            BudgetExhaustion::GssNodes => write!(f, "the maximum number of GSS nodes has been reached"),
+            BudgetExhaustion::SppfNodes => write!(f, "the maximum number of SPPF nodes has been reached"),
+            BudgetExhaustion::Cancelled => write!(f, "the parsing has been cancelled"),
+        }
+    }
+}
+
+/// A budget bounding the work of a lexer and a parser on an input
+/// A step is a token matched by the lexer, a shift or a reduction by the parser.
+/// The context-free lexers match all the tokens of the input before the parsing starts,
+/// so that an input with more tokens than the maximum number of steps exhausts the budget before being parsed.
+/// The GSS and SPPF nodes are only produced by RNGLR parsers.
+/// The cancellation flag is checked periodically, every few steps.
+/// When the budget is exhausted, the parsers produce no tree, the result only holds the tokens matched so far and the errors.
+#[derive(Debug, Default, Clone)]
+pub struct ParseBudget {
+    /// The maximum number of steps, if any
+    pub max_steps: Option<usize>,
+    /// The maximum number of GSS nodes, if any
+    pub max_gss_nodes: Option<usize>,
+    /// The maximum number of SPPF nodes, if any
+    pub max_sppf_nodes: Option<usize>,
+    /// The flag to set for cancelling the parsing, if any
+    pub cancellation: Option<Arc<AtomicBool>>,
+    /// The number of steps so far
+    steps: usize,
+    /// The reason of the exhaustion of this budget, if exhausted
+    exhaustion: Option<BudgetExhaustion>,
+    /// Whether the exhaustion has been reported
+    reported: bool,
+}
+
+impl ParseBudget {
+    /// Creates an unlimited budget
+    #[must_use]
+    pub fn new() -> ParseBudget {
+        ParseBudget::default()
+    }
+
+    /// Sets the maximum number of steps
+    #[must_use]
+    pub fn with_max_steps(mut self, max: usize) -> ParseBudget {
+        self.max_steps = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of GSS nodes
+    #[must_use]
+    pub fn with_max_gss_nodes(mut self, max: usize) -> ParseBudget {
+        self.max_gss_nodes = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of SPPF nodes
+    #[must_use]
+    pub fn with_max_sppf_nodes(mut self, max: usize) -> ParseBudget {
+        self.max_sppf_nodes = Some(max);
+        self
+    }
+
+    /// Sets the flag to set for cancelling the parsing
+    #[must_use]
+    pub fn with_cancellation(mut self, flag: Arc<AtomicBool>) -> ParseBudget {
+        self.cancellation = Some(flag);
+        self
+    }
+
+    /// Gets the number of steps so far
+    #[must_use]
+    pub fn get_steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Gets the reason of the exhaustion of this budget, if exhausted
+    #[must_use]
+    pub fn get_exhaustion(&self) -> Option<BudgetExhaustion> {
+        self.exhaustion
+    }
+
+    /// Gets whether this budget is exhausted
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.exhaustion.is_some()
+    }
+
+    /// Consumes a step
+    /// Returns whether the work can go on
+    pub fn step(&mut self) -> bool {
+        if self.exhaustion.is_some() {
+            return false;
+        }
+        if self.steps.is_multiple_of(CANCELLATION_CHECK_PERIOD) {
+            if let Some(flag) = self.cancellation.as_ref() {
+                if flag.load(Ordering::Relaxed) {
+                    self.exhaustion = Some(BudgetExhaustion::Cancelled);
+                    return false;
+                }
+            }
+        }
+        if self.max_steps.is_some_and(|max| self.steps >= max) {
+            self.exhaustion = Some(BudgetExhaustion::Steps);
+            return false;
+        }
+        self.steps += 1;
+        true
+    }
+
+    /// Checks the number of GSS nodes
+    /// Returns whether the work can go on
+    pub fn check_gss_nodes(&mut self, count: usize) -> bool {
+        self.check(self.max_gss_nodes, count, BudgetExhaustion::GssNodes)
+    }
+
+    /// Checks the number of SPPF nodes
+    /// Returns whether the work can go on
+    pub fn check_sppf_nodes(&mut self, count: usize) -> bool {
+        self.check(self.max_sppf_nodes, count, BudgetExhaustion::SppfNodes)
+    }
+
+    /// Checks a count against its maximum, if any
+    fn check(&mut self, max: Option<usize>, count: usize, reason: BudgetExhaustion) -> bool {
+        if self.exhaustion.is_some() {
+            return false;
+        }
+        if max.is_some_and(|max| count > max) {
+            self.exhaustion = Some(reason);
+            return false;
+        }
+        true
+    }
+
+    /// Takes the reason of the exhaustion of this budget, only the first time it is taken, for its report
+    pub(crate) fn take_report(&mut self) -> Option<BudgetExhaustion> {
+        if self.reported {
+            return None;
+        }
+        self.reported = self.exhaustion.is_some();
+        self.exhaustion
+    }
+}
@@ -23,6 +23,7 @@ use core::fmt::{Display, Formatter};
 
 use serde::Serialize;
 
+use crate::budget::BudgetExhaustion;
 use crate::symbols::Symbol;
 use crate::text::{TextPosition, Utf16C};
 
@@ -159,6 +160,93 @@ impl ParseErrorIncorrectEncodingSequence {
     }
 }
 
+/// Represents an inconsistent dedent in the input of an indentation-sensitive lexer,
+/// i.e. a line whose indentation does not match any outer indentation level
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseErrorInconsistentDedent {
+    /// The error's position in the input text
+    position: TextPosition,
+    /// The indentation of the line, in number of columns
+    indentation: usize,
+}
+
+impl ParseErrorDataTrait for ParseErrorInconsistentDedent {
+    /// Gets the error's position in the input
+    fn get_position(&self) -> TextPosition {
+        self.position
+    }
+
+    /// Gets the error's length in the input (in number of characters)
+    fn get_length(&self) -> usize {
+        0
+    }
+}
+
+impl Display for ParseErrorInconsistentDedent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Inconsistent dedent, the indentation of {} columns does not match any outer level",
+            self.indentation
+        )
+    }
+}
+
+impl ParseErrorInconsistentDedent {
+    /// Creates a new error
+    #[must_use]
+    pub fn new(position: TextPosition, indentation: usize) -> ParseErrorInconsistentDedent {
+        ParseErrorInconsistentDedent { position, indentation }
+    }
+
+    /// Gets the indentation of the line, in number of columns
+    #[must_use]
+    pub fn get_indentation(&self) -> usize {
+        self.indentation
+    }
+}
+
+/// Represents the exhaustion of the budget of a parsing, stopping it
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseErrorBudgetExhausted {
+    /// The error's position in the input text
+    position: TextPosition,
+    /// The reason of the exhaustion
+    reason: BudgetExhaustion,
+}
+
+impl ParseErrorDataTrait for ParseErrorBudgetExhausted {
+    /// Gets the error's position in the input
+    fn get_position(&self) -> TextPosition {
+        self.position
+    }
+
+    /// Gets the error's length in the input (in number of characters)
+    fn get_length(&self) -> usize {
+        0
+    }
+}
+
+impl Display for ParseErrorBudgetExhausted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Parsing stopped, {}", self.reason)
+    }
+}
+
+impl ParseErrorBudgetExhausted {
+    /// Creates a new error
+    #[must_use]
+    pub fn new(position: TextPosition, reason: BudgetExhaustion) -> ParseErrorBudgetExhausted {
+        ParseErrorBudgetExhausted { position, reason }
+    }
+
+    /// Gets the reason of the exhaustion
+    #[must_use]
+    pub fn get_reason(&self) -> BudgetExhaustion {
+        self.reason
+    }
+}
+
 /// Represents an unexpected token error in a parser
 #[derive(Debug, Clone, Serialize)]
 pub struct ParseErrorUnexpectedToken<'s> {
@@ -238,6 +326,18 @@ impl<'s> ParseErrorUnexpectedToken<'s> {
             expected,
         }
     }
+
+    /// Gets the terminal symbol for the unexpected token
+    #[must_use]
+    pub fn get_terminal(&self) -> Symbol<'s> {
+        self.terminal
+    }
+
+    /// Gets the terminals that were expected instead of the unexpected token
+    #[must_use]
+    pub fn get_expected(&self) -> &[Symbol<'s>] {
+        &self.expected
+    }
 }
 
 /// Represents a lexical or syntactic error
@@ -254,6 +354,10 @@ pub enum ParseError<'s> {
     IncorrectUTF16NoLowSurrogate(ParseErrorIncorrectEncodingSequence),
     /// Lexical error occurring when the high surrogate encoding point is missing in a UTF-16 encoding sequence with an expected high and low surrogate pair
     IncorrectUTF16NoHighSurrogate(ParseErrorIncorrectEncodingSequence),
+    /// Lexical error occurring when the indentation of a line does not match any outer indentation level
+    InconsistentDedent(ParseErrorInconsistentDedent),
+    /// Error occurring when the budget of the parsing is exhausted, or the parsing is cancelled, stopping it
+    BudgetExhausted(ParseErrorBudgetExhausted),
 }
 
 impl<'s> ParseErrorDataTrait for ParseError<'s> {
@@ -264,6 +368,8 @@ impl<'s> ParseErrorDataTrait for ParseError<'s> {
             ParseError::UnexpectedChar(x) => x.get_position(),
             ParseError::UnexpectedToken(x) => x.get_position(),
             ParseError::IncorrectUTF16NoLowSurrogate(x) | ParseError::IncorrectUTF16NoHighSurrogate(x) => x.get_position(),
+            ParseError::InconsistentDedent(x) => x.get_position(),
+            ParseError::BudgetExhausted(x) => x.get_position(),
         }
     }
 
@@ -274,6 +380,8 @@ impl<'s> ParseErrorDataTrait for ParseError<'s> {
             ParseError::UnexpectedChar(x) => x.get_length(),
             ParseError::UnexpectedToken(x) => x.get_length(),
             ParseError::IncorrectUTF16NoLowSurrogate(x) | ParseError::IncorrectUTF16NoHighSurrogate(x) => x.get_length(),
+            ParseError::InconsistentDedent(x) => x.get_length(),
+            ParseError::BudgetExhausted(x) => x.get_length(),
         }
     }
 }
@@ -286,6 +394,8 @@ impl<'s> Display for ParseError<'s> {
             ParseError::UnexpectedChar(x) => x.fmt(f),
             ParseError::UnexpectedToken(x) => x.fmt(f),
             ParseError::IncorrectUTF16NoLowSurrogate(x) | ParseError::IncorrectUTF16NoHighSurrogate(x) => x.fmt(f),
+            ParseError::InconsistentDedent(x) => x.fmt(f),
+            ParseError::BudgetExhausted(x) => x.fmt(f),
         }
     }
 }
@@ -325,4 +435,14 @@ impl<'s> ParseErrors<'s> {
     pub fn push_error_no_high_utf16_surrogate(&mut self, error: ParseErrorIncorrectEncodingSequence) {
         self.errors.push(ParseError::IncorrectUTF16NoHighSurrogate(error));
     }
+
+    /// Handles the inconsistent dedent error
+    pub fn push_error_inconsistent_dedent(&mut self, error: ParseErrorInconsistentDedent) {
+        self.errors.push(ParseError::InconsistentDedent(error));
+    }
+
+    /// Handles the exhaustion of the budget
+    pub fn push_error_budget_exhausted(&mut self, error: ParseErrorBudgetExhausted) {
+        self.errors.push(ParseError::BudgetExhausted(error));
+    }
 }
@@ -33,6 +33,18 @@ pub struct MatchedTerminal {
     pub index: u16,
 }
 
+/// Represents the action on the stack of lexer modes when a terminal is matched
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ModeAction {
+    /// No action
+    #[default]
+    None,
+    /// Pushes the context with the specified identifier as the new mode
+    Push(u16),
+    /// Pops the current mode
+    Pop,
+}
+
 /// Represents a transition in the automaton of a lexer
 /// A transition is matched by a range of UTF-16 code points
 /// Its target is a state in the automaton
@@ -164,6 +176,11 @@ impl<'a> AutomatonState<'a> {
 /// each entry is of the form:
 /// u32: offset of the state from the beginning of the states table in number of u16
 /// -- states table
+/// -- optional sections
+/// each section is of the form:
+/// u16: the kind of section, see `SECTION_MODES`, `SECTION_HEADS` and `SECTION_CONTEXTS`
+/// u16: the number of entries in the section
+/// -- entries of the section, each of 2 u16
 #[derive(Clone, Default)]
 pub struct Automaton {
     /// Table of indices in the states table
@@ -172,8 +189,31 @@ pub struct Automaton {
     states: Vec<u16>,
     /// The number of states in the automaton
     states_count: usize,
+    /// The actions on the stack of lexer modes for each terminal, if any
+    modes: Vec<ModeAction>,
+    /// The states at the end of the heads of terminals with a trailing context, sorted by state
+    heads: Vec<(u16, u16)>,
+    /// The entry states of the automata matching the trailing contexts, by terminal index
+    contexts: Vec<(u16, u16)>,
 }
 
+/// Kind of the section for the actions on the stack of lexer modes
+/// Each entry is of the form:
+/// u16: 0 for no action, 1 for a push, 2 for a pop
+/// u16: the context pushed as the new mode
+pub const SECTION_MODES: u16 = 1;
+/// Kind of the section for the ends of the heads of terminals with a trailing context
+/// Each entry is of the form:
+/// u16: the state at the end of the head
+/// u16: the index of the terminal
+pub const SECTION_HEADS: u16 = 2;
+/// Kind of the section for the automata matching the trailing contexts of terminals
+/// Their states follow the ones of the main automaton in the states table, they cannot be reached from the first state.
+/// Each entry is of the form:
+/// u16: the index of the terminal
+/// u16: the entry state of the automaton matching its trailing context
+pub const SECTION_CONTEXTS: u16 = 3;
+
 impl Automaton {
     /// Initializes a new automaton from the given binary data
     #[must_use]
@@ -181,11 +221,43 @@ impl Automaton {
         let states_count = read_u32(data, 0) as usize;
         let table = read_table_u32(data, 4, states_count);
         let rest = (data.len() - 4 - states_count * 4) / 2;
-        let states = read_table_u16(data, 4 + states_count * 4, rest);
+        let mut states = read_table_u16(data, 4 + states_count * 4, rest);
+        let mut modes = Vec::new();
+        let mut heads = Vec::new();
+        let mut contexts = Vec::new();
+        if let Some(&last) = table.last() {
+            // compute the end of the states table from the last state
+            let last = last as usize;
+            let end = last + 3 + states[last] as usize * 2 + 256 + states[last + 2] as usize * 3;
+            let mut sections = &states[end..];
+            while sections.len() >= 2 {
+                let count = (sections[1] as usize * 2).min(sections.len() - 2);
+                let entries = sections[2..(2 + count)].chunks_exact(2);
+                match sections[0] {
+                    SECTION_MODES => {
+                        modes = entries
+                            .map(|entry| match entry[0] {
+                                1 => ModeAction::Push(entry[1]),
+                                2 => ModeAction::Pop,
+                                _ => ModeAction::None,
+                            })
+                            .collect();
+                    }
+                    SECTION_HEADS => heads = entries.map(|entry| (entry[0], entry[1])).collect(),
+                    SECTION_CONTEXTS => contexts = entries.map(|entry| (entry[0], entry[1])).collect(),
+                    _ => {}
+                }
+                sections = &sections[(2 + count)..];
+            }
+            states.truncate(end);
+        }
         Automaton {
             table,
             states,
             states_count,
+            modes,
+            heads,
+            contexts,
         }
     }
 
@@ -208,6 +280,55 @@ impl Automaton {
     pub fn get_states(&self) -> impl Iterator<Item = AutomatonState<'_>> + '_ {
         (0..self.states_count).map(|i| self.get_state(i as u32))
     }
+
+    /// Gets whether the terminals in this automaton act on a stack of lexer modes
+    #[must_use]
+    pub fn has_mode_actions(&self) -> bool {
+        !self.modes.is_empty()
+    }
+
+    /// Gets the action on the stack of lexer modes for the terminal at the specified index
+    #[must_use]
+    pub fn get_mode_action(&self, index: usize) -> ModeAction {
+        self.modes.get(index).copied().unwrap_or_default()
+    }
+
+    /// Gets whether some terminals in this automaton have a trailing context
+    #[must_use]
+    pub fn has_trailing_contexts(&self) -> bool {
+        !self.heads.is_empty()
+    }
+
+    /// Gets the indices of the terminals whose head, i.e. the part before their trailing context, ends at the specified state
+    pub fn get_heads(&self, state: u32) -> impl Iterator<Item = u16> + '_ {
+        let start = self.heads.partition_point(|&(s, _)| u32::from(s) < state);
+        self.heads[start..]
+            .iter()
+            .take_while(move |&&(s, _)| u32::from(s) == state)
+            .map(|&(_, terminal)| terminal)
+    }
+
+    /// Gets whether the trailing context of the terminal at the specified index matches exactly the input between two indices
+    #[must_use]
+    pub fn matches_trailing_context(&self, terminal: u16, input: &Text, from: usize, to: usize) -> bool {
+        let Some(&(_, entry)) = self.contexts.iter().find(|&&(index, _)| index == terminal) else {
+            return false;
+        };
+        let mut state = u32::from(entry);
+        let mut position = from;
+        let mut input_iter = input.iter_utf16_from(from);
+        while position < to {
+            let Some((current, length)) = input_iter.next() else {
+                return false;
+            };
+            state = self.get_state(state).get_target_by(current);
+            if state == DEAD_STATE {
+                return false;
+            }
+            position += length;
+        }
+        self.get_state(state).get_terminals().any(|matched| matched.index == terminal)
+    }
 }
 
 /// Represents a match in the input
@@ -216,28 +337,110 @@ pub struct TokenMatch {
     pub state: u32,
     /// Length of the matched input
     pub length: u32,
+    /// The lengths of the heads of the terminals with a trailing context, by terminal index
+    pub heads: Vec<(u16, u32)>,
+}
+
+impl TokenMatch {
+    /// Creates a match for a state and a length, without terminals with a trailing context
+    #[must_use]
+    pub fn new(state: u32, length: u32) -> TokenMatch {
+        TokenMatch {
+            state,
+            length,
+            heads: Vec::new(),
+        }
+    }
+
+    /// Gets the length of the token for the matched terminal at the specified index
+    /// For a terminal with a trailing context, this is the length of the head only.
+    #[must_use]
+    pub fn get_length_for(&self, terminal: u16) -> u32 {
+        self.heads
+            .iter()
+            .find(|(index, _)| *index == terminal)
+            .map_or(self.length, |&(_, length)| length)
+    }
 }
 
 /// Runs the lexer's DFA to match a terminal in the input ahead
 #[must_use]
 pub fn run_dfa(automaton: &Automaton, input: &Text, index: usize) -> Option<TokenMatch> {
+    run_dfa_with_reach(automaton, input, index).0
+}
+
+/// Runs the lexer's DFA to match a terminal in the input ahead
+/// Also returns an upper bound (exclusive) of the indices in the input that have been examined,
+/// where examining the end of the input counts as examining the index past it.
+#[must_use]
+pub fn run_dfa_with_reach(automaton: &Automaton, input: &Text, index: usize) -> (Option<TokenMatch>, usize) {
+    run_dfa_with_filter(automaton, input, index, &|_| true)
+}
+
+/// Gets the terminals that more input may still match, when the lexer's DFA runs from an index to the end of the input
+/// The terminals are given by their index in the table of terminals.
+#[must_use]
+pub fn get_terminals_past_end(automaton: &Automaton, input: &Text, index: usize) -> Vec<u16> {
+    let mut state = 0;
+    for (current, _) in input.iter_utf16_from(index) {
+        state = automaton.get_state(state).get_target_by(current);
+        if state == DEAD_STATE {
+            return Vec::new();
+        }
+    }
+    // explore the states that are reachable with more input
+    let mut visited = alloc::vec![false; automaton.get_states_count()];
+    let mut stack = alloc::vec![state];
+    let mut terminals = Vec::new();
+    while let Some(current) = stack.pop() {
+        let state_data = automaton.get_state(current);
+        let targets = state_data
+            .get_cached_transitions()
+            .map(|(_, target)| target)
+            .chain(state_data.get_bulk_transitions().map(|transition| transition.target));
+        for target in targets {
+            if target == DEAD_STATE || visited[target as usize] {
+                continue;
+            }
+            visited[target as usize] = true;
+            stack.push(target);
+            for terminal in automaton.get_state(target).get_terminals() {
+                if !terminals.contains(&terminal.index) {
+                    terminals.push(terminal.index);
+                }
+            }
+        }
+    }
+    terminals
+}
+
+/// Runs the lexer's DFA to match a terminal in the input ahead, only accepting the matching states that pass the filter
+/// Also returns an upper bound (exclusive) of the indices in the input that have been examined.
+#[must_use]
+pub fn run_dfa_with_filter(
+    automaton: &Automaton,
+    input: &Text,
+    index: usize,
+    filter: &dyn Fn(AutomatonState) -> bool,
+) -> (Option<TokenMatch>, usize) {
     if input.is_end(index) {
-        return Some(TokenMatch { state: 0, length: 0 });
+        return (Some(TokenMatch::new(0, 0)), index + 1);
     }
 
     let mut result = None;
     let mut state = 0;
     let mut position = index;
     let mut input_iter = input.iter_utf16_from(index);
+    // all the ends of the heads of terminals with a trailing context on the path
+    let mut heads: Vec<(u16, u32)> = Vec::new();
 
     while state != DEAD_STATE {
         let state_data = automaton.get_state(state);
+        let length = (position - index) as u32;
+        heads.extend(automaton.get_heads(state).map(|terminal| (terminal, length)));
         // Is this state a matching state ?
-        if state_data.get_terminals_count() > 0 {
-            result = Some(TokenMatch {
-                state,
-                length: (position - index) as u32,
-            });
+        if state_data.get_terminals_count() > 0 && filter(state_data) {
+            result = Some(TokenMatch::new(state, length));
         }
         // No further transition => exit
         if state_data.is_dead_end() {
@@ -254,5 +457,36 @@ pub fn run_dfa(automaton: &Automaton, input: &Text, index: usize) -> Option<Toke
             }
         }
     }
-    result
+    if let Some(result) = result.as_mut() {
+        if !heads.is_empty() {
+            result.heads = resolve_heads(automaton, input, index, result, &heads);
+        }
+    }
+    // the position does not account for the end of the input, or for a partially read surrogate pair
+    (result, position + 1)
+}
+
+/// Gets the lengths of the heads of the matched terminals with a trailing context
+/// The head of a terminal may end at several places on the path to the match.
+/// The longest head is the last one after which the trailing context matches the rest of the input up to the end of the match.
+fn resolve_heads(
+    automaton: &Automaton,
+    input: &Text,
+    index: usize,
+    result: &TokenMatch,
+    heads: &[(u16, u32)],
+) -> Vec<(u16, u32)> {
+    let end = index + result.length as usize;
+    automaton
+        .get_state(result.state)
+        .get_terminals()
+        .filter_map(|matched| {
+            heads
+                .iter()
+                .rev()
+                .filter(|&&(terminal, length)| terminal == matched.index && length <= result.length)
+                .find(|&&(terminal, length)| automaton.matches_trailing_context(terminal, input, index + length as usize, end))
+                .copied()
+        })
+        .collect()
 }
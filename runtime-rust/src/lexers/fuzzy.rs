@@ -31,6 +31,8 @@ struct FuzzyMatcherHead {
     state: u32,
     /// The data representing this head
     errors: Vec<u32>,
+    /// The lengths of the heads of the terminals with a trailing context, by terminal index
+    heads: Vec<(u16, u32)>,
 }
 
 impl FuzzyMatcherHead {
@@ -39,6 +41,7 @@ impl FuzzyMatcherHead {
         FuzzyMatcherHead {
             state,
             errors: Vec::new(),
+            heads: Vec::new(),
         }
     }
 
@@ -47,6 +50,7 @@ impl FuzzyMatcherHead {
         FuzzyMatcherHead {
             state,
             errors: previous.errors.clone(),
+            heads: previous.heads.clone(),
         }
     }
 
@@ -59,7 +63,21 @@ impl FuzzyMatcherHead {
         while errors.len() < distance {
             errors.push(offset as u32);
         }
-        FuzzyMatcherHead { state, errors }
+        FuzzyMatcherHead {
+            state,
+            errors,
+            heads: previous.heads.clone(),
+        }
+    }
+
+    /// Records the ends of the heads of the terminals with a trailing context at the state of this head
+    pub fn record_heads(&mut self, automaton: &Automaton, offset: usize) {
+        for terminal in automaton.get_heads(self.state) {
+            match self.heads.iter_mut().find(|(index, _)| *index == terminal) {
+                Some(head) => head.1 = offset as u32,
+                None => self.heads.push((terminal, offset as u32)),
+            }
+        }
     }
 
     /// Gets the Levenshtein distance of this head from the input
@@ -176,7 +194,8 @@ impl<'s, 't, 'a> FuzzyMatcher<'s, 't, 'a> {
 
         let mut result = FuzzyMatcherResult::new();
         {
-            let head_begin = FuzzyMatcherHead::new(0);
+            let mut head_begin = FuzzyMatcherHead::new(0);
+            head_begin.record_heads(self.automaton, offset);
             if let Some(current) = current {
                 self.inspect(&mut result, &head_begin, offset, current);
             } else {
@@ -190,7 +209,8 @@ impl<'s, 't, 'a> FuzzyMatcher<'s, 't, 'a> {
                 Some((current, length)) => (Some(current), length),
             };
             let generation = take(&mut result.heads);
-            for head in generation {
+            for mut head in generation {
+                head.record_heads(self.automaton, offset);
                 if let Some(current) = current {
                     self.inspect(&mut result, &head, offset, current);
                 } else {
@@ -216,9 +236,11 @@ impl<'s, 't, 'a> FuzzyMatcher<'s, 't, 'a> {
             }
             last_error_index = error_index;
         }
+        let match_head = result.match_head.as_ref().unwrap();
         TokenMatch {
-            state: result.match_head.as_ref().unwrap().state,
+            state: match_head.state,
             length: result.match_length as u32,
+            heads: match_head.heads.clone(),
         }
     }
 
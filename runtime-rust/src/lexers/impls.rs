@@ -17,11 +17,13 @@
 
 //! Module for lexers' implementation
 
-use super::automaton::{run_dfa, Automaton, TokenMatch};
+use alloc::vec::Vec;
+
+use super::automaton::{run_dfa_with_filter, run_dfa_with_reach, Automaton, AutomatonState, ModeAction, TokenMatch};
 use super::fuzzy::FuzzyMatcher;
-use super::{ContextProvider, LexerData, TokenKernel};
-use crate::errors::{ParseErrorUnexpectedChar, ParseErrors};
-use crate::symbols::SID_DOLLAR;
+use super::{ContextProvider, ExternalScanner, Indentation, LexerData, TokenKernel, DEFAULT_CONTEXT};
+use crate::budget::ParseBudget;
+use crate::errors::{ParseErrorInconsistentDedent, ParseErrorUnexpectedChar, ParseErrors};
 use crate::tokens::TokenRepository;
 
 /// The default maximum Levenshtein distance to go to for the recovery of a matching failure
@@ -64,10 +66,108 @@ fn run_fuzzy_matcher<'s, 't, 'a>(
     }
 }
 
+/// The stack of indentation levels of an indentation-sensitive lexer
+struct IndentationStack {
+    /// The index of the terminal for an increase of the indentation
+    indent: usize,
+    /// The index of the terminal for a decrease of the indentation
+    dedent: usize,
+    /// The index of the terminal for the end of a line
+    newline: usize,
+    /// The number of columns for a tab character
+    tab_width: usize,
+    /// The indentation levels above the first one (at column 0)
+    levels: Vec<usize>,
+    /// The end index of the last token, if any
+    last_end: Option<usize>,
+}
+
+impl IndentationStack {
+    /// Creates the stack for the specified configuration
+    /// Returns `None` when one of the terminals is not in the repository
+    fn new(config: Indentation, repository: &TokenRepository) -> Option<IndentationStack> {
+        let find = |id: u32| repository.terminals.iter().position(|terminal| terminal.id == id);
+        Some(IndentationStack {
+            indent: find(config.indent_id)?,
+            dedent: find(config.dedent_id)?,
+            newline: find(config.newline_id)?,
+            tab_width: config.tab_width.max(1),
+            levels: Vec::new(),
+            last_end: None,
+        })
+    }
+
+    /// Handles a token at the specified index, before it is added to the repository
+    /// On the first token of a line, ends the previous line and adds the tokens for the change of indentation.
+    /// The lines without tokens, i.e. blank or with only comments, are skipped.
+    fn on_token(&mut self, data: &mut LexerData, index: usize, length: usize, reach: usize) {
+        let text = data.repository.text;
+        let line = text.get_position_at(index).line;
+        let last_end = self.last_end.replace(index + length);
+        if let Some(last_end) = last_end {
+            if text.get_position_at(last_end).line >= line {
+                // not the first token of a line
+                return;
+            }
+            data.repository.add_with_reach(self.newline, last_end, 0, reach);
+        }
+        // only the leading whitespace is measured, not the separators (e.g. comments) before the token
+        let line_start = text.get_line_index(line);
+        let column = text
+            .get_value(line_start, index - line_start)
+            .chars()
+            .take_while(|&c| c == ' ' || c == '\t')
+            .fold(0, |column, c| {
+                if c == '\t' {
+                    (column / self.tab_width + 1) * self.tab_width
+                } else {
+                    column + 1
+                }
+            });
+        if column > self.top() {
+            self.levels.push(column);
+            data.repository.add_with_reach(self.indent, index, 0, reach);
+            return;
+        }
+        while column < self.top() {
+            let outer = self.levels.len().checked_sub(2).map_or(0, |outer| self.levels[outer]);
+            if column > outer {
+                // between two levels, the column replaces the inner one so that the next lines at it are consistent
+                data.errors
+                    .push_error_inconsistent_dedent(ParseErrorInconsistentDedent::new(text.get_position_at(index), column));
+                if let Some(top) = self.levels.last_mut() {
+                    *top = column;
+                }
+                return;
+            }
+            self.levels.pop();
+            data.repository.add_with_reach(self.dedent, index, 0, reach);
+        }
+    }
+
+    /// Handles the end of the input at the specified index, before the dollar token is added
+    /// Ends the last line and closes all the indentation levels
+    fn on_end(&mut self, data: &mut LexerData, index: usize, reach: usize) {
+        if let Some(last_end) = self.last_end.take() {
+            data.repository.add_with_reach(self.newline, last_end, 0, reach);
+        }
+        while self.levels.pop().is_some() {
+            data.repository.add_with_reach(self.dedent, index, 0, reach);
+        }
+    }
+
+    /// Gets the current indentation level
+    fn top(&self) -> usize {
+        self.levels.last().copied().unwrap_or_default()
+    }
+}
+
 /// Represents a context-free lexer (lexing rules do not depend on the context)
 pub struct ContextFreeLexer<'s, 't, 'a> {
     /// The lexer's innner data
     data: LexerData<'s, 't, 'a>,
+    /// The stack of indentation levels, for an indentation-sensitive lexer
+    indentation: Option<IndentationStack>,
 }
 
 impl<'s, 't, 'a> ContextFreeLexer<'s, 't, 'a> {
@@ -87,7 +187,10 @@ impl<'s, 't, 'a> ContextFreeLexer<'s, 't, 'a> {
                 separator_id,
                 index: 0,
                 recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE,
+                budget: ParseBudget::default(),
+                scanner: None,
             },
+            indentation: None,
         }
     }
 
@@ -111,10 +214,29 @@ impl<'s, 't, 'a> ContextFreeLexer<'s, 't, 'a> {
     }
 
     /// Finds all the tokens in the lexer's input
+    /// When the repository reuses the tokens of a previous lexing,
+    /// the lexing restarts after the kept tokens and stops as soon as it synchronizes on the previous tokens.
     fn find_tokens(&mut self) {
-        let mut index = 0;
+        let (mut index, mut reach) = self.data.repository.get_restart();
+        // without the parser, all the terminals are acceptable
+        let acceptable: Vec<u32> = self.data.repository.terminals.iter().map(|terminal| terminal.id).collect();
         loop {
-            let mut result = run_dfa(&self.data.automaton, self.data.repository.text, index);
+            if self.data.repository.try_resync(index, reach) {
+                return;
+            }
+            if !self.data.budget.step() {
+                self.data.check_budget(index);
+                self.data.repository.end_reuse();
+                return;
+            }
+            if let Some((terminal, length, examined)) = self.data.run_scanner(index, &acceptable) {
+                reach = reach.max(examined);
+                self.add_token(terminal, index, length, reach);
+                index += length;
+                continue;
+            }
+            let (mut result, examined) = run_dfa_with_reach(&self.data.automaton, self.data.repository.text, index);
+            reach = reach.max(examined);
             if result.is_none() {
                 // failed to match, retry with error handling
                 result = run_fuzzy_matcher(
@@ -125,27 +247,46 @@ impl<'s, 't, 'a> ContextFreeLexer<'s, 't, 'a> {
                     self.data.errors,
                     index,
                 );
+                // the extent of the examined input is unknown
+                reach = usize::MAX;
             }
             if let Some(the_match) = result {
                 if the_match.state == 0 {
                     // this is the dollar terminal, at the end of the input
+                    if let Some(stack) = self.indentation.as_mut() {
+                        stack.on_end(&mut self.data, index, reach);
+                    }
                     // the index of the $ symbol is always 1
-                    self.data.repository.add(1, index, 0);
+                    self.data.repository.add_with_reach(1, index, 0, reach);
                     // exit here
+                    self.data.repository.end_reuse();
                     return;
                 }
                 // matched something
-                let terminal = self.data.automaton.get_state(the_match.state).get_terminal(0).index as usize;
-                if self.data.repository.terminals[terminal].id != self.data.separator_id {
-                    self.data.repository.add(terminal, index, the_match.length as usize);
-                }
-                index += the_match.length as usize;
+                let terminal = self.data.automaton.get_state(the_match.state).get_terminal(0).index;
+                let length = the_match.get_length_for(terminal) as usize;
+                self.add_token(terminal as usize, index, length, reach);
+                index += length;
             } else {
                 // skip this character
-                index += self.data.repository.text.at(index).len_utf8();
+                let length = self.data.repository.text.at(index).len_utf8();
+                self.data.repository.add_trivia(None, index, length);
+                index += length;
             }
         }
     }
+
+    /// Adds a token for the matched terminal, or a trivia for the separator
+    fn add_token(&mut self, terminal: usize, index: usize, length: usize, reach: usize) {
+        if self.data.repository.terminals[terminal].id == self.data.separator_id {
+            self.data.repository.add_trivia(Some(terminal), index, length);
+            return;
+        }
+        if let Some(stack) = self.indentation.as_mut() {
+            stack.on_token(&mut self.data, index, length, reach);
+        }
+        self.data.repository.add_with_reach(terminal, index, length, reach);
+    }
 }
 
 /// Represents a context-sensitive lexer (lexing rules do not depend on the context)
@@ -154,16 +295,29 @@ pub struct ContextSensitiveLexer<'s, 't, 'a> {
     data: LexerData<'s, 't, 'a>,
     /// The current index in the input
     input_index: usize,
+    /// The index up to which the input has been examined
+    reach: usize,
+    /// The stack of the current lexer modes
+    modes: Vec<u16>,
+    /// The stack of indentation levels, for an indentation-sensitive lexer
+    indentation: Option<IndentationStack>,
 }
 
 impl<'s, 't, 'a> ContextSensitiveLexer<'s, 't, 'a> {
     /// Creates a new lexer
     pub fn new(
-        repository: TokenRepository<'s, 't, 'a>,
+        mut repository: TokenRepository<'s, 't, 'a>,
         errors: &'a mut ParseErrors<'s>,
         automaton: Automaton,
         separator_id: u32,
     ) -> ContextSensitiveLexer<'s, 't, 'a> {
+        if automaton.has_mode_actions() {
+            // the modes at the end of the reused tokens are unknown, start over
+            repository.discard_reuse();
+        }
+        // the context-sensitive lexer only reuses the tokens at the start
+        let (input_index, reach) = repository.get_restart();
+        repository.end_reuse();
         ContextSensitiveLexer {
             data: LexerData {
                 repository,
@@ -173,18 +327,62 @@ impl<'s, 't, 'a> ContextSensitiveLexer<'s, 't, 'a> {
                 separator_id,
                 index: 0,
                 recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE,
+                budget: ParseBudget::default(),
+                scanner: None,
             },
-            input_index: 0,
+            input_index,
+            reach,
+            modes: Vec::new(),
+            indentation: None,
         }
     }
 
     /// Gets the next token in the input
+    /// The tokens already in the repository, reused or inserted for the indentation, are served first.
     fn get_next_token(&mut self, contexts: &dyn ContextProvider) -> Option<TokenKernel> {
-        if self.data.has_run {
-            return None;
+        loop {
+            if self.data.index < self.data.repository.get_tokens_count() {
+                let index = self.data.index;
+                self.data.index += 1;
+                return Some(TokenKernel {
+                    terminal_id: self.data.repository.get_symbol_id_for(index),
+                    index: index as u32,
+                });
+            }
+            if self.data.has_run {
+                return None;
+            }
+            self.find_next_token(contexts);
         }
+    }
+
+    /// Finds the next token in the input and adds it to the repository
+    fn find_next_token(&mut self, contexts: &dyn ContextProvider) {
         loop {
-            let mut result = run_dfa(&self.data.automaton, self.data.repository.text, self.input_index);
+            if !self.data.budget.step() {
+                self.data.check_budget(self.input_index);
+                self.data.has_run = true;
+                return;
+            }
+            if self.data.scanner.is_some() {
+                let acceptable = self.get_acceptable(contexts);
+                if let Some((terminal_index, length, examined)) = self.data.run_scanner(self.input_index, &acceptable) {
+                    self.reach = self.reach.max(examined);
+                    if self.add_token(terminal_index, length) {
+                        return;
+                    }
+                    continue;
+                }
+            }
+            let (mut result, examined) = if self.data.automaton.has_mode_actions() {
+                // the terminals of other modes shall not hide the shorter matches in the current one
+                run_dfa_with_filter(&self.data.automaton, self.data.repository.text, self.input_index, &|state| {
+                    self.is_possible(state, contexts)
+                })
+            } else {
+                run_dfa_with_reach(&self.data.automaton, self.data.repository.text, self.input_index)
+            };
+            self.reach = self.reach.max(examined);
             if result.is_none() {
                 // failed to match, retry with error handling
                 result = run_fuzzy_matcher(
@@ -195,69 +393,117 @@ impl<'s, 't, 'a> ContextSensitiveLexer<'s, 't, 'a> {
                     self.data.errors,
                     self.input_index,
                 );
+                // the extent of the examined input is unknown
+                self.reach = usize::MAX;
             }
             if let Some(the_match) = result {
                 if the_match.state == 0 {
                     // this is the dollar terminal, at the end of the input
+                    if let Some(stack) = self.indentation.as_mut() {
+                        stack.on_end(&mut self.data, self.input_index, self.reach);
+                    }
                     // the index of the $ symbol is always 1
-                    let token_index = self.data.repository.add(1, self.input_index, 0);
+                    self.data.repository.add_with_reach(1, self.input_index, 0, self.reach);
                     self.data.has_run = true;
-                    return Some(TokenKernel {
-                        terminal_id: SID_DOLLAR,
-                        index: token_index as u32,
-                    });
+                    return;
                 }
                 // matched something
                 let terminal_index = self.get_terminal_for(the_match.state, contexts);
-                let terminal_id = self.data.repository.terminals[terminal_index as usize].id;
-                if terminal_id != self.data.separator_id {
-                    let token_index =
-                        self.data
-                            .repository
-                            .add(terminal_index as usize, self.input_index, the_match.length as usize);
-                    self.input_index += the_match.length as usize;
-                    return Some(TokenKernel {
-                        terminal_id,
-                        index: token_index as u32,
-                    });
+                let length = the_match.get_length_for(terminal_index) as usize;
+                if self.add_token(terminal_index as usize, length) {
+                    return;
                 }
-                self.input_index += the_match.length as usize;
             } else {
                 // skip this character
-                self.input_index += self.data.repository.text.at(self.input_index).len_utf8();
+                let length = self.data.repository.text.at(self.input_index).len_utf8();
+                self.data.repository.add_trivia(None, self.input_index, length);
+                self.input_index += length;
             }
         }
     }
 
+    /// Adds a token for the matched terminal at the current index, or a trivia for the separator
+    /// Returns whether a token has been added
+    fn add_token(&mut self, terminal_index: usize, length: usize) -> bool {
+        let terminal_id = self.data.repository.terminals[terminal_index].id;
+        self.apply_mode_action(terminal_index);
+        let index = self.input_index;
+        self.input_index += length;
+        if terminal_id == self.data.separator_id {
+            self.data.repository.add_trivia(Some(terminal_index), index, length);
+            return false;
+        }
+        if let Some(stack) = self.indentation.as_mut() {
+            stack.on_token(&mut self.data, index, length, self.reach);
+        }
+        self.data.repository.add_with_reach(terminal_index, index, length, self.reach);
+        true
+    }
+
+    /// Gets the identifiers of the terminals that are acceptable for the next token, for the external scanner
+    /// The separator is always acceptable.
+    fn get_acceptable(&self, contexts: &dyn ContextProvider) -> Vec<u32> {
+        let count = self.data.repository.get_count();
+        self.data
+            .repository
+            .terminals
+            .iter()
+            .map(|terminal| terminal.id)
+            .filter(|&id| id == self.data.separator_id || contexts.is_acceptable(count, id))
+            .collect()
+    }
+
+    /// Gets whether a terminal matched at a state of the automaton is possible in the contexts
+    fn is_possible(&self, state: AutomatonState, contexts: &dyn ContextProvider) -> bool {
+        state.get_terminals().any(|matched| {
+            let id = self.data.repository.terminals[matched.index as usize].id;
+            self.get_priority(contexts, matched.context, id).is_some()
+        })
+    }
+
     /// Gets the index of the terminal with the highest priority that is possible in the contexts
     fn get_terminal_for(&self, state: u32, contexts: &dyn ContextProvider) -> u16 {
         let state_data = self.data.automaton.get_state(state);
-        let mut matched = state_data.get_terminal(0);
-        let mut result = matched.index;
-        let mut id = self.data.repository.terminals[result as usize].id;
-        if id == self.data.separator_id {
-            // the separator trumps all
-            return result;
-        }
-        let mut priority = contexts.get_context_priority(self.data.repository.get_count(), matched.context, id);
-        for i in 1..state_data.get_terminals_count() {
-            matched = state_data.get_terminal(i);
-            id = self.data.repository.terminals[matched.index as usize].id;
+        let mut result = state_data.get_terminal(0).index;
+        let mut priority = None;
+        for matched in state_data.get_terminals() {
+            let id = self.data.repository.terminals[matched.index as usize].id;
+            let priority_candidate = self.get_priority(contexts, matched.context, id);
+            if priority_candidate.is_none() {
+                continue;
+            }
             if id == self.data.separator_id {
                 // the separator trumps all
                 return matched.index;
             }
-            let priority_candidate = contexts.get_context_priority(self.data.repository.get_count(), matched.context, id);
-            if priority_candidate.is_none() {
-                continue;
-            }
-            if priority.is_none() || priority_candidate.unwrap() < priority.unwrap() {
+            if priority.is_none() || priority_candidate < priority {
                 result = matched.index;
                 priority = priority_candidate;
             }
         }
         result
     }
+
+    /// Gets the priority of a terminal in the specified context
+    /// In a lexer mode, only the terminals of this mode are possible.
+    /// Otherwise, the parser decides which contexts are available.
+    fn get_priority(&self, contexts: &dyn ContextProvider, context: u16, id: u32) -> Option<usize> {
+        match self.modes.last() {
+            Some(&mode) if mode != DEFAULT_CONTEXT => (context == mode).then_some(0),
+            _ => contexts.get_context_priority(self.data.repository.get_count(), context, id),
+        }
+    }
+
+    /// Applies the action on the stack of lexer modes for the matched terminal
+    fn apply_mode_action(&mut self, terminal_index: usize) {
+        match self.data.automaton.get_mode_action(terminal_index) {
+            ModeAction::None => {}
+            ModeAction::Push(mode) => self.modes.push(mode),
+            ModeAction::Pop => {
+                self.modes.pop();
+            }
+        }
+    }
 }
 
 /// Represents a lexer
@@ -286,6 +532,37 @@ impl<'s, 't, 'a> Lexer<'s, 't, 'a> {
         }
     }
 
+    /// Sets the budget bounding the work of the lexer and of the parser using it
+    pub fn set_budget(&mut self, budget: ParseBudget) {
+        self.get_data_mut().budget = budget;
+    }
+
+    /// Sets the scanner for the external terminals
+    pub fn set_scanner(&mut self, scanner: &'a mut dyn ExternalScanner) {
+        self.get_data_mut().scanner = Some(scanner);
+    }
+
+    /// Makes this lexer indentation-sensitive with the specified configuration
+    /// The tokens of a previous lexing are not reused.
+    /// Does nothing when one of the configured terminals is not a terminal of this lexer.
+    pub fn set_indentation(&mut self, config: Indentation) {
+        let Some(stack) = IndentationStack::new(config, &self.get_data().repository) else {
+            return;
+        };
+        match self {
+            Lexer::ContextFree(ref mut lexer) => {
+                lexer.data.repository.discard_reuse();
+                lexer.indentation = Some(stack);
+            }
+            Lexer::ContextSensitive(ref mut lexer) => {
+                lexer.data.repository.discard_reuse();
+                lexer.input_index = 0;
+                lexer.reach = 0;
+                lexer.indentation = Some(stack);
+            }
+        }
+    }
+
     /// Gets the next token in the input
     pub fn get_next_token(&mut self, contexts: &dyn ContextProvider) -> Option<TokenKernel> {
         match self {
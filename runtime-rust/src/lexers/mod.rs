@@ -21,8 +21,10 @@ pub mod automaton;
 pub mod fuzzy;
 pub mod impls;
 
-use crate::errors::ParseErrors;
+use crate::budget::ParseBudget;
+use crate::errors::{ParseErrorBudgetExhausted, ParseErrors};
 use crate::lexers::automaton::Automaton;
+use crate::text::Text;
 use crate::tokens::TokenRepository;
 
 /// Identifier of the default context
@@ -34,6 +36,50 @@ pub trait ContextProvider {
     /// The priority is an unsigned integer. The lesser the value the higher the priority.
     /// The absence of value represents the unavailability of the required context.
     fn get_context_priority(&self, token_count: usize, context: u16, terminal_id: u32) -> Option<usize>;
+
+    /// Gets whether the specified terminal is acceptable for the next token
+    /// By default, all terminals are acceptable.
+    fn is_acceptable(&self, _token_count: usize, _terminal_id: u32) -> bool {
+        true
+    }
+}
+
+/// Represents a match of a terminal by an external scanner
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExternalMatch {
+    /// The identifier of the matched terminal
+    pub terminal_id: u32,
+    /// The length of the match in the input
+    pub length: usize,
+    /// The exclusive upper bound of the indices in the text that the scanner examined for the match,
+    /// where examining the end of the input counts as examining the index past it
+    /// The tokens are lexed again after an edit of the text before this bound.
+    pub reach: usize,
+}
+
+/// A hand-written scanner for the terminals that cannot be matched by the lexer's automaton
+/// The scanner is consulted before the automaton at each index in the input.
+/// It may update a state of its own on each call.
+pub trait ExternalScanner {
+    /// Tries to match one of the acceptable terminals, given by their identifiers, at the specified index in the text
+    /// A context-sensitive lexer gives the terminals that the parser may accept at this index.
+    /// A context-free lexer runs ahead of the parser and gives all the terminals of the grammar.
+    fn scan(&mut self, text: &Text, index: usize, acceptable: &[u32]) -> Option<ExternalMatch>;
+}
+
+/// The configuration of an indentation-sensitive lexer
+/// At the start of each line with a token, the lexer compares the line's indentation to a stack of indentation levels.
+/// It inserts zero-width tokens for the end of the previous line and for each increase or decrease of the indentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Indentation {
+    /// The identifier of the terminal for an increase of the indentation
+    pub indent_id: u32,
+    /// The identifier of the terminal for a decrease of the indentation
+    pub dedent_id: u32,
+    /// The identifier of the terminal for the end of a line
+    pub newline_id: u32,
+    /// The number of columns for a tab character
+    pub tab_width: usize,
 }
 
 /// Implementation of the default context provider
@@ -75,6 +121,43 @@ pub struct LexerData<'s, 't, 'a> {
     /// The maximum Levenshtein distance to go to for the recovery of a matching failure.
     /// A distance of 0 indicates no recovery.
     pub recovery: usize,
+    /// The budget bounding the work of the lexer and of the parser using it
+    pub budget: ParseBudget,
+    /// The scanner for the external terminals, if any
+    pub scanner: Option<&'a mut dyn ExternalScanner>,
+}
+
+impl LexerData<'_, '_, '_> {
+    /// Reports the exhaustion of the budget at the specified index in the input, if it is exhausted and not yet reported
+    /// Returns whether the work can go on
+    pub fn check_budget(&mut self, index: usize) -> bool {
+        if let Some(reason) = self.budget.take_report() {
+            let position = self.repository.text.get_position_at(index);
+            self.errors
+                .push_error_budget_exhausted(ParseErrorBudgetExhausted::new(position, reason));
+        }
+        !self.budget.is_exhausted()
+    }
+
+    /// Runs the scanner for the external terminals, if any, at the specified index in the input
+    /// Returns the index of the matched terminal, the length of the match and the extent of the examined input
+    #[must_use]
+    pub fn run_scanner(&mut self, index: usize, acceptable: &[u32]) -> Option<(usize, usize, usize)> {
+        let scanner = self.scanner.as_deref_mut()?;
+        if self.repository.text.is_end(index) {
+            return None;
+        }
+        let result = scanner.scan(self.repository.text, index, acceptable)?;
+        if result.length == 0 || !acceptable.contains(&result.terminal_id) {
+            return None;
+        }
+        let terminal = self
+            .repository
+            .terminals
+            .iter()
+            .position(|terminal| terminal.id == result.terminal_id)?;
+        Some((terminal, result.length, result.reach.max(index + result.length)))
+    }
 }
 
 pub use impls::Lexer;
@@ -47,6 +47,7 @@ extern crate alloc;
 extern crate std;
 
 pub mod ast;
+pub mod budget;
 pub mod errors;
 pub mod lexers;
 pub mod parsers;
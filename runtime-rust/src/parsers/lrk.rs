@@ -17,20 +17,24 @@
 
 //! Module for LR(k) parsers
 
-use alloc::string::ToString;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use super::subtree::SubTree;
+use super::subtree::{ReusableSubTree, SubTree};
+use super::values::ValueStack;
 use super::{
-    get_op_code_base, get_op_code_tree_action, read_table_u16, read_u16, ContextProvider, LRAction, LRActionCode, LRColumnMap,
-    LRContexts, LRExpected, LRProduction, Parser, Symbol, TreeAction, LR_ACTION_CODE_ACCEPT, LR_ACTION_CODE_NONE,
-    LR_ACTION_CODE_REDUCE, LR_ACTION_CODE_SHIFT, LR_OP_CODE_BASE_ADD_VIRTUAL, LR_OP_CODE_BASE_SEMANTIC_ACTION,
-    TREE_ACTION_DROP, TREE_ACTION_NONE, TREE_ACTION_PROMOTE, TREE_ACTION_REPLACE_BY_CHILDREN, TREE_ACTION_REPLACE_BY_EPSILON,
+    get_entries_count, get_op_code_base, get_op_code_tree_action, read_labels, read_table_u16, read_u16, ContextProvider,
+    LRAction, LRActionCode, LRColumnMap, LRCompletion, LRContexts, LRExpected, LRProduction, Parser, Symbol, TreeAction,
+    GENERATED_AXIOM, LR_ACTION_CODE_ACCEPT, LR_ACTION_CODE_NONE, LR_ACTION_CODE_REDUCE, LR_ACTION_CODE_SHIFT,
+    LR_OP_CODE_BASE_ADD_VIRTUAL, LR_OP_CODE_BASE_SEMANTIC_ACTION, MAX_ERROR_COUNT, TREE_ACTION_DROP, TREE_ACTION_NONE,
+    TREE_ACTION_PROMOTE, TREE_ACTION_REPLACE_BY_CHILDREN, TREE_ACTION_REPLACE_BY_EPSILON,
 };
-use crate::ast::{AstImpl, TableElemRef, TableType};
+use crate::ast::{AstCell, AstImpl, TableElemRef, TableType};
+use crate::budget::ParseBudget;
 use crate::errors::ParseErrorUnexpectedToken;
 use crate::lexers::{Lexer, TokenKernel, DEFAULT_CONTEXT};
-use crate::symbols::{SemanticBody, SemanticElement, SemanticElementTrait};
+use crate::symbols::{SemanticBody, SemanticElement, SemanticElementTrait, SID_DOLLAR};
 
 /// Represents the LR(k) parsing table and productions
 #[derive(Clone)]
@@ -47,6 +51,8 @@ pub struct LRkAutomaton {
     table: Vec<u16>,
     /// The table of LR productions
     productions: Vec<LRProduction>,
+    /// The labels of the productions' elements
+    labels: Vec<String>,
 }
 
 impl LRkAutomaton {
@@ -76,6 +82,7 @@ impl LRkAutomaton {
             let production = LRProduction::new(data, &mut index);
             productions.push(production);
         }
+        let labels = read_labels(data, &mut index, &mut productions);
         LRkAutomaton {
             columns_count,
             states_count,
@@ -83,6 +90,7 @@ impl LRkAutomaton {
             contexts,
             table,
             productions,
+            labels,
         }
     }
 
@@ -132,6 +140,12 @@ impl LRkAutomaton {
         &self.productions[index]
     }
 
+    /// Gets the labels of the productions' elements
+    #[must_use]
+    pub fn get_labels(&self) -> &[String] {
+        &self.labels
+    }
+
     /// Gets the expected terminals for the specified state
     #[must_use]
     pub fn get_expected<'s>(&self, state: u32, terminals: &[Symbol<'s>]) -> LRExpected<'s> {
@@ -152,6 +166,9 @@ impl LRkAutomaton {
 
 const ESTIMATION_BIAS: usize = 5;
 
+/// The maximum number of terminals that can be inserted to complete the input when the recovery reaches its end
+const MAX_COMPLETION_LENGTH: usize = 3;
+
 /// The data about a reduction
 struct LRkAstReduction {
     /// The length of the reduction
@@ -185,6 +202,8 @@ struct LRkAstBuilder<'s, 't, 'a> {
     handle: Vec<usize>,
     /// The data of the current reduction
     reduction: Option<LRkAstReduction>,
+    /// The stack of the concrete syntax tree nodes, when building it
+    concrete: Option<Vec<AstCell>>,
 }
 
 impl<'s, 't, 'a> SemanticBody for LRkAstBuilder<'s, 't, 'a> {
@@ -197,7 +216,7 @@ impl<'s, 't, 'a> SemanticBody for LRkAstBuilder<'s, 't, 'a> {
                     TableType::Token => SemanticElement::Token(self.lexer.get_data().repository.get_token(label.index())),
                     TableType::Variable => SemanticElement::Variable(self.variables[label.index()]),
                     TableType::Virtual => SemanticElement::Virtual(self.virtuals[label.index()]),
-                    TableType::None => SemanticElement::Terminal(self.lexer.get_data().repository.terminals[0]),
+                    TableType::None => SemanticElement::Terminal(self.lexer.get_data().repository.terminals[label.index()]),
                 }
             }
         }
@@ -216,6 +235,8 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
         virtuals: &'a [Symbol<'s>],
         result: &'a mut AstImpl,
     ) -> LRkAstBuilder<'s, 't, 'a> {
+        // the concrete syntax tree is built for lossless parsing
+        let concrete = lexer.get_data().repository.is_lossless().then(Vec::new);
         LRkAstBuilder {
             lexer,
             variables,
@@ -224,24 +245,51 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
             result,
             handle: Vec::new(),
             reduction: None,
+            concrete,
         }
     }
 
-    /// Push a token onto the stack
-    pub fn push_token(&mut self, index: usize) {
+    /// Push a leaf onto the stack
+    /// The leaf is either a token, or a terminal inserted by the error recovery
+    pub fn push_leaf(&mut self, label: TableElemRef) {
         let mut single = SubTree::new(1);
-        single.push(TableElemRef::new(TableType::Token, index), TREE_ACTION_NONE);
+        single.push(label, TREE_ACTION_NONE);
         self.stack.push(single);
+        if let Some(concrete) = self.concrete.as_mut() {
+            concrete.push(AstCell::new_empty(label));
+        }
+    }
+
+    /// Drops the sub-trees on the stack beyond the specified length
+    pub fn pop_to(&mut self, length: usize) {
+        self.stack.truncate(length);
+        if let Some(concrete) = self.concrete.as_mut() {
+            concrete.truncate(length);
+        }
     }
 
     /// Prepares for the forthcoming reduction operations
-    pub fn reduction_prepare(&mut self, variable_index: usize, length: usize, action: TreeAction) {
+    pub fn reduction_prepare(&mut self, variable_index: usize, production: usize, length: usize, action: TreeAction) {
+        if let Some(concrete) = self.concrete.as_mut() {
+            // the concrete node has all the popped elements as children, regardless of the tree actions
+            let start = concrete.len() - length;
+            let first = self.result.get_concrete_mut().store(concrete, start, length);
+            concrete.truncate(start);
+            let mut cell = AstCell::new(
+                TableElemRef::new(TableType::Variable, variable_index),
+                length as u32,
+                first as u32,
+            );
+            cell.production = Some(production as u16);
+            concrete.push(cell);
+        }
         let mut estimation = ESTIMATION_BIAS;
         for i in 0..length {
             estimation += self.stack[self.stack.len() - length + i].get_size();
         }
         let mut cache = SubTree::new(estimation);
         cache.setup_root(TableElemRef::new(TableType::Variable, variable_index), action);
+        cache.set_production_at(0, Some(production as u16));
         self.reduction = Some(LRkAstReduction {
             length,
             cache,
@@ -250,7 +298,14 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
     }
 
     /// During a reduction, insert the given sub-tree
-    fn reduction_add_sub(reduction: &mut LRkAstReduction, handle: &mut Vec<usize>, sub: &SubTree, action: TreeAction) {
+    /// The children of a replaced sub-tree without a label of their own inherit the label of the element.
+    fn reduction_add_sub(
+        reduction: &mut LRkAstReduction,
+        handle: &mut Vec<usize>,
+        sub: &SubTree,
+        action: TreeAction,
+        label: Option<u16>,
+    ) {
         if sub.get_action_at(0) == TREE_ACTION_REPLACE_BY_CHILDREN {
             let children_count = sub.get_children_count_at(0);
             // copy the children to the cache
@@ -260,6 +315,9 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
             for _i in 0..children_count {
                 let size = sub.get_children_count_at(sub_index) + 1;
                 handle.push(cache_index);
+                if label.is_some() && reduction.cache.get_element_label_at(cache_index).is_none() {
+                    reduction.cache.set_element_label_at(cache_index, label);
+                }
                 cache_index += size;
                 sub_index += size;
             }
@@ -269,31 +327,33 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
             // copy the complete sub-tree to the cache
             let cache_index = sub.copy_to(&mut reduction.cache);
             handle.push(cache_index);
+            reduction.cache.set_element_label_at(cache_index, label);
             if action != TREE_ACTION_NONE {
                 reduction.cache.set_action_at(cache_index, action);
             }
         }
     }
 
-    /// During a redution, pops the top symbol from the stack and gives it a tree action
-    pub fn reduction_pop(&mut self, action: TreeAction) {
+    /// During a redution, pops the top symbol from the stack and gives it a tree action and a label
+    pub fn reduction_pop(&mut self, action: TreeAction, label: Option<u16>) {
         match self.reduction.as_mut() {
             None => panic!("Not in a reduction"),
             Some(reduction) => {
                 let sub = &self.stack[self.stack.len() - reduction.length + reduction.pop_count];
-                LRkAstBuilder::reduction_add_sub(reduction, &mut self.handle, sub, action);
+                LRkAstBuilder::reduction_add_sub(reduction, &mut self.handle, sub, action, label);
                 reduction.pop_count += 1;
             }
         }
     }
 
     /// During a reduction, inserts a virtual symbol
-    pub fn reduction_add_virtual(&mut self, index: usize, action: TreeAction) {
+    pub fn reduction_add_virtual(&mut self, index: usize, action: TreeAction, label: Option<u16>) {
         if action != TREE_ACTION_DROP {
             match self.reduction.as_mut() {
                 None => panic!("Not in a reduction"),
                 Some(reduction) => {
                     let cache_index = reduction.cache.push(TableElemRef::new(TableType::Virtual, index), action);
+                    reduction.cache.set_element_label_at(cache_index, label);
                     self.handle.push(cache_index);
                 }
             }
@@ -326,6 +386,7 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
         if reduction.cache.get_action_at(0) == TREE_ACTION_REPLACE_BY_EPSILON {
             reduction.cache.set_label_at(0, TableElemRef::new(TableType::None, 0));
             reduction.cache.set_action_at(0, TREE_ACTION_NONE);
+            reduction.cache.set_production_at(0, None);
         }
         // promotion data
         let mut promotion = false;
@@ -361,20 +422,39 @@ impl<'s, 't, 'a> LRkAstBuilder<'s, 't, 'a> {
         }
         // finalize the sub-tree data
         reduction.cache.set_children_count_at(0, insertion - 1);
+        // the label of a promoted node is not the one of the sub-tree
+        reduction.cache.set_element_label_at(0, None);
     }
 
-    /// Commits the tree's root
-    pub fn commit_root(&mut self) {
+    /// Commits the tree's root, for the entry point the input was parsed from
+    pub fn commit_root(&mut self, entry: usize) {
         let length = self.stack.len();
         if length > 1 {
             let head = &mut self.stack[length - 2];
             head.commit(self.result);
         }
+        if let Some(concrete) = self.concrete.as_ref() {
+            // the root of the concrete syntax tree is the generated axiom, with the axiom and the dollar token
+            if let Some(axiom) = self
+                .variables
+                .iter()
+                .position(|variable| variable.name == GENERATED_AXIOM)
+                .map(|axiom| axiom + entry)
+            {
+                let cst = self.result.get_concrete_mut();
+                let first = cst.store(concrete, 0, concrete.len());
+                cst.store_root(AstCell::new(
+                    TableElemRef::new(TableType::Variable, axiom),
+                    concrete.len() as u32,
+                    first as u32,
+                ));
+            }
+        }
     }
 }
 
 /// The head of a LR(k) parser
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone)]
 struct LRkHead {
     /// The automaton's state
     state: u32,
@@ -382,15 +462,55 @@ struct LRkHead {
     identifier: u32,
 }
 
+/// The origin of an element on the stack of a LR(k) parser, used to record the reusable sub-trees
+#[derive(Copy, Clone)]
+struct LRkOrigin {
+    /// The index of the first token covered by the element, if the element may be reused
+    token: Option<usize>,
+    /// The number of errors when the element started
+    errors: usize,
+}
+
+/// The state of a LR(k) parser before a token, to resume the parsing of an input fed piece by piece
+/// The state is recorded before the first token that more input may change.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LRkCheckpoint {
+    /// The index of the token to resume the parsing on, 0 when no state was recorded
+    index: usize,
+    /// The parser's stack
+    stack: Vec<LRkHead>,
+    /// The stack of the sub-trees for the AST
+    trees: Vec<SubTree>,
+}
+
+impl LRkCheckpoint {
+    /// Gets whether the parsing can be resumed from this state
+    pub(crate) fn can_resume(&self) -> bool {
+        self.index > 0
+    }
+}
+
+/// The sub-trees of a previous parse that may be reused
+struct LRkReuse {
+    /// The tree produced by the previous parse
+    tree: Box<AstImpl>,
+    /// The reusable sub-trees, sorted by their first token
+    subtrees: Vec<ReusableSubTree>,
+}
+
 struct LRkParserData<'s, 'a> {
     /// The parser's automaton
     automaton: LRkAutomaton,
     /// The parser's stack
     stack: Vec<LRkHead>,
+    /// The origins of the elements on the stack, when recording the reusable sub-trees
+    origins: Option<Vec<LRkOrigin>>,
     /// The grammar variables
     variables: &'a [Symbol<'s>],
     /// The semantic actions
     actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+    /// The stack of the values synthesized by the semantic actions, if any
+    values: Option<&'a mut dyn ValueStack>,
 }
 
 impl<'s, 'a> ContextProvider for LRkParserData<'s, 'a> {
@@ -404,7 +524,7 @@ impl<'s, 'a> ContextProvider for LRkParserData<'s, 'a> {
         }
         if token_count == 0 {
             // this is the first token, does it open the context?
-            let contexts = self.automaton.get_contexts(0);
+            let contexts = self.automaton.get_contexts(self.stack[0].state);
             return if contexts.opens(terminal_id, context) { Some(0) } else { None };
         }
         // retrieve the action for this terminal
@@ -482,64 +602,158 @@ impl<'s, 'a> ContextProvider for LRkParserData<'s, 'a> {
             None
         }
     }
+
+    /// Gets whether the specified terminal is acceptable for the next token
+    fn is_acceptable(&self, _token_count: usize, terminal_id: u32) -> bool {
+        let state = self.stack[self.stack.len() - 1].state;
+        self.automaton.get_action(state, terminal_id).get_code() != LR_ACTION_CODE_NONE
+    }
 }
 
 impl<'s, 't, 'a> LRkParserData<'s, 'a> {
-    /// Checks whether the specified terminal is indeed expected for a reduction
-    /// This check is required because in the case of a base LALR graph,
+    /// Simulates the shift of the specified terminal on a copy of a stack
+    /// Returns the resulting stack when the terminal can be shifted (or accepted)
+    fn simulate_shift(&self, mut my_stack: Vec<LRkHead>, terminal_id: u32) -> Option<Vec<LRkHead>> {
+        loop {
+            let action = self.automaton.get_action(my_stack[my_stack.len() - 1].state, terminal_id);
+            match action.get_code() {
+                LR_ACTION_CODE_SHIFT | LR_ACTION_CODE_ACCEPT => {
+                    my_stack.push(LRkHead {
+                        state: u32::from(action.get_data()),
+                        identifier: terminal_id,
+                    });
+                    return Some(my_stack);
+                }
+                LR_ACTION_CODE_REDUCE => {
+                    // execute the reduction
+                    let production = self.automaton.get_production(action.get_data() as usize);
+                    let variable = self.variables[production.head];
+                    let length = my_stack.len();
+                    my_stack.truncate(length - production.reduction_length);
+                    // this must be a shift
+                    let action = self.automaton.get_action(my_stack[my_stack.len() - 1].state, variable.id);
+                    my_stack.push(LRkHead {
+                        state: u32::from(action.get_data()),
+                        identifier: variable.id,
+                    });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Simulates the parsing of a sequence of terminals on a copy of a stack
+    /// Returns whether all the terminals can be shifted
+    fn simulate(&self, my_stack: Vec<LRkHead>, terminals: &[u32]) -> bool {
+        let mut my_stack = my_stack;
+        for &terminal_id in terminals {
+            match self.simulate_shift(my_stack, terminal_id) {
+                None => return false,
+                Some(next) => my_stack = next,
+            }
+        }
+        true
+    }
+
+    /// Gets the terminals that are expected on top of the specified stack
+    /// The terminals expected for a reduction are checked because in the case of a base LALR graph,
     /// some terminals expected for reduction in the automaton are coming from other paths.
-    fn check_is_expected(&self, terminal: Symbol<'s>) -> bool {
-        // copy the stack to use for the simulation
-        let mut my_stack = self.stack.clone();
-        let mut action = self.automaton.get_action(my_stack[my_stack.len() - 1].state, terminal.id);
-        while action.get_code() != LR_ACTION_CODE_NONE {
-            if action.get_code() == LR_ACTION_CODE_SHIFT {
-                // yep, the terminal was expected
-                return true;
-            }
-            if action.get_code() == LR_ACTION_CODE_REDUCE {
-                // execute the reduction
-                let production = self.automaton.get_production(action.get_data() as usize);
-                let variable = self.variables[production.head];
-                let length = my_stack.len();
-                my_stack.truncate(length - production.reduction_length);
-                // this must be a shift
-                action = self.automaton.get_action(my_stack[my_stack.len() - 1].state, variable.id);
-                my_stack.push(LRkHead {
-                    state: u32::from(action.get_data()),
-                    identifier: variable.id,
-                });
-                // now, get the new action for the terminal
-                action = self.automaton.get_action(u32::from(action.get_data()), terminal.id);
+    fn get_expected_terminals(&self, my_stack: &[LRkHead], terminals: &[Symbol<'s>]) -> Vec<Symbol<'s>> {
+        let expected_on_head = self.automaton.get_expected(my_stack[my_stack.len() - 1].state, terminals);
+        let mut result = expected_on_head.shifts;
+        for x in expected_on_head.reductions {
+            if self.simulate_shift(my_stack.to_vec(), x.id).is_some() {
+                result.push(x);
             }
         }
-        // nope, that was a pathological case in a LALR graph
-        false
+        result
+    }
+
+    /// Gets the symbols expected on top of the specified stack, for code completion
+    fn get_completion(&self, my_stack: &[LRkHead], terminals: &[Symbol<'s>]) -> LRCompletion<'s> {
+        let mut completion = LRCompletion::default();
+        for terminal in self.get_expected_terminals(my_stack, terminals) {
+            if terminal.id == SID_DOLLAR {
+                continue;
+            }
+            completion.add_terminal(terminal);
+            // the variables that may start here have a transition from the state on which the terminal is shifted
+            if let Some(next) = self.simulate_shift(my_stack.to_vec(), terminal.id) {
+                let state = next[next.len() - 2].state;
+                for variable in self.variables {
+                    if self.automaton.get_action(state, variable.id).get_code() == LR_ACTION_CODE_SHIFT {
+                        completion.add_variable(*variable);
+                    }
+                }
+            }
+        }
+        completion
+    }
+
+    /// Finds a sequence of exactly `length` terminals that completes the input on top of the specified stack
+    fn find_completion(&self, my_stack: &[LRkHead], terminals: &[Symbol<'s>], length: usize) -> Option<Vec<Symbol<'s>>> {
+        let expected = self.get_expected_terminals(my_stack, terminals);
+        for terminal in expected.into_iter().filter(|terminal| terminal.id != SID_DOLLAR) {
+            if length == 1 {
+                if self.simulate(my_stack.to_vec(), &[terminal.id, SID_DOLLAR]) {
+                    return Some(alloc::vec![terminal]);
+                }
+            } else if let Some(next_stack) = self.simulate_shift(my_stack.to_vec(), terminal.id) {
+                if let Some(mut rest) = self.find_completion(&next_stack, terminals, length - 1) {
+                    rest.insert(0, terminal);
+                    return Some(rest);
+                }
+            }
+        }
+        None
     }
 
     /// Parses on the specified token kernel
     fn parse_on_token(&mut self, kernel: TokenKernel, builder: &mut LRkAstBuilder) -> LRActionCode {
+        self.parse_on_terminal(
+            kernel.terminal_id,
+            TableElemRef::new(TableType::Token, kernel.index as usize),
+            builder,
+        )
+    }
+
+    /// Parses on the specified terminal, using the given label for the leaf in the AST
+    fn parse_on_terminal(&mut self, terminal_id: u32, leaf: TableElemRef, builder: &mut LRkAstBuilder) -> LRActionCode {
         let stack = &mut self.stack;
 
         loop {
             let head = stack[stack.len() - 1];
-            let action = self.automaton.get_action(head.state, kernel.terminal_id);
+            let action = self.automaton.get_action(head.state, terminal_id);
             if action.get_code() == LR_ACTION_CODE_SHIFT {
                 stack.push(LRkHead {
                     state: u32::from(action.get_data()),
-                    identifier: kernel.terminal_id,
+                    identifier: terminal_id,
                 });
-                builder.push_token(kernel.index as usize);
+                builder.push_leaf(leaf);
+                if let Some(values) = self.values.as_deref_mut() {
+                    values.push_empty();
+                }
+                if let Some(origins) = self.origins.as_mut() {
+                    origins.push(LRkOrigin {
+                        token: (leaf.table_type() == TableType::Token).then(|| leaf.index()),
+                        errors: builder.lexer.get_data().errors.errors.len(),
+                    });
+                }
                 return action.get_code();
             }
             if action.get_code() != LR_ACTION_CODE_REDUCE {
                 return action.get_code();
             }
-            // now reduce
-            let production = self.automaton.get_production(action.get_data() as usize);
-            let variable = LRkParserData::reduce(production, builder, &mut self.actions);
+            // now reduce, the exhaustion of the budget is handled on the next token
+            builder.lexer.get_data_mut().budget.step();
+            let index = action.get_data() as usize;
+            let production = self.automaton.get_production(index);
+            let variable = LRkParserData::reduce(index, production, builder, &mut self.actions, self.values.as_deref_mut());
             let length = stack.len();
             stack.truncate(length - production.reduction_length);
+            if let Some(origins) = self.origins.as_mut() {
+                LRkParserData::record(origins, production, stack[stack.len() - 1].state, leaf, builder);
+            }
             let action = self
                 .automaton
                 .get_action(stack[stack.len() - 1].state, builder.variables[production.head].id);
@@ -550,35 +764,84 @@ impl<'s, 't, 'a> LRkParserData<'s, 'a> {
         }
     }
 
-    /// Executes the given LR reduction
-    fn reduce(
+    /// Updates the origins of the elements on the stack after a reduction
+    /// and records the reduced sub-tree when it may be reused
+    /// A reduced sub-tree may be reused when it starts with a token shifted onto the given state
+    /// and no error occurred until its reduction on the lookahead.
+    fn record(
+        origins: &mut Vec<LRkOrigin>,
+        production: &LRProduction,
+        state: u32,
+        lookahead: TableElemRef,
+        builder: &mut LRkAstBuilder,
+    ) {
+        let errors = builder.lexer.get_data().errors.errors.len();
+        let origin = if production.reduction_length == 0 {
+            // an empty element is not pushed onto the stack on a shift, neither are the elements starting with it
+            LRkOrigin { token: None, errors }
+        } else {
+            origins[origins.len() - production.reduction_length]
+        };
+        origins.truncate(origins.len() - production.reduction_length);
+        origins.push(origin);
+        if let Some(first) = origin.token {
+            if origin.errors == errors && lookahead.table_type() == TableType::Token {
+                builder.result.record(ReusableSubTree {
+                    first,
+                    lookahead: lookahead.index(),
+                    state,
+                    variable: production.head,
+                    tree: builder.stack[builder.stack.len() - 1].clone(),
+                });
+            }
+        }
+    }
+
+    /// Executes the LR reduction for the production at the given index
+    /// When synthesizing values, the semantic actions are executed through the stack of values.
+    fn reduce<'v>(
+        index: usize,
         production: &LRProduction,
         builder: &mut LRkAstBuilder<'s, 't, 'a>,
         actions: &mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+        mut values: Option<&mut (dyn ValueStack + 'v)>,
     ) -> Symbol<'s> {
         let variable = builder.variables[production.head];
-        builder.reduction_prepare(production.head, production.reduction_length, production.head_action);
+        builder.reduction_prepare(production.head, index, production.reduction_length, production.head_action);
         let mut i = 0;
         while i < production.bytecode.len() {
             let op_code = production.bytecode[i];
+            let label = production.get_label_at(i);
             i += 1;
             match get_op_code_base(op_code) {
                 LR_OP_CODE_BASE_SEMANTIC_ACTION => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    actions(index, variable, builder);
+                    match values.as_deref_mut() {
+                        Some(values) => values.execute(
+                            index,
+                            variable,
+                            builder,
+                            production.reduction_length,
+                            builder.reduction.as_ref().map_or(0, |reduction| reduction.pop_count),
+                        ),
+                        None => actions(index, variable, builder),
+                    }
                 }
                 LR_OP_CODE_BASE_ADD_VIRTUAL => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    builder.reduction_add_virtual(index, get_op_code_tree_action(op_code));
+                    builder.reduction_add_virtual(index, get_op_code_tree_action(op_code), label);
                 }
                 _ => {
-                    builder.reduction_pop(get_op_code_tree_action(op_code));
+                    builder.reduction_pop(get_op_code_tree_action(op_code), label);
                 }
             }
         }
         builder.reduce();
+        if let Some(values) = values {
+            values.reduce(production.reduction_length);
+        }
         variable
     }
 }
@@ -589,10 +852,19 @@ pub struct LRkParser<'s, 't, 'a> {
     data: LRkParserData<'s, 'a>,
     /// The AST builder
     builder: LRkAstBuilder<'s, 't, 'a>,
+    /// A token read ahead by the error recovery and not yet parsed
+    pending: Option<TokenKernel>,
+    /// The sub-trees of a previous parse that may be reused
+    reuse: Option<LRkReuse>,
+    /// Whether the state of the parser is to be recorded, for the input fed piece by piece
+    recording: bool,
 }
 
 impl<'s, 't, 'a> LRkParser<'s, 't, 'a> {
     /// Initializes a new instance of the parser
+    /// When the AST holds the tree of a previous parse, its sub-trees are reused where possible.
+    /// This is only supported with a context-free lexer.
+    /// Note that the semantic actions are not executed for the reused sub-trees.
     pub fn new(
         lexer: &'a mut Lexer<'s, 't, 'a>,
         variables: &'a [Symbol<'s>],
@@ -601,19 +873,114 @@ impl<'s, 't, 'a> LRkParser<'s, 't, 'a> {
         ast: &'a mut AstImpl,
         actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
     ) -> LRkParser<'s, 't, 'a> {
-        LRkParser {
+        let context_free = matches!(lexer, Lexer::ContextFree(_));
+        let reuse = ast.take_previous().filter(|_| context_free).map(|mut tree| {
+            let mut subtrees = tree.take_subtrees();
+            subtrees.sort_by_key(|subtree| subtree.first);
+            LRkReuse { tree, subtrees }
+        });
+        let origins = (context_free && ast.is_recording()).then(Vec::new);
+        let checkpoint = ast.take_checkpoint();
+        ast.set_labels(automaton.get_labels());
+        let mut parser = LRkParser {
             data: LRkParserData {
                 automaton,
                 stack: alloc::vec![LRkHead { state: 0, identifier: 0 }],
+                origins,
                 variables,
                 actions,
+                values: None,
             },
             builder: LRkAstBuilder::<'s, 't, 'a>::new(lexer, variables, virtuals, ast),
+            pending: None,
+            reuse,
+            recording: checkpoint.is_some(),
+        };
+        if let Some(checkpoint) = checkpoint {
+            parser.resume(*checkpoint);
+        }
+        parser
+    }
+
+    /// Resumes the parsing from a state recorded by a previous parse, when the tokens before it are kept
+    fn resume(&mut self, checkpoint: LRkCheckpoint) {
+        // a state is recorded again by this parse
+        self.builder.result.set_checkpoint(LRkCheckpoint::default());
+        let data = self.builder.lexer.get_data_mut();
+        if !checkpoint.can_resume() || checkpoint.index > data.repository.get_tokens_count() {
+            return;
+        }
+        data.index = checkpoint.index;
+        self.data.stack = checkpoint.stack;
+        self.builder.stack = checkpoint.trees;
+    }
+
+    /// Records the state of the parser before the specified token, when more input may change it
+    /// The state is only recorded before the first such token and when no error occurred before,
+    /// so that the tokens before it are parsed in the same way whatever the input that follows.
+    fn record_checkpoint(&mut self, kernel: TokenKernel) {
+        let data = self.builder.lexer.get_data();
+        let index = kernel.index as usize;
+        if data.repository.get_reach(index) <= data.repository.text.len() {
+            return;
+        }
+        self.recording = false;
+        if self.pending.is_some() || !data.errors.errors.is_empty() {
+            return;
+        }
+        self.builder.result.set_checkpoint(LRkCheckpoint {
+            index,
+            stack: self.data.stack.clone(),
+            trees: self.builder.stack.clone(),
+        });
+    }
+
+    /// Sets the stack of the values to synthesize with the semantic actions
+    /// The semantic actions are then executed through the stack of values, instead of the actions given to the constructor.
+    #[must_use]
+    pub fn with_values(mut self, values: &'a mut dyn ValueStack) -> LRkParser<'s, 't, 'a> {
+        self.data.values = Some(values);
+        self
+    }
+
+    /// Sets the entry point to parse the input from
+    /// The entry 0 is the grammar's axiom, the next ones are its additional entry points, in the order of their declaration.
+    /// Returns `None` when the entry is not one of the grammar's entry points.
+    #[must_use]
+    pub fn with_entry(mut self, entry: usize) -> Option<LRkParser<'s, 't, 'a>> {
+        if entry >= get_entries_count(self.data.variables) {
+            return None;
+        }
+        self.data.stack[0].state = entry as u32;
+        Some(self)
+    }
+
+    /// Sets the budget bounding the work of the parser and of its lexer
+    /// The parsing stops when the budget is exhausted, or when it is cancelled.
+    #[must_use]
+    pub fn with_budget(self, budget: ParseBudget) -> LRkParser<'s, 't, 'a> {
+        self.builder.lexer.set_budget(budget);
+        self
+    }
+
+    /// Consumes a step of the budget before parsing on the specified token, if any
+    /// Returns whether the parsing can go on
+    fn consume_step(&mut self, kernel: Option<TokenKernel>) -> bool {
+        let data = self.builder.lexer.get_data_mut();
+        if data.budget.step() {
+            return true;
         }
+        let index = kernel
+            .and_then(|kernel| data.repository.get_token(kernel.index as usize).get_span())
+            .map_or(data.repository.text.len(), |span| span.index);
+        data.check_budget(index)
     }
 
     /// Gets the next token in the kernel
     fn get_next_token(&mut self) -> Option<TokenKernel> {
+        if let Some(kernel) = self.pending.take() {
+            return Some(kernel);
+        }
         let data = &self.data;
         self.builder.lexer.get_next_token(data)
     }
@@ -621,20 +988,11 @@ impl<'s, 't, 'a> LRkParser<'s, 't, 'a> {
     /// Builds the unexpected token error
     fn build_error(&self, kernel: TokenKernel) -> ParseErrorUnexpectedToken<'s> {
         let token = self.builder.lexer.get_data().repository.get_token(kernel.index as usize);
+        #[cfg(feature = "debug")]
         let state = self.data.stack[self.data.stack.len() - 1].state;
-        let expected_on_head = self
+        let my_expected = self
             .data
-            .automaton
-            .get_expected(state, self.builder.lexer.get_data().repository.terminals);
-        let mut my_expected = Vec::new();
-        for x in &expected_on_head.shifts {
-            my_expected.push(*x);
-        }
-        for x in &expected_on_head.reductions {
-            if self.data.check_is_expected(*x) {
-                my_expected.push(*x);
-            }
-        }
+            .get_expected_terminals(&self.data.stack, self.builder.lexer.get_data().repository.terminals);
         ParseErrorUnexpectedToken::new(
             token.get_position().unwrap(),
             token.get_span().unwrap().length,
@@ -645,33 +1003,235 @@ impl<'s, 't, 'a> LRkParser<'s, 't, 'a> {
             my_expected,
         )
     }
+
+    /// Reports an unexpected token and tries to recover from it
+    /// Returns the token to resume the parsing on, or `None` when the parsing shall stop
+    fn on_error(&mut self, kernel: TokenKernel) -> Option<TokenKernel> {
+        let error = self.build_error(kernel);
+        let errors = &mut *self.builder.lexer.get_data_mut().errors;
+        errors.push_error_unexpected_token(error);
+        if errors.errors.len() >= MAX_ERROR_COUNT {
+            return None;
+        }
+        self.recover(kernel)
+    }
+
+    /// Tries to recover from an unexpected token
+    /// Returns the token to resume the parsing on, or `None` when the error cannot be recovered
+    fn recover(&mut self, kernel: TokenKernel) -> Option<TokenKernel> {
+        if kernel.terminal_id == SID_DOLLAR {
+            // at the end of the input, try to complete it
+            let terminals = self.builder.lexer.get_data().repository.terminals;
+            for length in 1..=MAX_COMPLETION_LENGTH {
+                if let Some(completion) = self.data.find_completion(&self.data.stack, terminals, length) {
+                    for terminal in completion {
+                        self.insert_terminal(terminal);
+                    }
+                    return Some(kernel);
+                }
+            }
+            return self.recover_panic(kernel, None);
+        }
+        let next = self.get_next_token();
+        if let Some(next) = next {
+            if let Some(resume) = self.recover_phrase(kernel, next) {
+                return Some(resume);
+            }
+        }
+        self.recover_panic(kernel, next)
+    }
+
+    /// Tries a phrase-level recovery by inserting a terminal before the unexpected token,
+    /// deleting the unexpected token, or replacing it with an expected terminal
+    /// Each repair is only applied if the following token can then be parsed
+    fn recover_phrase(&mut self, kernel: TokenKernel, next: TokenKernel) -> Option<TokenKernel> {
+        let terminals = self.builder.lexer.get_data().repository.terminals;
+        let expected = self.data.get_expected_terminals(&self.data.stack, terminals);
+        // insertion of a missing terminal
+        for terminal in &expected {
+            if self
+                .data
+                .simulate(self.data.stack.clone(), &[terminal.id, kernel.terminal_id, next.terminal_id])
+            {
+                self.insert_terminal(*terminal);
+                self.pending = Some(next);
+                return Some(kernel);
+            }
+        }
+        // deletion of the unexpected token
+        if self.data.simulate(self.data.stack.clone(), &[next.terminal_id]) {
+            return Some(next);
+        }
+        // replacement of the unexpected token
+        for terminal in &expected {
+            if self.data.simulate(self.data.stack.clone(), &[terminal.id, next.terminal_id]) {
+                self.insert_terminal(*terminal);
+                return Some(next);
+            }
+        }
+        None
+    }
+
+    /// Tries a panic-mode recovery
+    /// The stack is popped down to a state that can parse the upcoming token,
+    /// while the tokens that cannot be parsed from any state in the stack are skipped.
+    fn recover_panic(&mut self, kernel: TokenKernel, next: Option<TokenKernel>) -> Option<TokenKernel> {
+        let mut current = Some(kernel);
+        let mut next = next;
+        while let Some(token) = current {
+            for length in (1..=self.data.stack.len()).rev() {
+                if self.data.simulate(self.data.stack[..length].to_vec(), &[token.terminal_id]) {
+                    self.data.stack.truncate(length);
+                    self.builder.pop_to(length - 1);
+                    if let Some(values) = self.data.values.as_deref_mut() {
+                        values.truncate(length - 1);
+                    }
+                    if let Some(origins) = self.data.origins.as_mut() {
+                        origins.truncate(length - 1);
+                    }
+                    self.pending = next;
+                    return Some(token);
+                }
+            }
+            // skip this token
+            current = next.take().or_else(|| self.get_next_token());
+        }
+        None
+    }
+
+    /// Tries to reuse a sub-tree of a previous parse after the shift of the specified token
+    /// The largest sub-tree of the previous parse that starts with the same token on the same state is reused,
+    /// provided that all its tokens and its lookahead are reused from the previous parse.
+    /// The sub-tree replaces the token on the stack and the parsing resumes on the lookahead.
+    fn reuse_subtree(&mut self, index: usize) {
+        let Some(reuse) = self.reuse.as_ref() else {
+            return;
+        };
+        let lexer = self.builder.lexer.get_data();
+        if lexer.index != index + 1 {
+            return;
+        }
+        let Some((previous, bound)) = lexer.repository.get_previous_index(index) else {
+            return;
+        };
+        let state = self.data.stack[self.data.stack.len() - 2].state;
+        let start = reuse.subtrees.partition_point(|subtree| subtree.first < previous);
+        let Some(subtree) = reuse.subtrees[start..]
+            .iter()
+            .take_while(|subtree| subtree.first == previous)
+            .filter(|subtree| subtree.state == state && subtree.lookahead < bound)
+            .max_by_key(|subtree| subtree.lookahead)
+        else {
+            return;
+        };
+        let errors = lexer.errors.errors.len();
+        let lookahead = subtree.lookahead - previous + index;
+        let remap = |label: TableElemRef| {
+            if label.table_type() == TableType::Token {
+                TableElemRef::new(TableType::Token, label.index() - previous + index)
+            } else {
+                label
+            }
+        };
+        let tree = subtree.tree.copy_from_previous(&reuse.tree, self.builder.result, &remap);
+        self.builder.result.count_reused();
+        // replace the shifted token by the sub-tree
+        let variable = self.data.variables[subtree.variable];
+        let action = self.data.automaton.get_action(state, variable.id);
+        self.data.stack.pop();
+        self.data.stack.push(LRkHead {
+            state: u32::from(action.get_data()),
+            identifier: variable.id,
+        });
+        if let Some(origins) = self.data.origins.as_mut() {
+            origins.pop();
+            origins.push(LRkOrigin {
+                token: Some(index),
+                errors,
+            });
+            self.builder.result.record(ReusableSubTree {
+                first: index,
+                lookahead,
+                state,
+                variable: subtree.variable,
+                tree: tree.clone(),
+            });
+        }
+        self.builder.stack.pop();
+        self.builder.stack.push(tree);
+        self.builder.lexer.get_data_mut().index = lookahead;
+    }
+
+    /// Parses the input up to the specified index in the text and gets the symbols expected there, for code completion
+    /// Only the tokens that end at or before the index are parsed,
+    /// so that the index of the start of a token being typed gives the symbols that may replace it.
+    /// The errors before the index are recovered as usual.
+    pub fn get_expected_at(&mut self, index: usize) -> LRCompletion<'s> {
+        // the sub-trees of a previous parse may span beyond the index
+        self.reuse = None;
+        let mut kernel_maybe = self.get_next_token();
+        while let Some(kernel) = kernel_maybe {
+            let repository = &self.builder.lexer.get_data().repository;
+            let end = repository
+                .get_token(kernel.index as usize)
+                .get_span()
+                .map_or(usize::MAX, |span| span.index + span.length);
+            if kernel.terminal_id == SID_DOLLAR || end > index {
+                break;
+            }
+            kernel_maybe = if self.data.parse_on_token(kernel, &mut self.builder) == LR_ACTION_CODE_SHIFT {
+                self.get_next_token()
+            } else {
+                self.on_error(kernel)
+            };
+        }
+        let terminals = self.builder.lexer.get_data().repository.terminals;
+        self.data.get_completion(&self.data.stack, terminals)
+    }
+
+    /// Inserts a terminal that is missing in the input
+    fn insert_terminal(&mut self, terminal: Symbol<'s>) {
+        let terminals = self.builder.lexer.get_data().repository.terminals;
+        let index = terminals.iter().position(|x| x.id == terminal.id).unwrap_or(0);
+        self.data
+            .parse_on_terminal(terminal.id, TableElemRef::new(TableType::None, index), &mut self.builder);
+    }
 }
 
 impl<'s, 't, 'a> Parser for LRkParser<'s, 't, 'a> {
     fn parse(&mut self) {
         let mut kernel_maybe = self.get_next_token();
         loop {
+            if !self.consume_step(kernel_maybe) {
+                return;
+            }
             match kernel_maybe {
                 None => {
-                    self.builder.commit_root();
+                    self.builder.commit_root(self.data.stack[0].state as usize);
                     return;
                 }
                 Some(kernel) => {
+                    if self.recording {
+                        self.record_checkpoint(kernel);
+                    }
                     let action = self.data.parse_on_token(kernel, &mut self.builder);
                     match action {
                         LR_ACTION_CODE_ACCEPT => {
-                            self.builder.commit_root();
+                            self.builder.commit_root(self.data.stack[0].state as usize);
                             return;
                         }
                         LR_ACTION_CODE_SHIFT => {
+                            if self.pending.is_none() {
+                                self.reuse_subtree(kernel.index as usize);
+                            }
                             kernel_maybe = self.get_next_token();
                         }
                         _ => {
                             // this is an error
-                            let error = self.build_error(kernel);
-                            self.builder.lexer.get_data_mut().errors.push_error_unexpected_token(error);
-                            // TODO: try to recover here
-                            return;
+                            match self.on_error(kernel) {
+                                None => return,
+                                Some(resume) => kernel_maybe = Some(resume),
+                            }
                         }
                     }
                 }
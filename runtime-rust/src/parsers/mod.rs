@@ -18,9 +18,12 @@
 //! Module for parsers API
 
 pub mod lrk;
+pub mod push;
 pub mod rnglr;
 pub mod subtree;
+pub mod values;
 
+use alloc::string::String;
 use alloc::vec::Vec;
 
 use crate::lexers::ContextProvider;
@@ -227,6 +230,18 @@ impl<'a> LRAction<'a> {
     }
 }
 
+/// The preference given to a production when resolving an ambiguity
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LRPreference {
+    /// No preference
+    #[default]
+    None,
+    /// The production is preferred over the others
+    Prefer,
+    /// The other productions are preferred over this one
+    Avoid,
+}
+
 /// Represents a rule's production in a LR parser
 /// The binary representation of a LR Production is as follow:
 /// --- header
@@ -236,6 +251,8 @@ impl<'a> LRAction<'a> {
 /// u8: bytecode length in number of op-code
 /// --- production's bytecode
 /// array of `LROpCode`
+/// The labels of the production's elements are read separately, see `read_labels`.
+/// The annotations used to resolve ambiguities are read separately, see `read_annotations`.
 #[derive(Clone)]
 pub struct LRProduction {
     /// Index of the rule's head in the parser's array of variables
@@ -246,6 +263,12 @@ pub struct LRProduction {
     pub reduction_length: usize,
     /// Bytecode for the rule's production
     pub bytecode: Vec<LROpCode>,
+    /// The labels of the elements, as pairs of the index of the op-code in the bytecode and the index of the label
+    pub labels: Vec<(usize, u16)>,
+    /// The priority of the production when resolving an ambiguity, higher priorities win
+    pub priority: u16,
+    /// The preference given to the production when resolving an ambiguity
+    pub preference: LRPreference,
 }
 
 impl LRProduction {
@@ -269,8 +292,79 @@ impl LRProduction {
             head_action,
             reduction_length,
             bytecode,
+            labels: Vec::new(),
+            priority: 0,
+            preference: LRPreference::None,
+        }
+    }
+
+    /// Gets the index of the label for the op-code at the given index in the bytecode, if any
+    #[must_use]
+    pub fn get_label_at(&self, op_index: usize) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(index, _)| *index == op_index)
+            .map(|(_, label)| *label)
+    }
+}
+
+/// Reads the labels of the productions' elements, if any
+/// The binary representation of the labels is an optional trailing section as follow:
+/// --- header
+/// u16: number of labels
+/// --- for each label
+/// u16: length in bytes
+/// the UTF-8 bytes of the label
+/// --- for each production
+/// u16: number of labelled op-codes
+/// array of pairs of u16 (index of the op-code in the bytecode) and u16 (index of the label)
+#[must_use]
+pub fn read_labels(data: &[u8], index: &mut usize, productions: &mut [LRProduction]) -> Vec<String> {
+    if *index >= data.len() {
+        return Vec::new();
+    }
+    let count = read_u16(data, *index) as usize;
+    *index += 2;
+    let mut labels = Vec::with_capacity(count);
+    for _i in 0..count {
+        let length = read_u16(data, *index) as usize;
+        *index += 2;
+        labels.push(String::from(
+            core::str::from_utf8(&data[*index..(*index + length)]).unwrap_or_default(),
+        ));
+        *index += length;
+    }
+    for production in productions {
+        let count = read_u16(data, *index) as usize;
+        *index += 2;
+        for _i in 0..count {
+            production
+                .labels
+                .push((read_u16(data, *index) as usize, read_u16(data, *index + 2)));
+            *index += 4;
         }
     }
+    labels
+}
+
+/// Reads the annotations of the productions used to resolve ambiguities, if any
+/// The binary representation of the annotations is an optional trailing section, after the labels, as follow:
+/// --- for each production
+/// u16: the priority of the production
+/// u8: the preference, 0=none, 1=prefer, 2=avoid
+pub fn read_annotations(data: &[u8], index: &mut usize, productions: &mut [LRProduction]) {
+    if *index >= data.len() {
+        return;
+    }
+    for production in productions {
+        production.priority = read_u16(data, *index);
+        production.preference = match data[*index + 2] {
+            1 => LRPreference::Prefer,
+            2 => LRPreference::Avoid,
+            _ => LRPreference::None,
+        };
+        *index += 3;
+    }
 }
 
 /// Container for the expected terminals for a LR state
@@ -313,6 +407,57 @@ impl<'s> LRExpected<'s> {
     }
 }
 
+/// The prefix of the names of the variables generated for the grammar
+const PREFIX_GENERATED_VARIABLE: &str = "__V";
+
+/// The name of the variable generated for the grammar axiom, i.e. `__VAxiom -> axiom $`
+/// The variables generated for the entry points immediately follow it.
+pub(crate) const GENERATED_AXIOM: &str = "__VAxiom";
+
+/// Gets the number of entry points for the specified variables, the grammar's axiom included
+pub(crate) fn get_entries_count(variables: &[Symbol]) -> usize {
+    let Some(axiom) = variables.iter().position(|variable| variable.name == GENERATED_AXIOM) else {
+        return 0;
+    };
+    1 + variables[axiom + 1..]
+        .iter()
+        .take_while(|variable| {
+            variable
+                .name
+                .strip_prefix(GENERATED_AXIOM)
+                .is_some_and(|rest| rest.starts_with('_'))
+        })
+        .count()
+}
+
+/// Container for the symbols expected at a position in the input, for code completion
+#[derive(Debug, Default, Clone)]
+pub struct LRCompletion<'s> {
+    /// The terminals that can be found at the position
+    pub terminals: Vec<Symbol<'s>>,
+    /// The variables that may start at the position, i.e. whose FIRST sets contain some of the terminals
+    /// The variables generated for the grammar are not included.
+    pub variables: Vec<Symbol<'s>>,
+    /// Whether the simulation of the parser was cut short, in which case some symbols may be missing
+    pub truncated: bool,
+}
+
+impl<'s> LRCompletion<'s> {
+    /// Adds the specified terminal, if not already present
+    pub fn add_terminal(&mut self, terminal: Symbol<'s>) {
+        if !self.terminals.contains(&terminal) {
+            self.terminals.push(terminal);
+        }
+    }
+
+    /// Adds the specified variable, if not already present and not generated
+    pub fn add_variable(&mut self, variable: Symbol<'s>) {
+        if !variable.name.starts_with(PREFIX_GENERATED_VARIABLE) && !self.variables.contains(&variable) {
+            self.variables.push(variable);
+        }
+    }
+}
+
 pub trait Parser {
     /// Parses the input
     fn parse(&mut self);
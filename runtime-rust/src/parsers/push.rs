@@ -0,0 +1,177 @@
+/*******************************************************************************
+ * Copyright (c) 2024 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for a push-style parser fed with the input piece by piece
+
+use alloc::boxed::Box;
+use core::mem::replace;
+
+use crate::ast::AstImpl;
+use crate::errors::{ParseError, ParseErrorDataTrait};
+use crate::lexers::automaton::{get_terminals_past_end, Automaton};
+use crate::result::ParseResult;
+use crate::symbols::{Symbol, SID_DOLLAR};
+
+/// The status of the input fed so far to a push parser
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PushStatus {
+    /// The input is a complete and valid parse
+    Complete,
+    /// The input is a valid prefix, more input is required to complete it
+    Prefix,
+    /// The input contains an error that more input cannot fix
+    Invalid,
+}
+
+/// The function parsing the input of a result into it
+type PushParseFn<'s, 'a> = dyn FnMut(ParseResult<'s, 'static, 'a, AstImpl>) -> ParseResult<'s, 'static, 'a, AstImpl> + 'a;
+
+/// A parser fed with the input piece by piece, as chunks of text or as tokens that have already been lexed
+/// Each piece is appended to the input, whose parsing resumes before the tokens that the piece may change.
+/// The tokens before them are kept, along with the stack of a LR(k) parser,
+/// so that only the last tokens are lexed and parsed again.
+/// The parsing starts over when an error occurred before these tokens, reusing the sub-trees for the chunks of text.
+/// RNGLR parsers always parse the whole input again.
+pub struct PushParser<'s, 'a> {
+    /// The result of the parsing of the input so far
+    result: ParseResult<'s, 'static, 'a, AstImpl>,
+    /// The function that parses a result
+    parse: Box<PushParseFn<'s, 'a>>,
+    /// The lexer's automaton, to find whether a token at the end may be extended
+    automaton: Automaton,
+    /// Whether the input ends with a token that has already been lexed
+    ends_with_token: bool,
+    /// The status of the input so far
+    status: PushStatus,
+}
+
+impl<'s, 'a> PushParser<'s, 'a> {
+    /// Initializes a new push parser, with an empty input
+    /// The parse function parses the input of a result into it, as the parsers generated for a grammar do.
+    pub fn new(
+        terminals: &'a [Symbol<'s>],
+        variables: &'a [Symbol<'s>],
+        virtuals: &'a [Symbol<'s>],
+        automaton: Automaton,
+        parse: impl FnMut(ParseResult<'s, 'static, 'a, AstImpl>) -> ParseResult<'s, 'static, 'a, AstImpl> + 'a,
+    ) -> PushParser<'s, 'a> {
+        let mut parse = Box::new(parse);
+        let result = parse(ParseResult::new_resumable(terminals, variables, virtuals));
+        let mut parser = PushParser {
+            result,
+            parse,
+            automaton,
+            ends_with_token: false,
+            status: PushStatus::Prefix,
+        };
+        parser.status = parser.compute_status();
+        parser
+    }
+
+    /// Appends a chunk of text to the input and gets the status of the input so far
+    /// The tokens that the lexer matched up to the end of the input are lexed again, as the chunk may extend them.
+    pub fn push_str(&mut self, chunk: &str) -> PushStatus {
+        if chunk.is_empty() {
+            return self.status;
+        }
+        let empty = self.empty_result();
+        let previous = replace(&mut self.result, empty);
+        self.result = (self.parse)(previous.append(chunk));
+        self.ends_with_token = false;
+        self.status = self.compute_status();
+        self.status
+    }
+
+    /// Appends a token that has already been lexed to the input and gets the status of the input so far
+    /// The token is given by the identifier of its terminal, as in a `TokenKernel`, and by its value in the input.
+    /// The tokens of the input so far are kept as they are, the lexer only matches the end of the input after them.
+    /// The separators between the tokens, if any, are expected to be pushed as tokens as well.
+    /// Pushing tokens is not supported for lexers with modes, whose input is always lexed again.
+    ///
+    /// Returns `PushStatus::Invalid` without changing the input when the terminal is not one of the grammar.
+    pub fn push_token(&mut self, terminal_id: u32, value: &str) -> PushStatus {
+        let Some(terminal) = self.result.terminals.iter().position(|symbol| symbol.id == terminal_id) else {
+            return PushStatus::Invalid;
+        };
+        let empty = self.empty_result();
+        let previous = replace(&mut self.result, empty);
+        self.result = (self.parse)(previous.append_token(terminal, value));
+        self.ends_with_token = true;
+        self.status = self.compute_status();
+        self.status
+    }
+
+    /// Gets the status of the input so far
+    #[must_use]
+    pub fn get_status(&self) -> PushStatus {
+        self.status
+    }
+
+    /// Gets the result of the parsing of the input so far
+    #[must_use]
+    pub fn get_result(&self) -> &ParseResult<'s, 'static, 'a, AstImpl> {
+        &self.result
+    }
+
+    /// Finalizes the parsing of the input so far into its result
+    #[must_use]
+    pub fn finish(self) -> ParseResult<'s, 'static, 'a, AstImpl> {
+        self.result
+    }
+
+    /// Gets an empty result for the same grammar
+    fn empty_result(&self) -> ParseResult<'s, 'static, 'a, AstImpl> {
+        ParseResult::new_resumable(self.result.terminals, self.result.variables, self.result.virtuals)
+    }
+
+    /// Gets the status of the input so far
+    /// An error at the end of the input may be fixed by more input when the text there may still be lexed as a longer token,
+    /// and when this token may be expected instead of the unexpected one.
+    fn compute_status(&self) -> PushStatus {
+        let result = &self.result;
+        if result.is_success() {
+            return PushStatus::Complete;
+        }
+        let end = result.text.len();
+        let fixable = result.errors.errors.iter().all(|error| match error {
+            ParseError::UnexpectedEndOfInput(_) => true,
+            // the end of the input was reached too early
+            ParseError::UnexpectedToken(error) if error.get_terminal().id == SID_DOLLAR => true,
+            // a stopped parsing cannot be completed
+            ParseError::BudgetExhausted(_) => false,
+            _ => {
+                let index = result.text.get_index_at(error.get_position());
+                if self.ends_with_token || index + error.get_length() < end {
+                    return false;
+                }
+                let terminals = get_terminals_past_end(&self.automaton, &result.text, index);
+                match error {
+                    ParseError::UnexpectedToken(error) => terminals.iter().any(|&terminal| {
+                        let id = result.terminals[terminal as usize].id;
+                        error.get_expected().iter().any(|expected| expected.id == id)
+                    }),
+                    _ => !terminals.is_empty(),
+                }
+            }
+        });
+        if fixable {
+            PushStatus::Prefix
+        } else {
+            PushStatus::Invalid
+        }
+    }
+}
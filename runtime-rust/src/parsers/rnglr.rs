@@ -17,24 +17,40 @@
 
 //! Module for RNGLR parsers
 
-use alloc::collections::VecDeque;
-use alloc::string::ToString;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 use super::{
-    get_op_code_base, get_op_code_tree_action, read_table_u16, read_u16, read_u32, ContextProvider, LRAction, LRColumnMap,
-    LRContexts, LRExpected, LRProduction, Parser, Symbol, TreeAction, LR_ACTION_CODE_ACCEPT, LR_ACTION_CODE_REDUCE,
-    LR_ACTION_CODE_SHIFT, LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE, LR_OP_CODE_BASE_ADD_VIRTUAL, LR_OP_CODE_BASE_SEMANTIC_ACTION,
-    TREE_ACTION_DROP, TREE_ACTION_PROMOTE, TREE_ACTION_REPLACE_BY_CHILDREN, TREE_ACTION_REPLACE_BY_EPSILON,
+    get_entries_count, get_op_code_base, get_op_code_tree_action, read_annotations, read_labels, read_table_u16, read_u16,
+    read_u32, ContextProvider, LRAction, LRColumnMap, LRCompletion, LRContexts, LRExpected, LRPreference, LRProduction, Parser,
+    Symbol, TreeAction, LR_ACTION_CODE_ACCEPT, LR_ACTION_CODE_REDUCE, LR_ACTION_CODE_SHIFT,
+    LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE, LR_OP_CODE_BASE_ADD_VIRTUAL, LR_OP_CODE_BASE_SEMANTIC_ACTION, MAX_ERROR_COUNT,
+    TREE_ACTION_DROP, TREE_ACTION_NONE, TREE_ACTION_PROMOTE, TREE_ACTION_REPLACE_BY_CHILDREN, TREE_ACTION_REPLACE_BY_EPSILON,
 };
 use crate::ast::{AstCell, AstImpl, TableElemRef, TableType};
+use crate::budget::ParseBudget;
 use crate::errors::ParseErrorUnexpectedToken;
 use crate::lexers::{Lexer, TokenKernel, DEFAULT_CONTEXT};
-use crate::sppf::{SppfImpl, SppfImplNodeRef, SppfImplNodeReplaceable, SppfImplNodeReplaceableVersion, SppfImplNodeVersions};
-use crate::symbols::{SemanticBody, SemanticElement, SemanticElementTrait, SID_EPSILON};
+use crate::sppf::{
+    SppfImpl, SppfImplAmbiguity, SppfImplNodeRef, SppfImplNodeReplaceable, SppfImplNodeReplaceableVersion, SppfImplNodeVersions,
+};
+use crate::symbols::{SemanticBody, SemanticElement, SemanticElementTrait, SID_DOLLAR, SID_EPSILON, SYMBOL_ERROR};
 use crate::utils::biglist::BigList;
 use crate::utils::OwnOrMut;
 
+/// The maximum number of terminals that can be inserted to complete the input when the recovery reaches its end
+const MAX_COMPLETION_LENGTH: usize = 3;
+
+/// The maximum number of steps when simulating the parsing of a terminal
+const MAX_SIMULATION_STEPS: usize = 1000;
+
+/// A function that resolves an ambiguity when building the AST from the SPPF
+/// It is given the SPPF, the ambiguous node and the indices of the node's versions that remain
+/// after applying the priorities and preferences of the productions, and returns the index of the chosen version.
+pub type AmbiguityResolver<'a> = dyn FnMut(&SppfImpl, SppfImplNodeRef, &[usize]) -> usize + 'a;
+
 /// Represents a cell in a RNGLR parse table
 #[derive(Copy, Clone)]
 struct RNGLRAutomatonCell {
@@ -65,6 +81,8 @@ pub struct RNGLRAutomaton {
     productions: Vec<LRProduction>,
     /// The table of nullable variables
     nullables: Vec<u16>,
+    /// The labels of the productions' elements
+    labels: Vec<String>,
 }
 
 impl RNGLRAutomaton {
@@ -113,8 +131,10 @@ impl RNGLRAutomaton {
         }
         // read the nullables table
         let nullables = read_table_u16(data, index, nullables_count);
-        // index += nullables_count * 2;
-        // assert_eq!(index, data.len());
+        index += nullables_count * 2;
+        // read the labels and the annotations, if any
+        let labels = read_labels(data, &mut index, &mut productions);
+        read_annotations(data, &mut index, &mut productions);
         RNGLRAutomaton {
             axiom: axiom_index,
             columns_count,
@@ -125,6 +145,7 @@ impl RNGLRAutomaton {
             table,
             productions,
             nullables,
+            labels,
         }
     }
 
@@ -200,6 +221,12 @@ impl RNGLRAutomaton {
         &self.productions[index]
     }
 
+    /// Gets the labels of the productions' elements
+    #[must_use]
+    pub fn get_labels(&self) -> &[String] {
+        &self.labels
+    }
+
     /// Gets the production for the nullable variable with the given index
     #[must_use]
     pub fn get_nullable_production(&self, index: usize) -> Option<&LRProduction> {
@@ -364,6 +391,17 @@ impl GSS {
         self.node_labels[node]
     }
 
+    /// Gets the total number of nodes in this GSS
+    pub fn get_nodes_count(&self) -> usize {
+        self.node_labels.len()
+    }
+
+    /// Gets the nodes in the specified generation
+    pub fn get_nodes(&self, generation: usize) -> Vec<usize> {
+        let data = self.node_generations[generation];
+        (data.start..(data.start + data.count)).collect()
+    }
+
     /// Finds in the given generation a node representing the given GLR state
     pub fn find_node(&self, generation: usize, state: u32) -> Option<usize> {
         let data = self.node_generations[generation];
@@ -382,6 +420,15 @@ impl GSS {
         None
     }
 
+    /// Gets all the edges starting from the given node
+    pub fn get_edges_from(&self, node: usize) -> Vec<GSSEdge> {
+        let data = self.edges_generations[self.get_generation_of(node)];
+        (data.start..(data.start + data.count))
+            .map(|i| self.edges[i])
+            .filter(|edge| edge.from as usize == node)
+            .collect()
+    }
+
     /// Opens a new generation in this GSS
     pub fn create_generation(&mut self) -> usize {
         self.node_generations.push(GSSGeneration {
@@ -519,6 +566,8 @@ struct SPPFReductionVersion {
     nodes: Vec<SppfImplNodeRef>,
     /// The actions for the reduction
     actions: Vec<TreeAction>,
+    /// The labels for the reduction
+    labels: Vec<Option<u16>>,
 }
 
 impl SPPFReductionVersion {
@@ -527,6 +576,7 @@ impl SPPFReductionVersion {
         Self {
             nodes: Vec::with_capacity(capacity),
             actions: Vec::with_capacity(capacity),
+            labels: Vec::with_capacity(capacity),
         }
     }
 }
@@ -572,8 +622,14 @@ struct SPPFBuilder<'s, 't, 'a, 'l> {
     replaceables: Vec<SppfImplNodeReplaceable>,
     /// The data of the current reductions
     reduction: Option<SPPFReduction>,
+    /// The elements skipped by the error recovery and not yet attached to the parse tree
+    skipped: Vec<SppfImplNodeRef>,
+    /// The error nodes attached to the leaf that follows them, ordered by leaf
+    errors: Vec<(SppfImplNodeRef, SppfImplNodeRef)>,
     /// The AST being built, if any
     ast: Option<&'a mut AstImpl>,
+    /// The user function resolving the ambiguities left by the productions' annotations, if any
+    resolver: Option<Box<AmbiguityResolver<'a>>>,
 }
 
 impl<'s, 't, 'a, 'l> SemanticBody for SPPFBuilder<'s, 't, 'a, 'l> {
@@ -585,7 +641,8 @@ impl<'s, 't, 'a, 'l> SemanticBody for SPPFBuilder<'s, 't, 'a, 'l> {
             TableType::Token => SemanticElement::Token(self.lexer.get_data().repository.get_token(label.index())),
             TableType::Variable => SemanticElement::Variable(self.variables[label.index()]),
             TableType::Virtual => SemanticElement::Virtual(self.virtuals[label.index()]),
-            TableType::None => SemanticElement::Terminal(self.lexer.get_data().repository.terminals[0]),
+            TableType::None if label.is_error() => SemanticElement::Terminal(SYMBOL_ERROR),
+            TableType::None => SemanticElement::Terminal(self.lexer.get_data().repository.terminals[label.index()]),
         }
     }
 
@@ -609,7 +666,10 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             sppf: OwnOrMut::Owned(SppfImpl::default()),
             replaceables: Vec::new(),
             reduction: None,
+            skipped: Vec::new(),
+            errors: Vec::new(),
             ast: Some(ast),
+            resolver: None,
         }
     }
 
@@ -627,7 +687,10 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             sppf: OwnOrMut::MutRef(sppf),
             replaceables: Vec::new(),
             reduction: None,
+            skipped: Vec::new(),
+            errors: Vec::new(),
             ast: None,
+            resolver: None,
         }
     }
 
@@ -636,6 +699,43 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
         self.sppf.new_normal_node(symbol)
     }
 
+    /// Creates the node for a shifted leaf in the result SPPF and returns it
+    /// The elements skipped by the error recovery so far are gathered into an error node attached to this leaf
+    pub fn get_leaf_node(&mut self, symbol: TableElemRef) -> SppfImplNodeRef {
+        let leaf = self.sppf.new_normal_node(symbol);
+        if !self.skipped.is_empty() {
+            let error = self
+                .sppf
+                .new_normal_node_with_children(TableElemRef::new_error(), None, &self.skipped, &[]);
+            self.skipped.clear();
+            self.errors.push((leaf, error));
+        }
+        leaf
+    }
+
+    /// Gets the error node attached to a leaf, if any
+    fn get_attached_error(errors: &[(SppfImplNodeRef, SppfImplNodeRef)], leaf: SppfImplNodeRef) -> Option<SppfImplNodeRef> {
+        errors
+            .binary_search_by_key(&leaf.node_id, |(candidate, _)| candidate.node_id)
+            .ok()
+            .map(|index| errors[index].1)
+    }
+
+    /// Adds an element skipped by the error recovery
+    pub fn skip(&mut self, sppf_node_ref: SppfImplNodeRef) {
+        if sppf_node_ref.is_replaceable() {
+            let children = self.replaceables[sppf_node_ref.node_id()].versions.first().children.clone();
+            for child in children {
+                self.skip(child);
+            }
+            return;
+        }
+        if let Some(error) = Self::get_attached_error(&self.errors, sppf_node_ref) {
+            self.skipped.extend(self.sppf.get_node(error).first_version().children.iter());
+        }
+        self.skipped.push(sppf_node_ref);
+    }
+
     /// Prepares for the forthcoming reduction operations
     pub fn reduction_prepare(&mut self, first: GSSLabel, path: &GSSPath, length: usize) {
         let mut stack = Vec::new();
@@ -654,13 +754,22 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
         });
     }
 
-    /// Adds the specified GSS label to the reduction cache with the given tree action
+    /// Adds the specified GSS label to the reduction cache with the given tree action and label
+    /// The children of a replaceable sub-tree without a label of their own inherit the label of the element.
     fn reduction_add_to_cache(
         reduction: &mut SPPFReduction,
         replaceables: &[SppfImplNodeReplaceable],
+        errors: &[(SppfImplNodeRef, SppfImplNodeRef)],
         sppf_node_ref: SppfImplNodeRef,
         action: TreeAction,
+        label: Option<u16>,
     ) {
+        if !errors.is_empty() {
+            if let Some(error) = SPPFBuilder::get_attached_error(errors, sppf_node_ref) {
+                // the error node that precedes the leaf is kept, whatever the action on the leaf
+                SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, error, TREE_ACTION_NONE, None);
+            }
+        }
         if action == TREE_ACTION_DROP {
             return;
         }
@@ -670,53 +779,68 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             let replaceable_versions_count = replaceable.versions.len();
             if replaceable_versions_count == 1 {
                 let version = replaceable.versions.first();
-                for (&node_ref, &action) in version.children.iter().zip(&version.actions) {
-                    SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, node_ref, action);
+                for ((&node_ref, &action), &child_label) in version.children.iter().zip(&version.actions).zip(&version.labels) {
+                    SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, node_ref, action, child_label.or(label));
                 }
             } else {
                 // compute the cross-product
                 let l = reduction.duplicate_versions(replaceable_versions_count - 1);
                 for (index, version) in replaceable.versions.into_iter().enumerate() {
                     let targets = &mut reduction.versions[(index * l)..((index + 1) * l)];
-                    for (&node_ref, &action) in version.children.iter().zip(&version.actions) {
-                        SPPFBuilder::reduction_add_to_cache_node(targets, node_ref, action);
+                    for ((&node_ref, &action), &child_label) in
+                        version.children.iter().zip(&version.actions).zip(&version.labels)
+                    {
+                        SPPFBuilder::reduction_add_to_cache_node(targets, node_ref, action, child_label.or(label));
                     }
                 }
             }
         } else {
             // this is a simple reference to an existing SPPF node
-            SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, sppf_node_ref, action);
+            SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, sppf_node_ref, action, label);
         }
     }
 
-    /// Adds the specified GSS label to the reduction cache with the given tree action
-    fn reduction_add_to_cache_node(versions: &mut [SPPFReductionVersion], sppf_node_ref: SppfImplNodeRef, action: TreeAction) {
+    /// Adds the specified GSS label to the reduction cache with the given tree action and label
+    fn reduction_add_to_cache_node(
+        versions: &mut [SPPFReductionVersion],
+        sppf_node_ref: SppfImplNodeRef,
+        action: TreeAction,
+        label: Option<u16>,
+    ) {
         // add the node in the cache for each version
         for version in versions {
             version.nodes.push(sppf_node_ref);
             version.actions.push(action);
+            version.labels.push(label);
         }
     }
 
-    /// During a reduction, pops the top symbol from the stack and gives it a tree action
-    pub fn reduction_pop(&mut self, action: TreeAction) {
+    /// During a reduction, pops the top symbol from the stack and gives it a tree action and a label
+    pub fn reduction_pop(&mut self, action: TreeAction, label: Option<u16>) {
         let reduction = self.reduction.as_mut().expect("Not in a reduction");
-        let label = reduction.stack[reduction.pop_count];
+        let gss_label = reduction.stack[reduction.pop_count];
         reduction.pop_count += 1;
-        SPPFBuilder::reduction_add_to_cache(reduction, &self.replaceables, label.sppf_node, action);
+        SPPFBuilder::reduction_add_to_cache(
+            reduction,
+            &self.replaceables,
+            &self.errors,
+            gss_label.sppf_node,
+            action,
+            label,
+        );
     }
 
     /// During a reduction, inserts a virtual symbol
-    pub fn reduction_add_virtual(&mut self, index: usize, action: TreeAction) {
+    pub fn reduction_add_virtual(&mut self, index: usize, action: TreeAction, label: Option<u16>) {
         let reduction = self.reduction.as_mut().expect("Not in a reduction");
         let sppf_node_ref = self.sppf.new_normal_node(TableElemRef::new(TableType::Virtual, index));
-        SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, sppf_node_ref, action);
+        SPPFBuilder::reduction_add_to_cache_node(&mut reduction.versions, sppf_node_ref, action, label);
     }
 
     /// During a reduction, inserts the sub-tree of a nullable variable
-    pub fn reduction_add_nullable(&mut self, nullable: SppfImplNodeRef, action: TreeAction) {
+    pub fn reduction_add_nullable(&mut self, nullable: SppfImplNodeRef, action: TreeAction, label: Option<u16>) {
         let reduction = self.reduction.as_mut().expect("Not in a reduction");
-        SPPFBuilder::reduction_add_to_cache(reduction, &self.replaceables, nullable, action);
+        SPPFBuilder::reduction_add_to_cache(reduction, &self.replaceables, &self.errors, nullable, action, label);
     }
 
     /// Finalizes the reduction operation
@@ -724,6 +848,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
     pub fn reduce(
         &mut self,
         variable_index: usize,
+        production: usize,
         head_action: TreeAction,
         target: Option<SppfImplNodeRef>,
     ) -> SppfImplNodeRef {
@@ -732,7 +857,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
         } else {
             #[cfg(all(feature = "std", feature = "debug"))]
             self.reduce_normal_pre_print(variable_index, head_action, target);
-            let result = self.reduce_normal(variable_index, head_action, target);
+            let result = self.reduce_normal(variable_index, production, head_action, target);
             #[cfg(all(feature = "std", feature = "debug"))]
             self.reduce_normal_post_print(result);
             result
@@ -808,6 +933,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             TableType::Token => self.lexer.get_data().repository.get_token(label.index()).get_symbol().name,
             TableType::Variable => self.variables[label.index()].name,
             TableType::Virtual => self.virtuals[label.index()].name,
+            TableType::None if label.is_error() => SYMBOL_ERROR.name,
             TableType::None => "ε",
         }
     }
@@ -816,23 +942,33 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
     pub fn reduce_normal(
         &mut self,
         variable_index: usize,
+        production: usize,
         head_action: TreeAction,
         target: Option<SppfImplNodeRef>,
     ) -> SppfImplNodeRef {
         let reduction = self.reduction.as_mut().expect("not in a reduction");
         let sppf = &mut self.sppf;
-        let first = Self::reduce_normal_version(&mut reduction.versions[0], sppf, variable_index, head_action, target);
+        let first = Self::reduce_normal_version(
+            &mut reduction.versions[0],
+            sppf,
+            variable_index,
+            production,
+            head_action,
+            target,
+        );
         for version in reduction.versions.iter_mut().skip(1) {
-            Self::reduce_normal_version(version, sppf, variable_index, head_action, Some(first));
+            Self::reduce_normal_version(version, sppf, variable_index, production, head_action, Some(first));
         }
         first
     }
 
     /// Executes the reduction as a normal reduction
+    /// A promoted node keeps its own production.
     pub fn reduce_normal_version(
         version: &mut SPPFReductionVersion,
         sppf: &mut SppfImpl,
         variable_index: usize,
+        production: usize,
         head_action: TreeAction,
         target: Option<SppfImplNodeRef>,
     ) -> SppfImplNodeRef {
@@ -846,12 +982,16 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
                     // not the first promotion, materialize the previous promotion
                     let promoted_ref = sppf.new_promoted_node(
                         promoted_ref,
-                        &version.nodes[b..promoted_index],
-                        &version.nodes[(promoted_index + 1)..e],
+                        (&version.nodes[b..promoted_index], &version.labels[b..promoted_index]),
+                        (
+                            &version.nodes[(promoted_index + 1)..e],
+                            &version.labels[(promoted_index + 1)..e],
+                        ),
                     );
                     // repack in the cache
                     b = e - 1;
                     version.nodes[b] = promoted_ref;
+                    version.labels[b] = version.labels[promoted_index];
                 }
                 // register the promotion
                 promoted = Some((e, version.nodes[e]));
@@ -859,35 +999,46 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             e += 1;
         }
 
-        let original_label = if head_action == TREE_ACTION_REPLACE_BY_EPSILON {
-            TableElemRef::new(TableType::None, 0)
+        let (original_label, production) = if head_action == TREE_ACTION_REPLACE_BY_EPSILON {
+            (TableElemRef::new(TableType::None, 0), None)
         } else {
-            TableElemRef::new(TableType::Variable, variable_index)
+            (
+                TableElemRef::new(TableType::Variable, variable_index),
+                Some(production as u16),
+            )
         };
         match (target, promoted) {
             (None, None) => {
                 // no target for a new version, no promotion
-                sppf.new_normal_node_with_children(original_label, &version.nodes)
+                sppf.new_normal_node_with_children(original_label, production, &version.nodes, &version.labels)
             }
             (Some(target), None) => {
                 // new version of an existing node, no promotion
-                let _version = sppf.get_node_mut(target).add_version(original_label, &version.nodes);
+                let _version =
+                    sppf.get_node_mut(target)
+                        .add_version(original_label, production, &version.nodes, &version.labels);
                 target
             }
             (None, Some((promoted_index, promoted_ref))) => {
                 // no target for a new version, got a promotion
                 sppf.new_promoted_node(
                     promoted_ref,
-                    &version.nodes[b..promoted_index],
-                    &version.nodes[(promoted_index + 1)..],
+                    (&version.nodes[b..promoted_index], &version.labels[b..promoted_index]),
+                    (
+                        &version.nodes[(promoted_index + 1)..],
+                        &version.labels[(promoted_index + 1)..],
+                    ),
                 )
             }
             (Some(target), Some((promoted_index, promoted_ref))) => {
                 // new version of an existing node, got a promotion
                 let promoted = sppf.create_promoted_node(
                     promoted_ref,
-                    &version.nodes[b..promoted_index],
-                    &version.nodes[(promoted_index + 1)..],
+                    (&version.nodes[b..promoted_index], &version.labels[b..promoted_index]),
+                    (
+                        &version.nodes[(promoted_index + 1)..],
+                        &version.labels[(promoted_index + 1)..],
+                    ),
                 );
                 sppf.get_node_mut(target).add_versions(promoted.versions);
                 target
@@ -903,7 +1054,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
             assert!(target.is_replaceable());
             let node = &mut self.replaceables[target.node_id()];
             for version in &reduction.versions {
-                node.add_version(label, &version.nodes, &version.actions);
+                node.add_version(label, &version.nodes, &version.actions, &version.labels);
             }
             target
         } else {
@@ -914,6 +1065,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
                     versions: SppfImplNodeVersions::Single(SppfImplNodeReplaceableVersion {
                         children: version.nodes,
                         actions: version.actions,
+                        labels: version.labels,
                         label,
                     }),
                 });
@@ -926,6 +1078,7 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
                             .map(|version| SppfImplNodeReplaceableVersion {
                                 children: version.nodes,
                                 actions: version.actions,
+                                labels: version.labels,
                                 label,
                             })
                             .collect(),
@@ -937,37 +1090,84 @@ impl<'s, 't, 'a, 'l> SPPFBuilder<'s, 't, 'a, 'l> {
     }
 
     /// Finalizes the parse tree
-    pub fn commit_root(&mut self, root: SppfImplNodeRef) {
+    /// The error node attached to the final dollar leaf, if any, is appended to the root
+    pub fn commit_root(&mut self, root: SppfImplNodeRef, dollar: SppfImplNodeRef, productions: &[LRProduction]) {
+        if let Some(error) = Self::get_attached_error(&self.errors, dollar) {
+            self.sppf.get_node_mut(root).add_tail(&[error]);
+        }
         self.sppf.store_root(root);
         if let Some(ast) = self.ast.as_mut() {
             let sppf = &self.sppf;
-            let cell_root = Self::build_final_ast(sppf, root, ast);
+            let ambiguities = sppf.find_ambiguities(&self.lexer.get_data().repository);
+            ast.set_ambiguities(ambiguities.iter().map(SppfImplAmbiguity::to_ast).collect());
+            let mut resolver = self.resolver.as_deref_mut();
+            let cell_root = Self::build_final_ast(sppf, root, ast, productions, &mut resolver);
             ast.store_root(cell_root);
         }
     }
 
-    /// Builds thSe final AST for the specified SPPF node reference
-    fn build_final_ast(sppf: &SppfImpl, sppf_node_ref: SppfImplNodeRef, result: &mut AstImpl) -> AstCell {
+    /// Chooses the version of an SPPF node to keep in the final AST
+    /// The versions from the productions with the highest priority are kept first.
+    /// Among them, the preferred versions are kept if any, otherwise the avoided ones are dropped if possible.
+    /// The remaining ambiguity, if any, is resolved by the user function or by taking the first version.
+    fn resolve_version(
+        sppf: &SppfImpl,
+        sppf_node_ref: SppfImplNodeRef,
+        productions: &[LRProduction],
+        resolver: &mut Option<&mut AmbiguityResolver<'a>>,
+    ) -> usize {
         let node = sppf.get_node(sppf_node_ref);
-        let version = &node.versions[0];
-        if version.children.is_empty() {
-            AstCell {
-                label: version.label,
-                first: 0,
-                count: 0,
+        if node.versions.len() == 1 {
+            return 0;
+        }
+        let get_production = |index: usize| node.versions[index].production.map(|p| &productions[p as usize]);
+        let get_priority = |index: usize| get_production(index).map_or(0, |production| production.priority);
+        let get_preference =
+            |index: usize| get_production(index).map_or(LRPreference::None, |production| production.preference);
+        let best = (0..node.versions.len()).map(get_priority).max().unwrap_or_default();
+        let mut candidates: Vec<usize> = (0..node.versions.len())
+            .filter(|&index| get_priority(index) == best)
+            .collect();
+        if candidates.iter().any(|&index| get_preference(index) == LRPreference::Prefer) {
+            candidates.retain(|&index| get_preference(index) == LRPreference::Prefer);
+        } else if candidates.iter().any(|&index| get_preference(index) != LRPreference::Avoid) {
+            candidates.retain(|&index| get_preference(index) != LRPreference::Avoid);
+        }
+        if candidates.len() > 1 {
+            if let Some(resolver) = resolver.as_mut() {
+                let chosen = resolver(sppf, sppf_node_ref, &candidates);
+                if candidates.contains(&chosen) {
+                    return chosen;
+                }
             }
+        }
+        candidates[0]
+    }
+
+    /// Builds the final AST for the specified SPPF node reference
+    fn build_final_ast(
+        sppf: &SppfImpl,
+        sppf_node_ref: SppfImplNodeRef,
+        result: &mut AstImpl,
+        productions: &[LRProduction],
+        resolver: &mut Option<&mut AmbiguityResolver<'a>>,
+    ) -> AstCell {
+        let node = sppf.get_node(sppf_node_ref);
+        let version = &node.versions[Self::resolve_version(sppf, sppf_node_ref, productions, resolver)];
+        let mut cell = if version.children.is_empty() {
+            AstCell::new_empty(version.label)
         } else {
             let mut buffer = Vec::with_capacity(version.children.len());
-            for child in &version.children {
-                buffer.push(SPPFBuilder::build_final_ast(sppf, child, result));
+            for (index, child) in version.children.iter().enumerate() {
+                let mut cell = SPPFBuilder::build_final_ast(sppf, child, result, productions, resolver);
+                cell.element_label = version.get_child_label(index);
+                buffer.push(cell);
             }
             let first = result.store(&buffer, 0, buffer.len());
-            AstCell {
-                label: version.label,
-                first: first as u32,
-                count: version.children.len() as u32,
-            }
-        }
+            AstCell::new(version.label, version.children.len() as u32, first as u32)
+        };
+        cell.production = version.production;
+        cell
     }
 }
 
@@ -995,6 +1195,15 @@ struct RNGLRShift {
     to: usize,
 }
 
+/// Represents a configuration of a simulated parser on top of a GSS node
+#[derive(Clone, PartialEq, Eq)]
+struct RNGLRSimulation {
+    /// The GSS node below the simulated stack
+    gss_node: usize,
+    /// The simulated stack of RNGLR states on top of the GSS node
+    stack: Vec<u32>,
+}
+
 struct RNGLRParserData<'s, 'a> {
     /// The parser's automaton
     automaton: RNGLRAutomaton,
@@ -1010,6 +1219,8 @@ struct RNGLRParserData<'s, 'a> {
     variables: &'a [Symbol<'s>],
     /// The semantic actions
     actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+    /// The start state, for the entry point to parse the input from
+    start: u32,
 }
 
 impl<'s, 'a> ContextProvider for RNGLRParserData<'s, 'a> {
@@ -1024,7 +1235,7 @@ impl<'s, 'a> ContextProvider for RNGLRParserData<'s, 'a> {
         }
         if token_count == 0 {
             // this is the first token, does it open the context?
-            let contexts = self.automaton.get_contexts(0);
+            let contexts = self.automaton.get_contexts(self.start);
             return if contexts.opens(terminal_id, context) { Some(0) } else { None };
         }
 
@@ -1196,6 +1407,17 @@ impl<'s, 'a> ContextProvider for RNGLRParserData<'s, 'a> {
         // the context is still unavailable
         None
     }
+
+    /// Gets whether the specified terminal is acceptable for the next token
+    #[allow(clippy::cast_possible_truncation)]
+    fn is_acceptable(&self, token_count: usize, terminal_id: u32) -> bool {
+        if token_count == 0 {
+            return self.automaton.get_actions_count(self.start, terminal_id) > 0;
+        }
+        self.shifts
+            .iter()
+            .any(|shift| self.automaton.get_actions_count(shift.to as u32, terminal_id) > 0)
+    }
 }
 
 impl<'s, 'a> RNGLRParserData<'s, 'a> {
@@ -1305,6 +1527,214 @@ impl<'s, 'a> RNGLRParserData<'s, 'a> {
         false
     }
 
+    /// Gets the symbols expected on the nodes of the specified generation, for code completion
+    fn get_completion(&self, generation: usize, terminals: &[Symbol<'s>]) -> LRCompletion<'s> {
+        let mut completion = LRCompletion::default();
+        for node in self.gss.get_nodes(generation) {
+            let expected = self.automaton.get_expected(self.gss.get_represented_state(node), terminals);
+            let reductions = expected
+                .reductions
+                .into_iter()
+                .filter(|terminal| self.check_is_expected(node, *terminal));
+            for terminal in expected.shifts.into_iter().chain(reductions) {
+                if terminal.id == SID_EPSILON || terminal.id == SID_DOLLAR {
+                    continue;
+                }
+                completion.add_terminal(terminal);
+                // the variables that may start here have a transition from the states on which the terminal is shifted
+                let configuration = RNGLRSimulation {
+                    gss_node: node,
+                    stack: Vec::new(),
+                };
+                let (nexts, truncated) = self.simulate_shift(&[configuration], terminal.id);
+                completion.truncated |= truncated;
+                for next in nexts {
+                    let state = if next.stack.len() >= 2 {
+                        next.stack[next.stack.len() - 2]
+                    } else {
+                        self.gss.get_represented_state(next.gss_node)
+                    };
+                    for variable in self.variables {
+                        if self.get_next_by_var(state, variable.id).is_some() {
+                            completion.add_variable(*variable);
+                        }
+                    }
+                }
+            }
+        }
+        completion
+    }
+
+    /// Simulates the shift of a terminal on the specified configurations
+    /// Returns the resulting configurations, and whether the simulation was cut short after too many steps,
+    /// in which case some resulting configurations may be missing.
+    fn simulate_shift(&self, configurations: &[RNGLRSimulation], terminal_id: u32) -> (Vec<RNGLRSimulation>, bool) {
+        let mut queue = configurations.to_vec();
+        let mut result = Vec::new();
+        let mut steps = 0;
+        while let Some(current) = queue.pop() {
+            steps += 1;
+            if steps > MAX_SIMULATION_STEPS {
+                return (result, true);
+            }
+            let head = current
+                .stack
+                .last()
+                .copied()
+                .unwrap_or_else(|| self.gss.get_represented_state(current.gss_node));
+            let count = self.automaton.get_actions_count(head, terminal_id);
+            for i in 0..count {
+                let action = self.automaton.get_action(head, terminal_id, i);
+                match action.get_code() {
+                    LR_ACTION_CODE_SHIFT => {
+                        let mut next = current.clone();
+                        next.stack.push(u32::from(action.get_data()));
+                        if !result.contains(&next) {
+                            result.push(next);
+                        }
+                    }
+                    LR_ACTION_CODE_ACCEPT if !result.contains(&current) => {
+                        result.push(current.clone());
+                    }
+                    LR_ACTION_CODE_REDUCE => {
+                        let production = self.automaton.get_production(action.get_data() as usize);
+                        let variable_id = self.variables[production.head].id;
+                        if production.reduction_length <= current.stack.len() {
+                            // we are still in the simulated stack
+                            let mut stack = current.stack.clone();
+                            stack.truncate(current.stack.len() - production.reduction_length);
+                            let top = stack
+                                .last()
+                                .copied()
+                                .unwrap_or_else(|| self.gss.get_represented_state(current.gss_node));
+                            if let Some(next) = self.get_next_by_var(top, variable_id) {
+                                stack.push(next);
+                                queue.push(RNGLRSimulation {
+                                    gss_node: current.gss_node,
+                                    stack,
+                                });
+                            }
+                        } else {
+                            // we reach the GSS
+                            let paths = self
+                                .gss
+                                .get_paths(current.gss_node, production.reduction_length - current.stack.len());
+                            for path in &paths {
+                                let top = self.gss.get_represented_state(path.last_node);
+                                if let Some(next) = self.get_next_by_var(top, variable_id) {
+                                    queue.push(RNGLRSimulation {
+                                        gss_node: path.last_node,
+                                        stack: alloc::vec![next],
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        (result, false)
+    }
+
+    /// Simulates the parsing of a sequence of terminals from the specified GSS node
+    /// Returns whether all the terminals can be shifted
+    /// A simulation cut short after too many steps only succeeds when some configurations remain.
+    fn simulate(&self, gss_node: usize, terminals: &[u32]) -> bool {
+        let mut configurations = alloc::vec![RNGLRSimulation {
+            gss_node,
+            stack: Vec::new(),
+        }];
+        for &terminal_id in terminals {
+            configurations = self.simulate_shift(&configurations, terminal_id).0;
+            if configurations.is_empty() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Gets the terminals expected by the automaton on the tops of the specified configurations
+    fn get_simulated_expected(&self, configurations: &[RNGLRSimulation], terminals: &[Symbol<'s>]) -> Vec<Symbol<'s>> {
+        let mut result: Vec<Symbol<'s>> = Vec::new();
+        for configuration in configurations {
+            let head = configuration
+                .stack
+                .last()
+                .copied()
+                .unwrap_or_else(|| self.gss.get_represented_state(configuration.gss_node));
+            let expected = self.automaton.get_expected(head, terminals);
+            for symbol in expected.shifts.into_iter().chain(expected.reductions) {
+                if symbol.id != SID_EPSILON && symbol.id != SID_DOLLAR && !result.contains(&symbol) {
+                    result.push(symbol);
+                }
+            }
+        }
+        result
+    }
+
+    /// Finds a sequence of exactly `length` terminals that completes the input from the specified configurations
+    /// Only the terminals expected on the configurations are tried.
+    fn find_completion(
+        &self,
+        configurations: &[RNGLRSimulation],
+        terminals: &[Symbol<'s>],
+        prefix: &[Symbol<'s>],
+        length: usize,
+    ) -> Option<Vec<Symbol<'s>>> {
+        for terminal in self.get_simulated_expected(configurations, terminals) {
+            let (nexts, _) = self.simulate_shift(configurations, terminal.id);
+            if nexts.is_empty() {
+                continue;
+            }
+            let mut sequence = prefix.to_vec();
+            sequence.push(terminal);
+            if sequence.len() == length {
+                if !self.simulate_shift(&nexts, SID_DOLLAR).0.is_empty() {
+                    return Some(sequence);
+                }
+            } else if let Some(completion) = self.find_completion(&nexts, terminals, &sequence, length) {
+                return Some(completion);
+            }
+        }
+        None
+    }
+
+    /// Queues the shifts and reductions on the next token for a GSS node
+    fn queue_actions(&mut self, node: usize) {
+        let state = self.gss.get_represented_state(node);
+        let edges = self.gss.get_edges_from(node);
+        let count = self.automaton.get_actions_count(state, self.get_next_token_id());
+        for i in 0..count {
+            let action = self.automaton.get_action(state, self.get_next_token_id(), i);
+            if action.get_code() == LR_ACTION_CODE_SHIFT {
+                self.shifts.push_back(RNGLRShift {
+                    from: node,
+                    to: action.get_data() as usize,
+                });
+            } else if action.get_code() == LR_ACTION_CODE_REDUCE {
+                let production = self.automaton.get_production(action.get_data() as usize);
+                if production.reduction_length == 0 {
+                    // Length 0 => reduce from the head
+                    self.reductions.push_back(RNGLRReduction {
+                        node,
+                        production: action.get_data() as usize,
+                        first: EPSILON,
+                    });
+                } else {
+                    // reduce from the second node on the paths
+                    for edge in &edges {
+                        self.reductions.push_back(RNGLRReduction {
+                            node: edge.to as usize,
+                            production: action.get_data() as usize,
+                            first: edge.label,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
     /// Gets the next RNGLR state by a shift with the given variable ID
     fn get_next_by_var(&self, state: u32, variable_id: u32) -> Option<u32> {
         let count = self.automaton.get_actions_count(state, variable_id);
@@ -1383,6 +1813,8 @@ pub struct RNGLRParser<'s, 't, 'a, 'l> {
     builder: SPPFBuilder<'s, 't, 'a, 'l>,
     /// The sub-trees for the constant nullable variables
     nullables: Vec<usize>,
+    /// A token read ahead by the error recovery and not yet parsed
+    pending: Option<TokenKernel>,
 }
 
 impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
@@ -1395,6 +1827,9 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
         ast: &'a mut AstImpl,
         actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
     ) -> RNGLRParser<'s, 't, 'a, 'l> {
+        // only the tokens of a previous parse are reused
+        ast.take_previous();
+        ast.set_labels(automaton.get_labels());
         let mut parser = RNGLRParser {
             data: RNGLRParserData {
                 automaton,
@@ -1404,9 +1839,11 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
                 shifts: VecDeque::new(),
                 variables,
                 actions,
+                start: 0,
             },
             builder: SPPFBuilder::new_ast(lexer, variables, virtuals, ast),
             nullables: alloc::vec![0xFFFF_FFFF ; variables.len()],
+            pending: None,
         };
         RNGLRParser::build_nullables(
             &mut parser.builder,
@@ -1436,9 +1873,11 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
                 shifts: VecDeque::new(),
                 variables,
                 actions,
+                start: 0,
             },
             builder: SPPFBuilder::new_sppf(lexer, variables, virtuals, sppf),
             nullables: alloc::vec![0xFFFF_FFFF ; variables.len()],
+            pending: None,
         };
         RNGLRParser::build_nullables(
             &mut parser.builder,
@@ -1450,6 +1889,130 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
         parser
     }
 
+    /// Sets the entry point to parse the input from
+    /// The entry 0 is the grammar's axiom, the next ones are its additional entry points, in the order of their declaration.
+    /// Returns `None` when the entry is not one of the grammar's entry points.
+    #[must_use]
+    pub fn with_entry(mut self, entry: usize) -> Option<RNGLRParser<'s, 't, 'a, 'l>> {
+        if entry >= get_entries_count(self.data.variables) {
+            return None;
+        }
+        self.data.start = entry as u32;
+        Some(self)
+    }
+
+    /// Sets the function resolving the ambiguities left by the productions' annotations when building the AST
+    #[must_use]
+    pub fn with_resolver(
+        mut self,
+        resolver: impl FnMut(&SppfImpl, SppfImplNodeRef, &[usize]) -> usize + 'a,
+    ) -> RNGLRParser<'s, 't, 'a, 'l> {
+        self.builder.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Sets the budget bounding the work of the parser and of its lexer
+    /// The parsing stops when the budget is exhausted, or when it is cancelled.
+    #[must_use]
+    pub fn with_budget(self, budget: ParseBudget) -> RNGLRParser<'s, 't, 'a, 'l> {
+        self.builder.lexer.set_budget(budget);
+        self
+    }
+
+    /// Checks the budget before parsing on the next token, with the current sizes of the GSS and the SPPF
+    /// Returns whether the parsing can go on
+    fn check_budget(&mut self) -> bool {
+        let gss_nodes = self.data.gss.get_nodes_count();
+        let sppf_nodes = self.builder.sppf.nodes.len();
+        let kernel = self.data.next_token;
+        let data = self.builder.lexer.get_data_mut();
+        if data.budget.check_gss_nodes(gss_nodes) && data.budget.check_sppf_nodes(sppf_nodes) {
+            return true;
+        }
+        let index = kernel
+            .filter(|kernel| (kernel.index as usize) < data.repository.get_tokens_count())
+            .and_then(|kernel| data.repository.get_token(kernel.index as usize).get_span())
+            .map_or(data.repository.text.len(), |span| span.index);
+        data.check_budget(index)
+    }
+
+    /// Parses the input up to the specified index in the text and gets the symbols expected there, for code completion
+    /// Only the tokens that end at or before the index are parsed,
+    /// so that the index of the start of a token being typed gives the symbols that may replace it.
+    /// The symbols expected by all the live stacks are merged.
+    pub fn get_expected_at(&mut self, index: usize) -> LRCompletion<'s> {
+        let Some(generation) = self.parse_up_to(Some(index)) else {
+            return LRCompletion::default();
+        };
+        let terminals = self.builder.lexer.get_data().repository.terminals;
+        self.data.get_completion(generation, terminals)
+    }
+
+    /// Parses the input, stopping before the first token that ends after the specified index, if any
+    /// Returns the last generation, or `None` when the parsing stopped on an error
+    fn parse_up_to(&mut self, index: Option<usize>) -> Option<usize> {
+        let mut generation = self.data.gss.create_generation();
+        let state0 = self.data.gss.create_node(self.data.start);
+        self.get_next_token();
+
+        // bootstrap the shifts and reductions queues
+        self.data.queue_actions(state0);
+
+        // Wait for ε token
+        while self.data.get_next_token_id() != SID_EPSILON {
+            if let Some(index) = index {
+                let kernel = self.data.next_token.unwrap();
+                let repository = &self.builder.lexer.get_data().repository;
+                let end = repository
+                    .get_token(kernel.index as usize)
+                    .get_span()
+                    .map_or(usize::MAX, |span| span.index + span.length);
+                if kernel.terminal_id == SID_DOLLAR || end > index {
+                    break;
+                }
+            }
+            // the shift of the next token is a step
+            self.builder.lexer.get_data_mut().budget.step();
+            if !self.check_budget() {
+                return None;
+            }
+            // the stem length (initial number of nodes in the generation before reductions)
+            let stem = self.data.gss.get_generation(generation).count;
+            // apply all reduction actions
+            self.parse_reductions(generation);
+            if !self.check_budget() {
+                return None;
+            }
+            // no scheduled shift actions?
+            if self.data.shifts.is_empty() {
+                // this is an error
+                let kernel = self.data.next_token.unwrap();
+                let error = self.build_error(kernel, stem);
+                let errors = &mut *self.builder.lexer.get_data_mut().errors;
+                errors.push_error_unexpected_token(error);
+                if errors.errors.len() >= MAX_ERROR_COUNT {
+                    return None;
+                }
+                match self.recover(generation, kernel) {
+                    None => return None,
+                    Some(resume) => {
+                        generation = resume;
+                        continue;
+                    }
+                }
+            }
+            // look for the next next-token
+            let old_token = self.data.next_token.unwrap();
+            self.get_next_token();
+            // apply the scheduled shift actions
+            generation = self.parse_shifts(
+                old_token.terminal_id,
+                TableElemRef::new(TableType::Token, old_token.index as usize),
+            );
+        }
+        Some(generation)
+    }
+
     /// Builds the constant sub-trees of nullable variables
     fn build_nullables(
         builder: &mut SPPFBuilder<'s, 't, 'a, 'l>,
@@ -1474,9 +2037,17 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
                 let can_resolve = dependencies[i].is_empty() || dependencies[i].iter().all(|&d| nullables[d] != 0xFFFF_FFFF);
                 if can_resolve {
                     let path = GSSPath::new(0, 0, 0);
-                    nullables[i] =
-                        RNGLRParser::build_sppf(builder, actions, nullables, production.unwrap(), EPSILON, &path, None)
-                            .node_id();
+                    nullables[i] = RNGLRParser::build_sppf(
+                        builder,
+                        actions,
+                        nullables,
+                        automaton.nullables[i] as usize,
+                        production.unwrap(),
+                        EPSILON,
+                        &path,
+                        None,
+                    )
+                    .node_id();
                     dependencies[i].clear();
                     resolved += 1;
                 } else {
@@ -1529,11 +2100,13 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
         result
     }
 
-    /// Builds the SPPF
+    /// Builds the SPPF for the production at the given index
+    #[allow(clippy::too_many_arguments)]
     fn build_sppf(
         builder: &mut SPPFBuilder<'s, 't, 'a, 'l>,
         actions: &mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
         nullables: &[usize],
+        index: usize,
         production: &LRProduction,
         first: GSSLabel,
         path: &GSSPath,
@@ -1544,6 +2117,7 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
         let mut i = 0;
         while i < production.bytecode.len() {
             let op_code = production.bytecode[i];
+            let label = production.get_label_at(i);
             i += 1;
             match get_op_code_base(op_code) {
                 LR_OP_CODE_BASE_SEMANTIC_ACTION => {
@@ -1554,34 +2128,49 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
                 LR_OP_CODE_BASE_ADD_VIRTUAL => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    builder.reduction_add_virtual(index, get_op_code_tree_action(op_code));
+                    builder.reduction_add_virtual(index, get_op_code_tree_action(op_code), label);
                 }
                 LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    builder
-                        .reduction_add_nullable(SppfImplNodeRef::new_usize(nullables[index]), get_op_code_tree_action(op_code));
+                    builder.reduction_add_nullable(
+                        SppfImplNodeRef::new_usize(nullables[index]),
+                        get_op_code_tree_action(op_code),
+                        label,
+                    );
                 }
                 _ => {
-                    builder.reduction_pop(get_op_code_tree_action(op_code));
+                    builder.reduction_pop(get_op_code_tree_action(op_code), label);
                 }
             }
         }
-        builder.reduce(production.head, production.head_action, target)
+        builder.reduce(production.head, index, production.head_action, target)
+    }
+
+    /// Reads the next token in the kernel
+    fn read_next_token(&mut self) -> Option<TokenKernel> {
+        if let Some(kernel) = self.pending.take() {
+            return Some(kernel);
+        }
+        let data = &self.data;
+        self.builder.lexer.get_next_token(data)
     }
 
     /// Gets the next token in the kernel
     fn get_next_token(&mut self) {
-        let next_token = {
-            let data = &self.data;
-            self.builder.lexer.get_next_token(data)
-        };
-        self.data.next_token = next_token;
+        self.data.next_token = self.read_next_token();
     }
 
     /// Executes the reduction operations from the given GSS generation
     fn parse_reductions(&mut self, generation: usize) {
         while !self.data.reductions.is_empty() {
+            let gss_nodes = self.data.gss.get_nodes_count();
+            let sppf_nodes = self.builder.sppf.nodes.len();
+            let budget = &mut self.builder.lexer.get_data_mut().budget;
+            if !budget.step() || !budget.check_gss_nodes(gss_nodes) || !budget.check_sppf_nodes(sppf_nodes) {
+                // the exhaustion of the budget is handled by the caller
+                return;
+            }
             let reduction = self.data.reductions.pop_front().unwrap();
             self.parse_reduction(generation, reduction);
         }
@@ -1625,6 +2214,7 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
                 &mut self.builder,
                 &mut self.data.actions,
                 &self.nullables,
+                reduction.production,
                 production,
                 reduction.first,
                 path,
@@ -1693,16 +2283,15 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
         }
     }
 
-    /// Executes the shift operations for the given token
-    fn parse_shifts(&mut self, old_token: TokenKernel) -> usize {
+    /// Executes the shift operations for the given terminal and its leaf
+    fn parse_shifts(&mut self, terminal_id: u32, leaf: TableElemRef) -> usize {
         // Create next generation
         let new_gen = self.data.gss.create_generation();
         // Create the GSS label to be used for the transitions
-        let symbol = TableElemRef::new(TableType::Token, old_token.index as usize);
-        let sppf_node = self.builder.get_single_node(symbol);
+        let sppf_node = self.builder.get_leaf_node(leaf);
         let label = GSSLabel {
             sppf_node,
-            symbol_id: old_token.terminal_id,
+            symbol_id: terminal_id,
         };
         // Execute all shifts in the queue at this point
         let count = self.data.shifts.len();
@@ -1751,55 +2340,216 @@ impl<'s, 't, 'a, 'l> RNGLRParser<'s, 't, 'a, 'l> {
             my_expected,
         )
     }
-}
 
-impl<'s, 't, 'a, 'l> Parser for RNGLRParser<'s, 't, 'a, 'l> {
-    fn parse(&mut self) {
-        let mut generation = self.data.gss.create_generation();
-        let state0 = self.data.gss.create_node(0);
-        self.get_next_token();
+    /// Gets the terminals expected by the automaton on the specified GSS nodes
+    fn get_expected_terminals(&self, nodes: &[usize]) -> Vec<Symbol<'s>> {
+        let terminals = self.builder.lexer.get_data().repository.terminals;
+        let mut result: Vec<Symbol<'s>> = Vec::new();
+        for &node in nodes {
+            let expected = self
+                .data
+                .automaton
+                .get_expected(self.data.gss.get_represented_state(node), terminals);
+            for symbol in expected.shifts.into_iter().chain(expected.reductions) {
+                if symbol.id != SID_DOLLAR && !result.contains(&symbol) {
+                    result.push(symbol);
+                }
+            }
+        }
+        result
+    }
 
-        // bootstrap the shifts and reductions queues
-        {
-            let count = self.data.automaton.get_actions_count(0, self.data.get_next_token_id());
-            for i in 0..count {
-                let action = self.data.automaton.get_action(0, self.data.get_next_token_id(), i);
-                if action.get_code() == LR_ACTION_CODE_SHIFT {
-                    self.data.shifts.push_back(RNGLRShift {
-                        from: state0,
-                        to: action.get_data() as usize,
-                    });
-                } else if action.get_code() == LR_ACTION_CODE_REDUCE {
-                    self.data.reductions.push_back(RNGLRReduction {
-                        node: state0,
-                        production: action.get_data() as usize,
-                        first: EPSILON,
-                    });
+    /// Tries to recover from an unexpected token
+    /// Returns the generation to resume the parsing from, or `None` when the error cannot be recovered
+    fn recover(&mut self, generation: usize, kernel: TokenKernel) -> Option<usize> {
+        let heads = self.data.gss.get_nodes(generation);
+        if kernel.terminal_id == SID_DOLLAR {
+            // at the end of the input, try to complete it
+            let terminals = self.builder.lexer.get_data().repository.terminals;
+            for length in 1..=MAX_COMPLETION_LENGTH {
+                for &head in &heads {
+                    let configuration = RNGLRSimulation {
+                        gss_node: head,
+                        stack: Vec::new(),
+                    };
+                    if let Some(completion) = self.data.find_completion(&[configuration], terminals, &[], length) {
+                        return Some(self.insert_terminals(&[head], &completion, kernel));
+                    }
                 }
             }
+            return self.recover_panic(&heads, kernel, None);
+        }
+        let next = self.read_next_token();
+        if let Some(next) = next {
+            if let Some(resume) = self.recover_phrase(&heads, kernel, next) {
+                return Some(resume);
+            }
+        }
+        self.recover_panic(&heads, kernel, next)
+    }
+
+    /// Tries a phrase-level recovery by inserting a terminal before the unexpected token,
+    /// deleting the unexpected token, or replacing it with an expected terminal
+    /// Each repair is only applied from the heads on which the following token can then be parsed
+    fn recover_phrase(&mut self, heads: &[usize], kernel: TokenKernel, next: TokenKernel) -> Option<usize> {
+        let expected = self.get_expected_terminals(heads);
+        // insertion of a missing terminal
+        for terminal in &expected {
+            let targets = self.get_simulated_heads(heads, &[terminal.id, kernel.terminal_id, next.terminal_id]);
+            if !targets.is_empty() {
+                self.pending = Some(next);
+                return Some(self.insert_terminals(&targets, &[*terminal], kernel));
+            }
+        }
+        // deletion of the unexpected token
+        let targets = self.get_simulated_heads(heads, &[next.terminal_id]);
+        if !targets.is_empty() {
+            self.skip_token(kernel);
+            return Some(self.resume_from(&targets, next));
+        }
+        // replacement of the unexpected token
+        for terminal in &expected {
+            let targets = self.get_simulated_heads(heads, &[terminal.id, next.terminal_id]);
+            if !targets.is_empty() {
+                self.skip_token(kernel);
+                return Some(self.insert_terminals(&targets, &[*terminal], next));
+            }
         }
+        None
+    }
 
-        // Wait for ε token
-        while self.data.get_next_token_id() != SID_EPSILON {
-            // the stem length (initial number of nodes in the generation before reductions)
-            let stem = self.data.gss.get_generation(generation).count;
-            // apply all reduction actions
-            self.parse_reductions(generation);
-            // no scheduled shift actions?
-            if self.data.shifts.is_empty() {
-                // this is an error
-                let error = self.build_error(self.data.next_token.unwrap(), stem);
-                self.builder.lexer.get_data_mut().errors.push_error_unexpected_token(error);
-                // TODO: try to recover here
-                return;
+    /// Tries a panic-mode recovery
+    /// The stacks are popped down to the nodes that can parse the upcoming token,
+    /// while the tokens that cannot be parsed from any node in the stacks are skipped.
+    /// The popped sub-trees and the skipped tokens end up in an error node.
+    /// The nodes are explored breadth-first from the heads, each one along the first path found to it.
+    fn recover_panic(&mut self, heads: &[usize], kernel: TokenKernel, next: Option<TokenKernel>) -> Option<usize> {
+        let mut skipped = Vec::new();
+        let mut current = Some(kernel);
+        let mut next = next;
+        while let Some(token) = current {
+            let mut visited: BTreeSet<usize> = heads.iter().copied().collect();
+            let mut paths: Vec<GSSPath> = heads.iter().map(|&head| GSSPath::new_length0(head, 0)).collect();
+            while !paths.is_empty() {
+                // each depth is a step, so that the recovery also stops on an exhausted or cancelled budget
+                if !self.builder.lexer.get_data_mut().budget.step() {
+                    self.check_budget();
+                    return None;
+                }
+                let targets: Vec<usize> = paths
+                    .iter()
+                    .map(|path| path.last_node)
+                    .filter(|&node| self.data.simulate(node, &[token.terminal_id]))
+                    .collect();
+                if let Some(path) = targets
+                    .first()
+                    .and_then(|&target| paths.iter().find(|path| path.last_node == target))
+                {
+                    for label in path.labels.iter().rev() {
+                        self.builder.skip(label.sppf_node);
+                    }
+                    for token in skipped {
+                        self.skip_token(token);
+                    }
+                    self.pending = next;
+                    return Some(self.resume_from(&targets, token));
+                }
+                let mut deeper = Vec::new();
+                for path in &paths {
+                    for edge in self.data.gss.get_paths(path.last_node, 1) {
+                        if visited.insert(edge.last_node) {
+                            deeper.push(GSSPath::from(path, edge.last_node, edge.generation, edge.labels[0]));
+                        }
+                    }
+                }
+                paths = deeper;
             }
-            // look for the next next-token
-            let old_token = self.data.next_token.unwrap();
-            self.get_next_token();
-            // apply the scheduled shift actions
-            generation = self.parse_shifts(old_token);
+            if token.terminal_id == SID_DOLLAR {
+                // cannot skip the end of the input
+                return None;
+            }
+            // skip this token
+            skipped.push(token);
+            current = next.take().or_else(|| self.read_next_token());
         }
+        None
+    }
+
+    /// Gets the heads from which the specified sequence of terminals can be parsed
+    fn get_simulated_heads(&self, heads: &[usize], terminals: &[u32]) -> Vec<usize> {
+        heads
+            .iter()
+            .copied()
+            .filter(|&head| self.data.simulate(head, terminals))
+            .collect()
+    }
 
+    /// Marks a token as skipped by the error recovery
+    fn skip_token(&mut self, kernel: TokenKernel) {
+        let leaf = self
+            .builder
+            .get_single_node(TableElemRef::new(TableType::Token, kernel.index as usize));
+        self.builder.skip(leaf);
+    }
+
+    /// Resumes the parsing of a token from the specified GSS nodes
+    /// The nodes are copied into a new generation
+    fn resume_from(&mut self, targets: &[usize], kernel: TokenKernel) -> usize {
+        self.data.reductions.clear();
+        self.data.shifts.clear();
+        self.data.next_token = Some(kernel);
+        let generation = self.data.gss.create_generation();
+        for &target in targets {
+            let state = self.data.gss.get_represented_state(target);
+            let node = self
+                .data
+                .gss
+                .find_node(generation, state)
+                .unwrap_or_else(|| self.data.gss.create_node(state));
+            for edge in self.data.gss.get_edges_from(target) {
+                if self.data.gss.get_edge(generation, node, edge.to as usize).is_none() {
+                    self.data.gss.create_edge(node, edge.to as usize, edge.label);
+                }
+            }
+        }
+        for node in self.data.gss.get_nodes(generation) {
+            self.data.queue_actions(node);
+        }
+        generation
+    }
+
+    /// Inserts a sequence of terminals that are missing in the input from the specified GSS nodes,
+    /// before the specified token
+    fn insert_terminals(&mut self, targets: &[usize], terminals: &[Symbol<'s>], kernel: TokenKernel) -> usize {
+        let all = self.builder.lexer.get_data().repository.terminals;
+        let mut generation = self.resume_from(targets, Self::get_missing_kernel(terminals[0]));
+        for (index, terminal) in terminals.iter().enumerate() {
+            self.parse_reductions(generation);
+            self.data.next_token = Some(
+                terminals
+                    .get(index + 1)
+                    .map_or(kernel, |&next| Self::get_missing_kernel(next)),
+            );
+            let position = all.iter().position(|x| x.id == terminal.id).unwrap_or(0);
+            generation = self.parse_shifts(terminal.id, TableElemRef::new(TableType::None, position));
+        }
+        generation
+    }
+
+    /// Gets a kernel for a terminal that is missing in the input
+    fn get_missing_kernel(terminal: Symbol) -> TokenKernel {
+        TokenKernel {
+            terminal_id: terminal.id,
+            index: u32::MAX,
+        }
+    }
+}
+
+impl<'s, 't, 'a, 'l> Parser for RNGLRParser<'s, 't, 'a, 'l> {
+    fn parse(&mut self) {
+        let Some(generation) = self.parse_up_to(None) else {
+            return;
+        };
         let generation_data = self.data.gss.get_generation(generation);
         for i in generation_data.start..(generation_data.start + generation_data.count) {
             let state = self.data.gss.get_represented_state(i);
@@ -1807,7 +2557,9 @@ impl<'s, 't, 'a, 'l> Parser for RNGLRParser<'s, 't, 'a, 'l> {
                 // Has reduction _Axiom_ -> axiom $ . on ε
                 let paths = self.data.gss.get_paths(i, 2);
                 let root = paths[0].labels[1];
-                self.builder.commit_root(root.sppf_node);
+                let dollar = paths[0].labels[0];
+                self.builder
+                    .commit_root(root.sppf_node, dollar.sppf_node, &self.data.automaton.productions);
             }
         }
         // At end of input but was still waiting for tokens
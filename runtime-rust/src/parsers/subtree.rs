@@ -29,7 +29,7 @@ use crate::ast::{AstCell, AstImpl, TableElemRef};
 /// The internal representation of a sub-tree is based on arrays.
 /// The organization is that a node's children are immediately following it in the array.
 /// For example, the tree `A(B(CD)E(FG))` is represented as `[ABCDEFG]`.
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct SubTree {
     /// The nodes in this buffer
     nodes: Vec<AstCell>,
@@ -64,6 +64,22 @@ impl SubTree {
         self.actions[index]
     }
 
+    /// Gets the index of the label of the node at the given index, if any
+    #[must_use]
+    pub fn get_element_label_at(&self, index: usize) -> Option<u16> {
+        self.nodes[index].element_label
+    }
+
+    /// Sets the index of the label of the node at the given index
+    pub fn set_element_label_at(&mut self, index: usize, label: Option<u16>) {
+        self.nodes[index].element_label = label;
+    }
+
+    /// Sets the index of the production that produced the node at the given index
+    pub fn set_production_at(&mut self, index: usize, production: Option<u16>) {
+        self.nodes[index].production = production;
+    }
+
     /// Sets the tree action applied onto the node at the given index
     pub fn set_action_at(&mut self, index: usize, action: TreeAction) {
         self.actions[index] = action;
@@ -96,11 +112,7 @@ impl SubTree {
 
     /// Initializes the root of this sub-tree
     pub fn setup_root(&mut self, symbol: TableElemRef, action: TreeAction) {
-        self.nodes.push(AstCell {
-            label: symbol,
-            count: 0,
-            first: 0,
-        });
+        self.nodes.push(AstCell::new_empty(symbol));
         self.actions.push(action);
     }
 
@@ -154,11 +166,7 @@ impl SubTree {
     /// Pushes a new node into this buffer
     pub fn push(&mut self, symbol: TableElemRef, action: TreeAction) -> usize {
         let result = self.nodes.len();
-        self.nodes.push(AstCell {
-            label: symbol,
-            count: 0,
-            first: 0,
-        });
+        self.nodes.push(AstCell::new_empty(symbol));
         self.actions.push(action);
         result
     }
@@ -175,4 +183,60 @@ impl SubTree {
             self.actions[to + i] = self.actions[from + i];
         }
     }
+
+    /// Copies this sub-tree, whose committed descendants are stored in a previous AST, for a new AST
+    /// The descendants are copied into the new AST and all the labels are transformed with the given function.
+    #[must_use]
+    pub fn copy_from_previous<F: Fn(TableElemRef) -> TableElemRef>(
+        &self,
+        previous: &AstImpl,
+        ast: &mut AstImpl,
+        remap: &F,
+    ) -> SubTree {
+        let replaced = self.actions[0] == TREE_ACTION_REPLACE_BY_CHILDREN;
+        let size = if replaced {
+            self.get_size()
+        } else {
+            self.nodes[0].count as usize + 1
+        };
+        let mut result = SubTree::new(size);
+        for i in 0..size {
+            let mut node = self.nodes[i];
+            node.label = remap(node.label);
+            result.nodes.push(node);
+            result.actions.push(self.actions[i]);
+        }
+        // the nodes whose children are committed are at depth 1, or at depth 2 when the root is replaced
+        let mut committed = Vec::new();
+        if replaced {
+            let mut index = 1;
+            for _i in 0..self.nodes[0].count {
+                let count = self.nodes[index].count as usize;
+                committed.extend(index + 1..=index + count);
+                index += count + 1;
+            }
+        } else {
+            committed.extend(1..size);
+        }
+        for index in committed {
+            let node = &mut result.nodes[index];
+            node.first = ast.store_from(previous, node.first as usize, node.count as usize, remap) as u32;
+        }
+        result
+    }
+}
+
+/// A sub-tree produced by a reduction that may be reused when parsing again after some edits
+#[derive(Debug, Clone)]
+pub struct ReusableSubTree {
+    /// The index of the first token covered by the sub-tree
+    pub first: usize,
+    /// The index of the lookahead token when the sub-tree was reduced
+    pub lookahead: usize,
+    /// The parser's state below the sub-tree
+    pub state: u32,
+    /// The index of the reduced variable
+    pub variable: usize,
+    /// The reduced sub-tree
+    pub tree: SubTree,
 }
@@ -0,0 +1,107 @@
+/*******************************************************************************
+ * Copyright (c) 2024 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the values synthesized by semantic actions during parsing
+
+use alloc::vec::Vec;
+
+use crate::symbols::{SemanticBody, Symbol};
+
+/// Delegate for user-defined semantic actions that produce values
+/// The semantic action to execute is given by its index.
+/// The values of the elements of the rule that have been popped so far are given in the order of the rule,
+/// excluding the virtual symbols and the semantic actions.
+/// The action returns the produced value, or `None` when it produces no value.
+pub type SemanticValueAction<'x, V> = dyn FnMut(usize, Symbol, &dyn SemanticBody, &mut [Option<V>]) -> Option<V> + 'x;
+
+/// Represents a stack of values kept by a parser parallel to its own stack
+pub trait ValueStack {
+    /// Pushes an element without value, for a token or a terminal
+    fn push_empty(&mut self);
+
+    /// Drops the values beyond the specified length
+    fn truncate(&mut self, length: usize);
+
+    /// Executes a semantic action during the reduction of `length` elements, of which `popped` have been popped so far
+    fn execute(&mut self, index: usize, head: Symbol, body: &dyn SemanticBody, length: usize, popped: usize);
+
+    /// Finalizes the reduction of the specified number of elements
+    fn reduce(&mut self, length: usize);
+}
+
+/// A stack of values synthesized by semantic actions
+/// The value of a variable is the value produced by the last semantic action executed for its reduction, if any.
+/// When no semantic action is executed, the variable takes the value of the first element of its rule, if any.
+/// Tokens have no value.
+pub struct SemanticValues<'x, V> {
+    /// The semantic actions
+    actions: &'x mut SemanticValueAction<'x, V>,
+    /// The values of the elements on the stack
+    stack: Vec<Option<V>>,
+    /// The value produced by the last semantic action executed for the current reduction, if any
+    current: Option<V>,
+    /// Whether a semantic action has been executed for the current reduction
+    executed: bool,
+}
+
+impl<'x, V> SemanticValues<'x, V> {
+    /// Creates a new stack of values for the given semantic actions
+    pub fn new(actions: &'x mut SemanticValueAction<'x, V>) -> SemanticValues<'x, V> {
+        SemanticValues {
+            actions,
+            stack: Vec::new(),
+            current: None,
+            executed: false,
+        }
+    }
+
+    /// Gets the value synthesized for the axiom, if any
+    /// This is the value of the first element on the stack once the input has been accepted.
+    #[must_use]
+    pub fn into_value(self) -> Option<V> {
+        self.stack.into_iter().next().flatten()
+    }
+}
+
+impl<V> ValueStack for SemanticValues<'_, V> {
+    fn push_empty(&mut self) {
+        self.stack.push(None);
+    }
+
+    fn truncate(&mut self, length: usize) {
+        self.stack.truncate(length);
+    }
+
+    fn execute(&mut self, index: usize, head: Symbol, body: &dyn SemanticBody, length: usize, popped: usize) {
+        let start = self.stack.len() - length;
+        let values = &mut self.stack[start..(start + popped)];
+        self.current = (self.actions)(index, head, body, values);
+        self.executed = true;
+    }
+
+    fn reduce(&mut self, length: usize) {
+        let mut values = self.stack.split_off(self.stack.len() - length);
+        let value = if core::mem::take(&mut self.executed) {
+            self.current.take()
+        } else if values.is_empty() {
+            None
+        } else {
+            values.swap_remove(0)
+        };
+        self.stack.push(value);
+    }
+}
@@ -17,13 +17,16 @@
 
 //! Module for the definition of a parse result
 
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
-use crate::ast::{Ast, AstImpl};
-use crate::errors::ParseErrors;
-use crate::sppf::{Sppf, SppfImpl};
+use crate::ast::{Ast, AstAmbiguity, AstImpl};
+use crate::errors::{ParseError, ParseErrors};
+use crate::sppf::{Sppf, SppfAmbiguity, SppfImpl, SppfTrees};
 use crate::symbols::Symbol;
-use crate::text::Text;
+use crate::text::{Text, TextEdit, TextEditError, TextSpan};
 use crate::tokens::{TokenRepository, TokenRepositoryImpl};
 
 /// Represents the output of a parser
@@ -64,20 +67,82 @@ impl<'s, 't, 'a, T: Default> ParseResult<'s, 't, 'a, T> {
         }
     }
 
+    /// Initialize a new parse result for lossless parsing
+    /// The separators and the skipped text are kept as trivia attached to the next token.
+    /// LR(k) parsers also build the concrete syntax tree of the input.
+    #[must_use]
+    pub fn new_lossless(
+        terminals: &'a [Symbol<'s>],
+        variables: &'a [Symbol<'s>],
+        virtuals: &'a [Symbol<'s>],
+        text: Text<'t>,
+    ) -> ParseResult<'s, 't, 'a, T> {
+        ParseResult {
+            tokens: TokenRepositoryImpl::new_lossless(),
+            ..ParseResult::new(terminals, variables, virtuals, text)
+        }
+    }
+
     /// Gets the token repository associated with this result
     #[must_use]
     pub fn get_tokens(&self) -> TokenRepository {
         TokenRepository::new(self.terminals, &self.text, &self.tokens)
     }
+
+    /// Applies checked edits to the input and gets a new result, ready to be parsed, for the new text
+    /// The tokens are reused where possible, unless the lexing of the input produced errors.
+    /// Nothing is reused for lossless results.
+    fn apply_edits_with<U: Default>(
+        self,
+        edits: &[TextEdit],
+        parse_tree: impl FnOnce(T) -> U,
+    ) -> ParseResult<'s, 'static, 'a, U> {
+        let text = self.text.apply_checked_edits(edits);
+        if self.tokens.is_lossless() {
+            return ParseResult::new_lossless(self.terminals, self.variables, self.virtuals, text);
+        }
+        let lexical_errors = self
+            .errors
+            .errors
+            .iter()
+            .any(|error| !matches!(error, ParseError::UnexpectedToken(_)));
+        let tokens = if lexical_errors {
+            TokenRepositoryImpl::default()
+        } else {
+            TokenRepositoryImpl::new_reusing(self.tokens, self.text.len(), edits)
+        };
+        ParseResult {
+            terminals: self.terminals,
+            variables: self.variables,
+            virtuals: self.virtuals,
+            text,
+            errors: ParseErrors::default(),
+            tokens,
+            parse_tree: parse_tree(self.parse_tree),
+        }
+    }
 }
 
 impl<'s, 't, 'a> ParseResult<'s, 't, 'a, AstImpl> {
     /// Gets whether this result denotes a successful parsing
+    /// The parsing is successful when a parse tree is produced without any error.
     #[must_use]
     pub fn is_success(&self) -> bool {
+        self.errors.errors.is_empty() && self.parse_tree.has_root()
+    }
+
+    /// Gets whether this result has a parse tree, even a partial one produced by the error recovery
+    #[must_use]
+    pub fn has_tree(&self) -> bool {
         self.parse_tree.has_root()
     }
 
+    /// Gets the number of sub-trees reused from a previous parse, after some edits of the input
+    #[must_use]
+    pub fn get_reused_subtrees_count(&self) -> usize {
+        self.parse_tree.get_reused_count()
+    }
+
     /// Gets the resulting AST
     #[must_use]
     pub fn get_ast<'x>(&'x self) -> Ast<'s, 't, 'x> {
@@ -89,6 +154,33 @@ impl<'s, 't, 'a> ParseResult<'s, 't, 'a, AstImpl> {
         )
     }
 
+    /// Gets the ambiguities found in the input, if any
+    /// Only RNGLR parsers find ambiguities, the SPPF they were found in is not kept with the AST.
+    #[must_use]
+    pub fn get_ambiguities(&self) -> Vec<AstAmbiguity<'s>> {
+        self.get_ast().get_ambiguities()
+    }
+
+    /// Gets the concrete syntax tree, if any
+    /// The concrete syntax tree is only built by LR(k) parsers for lossless results.
+    /// Its root is the generated axiom variable, with the grammar axiom and the dollar token as children.
+    /// Concatenating the leading trivia and the value of its leaves reproduces the input,
+    /// except for the tokens dropped by the error recovery.
+    #[must_use]
+    pub fn get_cst<'x>(&'x self) -> Option<Ast<'s, 't, 'x>> {
+        self.parse_tree
+            .get_concrete()
+            .filter(|concrete| concrete.has_root())
+            .map(|concrete| {
+                Ast::new(
+                    TokenRepository::new(self.terminals, &self.text, &self.tokens),
+                    self.variables,
+                    self.virtuals,
+                    concrete,
+                )
+            })
+    }
+
     /// Gets the mutable data required for parsing
     #[must_use]
     pub fn get_parsing_data<'x>(&'x mut self) -> (TokenRepository<'s, 't, 'x>, &'x mut ParseErrors<'s>, &'x mut AstImpl) {
@@ -98,12 +190,73 @@ impl<'s, 't, 'a> ParseResult<'s, 't, 'a, AstImpl> {
             &mut self.parse_tree,
         )
     }
+
+    /// Applies edits to the input and gets a new result, ready to be parsed again, for the new text
+    /// The spans of the edits refer to the current text and must not overlap.
+    /// The parsing of the new result reuses the tokens of this one where possible, unless the lexing produced errors.
+    /// A LR(k) parser with a context-free lexer also reuses the sub-trees that are not affected by the edits.
+    /// The semantic actions are not executed again for the reused sub-trees.
+    ///
+    /// # Errors
+    ///
+    /// Return an error when the edits are not valid for the text, see `Text::check_edits`
+    pub fn apply_edits(self, edits: &[TextEdit]) -> Result<ParseResult<'s, 'static, 'a, AstImpl>, TextEditError> {
+        self.text.check_edits(edits)?;
+        Ok(self.apply_edits_with(edits, AstImpl::new_reusing))
+    }
+
+    /// Initialize a new parse result for an empty input to be fed piece by piece
+    /// The state of a LR(k) parser is recorded, so that the parsing resumes from it when the input is extended.
+    pub(crate) fn new_resumable(
+        terminals: &'a [Symbol<'s>],
+        variables: &'a [Symbol<'s>],
+        virtuals: &'a [Symbol<'s>],
+    ) -> ParseResult<'s, 'static, 'a, AstImpl> {
+        ParseResult {
+            parse_tree: AstImpl::new_resuming(AstImpl::default(), false),
+            ..ParseResult::new(terminals, variables, virtuals, Text::from_string(String::new()))
+        }
+    }
+
+    /// Appends a chunk of text to the input and gets a new result, ready to be parsed again
+    /// A LR(k) parser resumes from its state before the tokens that the chunk may change, if it was recorded.
+    pub(crate) fn append(self, chunk: &str) -> ParseResult<'s, 'static, 'a, AstImpl> {
+        let edit = TextEdit::new(self.text.len(), 0, chunk);
+        self.apply_edits_with(&[edit], |tree| AstImpl::new_resuming(tree, true))
+    }
+
+    /// Appends a token that has already been lexed to the input and gets a new result, ready to be parsed again
+    /// The tokens of this result are kept, but its sub-trees are not reused.
+    /// A LR(k) parser resumes from its state before the end of the input, if it was recorded.
+    pub(crate) fn append_token(self, terminal: usize, value: &str) -> ParseResult<'s, 'static, 'a, AstImpl> {
+        let span = TextSpan {
+            index: self.text.len(),
+            length: value.len(),
+        };
+        let text = self.text.apply_checked_edits(&[TextEdit::new(span.index, 0, value)]);
+        ParseResult {
+            terminals: self.terminals,
+            variables: self.variables,
+            virtuals: self.virtuals,
+            text,
+            errors: ParseErrors::default(),
+            tokens: TokenRepositoryImpl::new_appending(&self.tokens, terminal, span),
+            parse_tree: AstImpl::new_resuming(self.parse_tree, false),
+        }
+    }
 }
 
 impl<'s, 't, 'a> ParseResult<'s, 't, 'a, SppfImpl> {
     /// Gets whether this result denotes a successful parsing
+    /// The parsing is successful when a parse tree is produced without any error.
     #[must_use]
     pub fn is_success(&self) -> bool {
+        self.errors.errors.is_empty() && self.parse_tree.has_root()
+    }
+
+    /// Gets whether this result has a parse tree, even a partial one produced by the error recovery
+    #[must_use]
+    pub fn has_tree(&self) -> bool {
         self.parse_tree.has_root()
     }
 
@@ -118,6 +271,24 @@ impl<'s, 't, 'a> ParseResult<'s, 't, 'a, SppfImpl> {
         )
     }
 
+    /// Gets the ambiguities in the resulting SPPF, i.e. the nodes with multiple versions
+    #[must_use]
+    pub fn get_ambiguities(&self) -> Vec<SppfAmbiguity<'s>> {
+        self.get_ast().get_ambiguities()
+    }
+
+    /// Gets the number of distinct parse trees in the resulting SPPF, saturating at `u64::MAX`
+    #[must_use]
+    pub fn get_trees_count(&self) -> u64 {
+        self.parse_tree.count_trees()
+    }
+
+    /// Gets an iterator over the distinct trees in the resulting SPPF, producing at most `limit` trees
+    #[must_use]
+    pub fn get_trees(&self, limit: usize) -> SppfTrees<'_> {
+        self.parse_tree.get_trees(limit)
+    }
+
     /// Gets the mutable data required for parsing
     #[must_use]
     pub fn get_parsing_data<'x>(&'x mut self) -> (TokenRepository<'s, 't, 'x>, &'x mut ParseErrors<'s>, &'x mut SppfImpl) {
@@ -127,6 +298,18 @@ impl<'s, 't, 'a> ParseResult<'s, 't, 'a, SppfImpl> {
             &mut self.parse_tree,
         )
     }
+
+    /// Applies edits to the input and gets a new result, ready to be parsed again, for the new text
+    /// The spans of the edits refer to the current text and must not overlap.
+    /// The parsing of the new result reuses the tokens of this one where possible, unless the lexing produced errors.
+    ///
+    /// # Errors
+    ///
+    /// Return an error when the edits are not valid for the text, see `Text::check_edits`
+    pub fn apply_edits(self, edits: &[TextEdit]) -> Result<ParseResult<'s, 'static, 'a, SppfImpl>, TextEditError> {
+        self.text.check_edits(edits)?;
+        Ok(self.apply_edits_with(edits, |_| SppfImpl::default()))
+    }
 }
 
 impl<'s, 't, 'a> Serialize for ParseResult<'s, 't, 'a, AstImpl> {
@@ -135,7 +318,7 @@ impl<'s, 't, 'a> Serialize for ParseResult<'s, 't, 'a, AstImpl> {
         S: Serializer,
     {
         let ast = self.get_ast();
-        let root = if self.is_success() { Some(ast.get_root()) } else { None };
+        let root = if self.has_tree() { Some(ast.get_root()) } else { None };
         let mut state = serializer.serialize_struct("ParseResult", 2)?;
         state.serialize_field("errors", &self.errors.errors)?;
         state.serialize_field("root", &root)?;
@@ -149,7 +332,7 @@ impl<'s, 't, 'a> Serialize for ParseResult<'s, 't, 'a, SppfImpl> {
         S: Serializer,
     {
         let ast = self.get_ast();
-        let root = if self.is_success() { Some(ast.get_root()) } else { None };
+        let root = if self.has_tree() { Some(ast.get_root()) } else { None };
         let mut state = serializer.serialize_struct("ParseResult", 2)?;
         state.serialize_field("errors", &self.errors.errors)?;
         state.serialize_field("root", &root)?;
@@ -25,9 +25,9 @@ use core::ops::Index;
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 
-use crate::ast::{TableElemRef, TableType};
+use crate::ast::{AstImplAmbiguity, TableElemRef, TableType};
 use crate::parsers::TreeAction;
-use crate::symbols::{SemanticElementTrait, Symbol};
+use crate::symbols::{SemanticElementTrait, Symbol, SYMBOL_ERROR};
 use crate::text::{TextContext, TextPosition, TextSpan};
 use crate::tokens::{Token, TokenRepository};
 
@@ -306,6 +306,18 @@ pub struct SppfImplNodeVersion {
     pub label: TableElemRef,
     /// The children of the node for this version
     pub children: SppfImplNodeChildren,
+    /// The labels of the children given by the rule, as pairs of the index of a child and the index of its label
+    pub labels: Vec<(u32, u16)>,
+    /// The index of the LR production that produced this version, if any
+    pub production: Option<u16>,
+}
+
+/// Gets the labels of children as pairs of the index of a child and the index of its label
+fn get_children_labels(offset: usize, labels: &[Option<u16>]) -> impl Iterator<Item = (u32, u16)> + '_ {
+    labels
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, label)| label.map(|label| ((offset + index) as u32, label)))
 }
 
 impl SppfImplNodeVersion {
@@ -315,21 +327,38 @@ impl SppfImplNodeVersion {
         SppfImplNodeVersion {
             label,
             children: SppfImplNodeChildren::None,
+            labels: Vec::new(),
+            production: None,
         }
     }
 
     /// Initializes this node version
-    #[must_use]
-    pub fn from(label: TableElemRef, children: &[SppfImplNodeRef]) -> SppfImplNodeVersion {
+    /// The labels of the children, if any, are given in the same order as the children.
+    #[must_use]
+    pub fn from(
+        label: TableElemRef,
+        production: Option<u16>,
+        children: &[SppfImplNodeRef],
+        labels: &[Option<u16>],
+    ) -> SppfImplNodeVersion {
         SppfImplNodeVersion {
             label,
             children: SppfImplNodeChildren::from(children),
+            labels: get_children_labels(0, labels).collect(),
+            production,
         }
     }
 
     /// Creates a new version with added head and tail
+    /// The labels of the head and tail, if any, are given in the same order as their children.
     #[must_use]
-    pub fn with_head_tail(&self, head: &[SppfImplNodeRef], tail: &[SppfImplNodeRef]) -> Self {
+    pub fn with_head_tail(
+        &self,
+        head: &[SppfImplNodeRef],
+        head_labels: &[Option<u16>],
+        tail: &[SppfImplNodeRef],
+        tail_labels: &[Option<u16>],
+    ) -> Self {
         let total = head.len() + self.len() + tail.len();
         let mut children = Vec::with_capacity(total);
         for &c in head {
@@ -341,7 +370,25 @@ impl SppfImplNodeVersion {
         for &c in tail {
             children.push(c);
         }
-        Self::from(self.label, &children)
+        let labels = get_children_labels(0, head_labels)
+            .chain(self.labels.iter().map(|&(index, label)| (index + head.len() as u32, label)))
+            .chain(get_children_labels(head.len() + self.len(), tail_labels))
+            .collect();
+        SppfImplNodeVersion {
+            label: self.label,
+            children: SppfImplNodeChildren::from(children.as_slice()),
+            labels,
+            production: self.production,
+        }
+    }
+
+    /// Gets the index of the label of the child at the given index, if any
+    #[must_use]
+    pub fn get_child_label(&self, index: usize) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(child, _)| *child as usize == index)
+            .map(|(_, label)| *label)
     }
 
     /// Gets the number of children
@@ -374,6 +421,9 @@ impl SppfImplNodeVersion {
             result.push(child);
         }
         self.children = SppfImplNodeChildren::from(result.as_slice());
+        for (index, _) in &mut self.labels {
+            *index += head.len() as u32;
+        }
     }
 
     /// Adds a series of children at the back
@@ -510,14 +560,23 @@ impl<T: PartialEq> SppfImplNodeVersions<T> {
 impl SppfImplNodeVersions<SppfImplNodeVersion> {
     /// Adds a new version to this node
     #[must_use]
-    pub fn with_new_version(self, label: TableElemRef, children: &[SppfImplNodeRef]) -> (Self, usize) {
+    pub fn with_new_version(
+        self,
+        label: TableElemRef,
+        production: Option<u16>,
+        children: &[SppfImplNodeRef],
+        labels: &[Option<u16>],
+    ) -> (Self, usize) {
         match self {
             SppfImplNodeVersions::Single(first) => {
                 if first.is_same_as(label, children) {
                     (SppfImplNodeVersions::Single(first), 0)
                 } else {
                     (
-                        SppfImplNodeVersions::Multiple(alloc::vec![first, SppfImplNodeVersion::from(label, children)]),
+                        SppfImplNodeVersions::Multiple(alloc::vec![
+                            first,
+                            SppfImplNodeVersion::from(label, production, children, labels)
+                        ]),
                         0,
                     )
                 }
@@ -531,7 +590,7 @@ impl SppfImplNodeVersions<SppfImplNodeVersion> {
                     (SppfImplNodeVersions::Multiple(versions), version)
                 } else {
                     let current = versions.len();
-                    versions.push(SppfImplNodeVersion::from(label, children));
+                    versions.push(SppfImplNodeVersion::from(label, production, children, labels));
                     (SppfImplNodeVersions::Multiple(versions), current)
                 }
             }
@@ -627,16 +686,27 @@ impl SppfImplNode {
 
     /// Initializes this node
     #[must_use]
-    pub fn new_with_children(label: TableElemRef, children: &[SppfImplNodeRef]) -> SppfImplNode {
+    pub fn new_with_children(
+        label: TableElemRef,
+        production: Option<u16>,
+        children: &[SppfImplNodeRef],
+        labels: &[Option<u16>],
+    ) -> SppfImplNode {
         SppfImplNode {
-            versions: SppfImplNodeVersions::Single(SppfImplNodeVersion::from(label, children)),
+            versions: SppfImplNodeVersions::Single(SppfImplNodeVersion::from(label, production, children, labels)),
         }
     }
 
     /// Adds a new version to this node
-    pub fn add_version(&mut self, label: TableElemRef, children: &[SppfImplNodeRef]) -> usize {
+    pub fn add_version(
+        &mut self,
+        label: TableElemRef,
+        production: Option<u16>,
+        children: &[SppfImplNodeRef],
+        labels: &[Option<u16>],
+    ) -> usize {
         let result;
-        (self.versions, result) = core::mem::take(&mut self.versions).with_new_version(label, children);
+        (self.versions, result) = core::mem::take(&mut self.versions).with_new_version(label, production, children, labels);
         result
     }
 
@@ -718,28 +788,41 @@ pub struct SppfImplNodeReplaceable {
 impl SppfImplNodeReplaceable {
     /// Initializes this node
     #[must_use]
-    pub fn new(label: TableElemRef, children: &[SppfImplNodeRef], actions: &[TreeAction]) -> SppfImplNodeReplaceable {
+    pub fn new(
+        label: TableElemRef,
+        children: &[SppfImplNodeRef],
+        actions: &[TreeAction],
+        labels: &[Option<u16>],
+    ) -> SppfImplNodeReplaceable {
         Self {
             versions: SppfImplNodeVersions::Single(if children.is_empty() {
                 SppfImplNodeReplaceableVersion {
                     label,
                     children: Vec::new(),
                     actions: Vec::new(),
+                    labels: Vec::new(),
                 }
             } else {
                 SppfImplNodeReplaceableVersion {
                     label,
                     children: children.to_vec(),
                     actions: actions.to_vec(),
+                    labels: labels.to_vec(),
                 }
             }),
         }
     }
 
     /// Adds a new version to this node
-    pub fn add_version(&mut self, label: TableElemRef, children: &[SppfImplNodeRef], actions: &[TreeAction]) -> usize {
+    pub fn add_version(
+        &mut self,
+        label: TableElemRef,
+        children: &[SppfImplNodeRef],
+        actions: &[TreeAction],
+        labels: &[Option<u16>],
+    ) -> usize {
         let result;
-        (self.versions, result) = core::mem::take(&mut self.versions).with_new_version(label, children, actions);
+        (self.versions, result) = core::mem::take(&mut self.versions).with_new_version(label, children, actions, labels);
         result
     }
 
@@ -769,7 +852,13 @@ impl SppfImplNodeReplaceable {
 impl SppfImplNodeVersions<SppfImplNodeReplaceableVersion> {
     /// Adds a new version to this node
     #[must_use]
-    pub fn with_new_version(self, label: TableElemRef, children: &[SppfImplNodeRef], actions: &[TreeAction]) -> (Self, usize) {
+    pub fn with_new_version(
+        self,
+        label: TableElemRef,
+        children: &[SppfImplNodeRef],
+        actions: &[TreeAction],
+        labels: &[Option<u16>],
+    ) -> (Self, usize) {
         match self {
             SppfImplNodeVersions::Single(first) => {
                 if first.is_same_as(label, children) {
@@ -778,7 +867,7 @@ impl SppfImplNodeVersions<SppfImplNodeReplaceableVersion> {
                     (
                         SppfImplNodeVersions::Multiple(alloc::vec![
                             first,
-                            SppfImplNodeReplaceableVersion::from(label, children, actions)
+                            SppfImplNodeReplaceableVersion::from(label, children, actions, labels)
                         ]),
                         0,
                     )
@@ -793,7 +882,7 @@ impl SppfImplNodeVersions<SppfImplNodeReplaceableVersion> {
                     (SppfImplNodeVersions::Multiple(versions), version)
                 } else {
                     let current = versions.len();
-                    versions.push(SppfImplNodeReplaceableVersion::from(label, children, actions));
+                    versions.push(SppfImplNodeReplaceableVersion::from(label, children, actions, labels));
                     (SppfImplNodeVersions::Multiple(versions), current)
                 }
             }
@@ -810,16 +899,24 @@ pub struct SppfImplNodeReplaceableVersion {
     pub children: Vec<SppfImplNodeRef>,
     /// The tree actions on the children of this node
     pub actions: Vec<TreeAction>,
+    /// The labels of the children of this node
+    pub labels: Vec<Option<u16>>,
 }
 
 impl SppfImplNodeReplaceableVersion {
     /// Initializes this node version
     #[must_use]
-    pub fn from(label: TableElemRef, children: &[SppfImplNodeRef], actions: &[TreeAction]) -> SppfImplNodeReplaceableVersion {
+    pub fn from(
+        label: TableElemRef,
+        children: &[SppfImplNodeRef],
+        actions: &[TreeAction],
+        labels: &[Option<u16>],
+    ) -> SppfImplNodeReplaceableVersion {
         SppfImplNodeReplaceableVersion {
             label,
             children: children.to_vec(),
             actions: actions.to_vec(),
+            labels: labels.to_vec(),
         }
     }
 
@@ -907,9 +1004,16 @@ impl SppfImpl {
     }
 
     /// Creates a new single node in the SPPF
-    pub fn new_normal_node_with_children(&mut self, label: TableElemRef, children: &[SppfImplNodeRef]) -> SppfImplNodeRef {
+    pub fn new_normal_node_with_children(
+        &mut self,
+        label: TableElemRef,
+        production: Option<u16>,
+        children: &[SppfImplNodeRef],
+        labels: &[Option<u16>],
+    ) -> SppfImplNodeRef {
         let identifier = self.nodes.len();
-        self.nodes.push(SppfImplNode::new_with_children(label, children));
+        self.nodes
+            .push(SppfImplNode::new_with_children(label, production, children, labels));
         SppfImplNodeRef::new_usize(identifier)
     }
 
@@ -917,8 +1021,8 @@ impl SppfImpl {
     pub fn new_promoted_node(
         &mut self,
         previous: SppfImplNodeRef,
-        head: &[SppfImplNodeRef],
-        tail: &[SppfImplNodeRef],
+        head: (&[SppfImplNodeRef], &[Option<u16>]),
+        tail: (&[SppfImplNodeRef], &[Option<u16>]),
     ) -> SppfImplNodeRef {
         let identifier = self.nodes.len();
         self.nodes.push(self.create_promoted_node(previous, head, tail));
@@ -926,25 +1030,396 @@ impl SppfImpl {
     }
 
     /// Creates a new single node in the SPPF as a promotion of another, with a head and a tail
+    /// The head and the tail are given with the labels of their children.
     #[must_use]
     pub fn create_promoted_node(
         &self,
         previous: SppfImplNodeRef,
-        head: &[SppfImplNodeRef],
-        tail: &[SppfImplNodeRef],
+        head: (&[SppfImplNodeRef], &[Option<u16>]),
+        tail: (&[SppfImplNodeRef], &[Option<u16>]),
     ) -> SppfImplNode {
         let previous = self.get_node(previous);
         match &previous.versions {
             SppfImplNodeVersions::Single(version) => SppfImplNode {
-                versions: SppfImplNodeVersions::Single(version.with_head_tail(head, tail)),
+                versions: SppfImplNodeVersions::Single(version.with_head_tail(head.0, head.1, tail.0, tail.1)),
             },
             SppfImplNodeVersions::Multiple(versions) => SppfImplNode {
                 versions: SppfImplNodeVersions::Multiple(
-                    versions.iter().map(|version| version.with_head_tail(head, tail)).collect(),
+                    versions
+                        .iter()
+                        .map(|version| version.with_head_tail(head.0, head.1, tail.0, tail.1))
+                        .collect(),
                 ),
             },
         }
     }
+
+    /// Finds the ambiguities in this SPPF, i.e. the nodes reachable from the root with multiple versions
+    /// The ambiguities are given in the order the nodes are reached from the root, so that the outermost come first.
+    #[must_use]
+    pub fn find_ambiguities(&self, tokens: &TokenRepository) -> Vec<SppfImplAmbiguity> {
+        let Some(root) = self.root else {
+            return Vec::new();
+        };
+        let mut visited = alloc::vec![false; self.nodes.len()];
+        let mut counts = alloc::vec![None; self.nodes.len()];
+        let mut spans = alloc::vec![(false, None); self.nodes.len()];
+        let mut ambiguities = Vec::new();
+        let mut stack = alloc::vec![root];
+        while let Some(current) = stack.pop() {
+            if visited[current] {
+                continue;
+            }
+            visited[current] = true;
+            let node = &self.nodes[current];
+            if node.versions.len() > 1 {
+                let node_ref = SppfImplNodeRef::new_usize(current);
+                ambiguities.push(SppfImplAmbiguity {
+                    node: node_ref,
+                    label: node.first_version().label,
+                    span: self.get_span_of(current, tokens, &mut spans),
+                    versions: (0..node.versions.len())
+                        .map(|index| node_ref.with_version_usize(index))
+                        .collect(),
+                    trees: self.count_trees_of(current, &mut counts),
+                });
+            }
+            for version in node.versions.iter().rev() {
+                for child in version.children.iter().rev() {
+                    if !visited[child.node_id()] {
+                        stack.push(child.node_id());
+                    }
+                }
+            }
+        }
+        ambiguities
+    }
+
+    /// Counts the number of distinct trees in this SPPF, saturating at `u64::MAX`
+    #[must_use]
+    pub fn count_trees(&self) -> u64 {
+        let mut counts = alloc::vec![None; self.nodes.len()];
+        self.root.map_or(0, |root| self.count_trees_of(root, &mut counts))
+    }
+
+    /// Gets an iterator over the distinct trees in this SPPF, producing at most `limit` trees
+    /// The trees are lazily produced, a tree going through a node within its own sub-tree is skipped.
+    #[must_use]
+    pub fn get_trees(&self, limit: usize) -> SppfTrees<'_> {
+        SppfTrees {
+            sppf: self,
+            choices: Vec::new(),
+            started: false,
+            remaining: if self.root.is_some() { limit } else { 0 },
+        }
+    }
+
+    /// Gets the identifiers of the nodes reachable from the root, in preorder
+    fn get_reachable_nodes(&self) -> Vec<usize> {
+        let Some(root) = self.root else {
+            return Vec::new();
+        };
+        let mut visited = alloc::vec![false; self.nodes.len()];
+        let mut result = Vec::new();
+        let mut stack = alloc::vec![root];
+        while let Some(current) = stack.pop() {
+            if visited[current] {
+                continue;
+            }
+            visited[current] = true;
+            result.push(current);
+            for version in self.nodes[current].versions.iter().rev() {
+                for child in version.children.iter().rev() {
+                    if !visited[child.node_id()] {
+                        stack.push(child.node_id());
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Counts the number of distinct trees for a node, saturating at `u64::MAX`
+    /// A node within its own sub-tree has infinitely many trees, the count then saturates.
+    fn count_trees_of(&self, node_id: usize, counts: &mut [Option<u64>]) -> u64 {
+        if let Some(count) = counts[node_id] {
+            return count;
+        }
+        counts[node_id] = Some(u64::MAX);
+        let mut count: u64 = 0;
+        for version in &self.nodes[node_id].versions {
+            let mut product: u64 = 1;
+            for child in &version.children {
+                product = product.saturating_mul(self.count_trees_of(child.node_id(), counts));
+            }
+            count = count.saturating_add(product);
+        }
+        counts[node_id] = Some(count);
+        count
+    }
+
+    /// Gets the span in the input of the first version of a node, if any
+    /// The spans are memoized as pairs of a flag telling whether the span has been computed and the span itself.
+    fn get_span_of(
+        &self,
+        node_id: usize,
+        tokens: &TokenRepository,
+        spans: &mut [(bool, Option<TextSpan>)],
+    ) -> Option<TextSpan> {
+        if spans[node_id].0 {
+            return spans[node_id].1;
+        }
+        spans[node_id].0 = true;
+        let version = self.nodes[node_id].first_version();
+        let mut result = match version.label.table_type() {
+            TableType::Token => tokens.get_token(version.label.index()).get_span(),
+            _ => None,
+        };
+        for child in &version.children {
+            if let Some(span) = self.get_span_of(child.node_id(), tokens, spans) {
+                result = Some(match result {
+                    None => span,
+                    Some(total) => {
+                        let index = total.index.min(span.index);
+                        let end = (total.index + total.length).max(span.index + span.length);
+                        TextSpan {
+                            index,
+                            length: end - index,
+                        }
+                    }
+                });
+            }
+        }
+        spans[node_id].1 = result;
+        result
+    }
+}
+
+/// An ambiguity in a SPPF, i.e. a node with multiple versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SppfImplAmbiguity {
+    /// The ambiguous node
+    pub node: SppfImplNodeRef,
+    /// The label of the node
+    pub label: TableElemRef,
+    /// The span of the node in the input, if any
+    pub span: Option<TextSpan>,
+    /// The competing versions of the node
+    pub versions: Vec<SppfImplNodeVersRef>,
+    /// The number of distinct trees for the node, saturating at `u64::MAX`
+    pub trees: u64,
+}
+
+impl SppfImplAmbiguity {
+    /// Gets the front for this ambiguity, with the symbol of its node
+    #[must_use]
+    pub fn with_symbols<'s>(
+        &self,
+        tokens: &TokenRepository<'s, '_, '_>,
+        variables: &[Symbol<'s>],
+        virtuals: &[Symbol<'s>],
+    ) -> SppfAmbiguity<'s> {
+        SppfAmbiguity {
+            node: self.node,
+            symbol: get_symbol_for(self.label, tokens, variables, virtuals),
+            span: self.span,
+            versions: self.versions.clone(),
+            trees: self.trees,
+        }
+    }
+
+    /// Gets the part of this ambiguity that an AST built from the SPPF keeps
+    #[must_use]
+    pub fn to_ast(&self) -> AstImplAmbiguity {
+        AstImplAmbiguity {
+            label: self.label,
+            span: self.span,
+            versions: self.versions.len(),
+            trees: self.trees,
+        }
+    }
+}
+
+/// An ambiguity in a parse result, i.e. a node of the SPPF with multiple competing versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SppfAmbiguity<'s> {
+    /// The ambiguous node in the SPPF
+    pub node: SppfImplNodeRef,
+    /// The grammar symbol of the node
+    pub symbol: Symbol<'s>,
+    /// The span of the node in the input, if any
+    pub span: Option<TextSpan>,
+    /// The competing versions of the node
+    pub versions: Vec<SppfImplNodeVersRef>,
+    /// The number of distinct trees for the node, saturating at `u64::MAX`
+    pub trees: u64,
+}
+
+impl Display for SppfAmbiguity<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "`{}` has {} competing versions, for {} distinct trees",
+            self.symbol.name,
+            self.versions.len(),
+            self.trees
+        )
+    }
+}
+
+/// A single tree in a SPPF, i.e. a choice of a version for each node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SppfTree {
+    /// The chosen version of the node
+    pub version: SppfImplNodeVersRef,
+    /// The trees for the children of the chosen version
+    pub children: Vec<SppfTree>,
+}
+
+/// An iterator over the distinct trees in a SPPF
+/// Each tree is identified by the versions chosen for the ambiguous nodes, in the order they are met in the tree.
+/// The iterator enumerates these choices as an odometer, the last choice changing first.
+pub struct SppfTrees<'a> {
+    /// The SPPF
+    sppf: &'a SppfImpl,
+    /// The chosen versions for the ambiguous nodes of the last tree, with their number of versions
+    choices: Vec<(usize, usize)>,
+    /// Whether a first tree has been produced
+    started: bool,
+    /// The remaining number of trees to produce
+    remaining: usize,
+}
+
+impl<'a> SppfTrees<'a> {
+    /// Moves to the next combination of choices, returns `false` when all of them have been produced
+    fn advance(&mut self) -> bool {
+        while let Some(last) = self.choices.last_mut() {
+            if last.0 + 1 < last.1 {
+                last.0 += 1;
+                return true;
+            }
+            self.choices.pop();
+        }
+        false
+    }
+
+    /// Builds the tree for a node following the current choices, appending the first version for new ambiguous nodes
+    /// Returns `None` when the node is met again within its own sub-tree.
+    fn build(&mut self, node_id: usize, cursor: &mut usize, on_path: &mut [bool]) -> Option<SppfTree> {
+        if on_path[node_id] {
+            return None;
+        }
+        let sppf: &'a SppfImpl = self.sppf;
+        let node = &sppf.nodes[node_id];
+        let mut version = 0;
+        if node.versions.len() > 1 {
+            if *cursor == self.choices.len() {
+                self.choices.push((0, node.versions.len()));
+            }
+            version = self.choices[*cursor].0;
+            *cursor += 1;
+        }
+        on_path[node_id] = true;
+        let mut children = Vec::with_capacity(node.versions[version].children.len());
+        for child in &node.versions[version].children {
+            children.push(self.build(child.node_id(), cursor, on_path)?);
+        }
+        on_path[node_id] = false;
+        Some(SppfTree {
+            version: SppfImplNodeVersRef::new_usize(node_id, version),
+            children,
+        })
+    }
+}
+
+impl Iterator for SppfTrees<'_> {
+    type Item = SppfTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            if self.started && !self.advance() {
+                self.remaining = 0;
+                return None;
+            }
+            self.started = true;
+            let root = self.sppf.root?;
+            let mut cursor = 0;
+            let mut on_path = alloc::vec![false; self.sppf.nodes.len()];
+            if let Some(tree) = self.build(root, &mut cursor, &mut on_path) {
+                self.remaining -= 1;
+                return Some(tree);
+            }
+        }
+        None
+    }
+}
+
+impl FusedIterator for SppfTrees<'_> {}
+
+/// A serializable view of the nodes of a SPPF reachable from its root
+#[derive(Debug, Clone, Serialize)]
+pub struct SppfGraph<'s, 'a> {
+    /// The identifier of the root node, if any
+    pub root: Option<usize>,
+    /// The nodes reachable from the root, in preorder
+    pub nodes: Vec<SppfGraphNode<'s, 'a>>,
+}
+
+/// A node in the serializable view of a SPPF
+#[derive(Debug, Clone, Serialize)]
+pub struct SppfGraphNode<'s, 'a> {
+    /// The identifier of the node
+    pub id: usize,
+    /// The grammar symbol of the node
+    pub symbol: Symbol<'s>,
+    /// The value of the node, if it is a token
+    pub value: Option<&'a str>,
+    /// The span of the node in the input, if it is a token
+    pub span: Option<TextSpan>,
+    /// The versions of the node, i.e. the packed nodes
+    pub versions: Vec<SppfGraphVersion>,
+}
+
+/// A version (packed node) in the serializable view of a SPPF
+#[derive(Debug, Clone, Serialize)]
+pub struct SppfGraphVersion {
+    /// The index of the LR production that produced this version, if any
+    pub production: Option<usize>,
+    /// The identifiers of the children nodes
+    pub children: Vec<usize>,
+}
+
+/// Writes a label for the DOT format, escaping the quotes and backslashes
+fn write_dot_label(writer: &mut dyn core::fmt::Write, label: &str) -> Result<(), Error> {
+    for c in label.chars() {
+        match c {
+            '"' => writer.write_str("\\\"")?,
+            '\\' => writer.write_str("\\\\")?,
+            '\n' => writer.write_str("\\n")?,
+            '\r' => {}
+            _ => writer.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Gets the grammar symbol for the label of a node
+pub(crate) fn get_symbol_for<'s>(
+    label: TableElemRef,
+    tokens: &TokenRepository<'s, '_, '_>,
+    variables: &[Symbol<'s>],
+    virtuals: &[Symbol<'s>],
+) -> Symbol<'s> {
+    match label.table_type() {
+        TableType::Token => tokens.get_token(label.index()).get_symbol(),
+        TableType::Variable => variables[label.index()],
+        TableType::Virtual => virtuals[label.index()],
+        TableType::None if label.is_error() => SYMBOL_ERROR,
+        TableType::None => {
+            // terminal epsilon, or a terminal inserted by the error recovery
+            tokens.terminals[label.index()]
+        }
+    }
 }
 
 /// Structure to display an SPPF node
@@ -1054,6 +1529,97 @@ impl<'s, 't, 'a> Sppf<'s, 't, 'a> {
         self.tokens.find_token_at(index).and_then(|token| self.find_node_for(&token))
     }
 
+    /// Gets the ambiguities in this SPPF, i.e. the nodes reachable from the root with multiple versions
+    #[must_use]
+    pub fn get_ambiguities(&self) -> Vec<SppfAmbiguity<'s>> {
+        self.data
+            .find_ambiguities(&self.tokens)
+            .iter()
+            .map(|ambiguity| ambiguity.with_symbols(&self.tokens, self.variables, self.virtuals))
+            .collect()
+    }
+
+    /// Gets an iterator over the distinct trees in this SPPF, producing at most `limit` trees
+    #[must_use]
+    pub fn get_trees(&self, limit: usize) -> SppfTrees<'a> {
+        self.data.get_trees(limit)
+    }
+
+    /// Gets the node version for the specified reference
+    #[must_use]
+    pub fn get_version(&'a self, node_ref: SppfImplNodeVersRef) -> SppfNodeVersion<'s, 't, 'a> {
+        SppfNodeVersion::new(self, node_ref)
+    }
+
+    /// Gets a serializable view of the nodes reachable from the root, with their versions
+    #[must_use]
+    pub fn get_graph(&'a self) -> SppfGraph<'s, 'a> {
+        let nodes = self
+            .data
+            .get_reachable_nodes()
+            .into_iter()
+            .map(|node_id| {
+                let node = &self.data.nodes[node_id];
+                let first = SppfNodeVersion::new(self, SppfImplNodeVersRef::new_usize(node_id, 0));
+                SppfGraphNode {
+                    id: node_id,
+                    symbol: first.get_symbol(),
+                    value: first.get_value(),
+                    span: first.get_span(),
+                    versions: node
+                        .versions
+                        .iter()
+                        .map(|version| SppfGraphVersion {
+                            production: version.production.map(usize::from),
+                            children: version.children.iter().map(SppfImplNodeRef::node_id).collect(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        SppfGraph {
+            root: self.data.root,
+            nodes,
+        }
+    }
+
+    /// Writes the nodes reachable from the root in the Graphviz DOT format
+    /// The versions of ambiguous nodes are written as explicit packed nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the writer
+    pub fn write_dot(&'a self, writer: &mut dyn core::fmt::Write) -> Result<(), Error> {
+        writeln!(writer, "digraph sppf {{")?;
+        writeln!(writer, "    node [shape=box];")?;
+        for node_id in self.data.get_reachable_nodes() {
+            let node = &self.data.nodes[node_id];
+            write!(writer, "    n{node_id} [label=\"")?;
+            let first = SppfNodeVersion::new(self, SppfImplNodeVersRef::new_usize(node_id, 0));
+            write_dot_label(writer, &alloc::format!("{first}"))?;
+            writeln!(writer, "\"];")?;
+            if node.versions.len() == 1 {
+                for child in &node.first_version().children {
+                    writeln!(writer, "    n{node_id} -> n{};", child.node_id())?;
+                }
+                continue;
+            }
+            for (index, version) in node.versions.iter().enumerate() {
+                match version.production {
+                    Some(production) => {
+                        writeln!(writer, "    n{node_id}v{index} [shape=circle, label=\"#{production}\"];")?;
+                    }
+                    None => writeln!(writer, "    n{node_id}v{index} [shape=circle, label=\"{index}\"];")?,
+                }
+                writeln!(writer, "    n{node_id} -> n{node_id}v{index} [style=dashed];")?;
+                for child in &version.children {
+                    writeln!(writer, "    n{node_id}v{index} -> n{};", child.node_id())?;
+                }
+            }
+        }
+        writeln!(writer, "}}")
+    }
+
     /// Gets the parent of the specified node, if any
     #[must_use]
     pub fn find_parent_of(&'a self, node_ref: SppfImplNodeRef) -> Option<SppfNodeVersion<'s, 't, 'a>> {
@@ -1273,8 +1839,9 @@ impl<'s, 't, 'a> Serialize for SppfNode<'s, 't, 'a> {
     {
         if self.versions_count() == 1 {
             let version = self.first_version();
-            let mut state = serializer.serialize_struct("SppfNode", 5)?;
+            let mut state = serializer.serialize_struct("SppfNode", 6)?;
             state.serialize_field("symbol", &version.get_symbol())?;
+            state.serialize_field("production", &version.get_production())?;
             state.serialize_field("position", &version.get_position())?;
             state.serialize_field("span", &version.get_span())?;
             state.serialize_field("value", &version.get_value())?;
@@ -1454,6 +2021,13 @@ impl<'s, 't, 'a> SppfNodeVersion<'s, 't, 'a> {
         self.version.children.len()
     }
 
+    /// Gets the index of the LR production that produced this version, if any
+    /// Only the versions of variables have a production.
+    #[must_use]
+    pub fn get_production(&self) -> Option<usize> {
+        self.version.production.map(usize::from)
+    }
+
     /// Gets the total span for the sub-tree at this node
     #[must_use]
     pub fn get_total_span(&self) -> Option<TextSpan> {
@@ -1492,19 +2066,7 @@ impl<'s, 't, 'a> SemanticElementTrait<'s, 'a> for SppfNodeVersion<'s, 't, 'a> {
 
     /// Gets the grammar symbol associated to this element
     fn get_symbol(&self) -> Symbol<'s> {
-        let label = self.version.label;
-        match label.table_type() {
-            TableType::Token => {
-                let token = self.sppf.tokens.get_token(label.index());
-                token.get_symbol()
-            }
-            TableType::Variable => self.sppf.variables[label.index()],
-            TableType::Virtual => self.sppf.virtuals[label.index()],
-            TableType::None => {
-                // terminal epsilon
-                self.sppf.tokens.terminals[0]
-            }
-        }
+        get_symbol_for(self.version.label, &self.sppf.tokens, self.sppf.variables, self.sppf.virtuals)
     }
 
     /// Gets the value of this element, if any
@@ -1538,8 +2100,9 @@ impl<'s, 't, 'a> Display for SppfNodeVersion<'s, 't, 'a> {
                 let symbol = self.sppf.virtuals[label.index()];
                 write!(f, "{}", symbol.name)
             }
+            TableType::None if label.is_error() => write!(f, "{}", SYMBOL_ERROR.name),
             TableType::None => {
-                let symbol = self.sppf.tokens.terminals[0];
+                let symbol = self.sppf.tokens.terminals[label.index()];
                 write!(f, "{}", symbol.name)
             }
         }
@@ -1551,8 +2114,9 @@ impl<'s, 't, 'a> Serialize for SppfNodeVersion<'s, 't, 'a> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("SppfNodeVersion", 5)?;
+        let mut state = serializer.serialize_struct("SppfNodeVersion", 6)?;
         state.serialize_field("symbol", &self.get_symbol())?;
+        state.serialize_field("production", &self.get_production())?;
         state.serialize_field("position", &self.get_position())?;
         state.serialize_field("span", &self.get_span())?;
         state.serialize_field("value", &self.get_value())?;
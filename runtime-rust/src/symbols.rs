@@ -41,6 +41,14 @@ pub const SID_NOTHING: u32 = 0;
 pub const SID_EPSILON: u32 = 1;
 /// Symbol ID of the Dollar terminal
 pub const SID_DOLLAR: u32 = 2;
+/// Symbol ID of the error symbol, labelling the nodes produced by the error recovery
+pub const SID_ERROR: u32 = 0xFFFF_FFFF;
+
+/// The symbol labelling the nodes produced by the error recovery
+pub const SYMBOL_ERROR: Symbol<'static> = Symbol {
+    id: SID_ERROR,
+    name: "error",
+};
 
 /// Represents a grammar symbol (terminal, variable or virtual)
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
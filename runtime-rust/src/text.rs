@@ -122,6 +122,50 @@ pub struct TextContext<'a> {
     pub pointer: String,
 }
 
+/// Represents an edit of a text input, i.e. the replacement of a span by a new value
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    /// The replaced span in the original text
+    pub span: TextSpan,
+    /// The new value for the span
+    pub value: String,
+}
+
+impl TextEdit {
+    /// Creates a new edit that replaces `length` bytes at `index` by a value
+    #[must_use]
+    pub fn new(index: usize, length: usize, value: &str) -> TextEdit {
+        TextEdit {
+            span: TextSpan { index, length },
+            value: value.to_string(),
+        }
+    }
+}
+
+/// An error in the edits of a text
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextEditError {
+    /// The span of an edit is out of the bounds of the text
+    OutOfBounds(TextSpan),
+    /// The span of an edit does not start or end on a char boundary
+    NotCharBoundary(TextSpan),
+    /// The spans of two edits overlap
+    Overlapping(TextSpan, TextSpan),
+}
+
+/// Implementation of `Display` for `TextEditError`
+impl Display for TextEditError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            TextEditError::OutOfBounds(span) => write!(f, "Edit {span} is out of the bounds of the text"),
+            TextEditError::NotCharBoundary(span) => write!(f, "Edit {span} is not on char boundaries"),
+            TextEditError::Overlapping(first, second) => write!(f, "Edits {first} and {second} overlap"),
+        }
+    }
+}
+
+impl core::error::Error for TextEditError {}
+
 /// Represents the input of parser with some metadata for line endings
 /// All line numbers and column numbers are 1-based.
 /// Indices in the content are 0-based.
@@ -356,6 +400,61 @@ impl<'a> Text<'a> {
             next_cp: None,
         }
     }
+
+    /// Checks that edits may be applied to this text
+    /// The spans of the edits must be within this text, on char boundaries, and must not overlap.
+    ///
+    /// # Errors
+    ///
+    /// Return the first invalid edit, or the first pair of overlapping ones
+    pub fn check_edits(&self, edits: &[TextEdit]) -> Result<(), TextEditError> {
+        let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+        sorted.sort_by_key(|edit| edit.span);
+        let mut previous: Option<TextSpan> = None;
+        for edit in sorted {
+            let span = edit.span;
+            let end = span.index.checked_add(span.length).filter(|&end| end <= self.content.len());
+            let Some(end) = end else {
+                return Err(TextEditError::OutOfBounds(span));
+            };
+            if !self.content.is_char_boundary(span.index) || !self.content.is_char_boundary(end) {
+                return Err(TextEditError::NotCharBoundary(span));
+            }
+            if let Some(previous) = previous {
+                if span.index < previous.index + previous.length {
+                    return Err(TextEditError::Overlapping(previous, span));
+                }
+            }
+            previous = Some(span);
+        }
+        Ok(())
+    }
+
+    /// Gets the text resulting from the application of edits to this one.
+    /// The spans of all the edits refer to this (original) text, in any order.
+    ///
+    /// # Errors
+    ///
+    /// Return an error when the edits are not valid for this text, see `check_edits`
+    pub fn apply_edits(&self, edits: &[TextEdit]) -> Result<Text<'static>, TextEditError> {
+        self.check_edits(edits)?;
+        Ok(self.apply_checked_edits(edits))
+    }
+
+    /// Gets the text resulting from the application of edits that have been checked
+    pub(crate) fn apply_checked_edits(&self, edits: &[TextEdit]) -> Text<'static> {
+        let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+        sorted.sort_by_key(|edit| edit.span);
+        let mut content = String::with_capacity(self.content.len());
+        let mut index = 0;
+        for edit in sorted {
+            content.push_str(&self.content[index..edit.span.index]);
+            content.push_str(&edit.value);
+            index = edit.span.index + edit.span.length;
+        }
+        content.push_str(&self.content[index..]);
+        Text::from_string(content)
+    }
 }
 
 /// An iterator over UTF-16 code points in the input text
@@ -17,8 +17,11 @@
 
 //! Module for the definition of lexical tokens
 
-use crate::symbols::{SemanticElementTrait, Symbol};
-use crate::text::{Text, TextContext, TextPosition, TextSpan};
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+
+use crate::symbols::{SemanticElementTrait, Symbol, SYMBOL_ERROR};
+use crate::text::{Text, TextContext, TextEdit, TextPosition, TextSpan};
 use crate::utils::biglist::BigList;
 use crate::utils::EitherMut;
 
@@ -26,16 +29,169 @@ use crate::utils::EitherMut;
 #[derive(Debug, Copy, Clone, Default)]
 struct TokenRepositoryCell {
     /// The terminal's index
-    terminal: usize,
+    terminal: u32,
+    /// The number of bytes after the token that the lexer examined up to this token
+    /// `u32::MAX` when unknown
+    lookahead: u32,
     /// The span of this token
     span: TextSpan,
 }
 
+impl TokenRepositoryCell {
+    /// Gets the index in the text up to which the lexer examined the input when matching this token
+    fn get_reach(&self) -> usize {
+        if self.lookahead == u32::MAX {
+            usize::MAX
+        } else {
+            self.span.index + self.span.length + self.lookahead as usize
+        }
+    }
+}
+
+/// The terminal index for trivia made of text skipped by the lexer
+const TRIVIA_SKIPPED: u32 = u32::MAX;
+
+/// The trivia kept between the tokens, i.e. the separators and the text skipped by the lexer
+#[derive(Debug, Default, Clone)]
+struct TokenTrivia {
+    /// The trivia elements
+    cells: BigList<TokenRepositoryCell>,
+    /// For each token, the number of trivia elements before it
+    ends: BigList<usize>,
+}
+
+/// The data for the reuse of the tokens of a previous parse, after some edits of the input
+#[derive(Debug, Default, Clone)]
+struct TokenReuse {
+    /// The tokens of the previous parse, kept until the lexer synchronizes on them
+    previous: BigList<TokenRepositoryCell>,
+    /// The number of tokens kept at the start
+    prefix: usize,
+    /// The index in the new text at which the lexer restarts
+    restart: usize,
+    /// The index up to which the lexer examined the text for the tokens that are kept at the start
+    reach: usize,
+    /// The end of the last edit in the previous text
+    old_sync: usize,
+    /// The end of the last edit in the new text
+    new_sync: usize,
+    /// The index in the previous tokens and in the new ones of the first token reused after the edits, if any
+    splice: Option<(usize, usize)>,
+    /// The number of tokens reused from the previous parse
+    reused: usize,
+}
+
 /// Implementation data of a repository of matched tokens
 #[derive(Debug, Default, Clone)]
 pub struct TokenRepositoryImpl {
     /// The token data in this content
     cells: BigList<TokenRepositoryCell>,
+    /// The reuse data of the tokens of a previous parse, if any
+    reuse: Option<Box<TokenReuse>>,
+    /// The trivia between the tokens, when kept
+    trivia: Option<Box<TokenTrivia>>,
+}
+
+impl TokenRepositoryImpl {
+    /// Creates a repository that keeps the trivia between the tokens
+    #[must_use]
+    pub fn new_lossless() -> TokenRepositoryImpl {
+        TokenRepositoryImpl {
+            cells: BigList::default(),
+            reuse: None,
+            trivia: Some(Box::default()),
+        }
+    }
+
+    /// Gets whether this repository keeps the trivia between the tokens
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.trivia.is_some()
+    }
+
+    /// Prepares a repository for the lexing of a text after edits, reusing the tokens of a previous lexing
+    /// The tokens at the start whose lexing did not examine the edited text are kept as is.
+    /// The tokens after the edits are kept aside so that the lexer can synchronize on them.
+    pub(crate) fn new_reusing(previous: TokenRepositoryImpl, length: usize, edits: &[TextEdit]) -> TokenRepositoryImpl {
+        let start = edits.iter().map(|edit| edit.span.index).min().unwrap_or(length);
+        let old_sync = edits
+            .iter()
+            .map(|edit| edit.span.index + edit.span.length)
+            .max()
+            .unwrap_or(length);
+        let removed: usize = edits.iter().map(|edit| edit.span.length).sum();
+        let added: usize = edits.iter().map(|edit| edit.value.len()).sum();
+        // the reach of the tokens is monotonic, find the first one that reaches the edits
+        let mut l = 0;
+        let mut r = previous.cells.len();
+        while l < r {
+            let m = l + (r - l) / 2;
+            if previous.cells[m].get_reach() <= start {
+                l = m + 1;
+            } else {
+                r = m;
+            }
+        }
+        let mut cells = BigList::default();
+        for i in 0..l {
+            cells.push(previous.cells[i]);
+        }
+        let (restart, reach) = if l == 0 {
+            (0, 0)
+        } else {
+            let last = previous.cells[l - 1];
+            (last.span.index + last.span.length, last.get_reach())
+        };
+        TokenRepositoryImpl {
+            cells,
+            reuse: Some(Box::new(TokenReuse {
+                previous: previous.cells,
+                prefix: l,
+                restart,
+                reach,
+                old_sync,
+                new_sync: old_sync + added - removed,
+                splice: None,
+                reused: l,
+            })),
+            trivia: None,
+        }
+    }
+
+    /// Prepares a repository for the parsing of an input ending with a token that has already been lexed
+    /// The tokens of the previous repository before the end of the input are kept, followed by the new token,
+    /// so that the lexer only matches the end of the input after them.
+    pub(crate) fn new_appending(previous: &TokenRepositoryImpl, terminal: usize, span: TextSpan) -> TokenRepositoryImpl {
+        let mut count = previous.cells.len();
+        if count > 0 && previous.cells[count - 1].terminal == 1 {
+            // the $ symbol, always at index 1
+            count -= 1;
+        }
+        let mut cells = BigList::default();
+        for i in 0..count {
+            cells.push(previous.cells[i]);
+        }
+        cells.push(TokenRepositoryCell {
+            terminal: terminal as u32,
+            lookahead: 0,
+            span,
+        });
+        let end = span.index + span.length;
+        TokenRepositoryImpl {
+            cells,
+            reuse: Some(Box::new(TokenReuse {
+                previous: BigList::default(),
+                prefix: count + 1,
+                restart: end,
+                reach: end,
+                old_sync: end,
+                new_sync: end,
+                splice: None,
+                reused: count,
+            })),
+            trivia: None,
+        }
+    }
 }
 
 /// The proxy structure for a repository of matched tokens
@@ -122,12 +278,137 @@ impl<'s, 't, 'a> TokenRepository<'s, 't, 'a> {
 
     /// Registers a new token in this repository
     pub fn add(&mut self, terminal: usize, index: usize, length: usize) -> usize {
+        self.add_with_reach(terminal, index, length, usize::MAX)
+    }
+
+    /// Registers a new token in this repository
+    /// with the index in the text up to which the lexer examined the input so far
+    pub fn add_with_reach(&mut self, terminal: usize, index: usize, length: usize, reach: usize) -> usize {
+        let lookahead = u32::try_from(reach.saturating_sub(index + length)).unwrap_or(u32::MAX);
+        if let Some(trivia) = self.data.trivia.as_mut() {
+            trivia.ends.push(trivia.cells.len());
+        }
         self.data.cells.push(TokenRepositoryCell {
-            terminal,
+            terminal: terminal as u32,
+            lookahead,
             span: TextSpan { index, length },
         })
     }
 
+    /// Gets whether this repository keeps the trivia between the tokens
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.data.is_lossless()
+    }
+
+    /// Registers a trivia element before the next token, when the trivia are kept
+    /// The terminal is the separator, or `None` for text skipped by the lexer.
+    pub(crate) fn add_trivia(&mut self, terminal: Option<usize>, index: usize, length: usize) {
+        let Some(trivia) = self.data.trivia.as_mut() else {
+            return;
+        };
+        let terminal = terminal.map_or(TRIVIA_SKIPPED, |terminal| terminal as u32);
+        let count = trivia.cells.len();
+        if terminal == TRIVIA_SKIPPED && count > 0 {
+            // extend the previous piece of skipped text, if any
+            let last = &mut trivia.cells[count - 1];
+            if last.terminal == TRIVIA_SKIPPED && last.span.index + last.span.length == index {
+                last.span.length += length;
+                return;
+            }
+        }
+        trivia.cells.push(TokenRepositoryCell {
+            terminal,
+            lookahead: u32::MAX,
+            span: TextSpan { index, length },
+        });
+    }
+
+    /// Discards the tokens of a previous lexing, so that the lexer starts over from the beginning
+    pub(crate) fn discard_reuse(&mut self) {
+        self.data.cells = BigList::default();
+        self.data.reuse = None;
+    }
+
+    /// Gets the index in the text at which the lexer shall start,
+    /// together with the index up to which the lexer examined the input for the tokens already in this repository
+    pub(crate) fn get_restart(&self) -> (usize, usize) {
+        self.data.reuse.as_ref().map_or((0, 0), |reuse| (reuse.restart, reuse.reach))
+    }
+
+    /// Tries to synchronize the lexer on the tokens of a previous lexing when it is about to match at the specified index
+    /// Returns `true` when the remaining tokens of the previous lexing have been reused
+    pub(crate) fn try_resync(&mut self, index: usize, reach: usize) -> bool {
+        let data = &mut *self.data;
+        let Some(reuse) = data.reuse.as_mut() else {
+            return false;
+        };
+        if index < reuse.new_sync || reuse.previous.is_empty() {
+            return false;
+        }
+        // look for a previous token starting at the same place
+        let target = index - reuse.new_sync + reuse.old_sync;
+        let mut l = reuse.prefix;
+        let mut r = reuse.previous.len();
+        while l < r {
+            let m = l + (r - l) / 2;
+            match reuse.previous[m].span.index.cmp(&target) {
+                Ordering::Less => l = m + 1,
+                Ordering::Greater => r = m,
+                Ordering::Equal => {
+                    reuse.splice = Some((m, data.cells.len()));
+                    for i in m..reuse.previous.len() {
+                        let mut cell = reuse.previous[i];
+                        cell.span.index = cell.span.index - reuse.old_sync + reuse.new_sync;
+                        // the lexer may have examined more of the input before synchronizing
+                        let end = cell.span.index + cell.span.length;
+                        if cell.lookahead != u32::MAX && reach > end + cell.lookahead as usize {
+                            cell.lookahead = u32::try_from(reach - end).unwrap_or(u32::MAX);
+                        }
+                        data.cells.push(cell);
+                    }
+                    reuse.reused += reuse.previous.len() - m;
+                    reuse.previous = BigList::default();
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Ends the reuse of the tokens of a previous lexing
+    pub(crate) fn end_reuse(&mut self) {
+        if let Some(reuse) = self.data.reuse.as_mut() {
+            reuse.previous = BigList::default();
+        }
+    }
+
+    /// Gets the index of the token of a previous lexing that was reused for the specified token, if any
+    /// Also gets the exclusive upper bound on the indices of the previous tokens that were reused along with it.
+    pub(crate) fn get_previous_index(&self, index: usize) -> Option<(usize, usize)> {
+        let reuse = self.data.reuse.as_ref()?;
+        if index < reuse.prefix {
+            return Some((index, reuse.prefix));
+        }
+        let (old, new) = reuse.splice?;
+        if index >= new {
+            Some((index - new + old, usize::MAX))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the exclusive upper bound of the indices in the text that the lexer examined for the specified token
+    pub(crate) fn get_reach(&self, index: usize) -> usize {
+        self.data.cells[index].get_reach()
+    }
+
+    /// Gets the number of tokens reused from a previous lexing, after some edits of the input
+    #[must_use]
+    pub fn get_reused_count(&self) -> usize {
+        self.data.reuse.as_ref().map_or(0, |reuse| reuse.reused)
+    }
+
     /// Gets the number of tokens in this repository
     #[must_use]
     pub fn get_tokens_count(&self) -> usize {
@@ -137,7 +418,7 @@ impl<'s, 't, 'a> TokenRepository<'s, 't, 'a> {
     /// Gets the terminal's identifier for the i-th token
     #[must_use]
     pub fn get_symbol_id_for(&self, index: usize) -> u32 {
-        self.terminals[self.data.cells[index].terminal].id
+        self.terminals[self.data.cells[index].terminal as usize].id
     }
 
     /// Gets the i-th token
@@ -193,6 +474,27 @@ impl<'s, 't, 'a> IntoIterator for &'a TokenRepository<'s, 't, 'a> {
     }
 }
 
+impl<'s, 't, 'a> Token<'s, 't, 'a> {
+    /// Gets the trivia before this token, i.e. the separators and the text skipped by the lexer
+    /// The trivia are only kept by lossless repositories.
+    /// The trivia at the end of the input are those before the dollar token.
+    #[must_use]
+    pub fn get_leading_trivia(&self) -> TriviaIterator<'s, 't, 'a> {
+        let (index, end) = match self.repository.data.trivia.as_ref() {
+            None => (0, 0),
+            Some(trivia) => (
+                if self.index == 0 { 0 } else { trivia.ends[self.index - 1] },
+                trivia.ends[self.index],
+            ),
+        };
+        TriviaIterator {
+            repository: self.repository,
+            index,
+            end,
+        }
+    }
+}
+
 impl<'s, 't, 'a> SemanticElementTrait<'s, 'a> for Token<'s, 't, 'a> {
     /// Gets the position in the input text of this element
     #[must_use]
@@ -222,7 +524,7 @@ impl<'s, 't, 'a> SemanticElementTrait<'s, 'a> for Token<'s, 't, 'a> {
     /// Gets the grammar symbol associated to this element
     #[must_use]
     fn get_symbol(&self) -> Symbol<'s> {
-        self.repository.terminals[self.repository.data.cells[self.index].terminal]
+        self.repository.terminals[self.repository.data.cells[self.index].terminal as usize]
     }
 
     /// Gets the value of this element, if any
@@ -235,3 +537,84 @@ impl<'s, 't, 'a> SemanticElementTrait<'s, 'a> for Token<'s, 't, 'a> {
         )
     }
 }
+
+/// Represents a trivia element before a token, i.e. a separator or some text skipped by the lexer
+#[derive(Copy, Clone)]
+pub struct Trivia<'s, 't, 'a> {
+    /// The repository containing this trivia
+    repository: &'a TokenRepository<'s, 't, 'a>,
+    /// The index of this trivia in the repository
+    pub index: usize,
+}
+
+/// The iterator over the trivia before a token
+pub struct TriviaIterator<'s, 't, 'a> {
+    /// The repository containing the trivia
+    repository: &'a TokenRepository<'s, 't, 'a>,
+    /// The current index within the trivia
+    index: usize,
+    /// The index of the first trivia after the token
+    end: usize,
+}
+
+/// Implementation of `Iterator` for `TriviaIterator`
+impl<'s, 't, 'a> Iterator for TriviaIterator<'s, 't, 'a> {
+    type Item = Trivia<'s, 't, 'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            None
+        } else {
+            let result = Trivia {
+                repository: self.repository,
+                index: self.index,
+            };
+            self.index += 1;
+            Some(result)
+        }
+    }
+}
+
+impl Trivia<'_, '_, '_> {
+    /// Gets the data of this trivia
+    fn get_cell(&self) -> TokenRepositoryCell {
+        self.repository.data.trivia.as_ref().unwrap().cells[self.index]
+    }
+
+    /// Gets whether this trivia is some text skipped by the lexer
+    #[must_use]
+    pub fn is_skipped(&self) -> bool {
+        self.get_cell().terminal == TRIVIA_SKIPPED
+    }
+}
+
+impl<'s, 'a> SemanticElementTrait<'s, 'a> for Trivia<'s, '_, 'a> {
+    fn get_position(&self) -> Option<TextPosition> {
+        Some(self.repository.text.get_position_at(self.get_cell().span.index))
+    }
+
+    fn get_span(&self) -> Option<TextSpan> {
+        Some(self.get_cell().span)
+    }
+
+    fn get_context(&self) -> Option<TextContext<'a>> {
+        let span = self.get_cell().span;
+        Some(
+            self.repository
+                .text
+                .get_context_for(self.repository.text.get_position_at(span.index), span.length),
+        )
+    }
+
+    /// Gets the grammar symbol associated to this element
+    /// This is the error symbol for some text skipped by the lexer.
+    fn get_symbol(&self) -> Symbol<'s> {
+        match self.get_cell().terminal {
+            TRIVIA_SKIPPED => SYMBOL_ERROR,
+            terminal => self.repository.terminals[terminal as usize],
+        }
+    }
+
+    fn get_value(&self) -> Option<&'a str> {
+        Some(self.repository.text.get_value_for(self.get_cell().span))
+    }
+}
@@ -58,6 +58,12 @@ pub enum Error {
     /// The grammar's axiom is not defined (does not exist)
     /// (`grammar_index`)
     AxiomNotDefined(usize),
+    /// An entry point specified by a grammar is not defined (does not exist)
+    /// (`grammar_index`, entry)
+    EntryNotDefined(usize, String),
+    /// A variable uses a name reserved for the generated axioms
+    /// (`grammar_index`, variable)
+    VariableNameReserved(usize, String),
     /// The separator token specified by a grammar is not defined
     /// (`grammar_index`)
     SeparatorNotDefined(usize),
@@ -73,12 +79,18 @@ pub enum Error {
     TemplateRuleWrongNumberOfArgs(InputReference, usize, usize),
     /// The specifiec symbol was not found
     SymbolNotFound(InputReference, String),
+    /// An external terminal is used in the definition of another terminal
+    ExternalTerminalReferenced(InputReference, String),
+    /// The lexer mode pushed by a terminal is not defined as a context
+    ModeNotDefined(InputReference, String),
     /// Invalid character span
     InvalidCharacterSpan(InputReference),
     /// The unicode block is not known
     UnknownUnicodeBlock(InputReference, String),
     /// The unicode category is not known
     UnknownUnicodeCategory(InputReference, String),
+    /// The disambiguation annotation on a rule is not valid
+    InvalidAnnotation(InputReference, String),
     /// A unicode character not in plane 0 was used in a character class, which is not supported
     UnsupportedNonPlane0InCharacterClass(InputReference, char),
     /// The specified value is not a valid unicode code point
@@ -96,6 +108,19 @@ pub enum Error {
     /// A terminal matches the empty string
     /// (`grammar_index`, terminal)
     TerminalMatchesEmpty(usize, TerminalRef),
+    /// Precedences are declared but the parsing method cannot use them
+    PrecedencesNotSupported(usize),
+    /// A terminal has a trailing context but the target runtime cannot match it
+    /// (`grammar_index`, terminal)
+    TrailingContextNotSupported(usize, TerminalRef),
+    /// A terminal acts on the lexer modes but the target runtime cannot follow them
+    /// (`grammar_index`, terminal)
+    ModesNotSupported(usize, TerminalRef),
+    /// An external terminal cannot be matched because the target runtime has no scanner for it
+    /// (`grammar_index`, terminal)
+    ExternalTerminalNotSupported(usize, TerminalRef),
+    /// The grammar is indentation-sensitive but the target runtime cannot insert the indentation tokens
+    IndentationNotSupported(usize),
 }
 
 impl From<io::Error> for Error {
@@ -119,6 +144,10 @@ impl Display for Error {
                 write!(f, "Grammar axiom has not been specified")
             }
             Error::AxiomNotDefined(_grammar_index) => write!(f, "Grammar axiom is not defined"),
+            Error::EntryNotDefined(_grammar_index, name) => write!(f, "Grammar entry `{name}` is not defined"),
+            Error::VariableNameReserved(_grammar_index, name) => {
+                write!(f, "Variable `{name}` uses a name reserved for the generated axioms")
+            }
             Error::SeparatorNotDefined(_grammar_index) => {
                 write!(f, "Grammar separator token is not defined",)
             }
@@ -135,6 +164,10 @@ impl Display for Error {
                 write!(f, "Template expected {expected} arguments, {provided} given")
             }
             Self::SymbolNotFound(_input, name) => write!(f, "Cannot find symbol `{name}`"),
+            Self::ExternalTerminalReferenced(_input, name) => {
+                write!(f, "External terminal `{name}` cannot be used in the definition of a terminal")
+            }
+            Self::ModeNotDefined(_input, name) => write!(f, "Lexer mode `{name}` is not defined"),
             Self::InvalidCharacterSpan(_input) => {
                 write!(f, "Invalid character span, swap left and right bounds")
             }
@@ -144,6 +177,10 @@ impl Display for Error {
             Self::UnknownUnicodeCategory(_input, name) => {
                 write!(f, "Unknown unicode category `{name}`")
             }
+            Self::InvalidAnnotation(_input, name) => write!(
+                f,
+                "Invalid annotation `%{name}`, expected `%prefer`, `%avoid` or `%priority:N`"
+            ),
             Self::UnsupportedNonPlane0InCharacterClass(_input, c) => write!(
                 f,
                 "Unsupported non-plane 0 Unicode character {} (U+{:04X}) in character class",
@@ -176,6 +213,21 @@ impl Display for Error {
             Self::TerminalMatchesEmpty(_grammar_index, _terminal_ref) => {
                 write!(f, "Terminal matches empty string, which is not allowed",)
             }
+            Self::PrecedencesNotSupported(_grammar_index) => {
+                write!(f, "Precedences are not supported by the LR(0) parsing method")
+            }
+            Self::TrailingContextNotSupported(_grammar_index, _terminal_ref) => {
+                write!(f, "Trailing contexts are only supported by the Rust runtime")
+            }
+            Self::ModesNotSupported(_grammar_index, _terminal_ref) => {
+                write!(f, "Lexer modes are only supported by the Rust runtime")
+            }
+            Self::ExternalTerminalNotSupported(_grammar_index, _terminal_ref) => {
+                write!(f, "External terminals are only supported by the Rust runtime")
+            }
+            Self::IndentationNotSupported(_grammar_index) => {
+                write!(f, "Indentation-sensitive lexers are only supported by the Rust runtime")
+            }
         }
     }
 }
@@ -228,6 +280,10 @@ impl<'context, 'error, 't> Display for ContextualizedError<'context, 'error, 't>
                 let option = self.context.grammars[*grammar_index].get_option(OPTION_AXIOM).unwrap();
                 write!(f, "Grammar axiom `{}` is not defined", &option.value)
             }
+            Error::EntryNotDefined(_grammar_index, name) => write!(f, "Grammar entry `{name}` is not defined"),
+            Error::VariableNameReserved(_grammar_index, name) => {
+                write!(f, "Variable `{name}` uses a name reserved for the generated axioms")
+            }
             Error::SeparatorNotDefined(grammar_index) => {
                 let option = self.context.grammars[*grammar_index].get_option(OPTION_SEPARATOR).unwrap();
                 write!(f, "Grammar separator token `{}` is not defined", &option.value)
@@ -255,6 +311,10 @@ impl<'context, 'error, 't> Display for ContextualizedError<'context, 'error, 't>
                 write!(f, "Template expected {expected} arguments, {provided} given")
             }
             Error::SymbolNotFound(_input, name) => write!(f, "Cannot find symbol `{name}`"),
+            Error::ExternalTerminalReferenced(_input, name) => {
+                write!(f, "External terminal `{name}` cannot be used in the definition of a terminal")
+            }
+            Error::ModeNotDefined(_input, name) => write!(f, "Lexer mode `{name}` is not defined"),
             Error::InvalidCharacterSpan(_input) => {
                 write!(f, "Invalid character span, swap left and right bounds")
             }
@@ -264,6 +324,10 @@ impl<'context, 'error, 't> Display for ContextualizedError<'context, 'error, 't>
             Error::UnknownUnicodeCategory(_input, name) => {
                 write!(f, "Unknown unicode category `{name}`")
             }
+            Error::InvalidAnnotation(_input, name) => write!(
+                f,
+                "Invalid annotation `%{name}`, expected `%prefer`, `%avoid` or `%priority:N`"
+            ),
             Error::UnsupportedNonPlane0InCharacterClass(_input, c) => write!(
                 f,
                 "Unsupported non-plane 0 Unicode character {} (U+{:04X}) in character class",
@@ -301,6 +365,42 @@ impl<'context, 'error, 't> Display for ContextualizedError<'context, 'error, 't>
                     .unwrap();
                 write!(f, "Terminal `{}` matches empty string, which is not allowed", &terminal.name)
             }
+            Error::PrecedencesNotSupported(_grammar_index) => {
+                write!(f, "Precedences are not supported by the LR(0) parsing method")
+            }
+            Error::TrailingContextNotSupported(grammar_index, terminal_ref) => {
+                let terminal = self.context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap();
+                write!(
+                    f,
+                    "Terminal `{}` has a trailing context, which is only supported by the Rust runtime",
+                    &terminal.name
+                )
+            }
+            Error::ModesNotSupported(grammar_index, terminal_ref) => {
+                let terminal = self.context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap();
+                write!(
+                    f,
+                    "Terminal `{}` acts on the lexer modes, which are only supported by the Rust runtime",
+                    &terminal.name
+                )
+            }
+            Error::ExternalTerminalNotSupported(grammar_index, terminal_ref) => {
+                let terminal = self.context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap();
+                write!(
+                    f,
+                    "Terminal `{}` is external, which is only supported by the Rust runtime",
+                    &terminal.name
+                )
+            }
+            Error::IndentationNotSupported(_grammar_index) => {
+                write!(f, "Indentation-sensitive lexers are only supported by the Rust runtime")
+            }
         }
     }
 }
@@ -311,6 +411,102 @@ impl<'context, 'error, 't> std::error::Error for ContextualizedError<'context, '
     }
 }
 
+/// A warning about a grammar, which does not prevent its compilation
+#[derive(Debug, Clone)]
+pub enum Warning {
+    /// A terminal is defined but never used
+    /// (`grammar_index`, terminal)
+    UnusedTerminal(usize, TerminalRef),
+    /// A variable cannot be reached from the grammar's axiom
+    /// (`grammar_index`, `variable_id`)
+    UnreachableVariable(usize, usize),
+    /// A variable can never derive a string of terminals
+    /// (`grammar_index`, `variable_id`)
+    UnproductiveVariable(usize, usize),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnusedTerminal(_grammar_index, _terminal_ref) => write!(f, "Terminal is defined but never used"),
+            Self::UnreachableVariable(_grammar_index, _variable_id) => {
+                write!(f, "Variable is not reachable from the grammar axiom")
+            }
+            Self::UnproductiveVariable(_grammar_index, _variable_id) => {
+                write!(f, "Variable can never derive a string of terminals")
+            }
+        }
+    }
+}
+
+impl Warning {
+    /// Transform into this warning into one with its context
+    #[must_use]
+    pub fn with_context<'context, 'warning, 't>(
+        &'warning self,
+        context: &'context LoadedData<'t>,
+    ) -> ContextualizedWarning<'context, 'warning, 't> {
+        ContextualizedWarning { context, warning: self }
+    }
+
+    /// Gets the reference in the input for the symbol this warning is about
+    ///
+    /// # Panics
+    ///
+    /// Panic when the terminal cannot be found in the grammar
+    #[must_use]
+    pub fn get_input_ref(&self, context: &LoadedData) -> InputReference {
+        match self {
+            Warning::UnusedTerminal(grammar_index, terminal_ref) => {
+                context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap()
+                    .input_ref
+            }
+            Warning::UnreachableVariable(grammar_index, variable_id)
+            | Warning::UnproductiveVariable(grammar_index, variable_id) => {
+                let grammar = &context.grammars[*grammar_index];
+                grammar
+                    .get_variable(*variable_id)
+                    .and_then(|variable| variable.rules.first())
+                    .map_or(grammar.input_ref, |rule| rule.head_input_ref)
+            }
+        }
+    }
+}
+
+/// A warning associated to its contextual data
+#[derive(Debug)]
+pub struct ContextualizedWarning<'context, 'warning, 't> {
+    /// The contextual data
+    pub context: &'context LoadedData<'t>,
+    /// The warning itself
+    pub warning: &'warning Warning,
+}
+
+impl Display for ContextualizedWarning<'_, '_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.warning {
+            Warning::UnusedTerminal(grammar_index, terminal_ref) => {
+                let terminal = self.context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap();
+                write!(f, "Terminal `{}` is defined but never used", &terminal.name)
+            }
+            Warning::UnreachableVariable(grammar_index, variable_id) => {
+                let variable = self.context.grammars[*grammar_index].get_variable(*variable_id).unwrap();
+                write!(f, "Variable `{}` is not reachable from the grammar axiom", &variable.name)
+            }
+            Warning::UnproductiveVariable(grammar_index, variable_id) => {
+                let variable = self.context.grammars[*grammar_index].get_variable(*variable_id).unwrap();
+                write!(f, "Variable `{}` can never derive a string of terminals", &variable.name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContextualizedWarning<'_, '_, '_> {}
+
 /// A collection of errors
 #[derive(Debug)]
 pub struct Errors<'t> {
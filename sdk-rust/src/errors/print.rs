@@ -20,9 +20,11 @@
 use hime_redist::text::TextPosition;
 use miette::{Diagnostic, LabeledSpan, MietteError, Severity, SourceCode, SourceOffset, SourceSpan, SpanContents};
 
-use super::{ContextualizedError, Error};
-use crate::grammars::{OPTION_AXIOM, OPTION_SEPARATOR};
-use crate::lr::LookaheadOrigin;
+use super::{ContextualizedError, ContextualizedWarning, Error};
+use crate::grammars::{
+    Grammar, SymbolRef, GENERATED_AXIOM, OPTION_AXIOM, OPTION_ENTRIES, OPTION_INDENTATION, OPTION_SEPARATOR,
+};
+use crate::lr::{ConflictKind, Counterexample, Derivation, LookaheadOrigin};
 use crate::{InputReference, LoadedInput};
 
 /// The content for a miette span
@@ -160,6 +162,8 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
             Error::InvalidOption(grammar_index, _name, _valid) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::AxiomNotSpecified(grammar_index) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::AxiomNotDefined(grammar_index) => Some(self.get_source_code_for_grammar(*grammar_index)),
+            Error::EntryNotDefined(grammar_index, _name) => Some(self.get_source_code_for_grammar(*grammar_index)),
+            Error::VariableNameReserved(grammar_index, _name) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::SeparatorNotDefined(grammar_index) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::SeparatorIsContextual(grammar_index, _terminal_ref) => {
                 Some(self.get_source_code_for_grammar(*grammar_index))
@@ -168,9 +172,12 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
             Error::TemplateRuleNotFound(input, _name) => Some(&self.context.inputs[input.input_index]),
             Error::TemplateRuleWrongNumberOfArgs(input, _expected, _provided) => Some(&self.context.inputs[input.input_index]),
             Error::SymbolNotFound(input, _name) => Some(&self.context.inputs[input.input_index]),
+            Error::ExternalTerminalReferenced(input, _name) => Some(&self.context.inputs[input.input_index]),
+            Error::ModeNotDefined(input, _name) => Some(&self.context.inputs[input.input_index]),
             Error::InvalidCharacterSpan(input) => Some(&self.context.inputs[input.input_index]),
             Error::UnknownUnicodeBlock(input, _name) => Some(&self.context.inputs[input.input_index]),
             Error::UnknownUnicodeCategory(input, _name) => Some(&self.context.inputs[input.input_index]),
+            Error::InvalidAnnotation(input, _name) => Some(&self.context.inputs[input.input_index]),
             Error::UnsupportedNonPlane0InCharacterClass(input, _c) => Some(&self.context.inputs[input.input_index]),
             Error::InvalidCodePoint(input, _c) => Some(&self.context.inputs[input.input_index]),
             Error::OverridingPreviousTerminal(input, _name, _previous) => Some(&self.context.inputs[input.input_index]),
@@ -179,6 +186,15 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
             Error::TerminalOutsideContext(grammar_index, _error) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::TerminalCannotBeMatched(grammar_index, _error) => Some(self.get_source_code_for_grammar(*grammar_index)),
             Error::TerminalMatchesEmpty(grammar_index, _terminal_ref) => Some(self.get_source_code_for_grammar(*grammar_index)),
+            Error::PrecedencesNotSupported(grammar_index) => Some(self.get_source_code_for_grammar(*grammar_index)),
+            Error::TrailingContextNotSupported(grammar_index, _terminal_ref) => {
+                Some(self.get_source_code_for_grammar(*grammar_index))
+            }
+            Error::ModesNotSupported(grammar_index, _terminal_ref) => Some(self.get_source_code_for_grammar(*grammar_index)),
+            Error::ExternalTerminalNotSupported(grammar_index, _terminal_ref) => {
+                Some(self.get_source_code_for_grammar(*grammar_index))
+            }
+            Error::IndentationNotSupported(grammar_index) => Some(self.get_source_code_for_grammar(*grammar_index)),
         }
     }
 
@@ -199,6 +215,24 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
                 let option = self.context.grammars[*grammar_index].get_option(OPTION_AXIOM).unwrap();
                 Some(self.get_single_label_with_input(&option.value_input_ref))
             }
+            Error::EntryNotDefined(grammar_index, _name) => {
+                let option = self.context.grammars[*grammar_index].get_option(OPTION_ENTRIES).unwrap();
+                Some(self.get_single_label_with_input(&option.value_input_ref))
+            }
+            Error::VariableNameReserved(grammar_index, name) => {
+                let grammar = &self.context.grammars[*grammar_index];
+                match grammar
+                    .get_variable_for_name(name)
+                    .and_then(|variable| variable.rules.first())
+                {
+                    Some(rule) => Some(self.get_single_label_with_input(&rule.head_input_ref)),
+                    None => Some(self.get_single_label_with_grammar(*grammar_index)),
+                }
+            }
+            Error::IndentationNotSupported(grammar_index) => {
+                let option = self.context.grammars[*grammar_index].get_option(OPTION_INDENTATION).unwrap();
+                Some(self.get_single_label_with_input(&option.name_input_ref))
+            }
             Error::SeparatorNotDefined(grammar_index) => {
                 let option = self.context.grammars[*grammar_index].get_option(OPTION_SEPARATOR).unwrap();
                 Some(self.get_single_label_with_input(&option.value_input_ref))
@@ -226,9 +260,12 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
             Error::TemplateRuleNotFound(input, _name) => Some(self.get_single_label_with_input(input)),
             Error::TemplateRuleWrongNumberOfArgs(input, _expected, _provided) => Some(self.get_single_label_with_input(input)),
             Error::SymbolNotFound(input, _name) => Some(self.get_single_label_with_input(input)),
+            Error::ExternalTerminalReferenced(input, _name) => Some(self.get_single_label_with_input(input)),
+            Error::ModeNotDefined(input, _name) => Some(self.get_single_label_with_input(input)),
             Error::InvalidCharacterSpan(input) => Some(self.get_single_label_with_input(input)),
             Error::UnknownUnicodeBlock(input, _name) => Some(self.get_single_label_with_input(input)),
             Error::UnknownUnicodeCategory(input, _name) => Some(self.get_single_label_with_input(input)),
+            Error::InvalidAnnotation(input, _name) => Some(self.get_single_label_with_input(input)),
             Error::UnsupportedNonPlane0InCharacterClass(input, _c) => Some(self.get_single_label_with_input(input)),
             Error::InvalidCodePoint(input, _c) => Some(self.get_single_label_with_input(input)),
             Error::OverridingPreviousTerminal(input, name, previous) => Some(Box::new(
@@ -314,6 +351,19 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
                     .input_ref;
                 Some(self.get_single_label_with_input(input))
             }
+            Error::PrecedencesNotSupported(grammar_index) => {
+                let input = &self.context.grammars[*grammar_index].precedences[0].input_ref;
+                Some(self.get_single_label_with_input(input))
+            }
+            Error::TrailingContextNotSupported(grammar_index, terminal_ref)
+            | Error::ModesNotSupported(grammar_index, terminal_ref)
+            | Error::ExternalTerminalNotSupported(grammar_index, terminal_ref) => {
+                let input = &self.context.grammars[*grammar_index]
+                    .get_terminal(terminal_ref.sid())
+                    .unwrap()
+                    .input_ref;
+                Some(self.get_single_label_with_input(input))
+            }
         }
     }
 
@@ -327,10 +377,12 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
                 }
             }
             Error::LrConflict(grammar_index, conflict) => {
-                if conflict.phrases.is_empty() {
+                let grammar = &self.context.grammars[*grammar_index];
+                if let Some(counterexample) = &conflict.counterexample {
+                    Some(Box::new(format_counterexample(grammar, conflict.kind, counterexample)))
+                } else if conflict.phrases.is_empty() {
                     None
                 } else {
-                    let grammar = &self.context.grammars[*grammar_index];
                     Some(Box::new(format!(
                         "Example of input that is ambiguous: {}",
                         conflict.phrases[0]
@@ -362,3 +414,92 @@ impl<'context, 'error, 't> Diagnostic for ContextualizedError<'context, 'error,
         }
     }
 }
+
+impl Diagnostic for ContextualizedWarning<'_, '_, '_> {
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Warning)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        let input = self.warning.get_input_ref(self.context);
+        Some(&self.context.inputs[input.input_index])
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let input = self.warning.get_input_ref(self.context);
+        let offset = self.context.inputs[input.input_index].content.get_index_at(input.position);
+        Some(Box::new(std::iter::once(LabeledSpan::new(
+            Some(self.to_string()),
+            offset,
+            input.length,
+        ))))
+    }
+}
+
+/// Formats the symbols produced by a derivation, with the conflict point
+fn format_example(grammar: &Grammar, derivation: &Derivation) -> String {
+    let (mut symbols, dot) = derivation.get_frontier();
+    if symbols.last() == Some(&SymbolRef::Dollar) {
+        symbols.pop();
+    }
+    let mut parts: Vec<&str> = symbols.iter().map(|symbol| grammar.get_symbol_value(*symbol)).collect();
+    parts.insert(dot, "•");
+    parts.join(" ")
+}
+
+/// Formats the rules used in a derivation, one per line
+fn format_derivation(grammar: &Grammar, derivation: &Derivation, depth: usize, lines: &mut Vec<String>) {
+    let Derivation::Rule(rule_ref, children) = derivation else {
+        return;
+    };
+    let head = grammar.get_symbol_name(SymbolRef::Variable(rule_ref.variable));
+    let child_depth = if head.starts_with(GENERATED_AXIOM) && !children.contains(&Derivation::Dot) {
+        // do not show the generated axiom rule
+        depth
+    } else {
+        let body: Vec<&str> = children
+            .iter()
+            .map(|child| match child {
+                Derivation::Symbol(symbol) => grammar.get_symbol_value(*symbol),
+                Derivation::Dot => "•",
+                Derivation::Rule(rule_ref, _) => grammar.get_symbol_name(SymbolRef::Variable(rule_ref.variable)),
+            })
+            .collect();
+        lines.push(format!("{}{head} -> {}", "  ".repeat(depth + 1), body.join(" ")));
+        depth + 1
+    };
+    for child in children {
+        format_derivation(grammar, child, child_depth, lines);
+    }
+}
+
+/// Formats a counterexample for a LR conflict
+fn format_counterexample(grammar: &Grammar, kind: ConflictKind, counterexample: &Counterexample) -> String {
+    let (first_name, second_name) = match kind {
+        ConflictKind::ShiftReduce => ("reduction", "shift"),
+        ConflictKind::ReduceReduce => ("first reduction", "second reduction"),
+    };
+    let mut lines = Vec::new();
+    if counterexample.unifying {
+        lines.push(format!(
+            "Example of input that is ambiguous: {}",
+            format_example(grammar, &counterexample.first)
+        ));
+        lines.push(format!("Derivation using the {first_name}:"));
+        format_derivation(grammar, &counterexample.first, 0, &mut lines);
+        lines.push(format!("Derivation using the {second_name}:"));
+        format_derivation(grammar, &counterexample.second, 0, &mut lines);
+    } else {
+        lines.push(format!(
+            "Example of input using the {first_name}: {}",
+            format_example(grammar, &counterexample.first)
+        ));
+        format_derivation(grammar, &counterexample.first, 0, &mut lines);
+        lines.push(format!(
+            "Example of input using the {second_name}: {}",
+            format_example(grammar, &counterexample.second)
+        ));
+        format_derivation(grammar, &counterexample.second, 0, &mut lines);
+    }
+    lines.join("\n")
+}
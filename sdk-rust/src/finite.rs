@@ -104,6 +104,8 @@ pub struct DFAState {
     pub transitions: HashMap<CharSpan, usize>,
     /// List of the items on this state
     pub items: Vec<FinalItem>,
+    /// The terminals whose head, i.e. the part before their trailing context, ends at this state
+    pub heads: Vec<FinalItem>,
 }
 
 impl DFAState {
@@ -114,6 +116,7 @@ impl DFAState {
             id,
             transitions: HashMap::new(),
             items: Vec::new(),
+            heads: Vec::new(),
         }
     }
 
@@ -132,6 +135,12 @@ impl DFAState {
         self.items.iter().all(|item| other.items.contains(item))
     }
 
+    /// Determines if the two states are at the end of the heads of the same terminals
+    #[must_use]
+    pub fn same_heads(&self, other: &DFAState) -> bool {
+        self.heads.len() == other.heads.len() && self.heads.iter().all(|item| other.heads.contains(item))
+    }
+
     /// Adds a new item making this state a final state
     fn do_add_item(&mut self, item: FinalItem) {
         if !self.items.contains(&item) {
@@ -295,6 +304,7 @@ impl DFA {
             }
             // Add finals
             states[i].add_items(&nfa_sets[i].get_finals(&nfa));
+            states[i].heads = nfa_sets[i].get_heads(&nfa);
             i += 1;
         }
         DFA { states }
@@ -498,18 +508,19 @@ impl<'a> DFAPartition<'a> {
 
     /// Initializes this partition as the first partition of the given DFA
     /// The first partition is according to final, non-final states
+    /// The states at the end of the heads of terminals with a trailing context are also kept apart.
     fn from_dfa(dfa: &'a DFA) -> DFAPartition<'a> {
         let mut groups = Vec::new();
         // Partition the DFA states between final and non-finals
         let mut non_finals: Option<DFAStateGroup<'a>> = None;
         // For each state in the DFA
         for state in &dfa.states {
-            if state.is_final() {
+            if state.is_final() || !state.heads.is_empty() {
                 // the state is final
                 // Look for a corresponding group in the existing ones
                 match groups
                     .iter_mut()
-                    .find(|g: &&mut DFAStateGroup| state.same_finals(g.states[0]))
+                    .find(|g: &&mut DFAStateGroup| state.same_finals(g.states[0]) && state.same_heads(g.states[0]))
                 {
                     None => groups.push(DFAStateGroup::new(state)),
                     Some(group) => group.states.push(state),
@@ -612,6 +623,7 @@ impl<'a> DFAPartition<'a> {
                     state.items.push(*item);
                 }
                 state.items.sort();
+                state.heads.clone_from(&group.states[0].heads);
                 state
             })
             .collect();
@@ -677,6 +689,8 @@ pub struct NFAState {
     pub transitions: Vec<NFATransition>,
     /// List of the items on this state
     pub items: Vec<FinalItem>,
+    /// The terminals whose head, i.e. the part before their trailing context, ends at this state
+    pub heads: Vec<FinalItem>,
     /// The watermark of this state
     pub mark: i32,
 }
@@ -689,6 +703,7 @@ impl NFAState {
             id,
             transitions: Vec::new(),
             items: Vec::new(),
+            heads: Vec::new(),
             mark: 0,
         }
     }
@@ -1255,6 +1270,8 @@ pub struct NFA {
     pub entry: usize,
     /// The exit state for this automaton
     pub exit: usize,
+    /// The state at the end of the head, i.e. before the trailing context, if any
+    pub head: Option<usize>,
 }
 
 impl NFA {
@@ -1265,9 +1282,24 @@ impl NFA {
             states: vec![NFAState::new(0), NFAState::new(1)],
             entry: 0,
             exit: 1,
+            head: None,
         }
     }
 
+    /// Creates an automaton matching any single character, including the surrogate pairs
+    #[must_use]
+    pub fn new_any() -> NFA {
+        let mut nfa = NFA::new_minimal();
+        // plane 0 transitions
+        nfa.add_transition(nfa.entry, CharSpan::new(0x0000, 0xD7FF), nfa.exit);
+        nfa.add_transition(nfa.entry, CharSpan::new(0xE000, 0xFFFF), nfa.exit);
+        // surrogate pairs
+        let intermediate = nfa.add_state().id;
+        nfa.add_transition(nfa.entry, CharSpan::new(0xD800, 0xDBFF), intermediate);
+        nfa.add_transition(intermediate, CharSpan::new(0xDC00, 0xDFFF), nfa.exit);
+        nfa
+    }
+
     /// Create an optional NFA
     #[must_use]
     pub fn new_optional(sub: &NFA) -> NFA {
@@ -1371,6 +1403,33 @@ impl NFA {
         result
     }
 
+    /// Creates an automaton that matches this one only when followed by the trailing context
+    /// The state between the two is marked as the end of the head, so that the trailing context is not part of the match.
+    #[must_use]
+    pub fn into_trailing_context(self, other: &NFA) -> NFA {
+        let mut result = self;
+        let left_entry = result.entry;
+        let left_exit = result.exit;
+        let (right_entry, right_exit) = result.insert_sub_nfa(other);
+        let (entry, exit) = result.add_entry_exit();
+        let head = result.add_state().id;
+        result.add_transition(entry, EPSILON, left_entry);
+        result.add_transition(left_exit, EPSILON, head);
+        result.add_transition(head, EPSILON, right_entry);
+        result.add_transition(right_exit, EPSILON, exit);
+        result.head = Some(head);
+        result
+    }
+
+    /// Marks the final state of this automaton with the item
+    /// When there is a trailing context, the end of the head is also marked.
+    pub fn add_final_item(&mut self, item: FinalItem) {
+        self.states[self.exit].add_item(item);
+        if let Some(head) = self.head {
+            self.states[head].heads.push(item);
+        }
+    }
+
     /// Creates an automaton that is the difference between the left and right sub-automata
     #[allow(clippy::similar_names)]
     #[must_use]
@@ -1394,6 +1453,10 @@ impl NFA {
         let mut dfa = DFA::from_nfa(nfa);
         dfa.prune();
         let mut nfa = NFA::from_dfa(&dfa);
+        if nfa.states.is_empty() {
+            // nothing is matched, keep the entry apart from the exit
+            nfa.entry = nfa.add_state().id;
+        }
         nfa.exit = nfa.add_state().id;
         for state in &mut nfa.states {
             if state.items.contains(&FinalItem::Dummy) {
@@ -1404,6 +1467,62 @@ impl NFA {
         nfa
     }
 
+    /// Creates the intersection of this automaton with the other
+    /// This is the product automaton, whose states are the pairs of states of the two automata that are reached together.
+    #[must_use]
+    pub fn into_intersection(self, other: &NFA) -> NFA {
+        let mut nfa = NFA {
+            states: vec![NFAState::new(0)],
+            entry: 0,
+            exit: 0,
+            head: None,
+        };
+        let mut pairs = HashMap::new();
+        pairs.insert((self.entry, other.entry), 0);
+        let mut queue = vec![(self.entry, other.entry)];
+        while let Some((left, right)) = queue.pop() {
+            let from = pairs[&(left, right)];
+            let mut targets = Vec::new();
+            for left_transition in &self.states[left].transitions {
+                if left_transition.value == EPSILON {
+                    targets.push((EPSILON, (left_transition.next, right)));
+                    continue;
+                }
+                for right_transition in &other.states[right].transitions {
+                    let value = left_transition.value.intersect(right_transition.value);
+                    if right_transition.value != EPSILON && !value.is_empty() {
+                        targets.push((value, (left_transition.next, right_transition.next)));
+                    }
+                }
+            }
+            for right_transition in &other.states[right].transitions {
+                if right_transition.value == EPSILON {
+                    targets.push((EPSILON, (left, right_transition.next)));
+                }
+            }
+            for (value, pair) in targets {
+                let to = *pairs.entry(pair).or_insert_with(|| {
+                    queue.push(pair);
+                    nfa.add_state().id
+                });
+                nfa.add_transition(from, value, to);
+            }
+        }
+        nfa.exit = match pairs.get(&(self.exit, other.exit)) {
+            Some(&exit) => exit,
+            // nothing is matched, keep the exit apart from the entry
+            None => nfa.add_state().id,
+        };
+        nfa
+    }
+
+    /// Creates the complement of this automaton
+    /// This matches any string of characters that is not matched by this automaton.
+    #[must_use]
+    pub fn into_complement(self) -> NFA {
+        NFA::new_any().into_zero_or_more().into_difference(&self)
+    }
+
     /// Adds entry and exit states
     fn add_entry_exit(&mut self) -> (usize, usize) {
         self.entry = self.add_state().id;
@@ -1430,11 +1549,13 @@ impl NFA {
                             next: *next,
                         })
                         .collect(),
+                    heads: state.heads.clone(),
                     mark: 0,
                 })
                 .collect(),
             entry: 0,
             exit: usize::MAX,
+            head: None,
         }
     }
 
@@ -1463,6 +1584,7 @@ impl NFA {
     }
 
     /// Clone this automaton without the final items
+    /// The clone has no trailing context, the head and the trailing context are simply concatenated.
     #[must_use]
     pub fn clone_no_finals(&self) -> NFA {
         NFA {
@@ -1473,14 +1595,39 @@ impl NFA {
                     id: state.id,
                     items: Vec::new(),
                     transitions: state.transitions.clone(),
+                    heads: Vec::new(),
                     mark: 0,
                 })
                 .collect(),
             entry: self.entry,
             exit: self.exit,
+            head: None,
         }
     }
 
+    /// Gets the automaton matching the trailing context only, if any
+    /// It starts at the end of the head and keeps the final items.
+    #[must_use]
+    pub fn get_trailing_context(&self) -> Option<NFA> {
+        let head = self.head?;
+        Some(NFA {
+            states: self
+                .states
+                .iter()
+                .map(|state| NFAState {
+                    id: state.id,
+                    items: state.items.clone(),
+                    transitions: state.transitions.clone(),
+                    heads: Vec::new(),
+                    mark: 0,
+                })
+                .collect(),
+            entry: head,
+            exit: self.exit,
+            head: None,
+        })
+    }
+
     /// Inserts all the states of the given automaton into this one
     pub fn insert_sub_nfa(&mut self, nfa: &NFA) -> (usize, usize) {
         let offset = self.states.len();
@@ -1488,6 +1635,7 @@ impl NFA {
             self.states.push(NFAState {
                 id: state.id + offset,
                 items: state.items.clone(),
+                heads: state.heads.clone(),
                 mark: state.mark,
                 transitions: state
                     .transitions
@@ -1576,6 +1724,20 @@ impl NFAStateSet {
         result
     }
 
+    /// Gets all the head markers of all the states in this set
+    fn get_heads(&self, nfa: &NFA) -> Vec<FinalItem> {
+        let mut result = Vec::new();
+        for state in &self.states {
+            for fi in &nfa.states[*state].heads {
+                if !result.contains(fi) {
+                    result.push(*fi);
+                }
+            }
+        }
+        result.sort();
+        result
+    }
+
     /// Builds transitions from this set to other sets
     fn get_transitions(&self, nfa: &NFA) -> Vec<(CharSpan, NFAStateSet)> {
         let mut transitions = HashMap::new();
@@ -1623,3 +1785,95 @@ impl PartialEq for NFAStateSet {
 }
 
 impl Eq for NFAStateSet {}
+
+#[cfg(test)]
+mod tests_nfa_operations {
+    use super::{FinalItem, DFA, NFA};
+    use crate::CharSpan;
+
+    /// Builds an automaton matching a single character in the range
+    fn new_span(begin: char, end: char) -> NFA {
+        let mut nfa = NFA::new_minimal();
+        nfa.add_transition(nfa.entry, CharSpan::new(begin as u16, end as u16), nfa.exit);
+        nfa
+    }
+
+    /// Gets whether the exit of the automaton can be reached from its entry
+    fn is_exit_reachable(nfa: &NFA) -> bool {
+        let mut visited = vec![nfa.entry];
+        let mut index = 0;
+        while index < visited.len() {
+            for transition in &nfa.states[visited[index]].transitions {
+                if !visited.contains(&transition.next) {
+                    visited.push(transition.next);
+                }
+            }
+            index += 1;
+        }
+        visited.contains(&nfa.exit)
+    }
+
+    /// Gets whether the automaton matches the whole input
+    fn matches(nfa: &NFA, input: &str) -> bool {
+        let mut nfa = nfa.clone();
+        nfa.add_final_item(FinalItem::Terminal(0, 0));
+        let dfa = DFA::from_nfa(nfa);
+        let mut state = 0;
+        for c in input.encode_utf16() {
+            let next = dfa.states[state]
+                .transitions
+                .iter()
+                .find(|(span, _)| span.begin <= c && c <= span.end)
+                .map(|(_, next)| *next);
+            match next {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+        dfa.states[state].is_final()
+    }
+
+    #[test]
+    fn test_intersection() {
+        let left = new_span('a', 'c').into_one_or_more();
+        let right = new_span('b', 'd').into_one_or_more();
+        let nfa = left.into_intersection(&right);
+        assert!(is_exit_reachable(&nfa));
+        assert!(matches(&nfa, "b"));
+        assert!(matches(&nfa, "cbc"));
+        assert!(!matches(&nfa, ""));
+        assert!(!matches(&nfa, "a"));
+        assert!(!matches(&nfa, "bd"));
+    }
+
+    #[test]
+    fn test_intersection_empty() {
+        let nfa = new_span('a', 'a').into_intersection(&new_span('b', 'b'));
+        assert_ne!(nfa.entry, nfa.exit);
+        assert!(!is_exit_reachable(&nfa));
+        assert!(!matches(&nfa, ""));
+        assert!(!matches(&nfa, "a"));
+        assert!(!matches(&nfa, "b"));
+    }
+
+    #[test]
+    fn test_complement() {
+        let nfa = new_span('a', 'a').into_complement();
+        assert!(is_exit_reachable(&nfa));
+        assert!(matches(&nfa, ""));
+        assert!(matches(&nfa, "b"));
+        assert!(matches(&nfa, "aa"));
+        assert!(matches(&nfa, "\u{10000}"));
+        assert!(!matches(&nfa, "a"));
+    }
+
+    #[test]
+    fn test_complement_of_any() {
+        let nfa = NFA::new_any().into_zero_or_more().into_complement();
+        assert_ne!(nfa.entry, nfa.exit);
+        assert!(!is_exit_reachable(&nfa));
+        assert!(!matches(&nfa, ""));
+        assert!(!matches(&nfa, "a"));
+        assert!(!matches(&nfa, "\u{10000}"));
+    }
+}
@@ -18,13 +18,14 @@
 //! Library for grammars
 
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 
+use hime_redist::lexers::Indentation;
 use hime_redist::parsers::{TreeAction, TREE_ACTION_DROP, TREE_ACTION_NONE, TREE_ACTION_PROMOTE};
 
-use crate::errors::{Error, UnmatchableTokenError};
+use crate::errors::{Error, UnmatchableTokenError, Warning};
 use crate::finite::{FinalItem, DFA, EPSILON, NFA};
 use crate::lr::Graph;
 use crate::sdk::InMemoryParser;
@@ -51,6 +52,18 @@ pub struct TerminalReference {
     pub input_ref: InputReference,
 }
 
+/// The action on the stack of lexer modes when a terminal is matched
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ModeAction {
+    /// No action
+    #[default]
+    None,
+    /// Pushes the context with the specified identifier as the new mode
+    Push(usize),
+    /// Pops the current mode
+    Pop,
+}
+
 /// Represents a terminal symbol in a grammar
 #[derive(Debug, Clone)]
 pub struct Terminal {
@@ -70,6 +83,10 @@ pub struct Terminal {
     pub is_anonymous: bool,
     /// Whether the terminal is a fragment
     pub is_fragment: bool,
+    /// Whether the terminal is external, i.e. matched by a hand-written scanner
+    pub is_external: bool,
+    /// The action on the stack of lexer modes when this terminal is matched
+    pub mode_action: ModeAction,
     /// The references to this terminal by others
     pub terminal_references: Vec<TerminalReference>,
 }
@@ -490,13 +507,15 @@ pub struct RuleBodyElement {
     pub symbol: SymbolRef,
     /// The action applied on this element
     pub action: TreeAction,
+    /// The index of the label given to this element, if any
+    pub label: Option<usize>,
     /// The reference to this body element in the input
     pub input_ref: Option<InputReference>,
 }
 
 impl PartialEq for RuleBodyElement {
     fn eq(&self, other: &Self) -> bool {
-        self.symbol == other.symbol && self.action == other.action
+        self.symbol == other.symbol && self.action == other.action && self.label == other.label
     }
 }
 
@@ -507,6 +526,7 @@ impl RuleBodyElement {
         RuleBodyElement {
             symbol,
             action,
+            label: None,
             input_ref,
         }
     }
@@ -515,9 +535,8 @@ impl RuleBodyElement {
     #[must_use]
     pub fn no_action(&self) -> RuleBodyElement {
         RuleBodyElement {
-            symbol: self.symbol,
             action: TREE_ACTION_NONE,
-            input_ref: self.input_ref,
+            ..*self
         }
     }
 }
@@ -548,6 +567,7 @@ impl RuleChoice {
             elements,
             firsts: TerminalSet::default(),
             choices: Vec::new(),
+            annotations: RuleAnnotations::default(),
         }
     }
 
@@ -623,6 +643,9 @@ pub trait RuleBodyTrait {
 
     /// Apply a tree action to all elements in the body
     fn apply_action(&mut self, action: TreeAction);
+
+    /// Apply a label to all elements in the body
+    fn apply_label(&mut self, label: usize);
 }
 
 /// A set of rule bodies
@@ -659,6 +682,49 @@ impl<T: RuleBodyTrait> BodySet<T> {
             body.apply_action(action);
         }
     }
+
+    /// Apply a label to all elements in the bodies
+    pub fn apply_label(&mut self, label: usize) {
+        for body in &mut self.bodies {
+            body.apply_label(label);
+        }
+    }
+}
+
+/// The preference given to a rule when resolving an ambiguity
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum RulePreference {
+    /// No preference
+    #[default]
+    None,
+    /// The rule is preferred over the others
+    Prefer,
+    /// The others rules are preferred over this one
+    Avoid,
+}
+
+/// The annotations on a rule body, used to resolve ambiguities
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RuleAnnotations {
+    /// The priority of the rule, higher priorities win
+    pub priority: u16,
+    /// The preference given to the rule
+    pub preference: RulePreference,
+}
+
+impl RuleAnnotations {
+    /// Merges the annotations of two concatenated bodies
+    #[must_use]
+    pub fn merge(left: RuleAnnotations, right: RuleAnnotations) -> RuleAnnotations {
+        RuleAnnotations {
+            priority: left.priority.max(right.priority),
+            preference: if right.preference == RulePreference::None {
+                left.preference
+            } else {
+                right.preference
+            },
+        }
+    }
 }
 
 /// Represents the body of a grammar rule
@@ -670,6 +736,8 @@ pub struct RuleBody {
     pub firsts: TerminalSet,
     /// The choices in this body
     pub choices: Vec<RuleChoice>,
+    /// The annotations used to resolve ambiguities
+    pub annotations: RuleAnnotations,
 }
 
 impl RuleBodyTrait for RuleBody {
@@ -685,6 +753,7 @@ impl RuleBodyTrait for RuleBody {
             elements,
             firsts: TerminalSet::default(),
             choices: Vec::new(),
+            annotations: RuleAnnotations::merge(left.annotations, right.annotations),
         }
     }
 
@@ -693,6 +762,12 @@ impl RuleBodyTrait for RuleBody {
             element.action = action;
         }
     }
+
+    fn apply_label(&mut self, label: usize) {
+        for element in &mut self.elements {
+            element.label = Some(label);
+        }
+    }
 }
 
 impl RuleBody {
@@ -703,6 +778,16 @@ impl RuleBody {
             elements: Vec::new(),
             firsts: TerminalSet::default(),
             choices: Vec::new(),
+            annotations: RuleAnnotations::default(),
+        }
+    }
+
+    /// Initializes this rule body with only annotations
+    #[must_use]
+    pub fn annotated(annotations: RuleAnnotations) -> RuleBody {
+        RuleBody {
+            annotations,
+            ..RuleBody::empty()
         }
     }
 
@@ -713,6 +798,7 @@ impl RuleBody {
             elements: vec![RuleBodyElement::new(symbol, TREE_ACTION_NONE, Some(input_ref))],
             firsts: TerminalSet::default(),
             choices: Vec::new(),
+            annotations: RuleAnnotations::default(),
         }
     }
 
@@ -723,6 +809,7 @@ impl RuleBody {
             elements,
             firsts: TerminalSet::default(),
             choices: Vec::new(),
+            annotations: RuleAnnotations::default(),
         }
     }
 
@@ -869,7 +956,7 @@ impl PartialEq for Rule {
 impl Eq for Rule {}
 
 /// A reference to a grammar rule
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct RuleRef {
     /// The identifier of the variable
     pub variable: usize,
@@ -944,13 +1031,15 @@ pub struct TemplateRuleElement {
     pub symbol: TemplateRuleSymbol,
     /// The action applied on this element
     pub action: TreeAction,
+    /// The index of the label given to this element, if any
+    pub label: Option<usize>,
     /// The reference to this body element in the input
     pub input_ref: InputReference,
 }
 
 impl PartialEq for TemplateRuleElement {
     fn eq(&self, other: &Self) -> bool {
-        self.symbol == other.symbol && self.action == other.action
+        self.symbol == other.symbol && self.action == other.action && self.label == other.label
     }
 }
 
@@ -961,6 +1050,7 @@ impl TemplateRuleElement {
         TemplateRuleElement {
             symbol,
             action,
+            label: None,
             input_ref,
         }
     }
@@ -993,6 +1083,8 @@ impl Eq for TemplateRuleInstance {}
 pub struct TemplateRuleBody {
     /// The elements in the rule's body
     pub elements: Vec<TemplateRuleElement>,
+    /// The annotations used to resolve ambiguities
+    pub annotations: RuleAnnotations,
 }
 
 impl RuleBodyTrait for TemplateRuleBody {
@@ -1004,7 +1096,10 @@ impl RuleBodyTrait for TemplateRuleBody {
         for element in &right.elements {
             elements.push(element.clone());
         }
-        TemplateRuleBody { elements }
+        TemplateRuleBody {
+            elements,
+            annotations: RuleAnnotations::merge(left.annotations, right.annotations),
+        }
     }
 
     fn apply_action(&mut self, action: TreeAction) {
@@ -1012,13 +1107,31 @@ impl RuleBodyTrait for TemplateRuleBody {
             element.action = action;
         }
     }
+
+    fn apply_label(&mut self, label: usize) {
+        for element in &mut self.elements {
+            element.label = Some(label);
+        }
+    }
 }
 
 impl TemplateRuleBody {
     /// Initializes this rule body
     #[must_use]
     pub fn empty() -> TemplateRuleBody {
-        TemplateRuleBody { elements: Vec::new() }
+        TemplateRuleBody {
+            elements: Vec::new(),
+            annotations: RuleAnnotations::default(),
+        }
+    }
+
+    /// Initializes this rule body with only annotations
+    #[must_use]
+    pub fn annotated(annotations: RuleAnnotations) -> TemplateRuleBody {
+        TemplateRuleBody {
+            elements: Vec::new(),
+            annotations,
+        }
     }
 
     /// Initializes this rule body
@@ -1026,6 +1139,7 @@ impl TemplateRuleBody {
     pub fn single(symbol: TemplateRuleSymbol, input_ref: InputReference) -> TemplateRuleBody {
         TemplateRuleBody {
             elements: vec![TemplateRuleElement::new(symbol, TREE_ACTION_NONE, input_ref)],
+            annotations: RuleAnnotations::default(),
         }
     }
 }
@@ -1091,8 +1205,16 @@ pub const PREFIX_GENERATED_VARIABLE: &str = "__V";
 pub const GENERATED_AXIOM: &str = "__VAxiom";
 /// Name of the grammar option specifying the grammar's axiom variable
 pub const OPTION_AXIOM: &str = "Axiom";
+/// Name of the grammar option listing the additional entry points (variables) of the grammar
+pub const OPTION_ENTRIES: &str = "Entries";
 /// Name of the grammar option specifying the grammar's separator terminal
 pub const OPTION_SEPARATOR: &str = "Separator";
+/// Name of the grammar option listing the terminals inserted by an indentation-sensitive lexer, i.e. `INDENT,DEDENT,NEWLINE`
+pub const OPTION_INDENTATION: &str = "Indentation";
+/// Name of the grammar option specifying the number of columns for a tab character, defaults to 8
+pub const OPTION_TAB_WIDTH: &str = "TabWidth";
+/// The default number of columns for a tab character
+pub const DEFAULT_TAB_WIDTH: usize = 8;
 /// The output path for compilation artifacts
 pub const OPTION_OUTPUT_PATH: &str = "OutputPath";
 /// The parser type to generate, defaults to LALR1
@@ -1108,6 +1230,12 @@ pub const OPTION_ACCESS_MODIFIER: &str = "Modifier";
 /// The name of the default lexical context
 pub const DEFAULT_CONTEXT_NAME: &str = "__default";
 
+/// Gets the name of the axiom variable generated for an entry point, i.e. `__VAxiom_entry -> entry $`
+#[must_use]
+pub fn get_generated_entry_name(entry: &str) -> String {
+    format!("{GENERATED_AXIOM}_{entry}")
+}
+
 /// The counter for the generation of unique names across multiple grammars
 static NEXT_UNIQUE_SID: AtomicUsize = AtomicUsize::new(0);
 
@@ -1128,6 +1256,38 @@ pub struct GrammarOption {
     pub value: String,
 }
 
+/// The associativity of a precedence level
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Associativity {
+    /// Left associative, conflicts on the same level are solved by reducing
+    Left,
+    /// Right associative, conflicts on the same level are solved by shifting
+    Right,
+    /// Not associative, conflicts on the same level produce a syntax error
+    NonAssoc,
+}
+
+impl Display for Associativity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Associativity::Left => write!(f, "left"),
+            Associativity::Right => write!(f, "right"),
+            Associativity::NonAssoc => write!(f, "nonassoc"),
+        }
+    }
+}
+
+/// A precedence level declared in a grammar
+#[derive(Debug, Clone)]
+pub struct PrecedenceLevel {
+    /// The reference in the input for this level
+    pub input_ref: InputReference,
+    /// The associativity for the symbols on this level
+    pub associativity: Associativity,
+    /// The terminals and variables on this level
+    pub symbols: Vec<SymbolRef>,
+}
+
 /// Represents a grammar
 #[derive(Debug, Clone)]
 pub struct Grammar {
@@ -1141,6 +1301,8 @@ pub struct Grammar {
     pub options: HashMap<String, GrammarOption>,
     /// The lexical contexts defined in this grammar
     pub contexts: Vec<String>,
+    /// The labels given to elements in the rules of this grammar
+    pub labels: Vec<String>,
     /// The grammar's terminals
     pub terminals: Vec<Terminal>,
     /// The grammar's variables
@@ -1151,6 +1313,8 @@ pub struct Grammar {
     pub actions: Vec<Action>,
     /// The template rules
     pub template_rules: Vec<TemplateRule>,
+    /// The precedence levels, from the loosest to the tightest binding
+    pub precedences: Vec<PrecedenceLevel>,
 }
 
 /// Represents the build data for a grammar
@@ -1166,6 +1330,10 @@ pub struct BuildData {
     pub method: ParsingMethod,
     /// The LR graph
     pub graph: Graph,
+    /// The configuration of the indentation-sensitive lexer, if any
+    pub indentation: Option<Indentation>,
+    /// The warnings about the grammar
+    pub warnings: Vec<Warning>,
 }
 
 impl Grammar {
@@ -1178,11 +1346,13 @@ impl Grammar {
             next_sid: 3,
             options: HashMap::new(),
             contexts: vec![DEFAULT_CONTEXT_NAME.to_string()],
+            labels: Vec::new(),
             terminals: Vec::new(),
             variables: Vec::new(),
             virtuals: Vec::new(),
             actions: Vec::new(),
             template_rules: Vec::new(),
+            precedences: Vec::new(),
         }
     }
 
@@ -1271,6 +1441,17 @@ impl Grammar {
         }
     }
 
+    /// Resolves the specified label name for this grammar
+    pub fn resolve_label(&mut self, name: &str) -> usize {
+        if let Some(index) = self.labels.iter().position(|l| name == l) {
+            index
+        } else {
+            let index = self.labels.len();
+            self.labels.push(name.to_string());
+            index
+        }
+    }
+
     /// Adds the given anonymous terminal to this grammar
     pub fn add_terminal_anonymous(&mut self, value: String, input_ref: InputReference, nfa: NFA) -> &mut Terminal {
         let name = format!("{}{}", PREFIX_GENERATED_TERMINAL, generate_unique_id());
@@ -1295,6 +1476,14 @@ impl Grammar {
         self.add_terminal(name, value, input_ref, nfa, context, false, is_fragment)
     }
 
+    /// Adds an external terminal to the grammar, matched by a hand-written scanner instead of the lexer's automaton
+    pub fn add_terminal_external(&mut self, name: String, input_ref: InputReference) -> &mut Terminal {
+        let value = name.clone();
+        let terminal = self.add_terminal(name, value, input_ref, NFA::new_minimal(), 0, false, false);
+        terminal.is_external = true;
+        terminal
+    }
+
     /// Adds a terminal to the grammar
     #[allow(clippy::too_many_arguments)]
     fn add_terminal(
@@ -1317,6 +1506,8 @@ impl Grammar {
             context,
             is_anonymous,
             is_fragment,
+            is_external: false,
+            mode_action: ModeAction::None,
             terminal_references: Vec::new(),
         };
         self.terminals.push(terminal);
@@ -1359,6 +1550,61 @@ impl Grammar {
         }
     }
 
+    /// Gets whether the specified context is a lexer mode, i.e. pushed by a terminal
+    #[must_use]
+    pub fn is_lexer_mode(&self, context: usize) -> bool {
+        self.terminals
+            .iter()
+            .any(|terminal| terminal.mode_action == ModeAction::Push(context))
+    }
+
+    /// Gets whether any terminal acts on the stack of lexer modes
+    #[must_use]
+    pub fn has_lexer_modes(&self) -> bool {
+        self.terminals.iter().any(|terminal| terminal.mode_action != ModeAction::None)
+    }
+
+    /// Gets whether any terminal is external, i.e. matched by a hand-written scanner
+    #[must_use]
+    pub fn has_external_terminals(&self) -> bool {
+        self.terminals.iter().any(|terminal| terminal.is_external)
+    }
+
+    /// Adds a precedence level binding tighter than all the previous ones
+    pub fn add_precedence_level(&mut self, input_ref: InputReference, associativity: Associativity, symbols: Vec<SymbolRef>) {
+        self.precedences.push(PrecedenceLevel {
+            input_ref,
+            associativity,
+            symbols,
+        });
+    }
+
+    /// Gets the precedence level and associativity of a symbol, if any
+    #[must_use]
+    pub fn get_symbol_precedence(&self, symbol: SymbolRef) -> Option<(usize, Associativity)> {
+        self.precedences
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, level)| level.symbols.contains(&symbol))
+            .map(|(index, level)| (index, level.associativity))
+    }
+
+    /// Gets the precedence level and associativity of a rule, if any
+    /// This is the level of the rule's head when declared,
+    /// otherwise the level of the last terminal in the rule's body that has one
+    #[must_use]
+    pub fn get_rule_precedence(&self, rule: &Rule) -> Option<(usize, Associativity)> {
+        self.get_symbol_precedence(SymbolRef::Variable(rule.head)).or_else(|| {
+            rule.body
+                .elements
+                .iter()
+                .rev()
+                .filter(|element| matches!(element.symbol, SymbolRef::Terminal(_)))
+                .find_map(|element| self.get_symbol_precedence(element.symbol))
+        })
+    }
+
     /// Generates a new variable
     pub fn generate_variable(&mut self, context_variable: usize) -> &mut Variable {
         let index = self.variables.len();
@@ -1562,10 +1808,14 @@ impl Grammar {
                     elements.push(RuleBodyElement {
                         symbol: self.instantiate_template_symbol(template_index, instance_index, &element.symbol),
                         action: element.action,
+                        label: element.label,
                         input_ref: Some(element.input_ref),
                     });
                 }
-                bodies.push(RuleBody::from_parts(elements));
+                bodies.push(RuleBody {
+                    annotations: body.annotations,
+                    ..RuleBody::from_parts(elements)
+                });
             }
             let head = {
                 let variable = self.add_variable(&name);
@@ -1592,8 +1842,10 @@ impl Grammar {
         self.inherit_variables(other);
         self.inherit_virtuals(other);
         self.inherit_actions(other);
+        self.inherit_labels(other);
         self.inherit_rules(other);
         self.inherit_template_rules(other);
+        self.inherit_precedences(other);
         self.next_sid += other.next_sid - 3;
     }
 
@@ -1616,7 +1868,12 @@ impl Grammar {
                 let sid = self.next_sid + terminal.id - 3;
                 let context = self.resolve_context(&other.contexts[terminal.context]);
                 let mut nfa = terminal.nfa.clone_no_finals();
-                nfa.states[nfa.exit].items.push(FinalItem::Terminal(sid, context));
+                nfa.head = terminal.nfa.head;
+                nfa.add_final_item(FinalItem::Terminal(sid, context));
+                let mode_action = match terminal.mode_action {
+                    ModeAction::Push(mode) => ModeAction::Push(self.resolve_context(&other.contexts[mode])),
+                    action => action,
+                };
                 self.terminals.push(Terminal {
                     id: sid,
                     name: terminal.name.clone(),
@@ -1626,6 +1883,8 @@ impl Grammar {
                     context,
                     is_fragment: terminal.is_fragment,
                     is_anonymous: terminal.is_anonymous,
+                    is_external: terminal.is_external,
+                    mode_action,
                     terminal_references: Vec::new(),
                 });
             }
@@ -1646,6 +1905,18 @@ impl Grammar {
         }
     }
 
+    /// Inherits the labels from the parent grammar
+    fn inherit_labels(&mut self, other: &Grammar) {
+        for label in &other.labels {
+            self.resolve_label(label);
+        }
+    }
+
+    /// Maps a label from a grammar to this one
+    fn map_label(&self, other: &Grammar, label: Option<usize>) -> Option<usize> {
+        label.map(|label| self.labels.iter().position(|l| *l == other.labels[label]).unwrap())
+    }
+
     /// Inherits the variables from the parent grammar
     fn inherit_variables(&mut self, other: &Grammar) {
         for symbol in &other.variables {
@@ -1667,15 +1938,21 @@ impl Grammar {
                         .body
                         .elements
                         .iter()
-                        .map(|element| {
-                            RuleBodyElement::new(self.map_symbol_ref(other, element.symbol), element.action, element.input_ref)
+                        .map(|element| RuleBodyElement {
+                            symbol: self.map_symbol_ref(other, element.symbol),
+                            action: element.action,
+                            label: self.map_label(other, element.label),
+                            input_ref: element.input_ref,
                         })
                         .collect();
                     Rule::new(
                         head,
                         rule.head_action,
                         rule.head_input_ref,
-                        RuleBody::from_parts(elements),
+                        RuleBody {
+                            annotations: rule.body.annotations,
+                            ..RuleBody::from_parts(elements)
+                        },
                         context,
                     )
                 })
@@ -1685,6 +1962,26 @@ impl Grammar {
         }
     }
 
+    /// Inherits the precedence levels from the parent grammar
+    /// The inherited levels bind looser than the ones declared in this grammar
+    fn inherit_precedences(&mut self, other: &Grammar) {
+        let mut levels: Vec<PrecedenceLevel> = other
+            .precedences
+            .iter()
+            .map(|level| PrecedenceLevel {
+                input_ref: level.input_ref,
+                associativity: level.associativity,
+                symbols: level
+                    .symbols
+                    .iter()
+                    .map(|symbol| self.map_symbol_ref(other, *symbol))
+                    .collect(),
+            })
+            .collect();
+        levels.append(&mut self.precedences);
+        self.precedences = levels;
+    }
+
     /// Creates the equivalent template rule symbol for this grammar
     fn inherit_template_rule_symbol(&self, other: &Grammar, symbol: &TemplateRuleSymbol) -> TemplateRuleSymbol {
         match symbol {
@@ -1755,10 +2052,14 @@ impl Grammar {
                     elements.push(TemplateRuleElement {
                         symbol,
                         action: element.action,
+                        label: self.map_label(other, element.label),
                         input_ref: element.input_ref,
                     });
                 }
-                self.template_rules[index].bodies.push(TemplateRuleBody { elements });
+                self.template_rules[index].bodies.push(TemplateRuleBody {
+                    elements,
+                    annotations: body.annotations,
+                });
             }
         }
     }
@@ -1798,7 +2099,7 @@ impl Grammar {
     #[must_use]
     pub fn build_dfa(&self) -> DFA {
         let mut nfa = NFA::new_minimal();
-        for terminal in self.terminals.iter().filter(|t| !t.is_fragment) {
+        for terminal in self.terminals.iter().filter(|t| !t.is_fragment && !t.is_external) {
             let (entry, _) = nfa.insert_sub_nfa(&terminal.nfa);
             nfa.add_transition(nfa.entry, EPSILON, entry);
         }
@@ -1824,8 +2125,45 @@ impl Grammar {
         Ok(())
     }
 
+    /// Gets the names of the additional entry points of this grammar, as specified by its options
+    #[must_use]
+    pub fn get_entries(&self) -> Vec<&str> {
+        let mut entries = Vec::new();
+        if let Some(option) = self.options.get(OPTION_ENTRIES) {
+            for entry in option.value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+        entries
+    }
+
+    /// Gets the generated axioms of this grammar, first the one for the axiom, then the ones for the entries
+    /// The generated axiom at an index has the start state with the same index in the LR automata.
+    #[must_use]
+    pub fn get_generated_axioms(&self) -> Vec<&Variable> {
+        let mut axioms: Vec<&Variable> = self.get_variable_for_name(GENERATED_AXIOM).into_iter().collect();
+        for entry in self.get_entries() {
+            if let Some(variable) = self.get_variable_for_name(&get_generated_entry_name(entry)) {
+                axioms.push(variable);
+            }
+        }
+        axioms
+    }
+
     /// Adds the real axiom to this grammar
     fn add_real_axiom(&mut self, grammar_index: usize) -> Result<(), Error> {
+        // The generated axioms are recognized by their name, reject the user variables that would be mistaken for them
+        if let Some(variable) = self.variables.iter().find(|variable| {
+            variable.name.starts_with(GENERATED_AXIOM)
+                && variable
+                    .rules
+                    .iter()
+                    .any(|rule| rule.body.elements.last().map(|element| element.symbol) != Some(SymbolRef::Dollar))
+        }) {
+            return Err(Error::VariableNameReserved(grammar_index, variable.name.clone()));
+        }
         let axiom_option = self
             .options
             .get(OPTION_AXIOM)
@@ -1838,18 +2176,38 @@ impl Grammar {
             .id;
         let input_ref = axiom_option.value_input_ref;
         // Create the real axiom rule variable and rule
-        let real_axiom = self.add_variable(GENERATED_AXIOM);
+        self.add_generated_axiom(GENERATED_AXIOM, axiom_id, input_ref);
+        // Create one for each additional entry point
+        let Some(input_ref) = self.options.get(OPTION_ENTRIES).map(|option| option.value_input_ref) else {
+            return Ok(());
+        };
+        let entries: Vec<(String, Option<usize>)> = self
+            .get_entries()
+            .into_iter()
+            .map(|entry| (entry.to_string(), self.get_variable_for_name(entry).map(|v| v.id)))
+            .collect();
+        for (entry, variable_id) in entries {
+            let Some(variable_id) = variable_id else {
+                return Err(Error::EntryNotDefined(grammar_index, entry));
+            };
+            self.add_generated_axiom(&get_generated_entry_name(&entry), variable_id, input_ref);
+        }
+        Ok(())
+    }
+
+    /// Adds a generated axiom rule of the form `name -> variable $` to this grammar
+    fn add_generated_axiom(&mut self, name: &str, variable_id: usize, input_ref: InputReference) {
+        let real_axiom = self.add_variable(name);
         real_axiom.rules.push(Rule::new(
             real_axiom.id,
             TREE_ACTION_NONE,
             input_ref,
             RuleBody::from_parts(vec![
-                RuleBodyElement::new(SymbolRef::Variable(axiom_id), TREE_ACTION_PROMOTE, None),
+                RuleBodyElement::new(SymbolRef::Variable(variable_id), TREE_ACTION_PROMOTE, None),
                 RuleBodyElement::new(SymbolRef::Dollar, TREE_ACTION_DROP, None),
             ]),
             0,
         ));
-        Ok(())
     }
 
     /// Computes the FIRSTS sets for this grammar
@@ -1895,6 +2253,7 @@ impl Grammar {
         if let Err(error) = self.prepare(grammar_index) {
             return Err(vec![error]);
         };
+        let warnings = self.collect_warnings(grammar_index);
         // Build DFA
         let dfa = self.build_dfa();
         // Check that no terminal match the empty string
@@ -1905,12 +2264,28 @@ impl Grammar {
                 .map(|item| Error::TerminalMatchesEmpty(grammar_index, (*item).into()))
                 .collect());
         }
+        // Check that no head before a trailing context matches the empty string
+        if !dfa.states.is_empty() && !dfa.states[0].heads.is_empty() {
+            return Err(dfa.states[0]
+                .heads
+                .iter()
+                .map(|item| Error::TerminalMatchesEmpty(grammar_index, (*item).into()))
+                .collect());
+        }
         // Build the data for the lexer
-        let expected = dfa.get_expected();
+        let mut expected = dfa.get_expected();
+        // the external terminals are not matched by the DFA
+        for terminal in self.terminals.iter().filter(|t| t.is_external) {
+            expected.add(TerminalRef::Terminal(terminal.id));
+        }
         let separator = match self.get_separator(grammar_index, &expected, &dfa) {
             Ok(separator) => separator,
             Err(error) => return Err(vec![error]),
         };
+        let indentation = match self.get_indentation(grammar_index) {
+            Ok(indentation) => indentation,
+            Err(error) => return Err(vec![error]),
+        };
         let method = match self.get_parsing_method(parsing_method, grammar_index) {
             Ok(method) => method,
             Err(error) => return Err(vec![error]),
@@ -1923,9 +2298,101 @@ impl Grammar {
             separator,
             method,
             graph,
+            indentation,
+            warnings,
         })
     }
 
+    /// Collects the warnings for this grammar
+    /// This looks for unused terminals, as well as variables that are unreachable from the axiom or unproductive
+    #[must_use]
+    pub fn collect_warnings(&self, grammar_index: usize) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        self.collect_unused_terminals(grammar_index, &mut warnings);
+        self.collect_unreachable_variables(grammar_index, &mut warnings);
+        self.collect_unproductive_variables(grammar_index, &mut warnings);
+        warnings
+    }
+
+    /// Collects the terminals that are neither used by a rule, nor referenced by another terminal
+    fn collect_unused_terminals(&self, grammar_index: usize, warnings: &mut Vec<Warning>) {
+        let separator = self.get_option(OPTION_SEPARATOR).map(|option| option.value.as_str());
+        let used: HashSet<usize> = self
+            .variables
+            .iter()
+            .flat_map(|variable| variable.rules.iter())
+            .flat_map(|rule| rule.body.elements.iter())
+            .filter_map(|element| match element.symbol {
+                SymbolRef::Terminal(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+        for terminal in &self.terminals {
+            if used.contains(&terminal.id)
+                || !terminal.terminal_references.is_empty()
+                || separator == Some(terminal.name.as_str())
+            {
+                continue;
+            }
+            warnings.push(Warning::UnusedTerminal(grammar_index, TerminalRef::Terminal(terminal.id)));
+        }
+    }
+
+    /// Collects the variables that cannot be reached from the axiom
+    fn collect_unreachable_variables(&self, grammar_index: usize, warnings: &mut Vec<Warning>) {
+        let axioms = self.get_generated_axioms();
+        if axioms.is_empty() {
+            // the grammar has not been prepared
+            return;
+        }
+        let mut reachable: HashSet<usize> = axioms.iter().map(|axiom| axiom.id).collect();
+        let mut stack = axioms;
+        while let Some(variable) = stack.pop() {
+            for element in variable.rules.iter().flat_map(|rule| rule.body.elements.iter()) {
+                if let SymbolRef::Variable(id) = element.symbol {
+                    if reachable.insert(id) {
+                        stack.push(self.get_variable(id).unwrap());
+                    }
+                }
+            }
+        }
+        for variable in &self.variables {
+            if variable.generated_for.is_none() && !reachable.contains(&variable.id) {
+                warnings.push(Warning::UnreachableVariable(grammar_index, variable.id));
+            }
+        }
+    }
+
+    /// Collects the variables that can never derive a string of terminals
+    fn collect_unproductive_variables(&self, grammar_index: usize, warnings: &mut Vec<Warning>) {
+        let mut productive = HashSet::new();
+        let mut modified = true;
+        while modified {
+            modified = false;
+            for variable in &self.variables {
+                if productive.contains(&variable.id) {
+                    continue;
+                }
+                let is_productive = variable.rules.iter().any(|rule| {
+                    rule.body.elements.iter().all(|element| match element.symbol {
+                        SymbolRef::Variable(id) => productive.contains(&id),
+                        _ => true,
+                    })
+                });
+                if is_productive {
+                    productive.insert(variable.id);
+                    modified = true;
+                }
+            }
+        }
+        let axioms: Vec<usize> = self.get_generated_axioms().iter().map(|axiom| axiom.id).collect();
+        for variable in &self.variables {
+            if variable.generated_for.is_none() && !axioms.contains(&variable.id) && !productive.contains(&variable.id) {
+                warnings.push(Warning::UnproductiveVariable(grammar_index, variable.id));
+            }
+        }
+    }
+
     /// Gets the separator for the grammar
     fn get_separator(&self, grammar_index: usize, expected: &TerminalSet, dfa: &DFA) -> Result<Option<TerminalRef>, Error> {
         let Some(option) = self.get_option(OPTION_SEPARATOR) else {
@@ -1954,6 +2421,57 @@ impl Grammar {
         ))
     }
 
+    /// Gets the names of the terminals inserted by an indentation-sensitive lexer, as specified by its options
+    #[must_use]
+    pub fn get_indentation_terminals(&self) -> Vec<&str> {
+        self.options
+            .get(OPTION_INDENTATION)
+            .map(|option| {
+                option
+                    .value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Gets the configuration of the indentation-sensitive lexer, if any
+    /// The inserted terminals must be external terminals, so that the lexer's automaton does not match them.
+    fn get_indentation(&self, grammar_index: usize) -> Result<Option<Indentation>, Error> {
+        if self.get_option(OPTION_INDENTATION).is_none() {
+            return Ok(None);
+        }
+        let ids: Vec<u32> = self
+            .get_indentation_terminals()
+            .into_iter()
+            .filter_map(|name| self.get_terminal_for_name(name))
+            .filter(|terminal| terminal.is_external)
+            .map(|terminal| terminal.id as u32)
+            .collect();
+        let [indent_id, dedent_id, newline_id] = ids[..] else {
+            return Err(Error::InvalidOption(
+                grammar_index,
+                OPTION_INDENTATION.to_string(),
+                Vec::new(),
+            ));
+        };
+        let tab_width = match self.get_option(OPTION_TAB_WIDTH) {
+            None => DEFAULT_TAB_WIDTH,
+            Some(option) => match option.value.parse::<usize>() {
+                Ok(value) if value > 0 => value,
+                _ => return Err(Error::InvalidOption(grammar_index, OPTION_TAB_WIDTH.to_string(), Vec::new())),
+            },
+        };
+        Ok(Some(Indentation {
+            indent_id,
+            dedent_id,
+            newline_id,
+            tab_width,
+        }))
+    }
+
     /// Gets the parsing method
     fn get_parsing_method(&self, parsing_method: Option<ParsingMethod>, grammar_index: usize) -> Result<ParsingMethod, Error> {
         match parsing_method {
@@ -1965,6 +2483,8 @@ impl Grammar {
                     "lalr1" => Ok(ParsingMethod::LALR1),
                     "rnglr1" => Ok(ParsingMethod::RNGLR1),
                     "rnglalr1" => Ok(ParsingMethod::RNGLALR1),
+                    "minlr1" => Ok(ParsingMethod::MinLR1),
+                    "rngminlr1" => Ok(ParsingMethod::RNGMinLR1),
                     _ => Err(Error::InvalidOption(
                         grammar_index,
                         OPTION_METHOD.to_string(),
@@ -1974,6 +2494,8 @@ impl Grammar {
                             String::from("lalr1"),
                             String::from("rnglr1"),
                             String::from("rnglalr1"),
+                            String::from("minlr1"),
+                            String::from("rngminlr1"),
                         ],
                     )),
                 },
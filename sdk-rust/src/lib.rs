@@ -39,7 +39,7 @@ use grammars::BuildData;
 use hime_redist::ast::AstNode;
 use hime_redist::text::{Text, TextPosition};
 
-use crate::errors::{Error, Errors};
+use crate::errors::{Error, Errors, Warning};
 use crate::grammars::{Grammar, OPTION_ACCESS_MODIFIER, OPTION_MODE, OPTION_NAMESPACE, OPTION_OUTPUT_PATH, OPTION_RUNTIME};
 use crate::sdk::InMemoryParser;
 
@@ -261,6 +261,8 @@ pub struct LoadedData<'t> {
     pub inputs: Vec<LoadedInput<'t>>,
     /// The loaded grammars
     pub grammars: Vec<Grammar>,
+    /// The warnings produced when building the grammars
+    pub warnings: Vec<Warning>,
 }
 
 /// Transforms into an owned static version of the data
@@ -268,6 +270,7 @@ fn loaded_data_into_static(data: LoadedData<'_>) -> LoadedData<'static> {
     LoadedData {
         inputs: data.inputs.into_iter().map(loaded_input_into_static).collect(),
         grammars: data.grammars,
+        warnings: data.warnings,
     }
 }
 
@@ -346,6 +349,10 @@ pub enum ParsingMethod {
     RNGLR1,
     /// The RNGLR parsing method based on a LALR(1) graph
     RNGLALR1,
+    /// The minimal LR(1) parsing method, with LR(1) power and a number of states close to LALR(1)
+    MinLR1,
+    /// The RNGLR parsing method based on a minimal LR(1) graph
+    RNGMinLR1,
 }
 
 impl ParsingMethod {
@@ -359,8 +366,8 @@ impl ParsingMethod {
     #[must_use]
     pub fn is_rnglr(self) -> bool {
         match self {
-            ParsingMethod::LR0 | ParsingMethod::LR1 | ParsingMethod::LALR1 => false,
-            ParsingMethod::RNGLR1 | ParsingMethod::RNGLALR1 => true,
+            ParsingMethod::LR0 | ParsingMethod::LR1 | ParsingMethod::LALR1 | ParsingMethod::MinLR1 => false,
+            ParsingMethod::RNGLR1 | ParsingMethod::RNGLALR1 | ParsingMethod::RNGMinLR1 => true,
         }
     }
 }
@@ -434,6 +441,8 @@ pub struct CompilationTask<'a> {
     pub method: Option<ParsingMethod>,
     /// Whether to print debug data when building a grammar
     pub print_debug_data: Option<bool>,
+    /// Whether to fail when warnings are produced for the grammars, before any output
+    pub deny_warnings: Option<bool>,
     /// Java-only, the path to the local maven repository to use
     pub java_maven_repository: Option<String>,
     /// Rust-only, indicates whether standard library exclusive features are enabled
@@ -443,6 +452,8 @@ pub struct CompilationTask<'a> {
     pub rust_suppress_module_doc: Option<bool>,
     /// Rust-only, indicates whether to compress automata binary files
     pub rust_compress_automata: Option<bool>,
+    /// Rust-only, indicates whether to generate strongly typed wrappers for the AST nodes
+    pub rust_typed_ast: Option<bool>,
 }
 
 impl<'a> CompilationTask<'a> {
@@ -566,7 +577,14 @@ impl<'a> CompilationTask<'a> {
         self.rust_compress_automata.unwrap_or(false)
     }
 
+    /// Rust-only, gets whether to generate strongly typed wrappers for the AST nodes
+    #[must_use]
+    pub fn get_rust_typed_ast(&self) -> bool {
+        self.rust_typed_ast.unwrap_or(false)
+    }
+
     /// Executes this task
+    /// The warnings produced when building the grammars are collected in the loaded data
     ///
     /// # Errors
     ///
@@ -584,10 +602,13 @@ impl<'a> CompilationTask<'a> {
                 }
             }
         }
-        let all_data = match self.execute_build_grammars(&mut data.grammars) {
+        let all_data = match self.execute_build_grammars(&mut data.grammars, &mut data.warnings) {
             Ok(d) => d,
             Err(errors) => return Err(Errors::from(data, errors)),
         };
+        if let Some(error) = self.check_warnings(&data.warnings) {
+            return Err(Errors::from(data, vec![error]));
+        }
         if self.print_debug_data.unwrap_or_default() {
             for (grammar, data) in data.grammars.iter().zip(all_data.iter()) {
                 println!("================ {}", &grammar.name);
@@ -638,20 +659,38 @@ impl<'a> CompilationTask<'a> {
         grammar_index: usize,
     ) -> Result<InMemoryParser<'g>, Vec<Error>> {
         let data = grammar.build(self.method, grammar_index)?;
+        if let Some(error) = self.check_warnings(&data.warnings) {
+            return Err(vec![error]);
+        }
         output::build_in_memory_grammar(grammar, &data)
     }
 
+    /// Gets the error for the warnings, when they are denied
+    fn check_warnings(&self, warnings: &[Warning]) -> Option<Error> {
+        if self.deny_warnings.unwrap_or_default() && !warnings.is_empty() {
+            Some(Error::Msg(format!("{} warning(s) denied", warnings.len())))
+        } else {
+            None
+        }
+    }
+
     /// Build the specified grammars
-    fn execute_build_grammars(&self, grammars: &mut [Grammar]) -> Result<Vec<BuildData>, Vec<Error>> {
+    fn execute_build_grammars(
+        &self,
+        grammars: &mut [Grammar],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<BuildData>, Vec<Error>> {
         let mut errors = Vec::new();
         let mut results = Vec::new();
         // prepare the grammars
         for (index, grammar) in grammars.iter_mut().enumerate() {
             match grammar.build(self.method, index) {
                 Ok(data) => {
+                    warnings.extend(data.warnings.iter().cloned());
                     results.push(data);
                 }
                 Err(mut errs) => {
+                    warnings.append(&mut grammar.collect_warnings(index));
                     errors.append(&mut errs);
                 }
             }
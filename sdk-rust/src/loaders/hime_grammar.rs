@@ -7,13 +7,14 @@ use std::io::Read;
 use hime_redist::ast::{AstImpl, AstNode};
 use hime_redist::errors::ParseErrors;
 use hime_redist::lexers::automaton::Automaton;
-use hime_redist::lexers::impls::ContextFreeLexer;
+use hime_redist::lexers::impls::ContextSensitiveLexer;
 use hime_redist::lexers::Lexer;
 use hime_redist::parsers::lrk::{LRkAutomaton, LRkParser};
-use hime_redist::parsers::Parser;
+use hime_redist::parsers::push::PushParser;
+use hime_redist::parsers::{LRCompletion, Parser};
 use hime_redist::result::{ParseResult, ParseResultAst};
 use hime_redist::symbols::{SemanticBody, SemanticElementTrait, Symbol};
-use hime_redist::text::Text;
+use hime_redist::text::{Text, TextEdit, TextEditError};
 use hime_redist::tokens::TokenRepository;
 
 /// Static resource for the serialized lexer automaton
@@ -51,21 +52,47 @@ pub const ID_TERMINAL_OPERATOR_ONEMORE: u32 = 0x0016;
 pub const ID_TERMINAL_OPERATOR_UNION: u32 = 0x0017;
 /// The unique identifier for terminal `OPERATOR_DIFFERENCE`
 pub const ID_TERMINAL_OPERATOR_DIFFERENCE: u32 = 0x0018;
+/// The unique identifier for terminal `OPERATOR_TRAILING`
+pub const ID_TERMINAL_OPERATOR_TRAILING: u32 = 0x0019;
+/// The unique identifier for terminal `OPERATOR_INTERSECTION`
+pub const ID_TERMINAL_OPERATOR_INTERSECTION: u32 = 0x001A;
+/// The unique identifier for terminal `OPERATOR_COMPLEMENT`
+pub const ID_TERMINAL_OPERATOR_COMPLEMENT: u32 = 0x001B;
 /// The unique identifier for terminal `TREE_ACTION_PROMOTE`
-pub const ID_TERMINAL_TREE_ACTION_PROMOTE: u32 = 0x0019;
+pub const ID_TERMINAL_TREE_ACTION_PROMOTE: u32 = 0x001C;
 /// The unique identifier for terminal `TREE_ACTION_DROP`
-pub const ID_TERMINAL_TREE_ACTION_DROP: u32 = 0x001A;
+pub const ID_TERMINAL_TREE_ACTION_DROP: u32 = 0x001D;
 /// The unique identifier for terminal `BLOCK_OPTIONS`
-pub const ID_TERMINAL_BLOCK_OPTIONS: u32 = 0x001B;
+pub const ID_TERMINAL_BLOCK_OPTIONS: u32 = 0x001E;
 /// The unique identifier for terminal `BLOCK_TERMINALS`
-pub const ID_TERMINAL_BLOCK_TERMINALS: u32 = 0x001C;
+pub const ID_TERMINAL_BLOCK_TERMINALS: u32 = 0x001F;
 /// The unique identifier for terminal `BLOCK_RULES`
-pub const ID_TERMINAL_BLOCK_RULES: u32 = 0x001D;
+pub const ID_TERMINAL_BLOCK_RULES: u32 = 0x0020;
 /// The unique identifier for terminal `BLOCK_CONTEXT`
-pub const ID_TERMINAL_BLOCK_CONTEXT: u32 = 0x001E;
+pub const ID_TERMINAL_BLOCK_CONTEXT: u32 = 0x0021;
+/// The unique identifier for terminal `BLOCK_PRECEDENCE`
+pub const ID_TERMINAL_BLOCK_PRECEDENCE: u32 = 0x0022;
+/// The unique identifier for terminal `PRECEDENCE_LEFT`
+pub const ID_TERMINAL_PRECEDENCE_LEFT: u32 = 0x0023;
+/// The unique identifier for terminal `PRECEDENCE_RIGHT`
+pub const ID_TERMINAL_PRECEDENCE_RIGHT: u32 = 0x0024;
+/// The unique identifier for terminal `PRECEDENCE_NONASSOC`
+pub const ID_TERMINAL_PRECEDENCE_NONASSOC: u32 = 0x0025;
+/// The unique identifier for terminal `MODE_PUSH`
+pub const ID_TERMINAL_MODE_PUSH: u32 = 0x0026;
+/// The unique identifier for terminal `MODE_POP`
+pub const ID_TERMINAL_MODE_POP: u32 = 0x0027;
+/// The unique identifier for terminal `BLOCK_EXTERNAL`
+pub const ID_TERMINAL_BLOCK_EXTERNAL: u32 = 0x0028;
 
 /// The unique identifier for the default context
 pub const CONTEXT_DEFAULT: u16 = 0;
+/// The unique identifier for context precedence
+pub const CONTEXT_PRECEDENCE: u16 = 0x0001;
+/// The unique identifier for context mode
+pub const CONTEXT_MODE: u16 = 0x0002;
+/// The unique identifier for context external
+pub const CONTEXT_EXTERNAL: u16 = 0x0003;
 
 /// The collection of terminals matched by this lexer
 /// The terminals are in an order consistent with the automaton,
@@ -139,47 +166,88 @@ pub const TERMINALS: &[Symbol] = &[
     },
     Symbol {
         id: 0x0019,
-        name: "TREE_ACTION_PROMOTE",
+        name: "OPERATOR_TRAILING",
     },
     Symbol {
         id: 0x001A,
-        name: "TREE_ACTION_DROP",
+        name: "OPERATOR_INTERSECTION",
     },
     Symbol {
         id: 0x001B,
-        name: "BLOCK_OPTIONS",
+        name: "OPERATOR_COMPLEMENT",
     },
     Symbol {
         id: 0x001C,
-        name: "BLOCK_TERMINALS",
+        name: "TREE_ACTION_PROMOTE",
     },
     Symbol {
         id: 0x001D,
-        name: "BLOCK_RULES",
+        name: "TREE_ACTION_DROP",
     },
     Symbol {
         id: 0x001E,
+        name: "BLOCK_OPTIONS",
+    },
+    Symbol {
+        id: 0x001F,
+        name: "BLOCK_TERMINALS",
+    },
+    Symbol {
+        id: 0x0020,
+        name: "BLOCK_RULES",
+    },
+    Symbol {
+        id: 0x0021,
         name: "BLOCK_CONTEXT",
     },
-    Symbol { id: 0x0043, name: "=" },
-    Symbol { id: 0x0044, name: ";" },
-    Symbol { id: 0x0045, name: "(" },
-    Symbol { id: 0x0046, name: ")" },
-    Symbol { id: 0x0048, name: "{" },
-    Symbol { id: 0x0049, name: "," },
-    Symbol { id: 0x004A, name: "}" },
-    Symbol { id: 0x004F, name: "->" },
     Symbol {
-        id: 0x0050,
+        id: 0x0022,
+        name: "BLOCK_PRECEDENCE",
+    },
+    Symbol {
+        id: 0x0023,
+        name: "PRECEDENCE_LEFT",
+    },
+    Symbol {
+        id: 0x0024,
+        name: "PRECEDENCE_RIGHT",
+    },
+    Symbol {
+        id: 0x0025,
+        name: "PRECEDENCE_NONASSOC",
+    },
+    Symbol {
+        id: 0x0026,
+        name: "MODE_PUSH",
+    },
+    Symbol {
+        id: 0x0027,
+        name: "MODE_POP",
+    },
+    Symbol {
+        id: 0x0028,
+        name: "BLOCK_EXTERNAL",
+    },
+    Symbol { id: 0x0055, name: "=" },
+    Symbol { id: 0x0056, name: ";" },
+    Symbol { id: 0x0057, name: "(" },
+    Symbol { id: 0x0058, name: ")" },
+    Symbol { id: 0x005A, name: "{" },
+    Symbol { id: 0x005B, name: "," },
+    Symbol { id: 0x005C, name: "}" },
+    Symbol { id: 0x0061, name: "->" },
+    Symbol {
+        id: 0x0064,
         name: "fragment",
     },
-    Symbol { id: 0x0052, name: "@" },
-    Symbol { id: 0x0053, name: "<" },
-    Symbol { id: 0x0055, name: ">" },
-    Symbol { id: 0x0056, name: "#" },
-    Symbol { id: 0x005E, name: ":" },
+    Symbol { id: 0x0067, name: "@" },
+    Symbol { id: 0x0068, name: "<" },
+    Symbol { id: 0x006A, name: ">" },
+    Symbol { id: 0x006B, name: ":" },
+    Symbol { id: 0x006C, name: "%" },
+    Symbol { id: 0x006D, name: "#" },
     Symbol {
-        id: 0x0060,
+        id: 0x0079,
         name: "grammar",
     },
 ];
@@ -187,294 +255,370 @@ pub const TERMINALS: &[Symbol] = &[
 /// Creates a new lexer
 fn new_lexer<'a: 'b, 'b, 'c>(repository: TokenRepository<'a, 'b, 'c>, errors: &'c mut ParseErrors<'a>) -> Lexer<'a, 'b, 'c> {
     let automaton = Automaton::new(LEXER_AUTOMATON);
-    Lexer::ContextFree(ContextFreeLexer::new(repository, errors, automaton, 0x0007))
+    Lexer::ContextSensitive(ContextSensitiveLexer::new(repository, errors, automaton, 0x0007))
 }
 
 /// Static resource for the serialized parser automaton
 const PARSER_AUTOMATON: &[u8] = include_bytes!("hime_grammar_parser.bin");
 
 /// The unique identifier for variable option
-pub const ID_VARIABLE_OPTION: u32 = 0x001F;
+pub const ID_VARIABLE_OPTION: u32 = 0x0029;
 /// The unique identifier for variable `terminal_def_atom`
-pub const ID_VARIABLE_TERMINAL_DEF_ATOM: u32 = 0x0020;
+pub const ID_VARIABLE_TERMINAL_DEF_ATOM: u32 = 0x002A;
 /// The unique identifier for variable `terminal_def_element`
-pub const ID_VARIABLE_TERMINAL_DEF_ELEMENT: u32 = 0x0021;
+pub const ID_VARIABLE_TERMINAL_DEF_ELEMENT: u32 = 0x002B;
 /// The unique identifier for variable `terminal_def_cardinalilty`
-pub const ID_VARIABLE_TERMINAL_DEF_CARDINALILTY: u32 = 0x0022;
+pub const ID_VARIABLE_TERMINAL_DEF_CARDINALILTY: u32 = 0x002C;
 /// The unique identifier for variable `terminal_def_repetition`
-pub const ID_VARIABLE_TERMINAL_DEF_REPETITION: u32 = 0x0023;
+pub const ID_VARIABLE_TERMINAL_DEF_REPETITION: u32 = 0x002D;
+/// The unique identifier for variable `terminal_def_complement`
+pub const ID_VARIABLE_TERMINAL_DEF_COMPLEMENT: u32 = 0x002E;
 /// The unique identifier for variable `terminal_def_fragment`
-pub const ID_VARIABLE_TERMINAL_DEF_FRAGMENT: u32 = 0x0024;
+pub const ID_VARIABLE_TERMINAL_DEF_FRAGMENT: u32 = 0x002F;
 /// The unique identifier for variable `terminal_def_restrict`
-pub const ID_VARIABLE_TERMINAL_DEF_RESTRICT: u32 = 0x0025;
+pub const ID_VARIABLE_TERMINAL_DEF_RESTRICT: u32 = 0x0030;
 /// The unique identifier for variable `terminal_definition`
-pub const ID_VARIABLE_TERMINAL_DEFINITION: u32 = 0x0026;
+pub const ID_VARIABLE_TERMINAL_DEFINITION: u32 = 0x0031;
+/// The unique identifier for variable `terminal_def_trailing`
+pub const ID_VARIABLE_TERMINAL_DEF_TRAILING: u32 = 0x0032;
+/// The unique identifier for variable `terminal_mode_action`
+pub const ID_VARIABLE_TERMINAL_MODE_ACTION: u32 = 0x0033;
 /// The unique identifier for variable `terminal_rule`
-pub const ID_VARIABLE_TERMINAL_RULE: u32 = 0x0027;
+pub const ID_VARIABLE_TERMINAL_RULE: u32 = 0x0034;
 /// The unique identifier for variable `terminal_fragment`
-pub const ID_VARIABLE_TERMINAL_FRAGMENT: u32 = 0x0028;
+pub const ID_VARIABLE_TERMINAL_FRAGMENT: u32 = 0x0035;
 /// The unique identifier for variable `terminal_context`
-pub const ID_VARIABLE_TERMINAL_CONTEXT: u32 = 0x0029;
+pub const ID_VARIABLE_TERMINAL_CONTEXT: u32 = 0x0036;
+/// The unique identifier for variable `terminal_external`
+pub const ID_VARIABLE_TERMINAL_EXTERNAL: u32 = 0x0037;
 /// The unique identifier for variable `terminal_item`
-pub const ID_VARIABLE_TERMINAL_ITEM: u32 = 0x002A;
+pub const ID_VARIABLE_TERMINAL_ITEM: u32 = 0x0038;
 /// The unique identifier for variable `rule_sym_action`
-pub const ID_VARIABLE_RULE_SYM_ACTION: u32 = 0x002B;
+pub const ID_VARIABLE_RULE_SYM_ACTION: u32 = 0x0039;
 /// The unique identifier for variable `rule_sym_virtual`
-pub const ID_VARIABLE_RULE_SYM_VIRTUAL: u32 = 0x002C;
+pub const ID_VARIABLE_RULE_SYM_VIRTUAL: u32 = 0x003A;
 /// The unique identifier for variable `rule_sym_ref_params`
-pub const ID_VARIABLE_RULE_SYM_REF_PARAMS: u32 = 0x002D;
+pub const ID_VARIABLE_RULE_SYM_REF_PARAMS: u32 = 0x003B;
 /// The unique identifier for variable `rule_sym_ref_template`
-pub const ID_VARIABLE_RULE_SYM_REF_TEMPLATE: u32 = 0x002E;
+pub const ID_VARIABLE_RULE_SYM_REF_TEMPLATE: u32 = 0x003C;
 /// The unique identifier for variable `rule_sym_ref_simple`
-pub const ID_VARIABLE_RULE_SYM_REF_SIMPLE: u32 = 0x002F;
+pub const ID_VARIABLE_RULE_SYM_REF_SIMPLE: u32 = 0x003D;
+/// The unique identifier for variable `rule_sym_label`
+pub const ID_VARIABLE_RULE_SYM_LABEL: u32 = 0x003E;
+/// The unique identifier for variable `rule_sym_annotation`
+pub const ID_VARIABLE_RULE_SYM_ANNOTATION: u32 = 0x003F;
 /// The unique identifier for variable `rule_def_atom`
-pub const ID_VARIABLE_RULE_DEF_ATOM: u32 = 0x0030;
+pub const ID_VARIABLE_RULE_DEF_ATOM: u32 = 0x0040;
 /// The unique identifier for variable `rule_def_context`
-pub const ID_VARIABLE_RULE_DEF_CONTEXT: u32 = 0x0031;
+pub const ID_VARIABLE_RULE_DEF_CONTEXT: u32 = 0x0041;
 /// The unique identifier for variable `rule_def_sub`
-pub const ID_VARIABLE_RULE_DEF_SUB: u32 = 0x0032;
+pub const ID_VARIABLE_RULE_DEF_SUB: u32 = 0x0042;
 /// The unique identifier for variable `rule_def_element`
-pub const ID_VARIABLE_RULE_DEF_ELEMENT: u32 = 0x0033;
+pub const ID_VARIABLE_RULE_DEF_ELEMENT: u32 = 0x0043;
 /// The unique identifier for variable `rule_def_tree_action`
-pub const ID_VARIABLE_RULE_DEF_TREE_ACTION: u32 = 0x0034;
+pub const ID_VARIABLE_RULE_DEF_TREE_ACTION: u32 = 0x0044;
 /// The unique identifier for variable `rule_def_repetition`
-pub const ID_VARIABLE_RULE_DEF_REPETITION: u32 = 0x0035;
+pub const ID_VARIABLE_RULE_DEF_REPETITION: u32 = 0x0045;
 /// The unique identifier for variable `rule_def_fragment`
-pub const ID_VARIABLE_RULE_DEF_FRAGMENT: u32 = 0x0036;
+pub const ID_VARIABLE_RULE_DEF_FRAGMENT: u32 = 0x0046;
 /// The unique identifier for variable `rule_def_choice`
-pub const ID_VARIABLE_RULE_DEF_CHOICE: u32 = 0x0037;
+pub const ID_VARIABLE_RULE_DEF_CHOICE: u32 = 0x0047;
 /// The unique identifier for variable `rule_definition`
-pub const ID_VARIABLE_RULE_DEFINITION: u32 = 0x0038;
+pub const ID_VARIABLE_RULE_DEFINITION: u32 = 0x0048;
 /// The unique identifier for variable `rule_template_params`
-pub const ID_VARIABLE_RULE_TEMPLATE_PARAMS: u32 = 0x0039;
+pub const ID_VARIABLE_RULE_TEMPLATE_PARAMS: u32 = 0x0049;
 /// The unique identifier for variable `cf_rule_template`
-pub const ID_VARIABLE_CF_RULE_TEMPLATE: u32 = 0x003A;
+pub const ID_VARIABLE_CF_RULE_TEMPLATE: u32 = 0x004A;
 /// The unique identifier for variable `cf_rule_simple`
-pub const ID_VARIABLE_CF_RULE_SIMPLE: u32 = 0x003B;
+pub const ID_VARIABLE_CF_RULE_SIMPLE: u32 = 0x004B;
 /// The unique identifier for variable `cf_rule`
-pub const ID_VARIABLE_CF_RULE: u32 = 0x003C;
+pub const ID_VARIABLE_CF_RULE: u32 = 0x004C;
+/// The unique identifier for variable `precedence_level`
+pub const ID_VARIABLE_PRECEDENCE_LEVEL: u32 = 0x004D;
 /// The unique identifier for variable `grammar_options`
-pub const ID_VARIABLE_GRAMMAR_OPTIONS: u32 = 0x003D;
+pub const ID_VARIABLE_GRAMMAR_OPTIONS: u32 = 0x004E;
 /// The unique identifier for variable `grammar_terminals`
-pub const ID_VARIABLE_GRAMMAR_TERMINALS: u32 = 0x003E;
+pub const ID_VARIABLE_GRAMMAR_TERMINALS: u32 = 0x004F;
+/// The unique identifier for variable `grammar_precedence`
+pub const ID_VARIABLE_GRAMMAR_PRECEDENCE: u32 = 0x0050;
 /// The unique identifier for variable `grammar_cf_rules`
-pub const ID_VARIABLE_GRAMMAR_CF_RULES: u32 = 0x003F;
+pub const ID_VARIABLE_GRAMMAR_CF_RULES: u32 = 0x0051;
 /// The unique identifier for variable `grammar_parency`
-pub const ID_VARIABLE_GRAMMAR_PARENCY: u32 = 0x0040;
+pub const ID_VARIABLE_GRAMMAR_PARENCY: u32 = 0x0052;
 /// The unique identifier for variable `cf_grammar`
-pub const ID_VARIABLE_CF_GRAMMAR: u32 = 0x0041;
+pub const ID_VARIABLE_CF_GRAMMAR: u32 = 0x0053;
 /// The unique identifier for variable file
-pub const ID_VARIABLE_FILE: u32 = 0x0042;
+pub const ID_VARIABLE_FILE: u32 = 0x0054;
 
 /// The unique identifier for virtual range
-pub const ID_VIRTUAL_RANGE: u32 = 0x0047;
+pub const ID_VIRTUAL_RANGE: u32 = 0x0059;
 /// The unique identifier for virtual concat
-pub const ID_VIRTUAL_CONCAT: u32 = 0x004B;
+pub const ID_VIRTUAL_CONCAT: u32 = 0x005D;
 /// The unique identifier for virtual emptypart
-pub const ID_VIRTUAL_EMPTYPART: u32 = 0x0058;
+pub const ID_VIRTUAL_EMPTYPART: u32 = 0x006F;
 
 /// The collection of variables matched by this parser
 /// The variables are in an order consistent with the automaton,
 /// so that variable indices in the automaton can be used to retrieve the variables in this table
 pub const VARIABLES: &[Symbol] = &[
     Symbol {
-        id: 0x001F,
+        id: 0x0029,
         name: "option",
     },
     Symbol {
-        id: 0x0020,
+        id: 0x002A,
         name: "terminal_def_atom",
     },
     Symbol {
-        id: 0x0021,
+        id: 0x002B,
         name: "terminal_def_element",
     },
     Symbol {
-        id: 0x0022,
+        id: 0x002C,
         name: "terminal_def_cardinalilty",
     },
     Symbol {
-        id: 0x0023,
+        id: 0x002D,
         name: "terminal_def_repetition",
     },
     Symbol {
-        id: 0x0024,
+        id: 0x002E,
+        name: "terminal_def_complement",
+    },
+    Symbol {
+        id: 0x002F,
         name: "terminal_def_fragment",
     },
     Symbol {
-        id: 0x0025,
+        id: 0x0030,
         name: "terminal_def_restrict",
     },
     Symbol {
-        id: 0x0026,
+        id: 0x0031,
         name: "terminal_definition",
     },
     Symbol {
-        id: 0x0027,
+        id: 0x0032,
+        name: "terminal_def_trailing",
+    },
+    Symbol {
+        id: 0x0033,
+        name: "terminal_mode_action",
+    },
+    Symbol {
+        id: 0x0034,
         name: "terminal_rule",
     },
     Symbol {
-        id: 0x0028,
+        id: 0x0035,
         name: "terminal_fragment",
     },
     Symbol {
-        id: 0x0029,
+        id: 0x0036,
         name: "terminal_context",
     },
     Symbol {
-        id: 0x002A,
+        id: 0x0037,
+        name: "terminal_external",
+    },
+    Symbol {
+        id: 0x0038,
         name: "terminal_item",
     },
     Symbol {
-        id: 0x002B,
+        id: 0x0039,
         name: "rule_sym_action",
     },
     Symbol {
-        id: 0x002C,
+        id: 0x003A,
         name: "rule_sym_virtual",
     },
     Symbol {
-        id: 0x002D,
+        id: 0x003B,
         name: "rule_sym_ref_params",
     },
     Symbol {
-        id: 0x002E,
+        id: 0x003C,
         name: "rule_sym_ref_template",
     },
     Symbol {
-        id: 0x002F,
+        id: 0x003D,
         name: "rule_sym_ref_simple",
     },
     Symbol {
-        id: 0x0030,
+        id: 0x003E,
+        name: "rule_sym_label",
+    },
+    Symbol {
+        id: 0x003F,
+        name: "rule_sym_annotation",
+    },
+    Symbol {
+        id: 0x0040,
         name: "rule_def_atom",
     },
     Symbol {
-        id: 0x0031,
+        id: 0x0041,
         name: "rule_def_context",
     },
     Symbol {
-        id: 0x0032,
+        id: 0x0042,
         name: "rule_def_sub",
     },
     Symbol {
-        id: 0x0033,
+        id: 0x0043,
         name: "rule_def_element",
     },
     Symbol {
-        id: 0x0034,
+        id: 0x0044,
         name: "rule_def_tree_action",
     },
     Symbol {
-        id: 0x0035,
+        id: 0x0045,
         name: "rule_def_repetition",
     },
     Symbol {
-        id: 0x0036,
+        id: 0x0046,
         name: "rule_def_fragment",
     },
     Symbol {
-        id: 0x0037,
+        id: 0x0047,
         name: "rule_def_choice",
     },
     Symbol {
-        id: 0x0038,
+        id: 0x0048,
         name: "rule_definition",
     },
     Symbol {
-        id: 0x0039,
+        id: 0x0049,
         name: "rule_template_params",
     },
     Symbol {
-        id: 0x003A,
+        id: 0x004A,
         name: "cf_rule_template",
     },
     Symbol {
-        id: 0x003B,
+        id: 0x004B,
         name: "cf_rule_simple",
     },
     Symbol {
-        id: 0x003C,
+        id: 0x004C,
         name: "cf_rule",
     },
     Symbol {
-        id: 0x003D,
+        id: 0x004D,
+        name: "precedence_level",
+    },
+    Symbol {
+        id: 0x004E,
         name: "grammar_options",
     },
     Symbol {
-        id: 0x003E,
+        id: 0x004F,
         name: "grammar_terminals",
     },
     Symbol {
-        id: 0x003F,
+        id: 0x0050,
+        name: "grammar_precedence",
+    },
+    Symbol {
+        id: 0x0051,
         name: "grammar_cf_rules",
     },
     Symbol {
-        id: 0x0040,
+        id: 0x0052,
         name: "grammar_parency",
     },
     Symbol {
-        id: 0x0041,
+        id: 0x0053,
         name: "cf_grammar",
     },
     Symbol {
-        id: 0x0042,
+        id: 0x0054,
         name: "file",
     },
     Symbol {
-        id: 0x004C,
-        name: "__V76",
+        id: 0x005E,
+        name: "__V94",
     },
     Symbol {
-        id: 0x004D,
-        name: "__V77",
+        id: 0x005F,
+        name: "__V95",
     },
     Symbol {
-        id: 0x004E,
-        name: "__V78",
+        id: 0x0060,
+        name: "__V96",
     },
     Symbol {
-        id: 0x0051,
-        name: "__V81",
+        id: 0x0062,
+        name: "__V98",
     },
     Symbol {
-        id: 0x0054,
-        name: "__V84",
+        id: 0x0063,
+        name: "__V99",
     },
     Symbol {
-        id: 0x0057,
-        name: "__V87",
+        id: 0x0065,
+        name: "__V101",
     },
     Symbol {
-        id: 0x0059,
-        name: "__V89",
+        id: 0x0066,
+        name: "__V102",
     },
     Symbol {
-        id: 0x005A,
-        name: "__V90",
+        id: 0x0069,
+        name: "__V105",
     },
     Symbol {
-        id: 0x005B,
-        name: "__V91",
+        id: 0x006E,
+        name: "__V110",
     },
     Symbol {
-        id: 0x005C,
-        name: "__V92",
+        id: 0x0070,
+        name: "__V112",
     },
     Symbol {
-        id: 0x005D,
-        name: "__V93",
+        id: 0x0071,
+        name: "__V113",
     },
     Symbol {
-        id: 0x005F,
-        name: "__V95",
+        id: 0x0072,
+        name: "__V114",
     },
     Symbol {
-        id: 0x0061,
-        name: "__V97",
+        id: 0x0073,
+        name: "__V115",
     },
     Symbol {
-        id: 0x0062,
+        id: 0x0074,
+        name: "__V116",
+    },
+    Symbol {
+        id: 0x0075,
+        name: "__V117",
+    },
+    Symbol {
+        id: 0x0076,
+        name: "__V118",
+    },
+    Symbol {
+        id: 0x0077,
+        name: "__V119",
+    },
+    Symbol {
+        id: 0x0078,
+        name: "__V120",
+    },
+    Symbol {
+        id: 0x007A,
+        name: "__V122",
+    },
+    Symbol {
+        id: 0x007B,
+        name: "__V123",
+    },
+    Symbol {
+        id: 0x007C,
         name: "__VAxiom",
     },
 ];
@@ -484,15 +628,15 @@ pub const VARIABLES: &[Symbol] = &[
 /// so that virtual indices in the automaton can be used to retrieve the virtuals in this table
 pub const VIRTUALS: &[Symbol] = &[
     Symbol {
-        id: 0x0047,
+        id: 0x0059,
         name: "range",
     },
     Symbol {
-        id: 0x004B,
+        id: 0x005D,
         name: "concat",
     },
     Symbol {
-        id: 0x0058,
+        id: 0x006F,
         name: "emptypart",
     },
 ];
@@ -521,6 +665,46 @@ pub fn parse_utf8_stream(input: &mut dyn Read) -> Result<ParseResultAst, std::io
     Ok(parse_text(text))
 }
 
+/// Parses again the input of a previous result after some edits, reusing what the edits do not affect
+/// The spans of the edits refer to the previous input and must not overlap.
+///
+/// # Errors
+///
+/// Return a `TextEditError` when the edits are not valid for the previous input
+pub fn reparse(
+    previous: ParseResult<'static, '_, 'static, AstImpl>,
+    edits: &[TextEdit],
+) -> Result<ParseResultAst, TextEditError> {
+    Ok(parse_into(previous.apply_edits(edits)?, 0))
+}
+
+/// Gets the symbols expected at the specified index in an input, for code completion
+/// Only the tokens that end at or before the index are parsed.
+#[must_use]
+pub fn get_expected_at(input: &str, index: usize) -> LRCompletion<'static> {
+    let mut result = ParseResult::<AstImpl>::new(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));
+    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+    let data = result.get_parsing_data();
+    let mut lexer = new_lexer(data.0, data.1);
+    let automaton = LRkAutomaton::new(PARSER_AUTOMATON);
+    let mut parser = LRkParser::new(&mut lexer, VARIABLES, VIRTUALS, automaton, data.2, &mut my_actions);
+    parser.get_expected_at(index)
+}
+
+/// Parses the specified string with this parser, keeping the separators as trivia and building the concrete syntax tree
+#[must_use]
+pub fn parse_str_lossless(input: &str) -> ParseResult<'static, '_, 'static, AstImpl> {
+    let result = ParseResult::<AstImpl>::new_lossless(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));
+    parse_into(result, 0)
+}
+
+/// Creates a push parser, to be fed with the input piece by piece
+#[must_use]
+pub fn new_push_parser() -> PushParser<'static, 'static> {
+    let automaton = Automaton::new(LEXER_AUTOMATON);
+    PushParser::new(TERMINALS, VARIABLES, VIRTUALS, automaton, |result| parse_into(result, 0))
+}
+
 /// Parses the specified text with this parser
 fn parse_text(text: Text) -> ParseResult<'static, '_, 'static, AstImpl> {
     parse_text_with(text, TERMINALS, VARIABLES, VIRTUALS)
@@ -533,14 +717,23 @@ fn parse_text_with<'s, 't, 'a>(
     variables: &'a [Symbol<'s>],
     virtuals: &'a [Symbol<'s>],
 ) -> ParseResult<'s, 't, 'a, AstImpl> {
+    let result = ParseResult::<AstImpl>::new(terminals, variables, virtuals, text);
+    parse_into(result, 0)
+}
+
+/// Parses the input of the specified result into it with this parser, from the specified entry point
+fn parse_into<'s, 't, 'a>(mut result: ParseResult<'s, 't, 'a, AstImpl>, entry: usize) -> ParseResult<'s, 't, 'a, AstImpl> {
     let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
-    let mut result = ParseResult::<AstImpl>::new(terminals, variables, virtuals, text);
+    let variables = result.variables;
+    let virtuals = result.virtuals;
     {
         let data = result.get_parsing_data();
         let mut lexer = new_lexer(data.0, data.1);
         let automaton = LRkAutomaton::new(PARSER_AUTOMATON);
-        let mut parser = LRkParser::new(&mut lexer, variables, virtuals, automaton, data.2, &mut my_actions);
-        parser.parse();
+        let parser = LRkParser::new(&mut lexer, variables, virtuals, automaton, data.2, &mut my_actions).with_entry(entry);
+        if let Some(mut parser) = parser {
+            parser.parse();
+        }
     }
     result
 }
@@ -563,29 +756,45 @@ pub trait Visitor {
     fn on_terminal_operator_onemore(&self, _node: &AstNode) {}
     fn on_terminal_operator_union(&self, _node: &AstNode) {}
     fn on_terminal_operator_difference(&self, _node: &AstNode) {}
+    fn on_terminal_operator_trailing(&self, _node: &AstNode) {}
+    fn on_terminal_operator_intersection(&self, _node: &AstNode) {}
+    fn on_terminal_operator_complement(&self, _node: &AstNode) {}
     fn on_terminal_tree_action_promote(&self, _node: &AstNode) {}
     fn on_terminal_tree_action_drop(&self, _node: &AstNode) {}
     fn on_terminal_block_options(&self, _node: &AstNode) {}
     fn on_terminal_block_terminals(&self, _node: &AstNode) {}
     fn on_terminal_block_rules(&self, _node: &AstNode) {}
     fn on_terminal_block_context(&self, _node: &AstNode) {}
+    fn on_terminal_block_precedence(&self, _node: &AstNode) {}
+    fn on_terminal_precedence_left(&self, _node: &AstNode) {}
+    fn on_terminal_precedence_right(&self, _node: &AstNode) {}
+    fn on_terminal_precedence_nonassoc(&self, _node: &AstNode) {}
+    fn on_terminal_mode_push(&self, _node: &AstNode) {}
+    fn on_terminal_mode_pop(&self, _node: &AstNode) {}
+    fn on_terminal_block_external(&self, _node: &AstNode) {}
     fn on_variable_option(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_atom(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_element(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_cardinalilty(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_repetition(&self, _node: &AstNode) {}
+    fn on_variable_terminal_def_complement(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_fragment(&self, _node: &AstNode) {}
     fn on_variable_terminal_def_restrict(&self, _node: &AstNode) {}
     fn on_variable_terminal_definition(&self, _node: &AstNode) {}
+    fn on_variable_terminal_def_trailing(&self, _node: &AstNode) {}
+    fn on_variable_terminal_mode_action(&self, _node: &AstNode) {}
     fn on_variable_terminal_rule(&self, _node: &AstNode) {}
     fn on_variable_terminal_fragment(&self, _node: &AstNode) {}
     fn on_variable_terminal_context(&self, _node: &AstNode) {}
+    fn on_variable_terminal_external(&self, _node: &AstNode) {}
     fn on_variable_terminal_item(&self, _node: &AstNode) {}
     fn on_variable_rule_sym_action(&self, _node: &AstNode) {}
     fn on_variable_rule_sym_virtual(&self, _node: &AstNode) {}
     fn on_variable_rule_sym_ref_params(&self, _node: &AstNode) {}
     fn on_variable_rule_sym_ref_template(&self, _node: &AstNode) {}
     fn on_variable_rule_sym_ref_simple(&self, _node: &AstNode) {}
+    fn on_variable_rule_sym_label(&self, _node: &AstNode) {}
+    fn on_variable_rule_sym_annotation(&self, _node: &AstNode) {}
     fn on_variable_rule_def_atom(&self, _node: &AstNode) {}
     fn on_variable_rule_def_context(&self, _node: &AstNode) {}
     fn on_variable_rule_def_sub(&self, _node: &AstNode) {}
@@ -599,8 +808,10 @@ pub trait Visitor {
     fn on_variable_cf_rule_template(&self, _node: &AstNode) {}
     fn on_variable_cf_rule_simple(&self, _node: &AstNode) {}
     fn on_variable_cf_rule(&self, _node: &AstNode) {}
+    fn on_variable_precedence_level(&self, _node: &AstNode) {}
     fn on_variable_grammar_options(&self, _node: &AstNode) {}
     fn on_variable_grammar_terminals(&self, _node: &AstNode) {}
+    fn on_variable_grammar_precedence(&self, _node: &AstNode) {}
     fn on_variable_grammar_cf_rules(&self, _node: &AstNode) {}
     fn on_variable_grammar_parency(&self, _node: &AstNode) {}
     fn on_variable_cf_grammar(&self, _node: &AstNode) {}
@@ -640,51 +851,69 @@ pub fn visit_ast_node(node: AstNode, visitor: &dyn Visitor) {
         0x0016 => visitor.on_terminal_operator_onemore(&node),
         0x0017 => visitor.on_terminal_operator_union(&node),
         0x0018 => visitor.on_terminal_operator_difference(&node),
-        0x0019 => visitor.on_terminal_tree_action_promote(&node),
-        0x001A => visitor.on_terminal_tree_action_drop(&node),
-        0x001B => visitor.on_terminal_block_options(&node),
-        0x001C => visitor.on_terminal_block_terminals(&node),
-        0x001D => visitor.on_terminal_block_rules(&node),
-        0x001E => visitor.on_terminal_block_context(&node),
-        0x001F => visitor.on_variable_option(&node),
-        0x0020 => visitor.on_variable_terminal_def_atom(&node),
-        0x0021 => visitor.on_variable_terminal_def_element(&node),
-        0x0022 => visitor.on_variable_terminal_def_cardinalilty(&node),
-        0x0023 => visitor.on_variable_terminal_def_repetition(&node),
-        0x0024 => visitor.on_variable_terminal_def_fragment(&node),
-        0x0025 => visitor.on_variable_terminal_def_restrict(&node),
-        0x0026 => visitor.on_variable_terminal_definition(&node),
-        0x0027 => visitor.on_variable_terminal_rule(&node),
-        0x0028 => visitor.on_variable_terminal_fragment(&node),
-        0x0029 => visitor.on_variable_terminal_context(&node),
-        0x002A => visitor.on_variable_terminal_item(&node),
-        0x002B => visitor.on_variable_rule_sym_action(&node),
-        0x002C => visitor.on_variable_rule_sym_virtual(&node),
-        0x002D => visitor.on_variable_rule_sym_ref_params(&node),
-        0x002E => visitor.on_variable_rule_sym_ref_template(&node),
-        0x002F => visitor.on_variable_rule_sym_ref_simple(&node),
-        0x0030 => visitor.on_variable_rule_def_atom(&node),
-        0x0031 => visitor.on_variable_rule_def_context(&node),
-        0x0032 => visitor.on_variable_rule_def_sub(&node),
-        0x0033 => visitor.on_variable_rule_def_element(&node),
-        0x0034 => visitor.on_variable_rule_def_tree_action(&node),
-        0x0035 => visitor.on_variable_rule_def_repetition(&node),
-        0x0036 => visitor.on_variable_rule_def_fragment(&node),
-        0x0037 => visitor.on_variable_rule_def_choice(&node),
-        0x0038 => visitor.on_variable_rule_definition(&node),
-        0x0039 => visitor.on_variable_rule_template_params(&node),
-        0x003A => visitor.on_variable_cf_rule_template(&node),
-        0x003B => visitor.on_variable_cf_rule_simple(&node),
-        0x003C => visitor.on_variable_cf_rule(&node),
-        0x003D => visitor.on_variable_grammar_options(&node),
-        0x003E => visitor.on_variable_grammar_terminals(&node),
-        0x003F => visitor.on_variable_grammar_cf_rules(&node),
-        0x0040 => visitor.on_variable_grammar_parency(&node),
-        0x0041 => visitor.on_variable_cf_grammar(&node),
-        0x0042 => visitor.on_variable_file(&node),
-        0x0047 => visitor.on_virtual_range(&node),
-        0x004B => visitor.on_virtual_concat(&node),
-        0x0058 => visitor.on_virtual_emptypart(&node),
+        0x0019 => visitor.on_terminal_operator_trailing(&node),
+        0x001A => visitor.on_terminal_operator_intersection(&node),
+        0x001B => visitor.on_terminal_operator_complement(&node),
+        0x001C => visitor.on_terminal_tree_action_promote(&node),
+        0x001D => visitor.on_terminal_tree_action_drop(&node),
+        0x001E => visitor.on_terminal_block_options(&node),
+        0x001F => visitor.on_terminal_block_terminals(&node),
+        0x0020 => visitor.on_terminal_block_rules(&node),
+        0x0021 => visitor.on_terminal_block_context(&node),
+        0x0022 => visitor.on_terminal_block_precedence(&node),
+        0x0023 => visitor.on_terminal_precedence_left(&node),
+        0x0024 => visitor.on_terminal_precedence_right(&node),
+        0x0025 => visitor.on_terminal_precedence_nonassoc(&node),
+        0x0026 => visitor.on_terminal_mode_push(&node),
+        0x0027 => visitor.on_terminal_mode_pop(&node),
+        0x0028 => visitor.on_terminal_block_external(&node),
+        0x0029 => visitor.on_variable_option(&node),
+        0x002A => visitor.on_variable_terminal_def_atom(&node),
+        0x002B => visitor.on_variable_terminal_def_element(&node),
+        0x002C => visitor.on_variable_terminal_def_cardinalilty(&node),
+        0x002D => visitor.on_variable_terminal_def_repetition(&node),
+        0x002E => visitor.on_variable_terminal_def_complement(&node),
+        0x002F => visitor.on_variable_terminal_def_fragment(&node),
+        0x0030 => visitor.on_variable_terminal_def_restrict(&node),
+        0x0031 => visitor.on_variable_terminal_definition(&node),
+        0x0032 => visitor.on_variable_terminal_def_trailing(&node),
+        0x0033 => visitor.on_variable_terminal_mode_action(&node),
+        0x0034 => visitor.on_variable_terminal_rule(&node),
+        0x0035 => visitor.on_variable_terminal_fragment(&node),
+        0x0036 => visitor.on_variable_terminal_context(&node),
+        0x0037 => visitor.on_variable_terminal_external(&node),
+        0x0038 => visitor.on_variable_terminal_item(&node),
+        0x0039 => visitor.on_variable_rule_sym_action(&node),
+        0x003A => visitor.on_variable_rule_sym_virtual(&node),
+        0x003B => visitor.on_variable_rule_sym_ref_params(&node),
+        0x003C => visitor.on_variable_rule_sym_ref_template(&node),
+        0x003D => visitor.on_variable_rule_sym_ref_simple(&node),
+        0x003E => visitor.on_variable_rule_sym_label(&node),
+        0x003F => visitor.on_variable_rule_sym_annotation(&node),
+        0x0040 => visitor.on_variable_rule_def_atom(&node),
+        0x0041 => visitor.on_variable_rule_def_context(&node),
+        0x0042 => visitor.on_variable_rule_def_sub(&node),
+        0x0043 => visitor.on_variable_rule_def_element(&node),
+        0x0044 => visitor.on_variable_rule_def_tree_action(&node),
+        0x0045 => visitor.on_variable_rule_def_repetition(&node),
+        0x0046 => visitor.on_variable_rule_def_fragment(&node),
+        0x0047 => visitor.on_variable_rule_def_choice(&node),
+        0x0048 => visitor.on_variable_rule_definition(&node),
+        0x0049 => visitor.on_variable_rule_template_params(&node),
+        0x004A => visitor.on_variable_cf_rule_template(&node),
+        0x004B => visitor.on_variable_cf_rule_simple(&node),
+        0x004C => visitor.on_variable_cf_rule(&node),
+        0x004D => visitor.on_variable_precedence_level(&node),
+        0x004E => visitor.on_variable_grammar_options(&node),
+        0x004F => visitor.on_variable_grammar_terminals(&node),
+        0x0050 => visitor.on_variable_grammar_precedence(&node),
+        0x0051 => visitor.on_variable_grammar_cf_rules(&node),
+        0x0052 => visitor.on_variable_grammar_parency(&node),
+        0x0053 => visitor.on_variable_cf_grammar(&node),
+        0x0054 => visitor.on_variable_file(&node),
+        0x0059 => visitor.on_virtual_range(&node),
+        0x005D => visitor.on_virtual_concat(&node),
+        0x006F => visitor.on_virtual_emptypart(&node),
         _ => (),
     };
 }
@@ -34,8 +34,8 @@ use hime_redist::symbols::SemanticElementTrait;
 use crate::errors::{Error, Errors};
 use crate::finite::{FinalItem, NFA};
 use crate::grammars::{
-    BodySet, Grammar, Rule, RuleBody, SymbolRef, TemplateRuleBody, TemplateRuleParam, TemplateRuleRef, TemplateRuleSymbol,
-    TerminalReference, DEFAULT_CONTEXT_NAME,
+    Associativity, BodySet, Grammar, ModeAction, Rule, RuleAnnotations, RuleBody, RulePreference, SymbolRef, TemplateRuleBody,
+    TemplateRuleParam, TemplateRuleRef, TemplateRuleSymbol, TerminalReference, DEFAULT_CONTEXT_NAME, OPTION_INDENTATION,
 };
 use crate::unicode::{Span, BLOCKS, CATEGORIES};
 use crate::{CharSpan, Input, InputReference, LoadedData, LoadedInput, CHARSPAN_INVALID};
@@ -80,6 +80,7 @@ fn build_loaded_data<'t>(
             })
             .collect(),
         grammars,
+        warnings: Vec::new(),
     }
 }
 
@@ -333,14 +334,23 @@ impl<'s, 't, 'a> Loader<'s, 't, 'a> {
                     load_terminals(self.input_index, errors, &mut self.grammar, node);
                 }
                 hime_grammar::ID_TERMINAL_BLOCK_RULES => {
+                    load_indentation_terminals(&mut self.grammar);
                     load_rules(self.input_index, errors, &mut self.grammar, node);
                 }
-                hime_grammar::ID_TERMINAL_NAME | hime_grammar::ID_VARIABLE_GRAMMAR_PARENCY => {}
+                hime_grammar::ID_TERMINAL_NAME
+                | hime_grammar::ID_VARIABLE_GRAMMAR_PARENCY
+                | hime_grammar::ID_TERMINAL_BLOCK_PRECEDENCE => {}
                 _ => {
                     panic!("Unrecognized symbol: {}", node.get_symbol().name);
                 }
             }
         }
+        // load the precedences last so that they can refer to all the symbols
+        for node in self.root {
+            if node.get_symbol().id == hime_grammar::ID_TERMINAL_BLOCK_PRECEDENCE {
+                load_precedences(self.input_index, errors, &mut self.grammar, node);
+            }
+        }
     }
 }
 
@@ -364,8 +374,31 @@ fn load_option(input_index: usize, grammar: &mut Grammar, node: AstNode) {
     );
 }
 
+/// Declares the terminals inserted by an indentation-sensitive lexer as external terminals,
+/// when they are not already defined by the grammar
+fn load_indentation_terminals(grammar: &mut Grammar) {
+    let Some(input_ref) = grammar.get_option(OPTION_INDENTATION).map(|option| option.value_input_ref) else {
+        return;
+    };
+    let names: Vec<String> = grammar
+        .get_indentation_terminals()
+        .into_iter()
+        .filter(|name| grammar.get_terminal_for_name(name).is_none())
+        .map(str::to_string)
+        .collect();
+    for name in names {
+        grammar.add_terminal_external(name, input_ref);
+    }
+}
+
 /// Loads the terminal blocks of a grammar
 fn load_terminals(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Grammar, node: AstNode) {
+    // declare the contexts first, so that the terminals may push the modes defined after them
+    for child in node {
+        if child.get_symbol().id == hime_grammar::ID_TERMINAL_BLOCK_CONTEXT {
+            grammar.resolve_context(child.child(0).get_value().unwrap());
+        }
+    }
     for child in node {
         let id = child.get_symbol().id;
         if id == hime_grammar::ID_TERMINAL_BLOCK_CONTEXT {
@@ -374,6 +407,8 @@ fn load_terminals(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Gra
             load_terminal_rule(input_index, errors, grammar, child, DEFAULT_CONTEXT_NAME, true);
         } else if id == hime_grammar::ID_VARIABLE_TERMINAL_RULE {
             load_terminal_rule(input_index, errors, grammar, child, DEFAULT_CONTEXT_NAME, false);
+        } else if id == hime_grammar::ID_VARIABLE_TERMINAL_EXTERNAL {
+            load_terminal_external(input_index, errors, grammar, child);
         } else {
             panic!("Unrecognized symbol: {}", node.get_symbol().name);
         }
@@ -389,6 +424,21 @@ fn load_terminal_rule_context(input_index: usize, errors: &mut Vec<Error>, gramm
     }
 }
 
+/// Loads the declaration of an external terminal in the given AST
+fn load_terminal_external(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Grammar, node: AstNode) {
+    let node_name = node.child(0);
+    let name = node_name.get_value().unwrap();
+    if let Some(previous) = grammar.get_terminal_for_name(name) {
+        errors.push(Error::OverridingPreviousTerminal(
+            InputReference::from(input_index, &node_name),
+            name.to_string(),
+            previous.input_ref,
+        ));
+        return;
+    }
+    grammar.add_terminal_external(name.to_string(), InputReference::from(input_index, &node_name));
+}
+
 /// Loads the terminal rule in the given AST
 fn load_terminal_rule(
     input_index: usize,
@@ -410,6 +460,11 @@ fn load_terminal_rule(
     }
     let mut references = Vec::new();
     let nfa = load_nfa(input_index, errors, &mut references, grammar, node.child(1));
+    let mode_action = if node.children_count() > 2 {
+        load_terminal_mode_action(input_index, errors, grammar, node.child(2))
+    } else {
+        ModeAction::None
+    };
     let terminal = grammar.add_terminal_named(
         name.to_string(),
         InputReference::from(input_index, &node_name),
@@ -417,7 +472,10 @@ fn load_terminal_rule(
         context,
         is_fragment,
     );
-    terminal.nfa.states[terminal.nfa.exit].add_item(FinalItem::Terminal(terminal.id, terminal.context));
+    terminal
+        .nfa
+        .add_final_item(FinalItem::Terminal(terminal.id, terminal.context));
+    terminal.mode_action = mode_action;
     let referring_id = terminal.id;
     for (referred_id, input_ref) in references {
         if let Some(referred) = grammar.get_terminal_mut(referred_id) {
@@ -428,6 +486,26 @@ fn load_terminal_rule(
     }
 }
 
+/// Loads the action on the stack of lexer modes of a terminal
+/// Pushing the `default` mode gives back the lexing to the parser's contexts.
+fn load_terminal_mode_action(input_index: usize, errors: &mut Vec<Error>, grammar: &Grammar, node: AstNode) -> ModeAction {
+    if node.child(0).get_symbol().id != hime_grammar::ID_TERMINAL_MODE_PUSH {
+        return ModeAction::Pop;
+    }
+    let node_name = node.child(1);
+    let name = node_name.get_value().unwrap();
+    let context = if name == "default" { DEFAULT_CONTEXT_NAME } else { name };
+    if let Some(mode) = grammar.contexts.iter().position(|candidate| candidate == context) {
+        ModeAction::Push(mode)
+    } else {
+        errors.push(Error::ModeNotDefined(
+            InputReference::from(input_index, &node_name),
+            name.to_string(),
+        ));
+        ModeAction::None
+    }
+}
+
 /// Builds the NFA represented by the AST node
 fn load_nfa(
     input_index: usize,
@@ -443,7 +521,7 @@ fn load_nfa(
         hime_grammar::ID_TERMINAL_UNICODE_CATEGORY => load_nfa_unicode_category(input_index, errors, node),
         hime_grammar::ID_TERMINAL_UNICODE_BLOCK => load_nfa_unicode_block(input_index, errors, node),
         hime_grammar::ID_TERMINAL_UNICODE_SPAN_MARKER => load_nfa_unicode_span(input_index, errors, node),
-        hime_grammar::ID_TERMINAL_LITERAL_ANY => load_nfa_any(),
+        hime_grammar::ID_TERMINAL_LITERAL_ANY => NFA::new_any(),
         hime_grammar::ID_TERMINAL_NAME => load_nfa_reference(input_index, errors, references, grammar, node),
         hime_grammar::ID_TERMINAL_OPERATOR_OPTIONAL => {
             let inner = load_nfa(input_index, errors, references, grammar, node.child(0));
@@ -467,6 +545,20 @@ fn load_nfa(
             let right = load_nfa(input_index, errors, references, grammar, node.child(1));
             left.into_difference(&right)
         }
+        hime_grammar::ID_TERMINAL_OPERATOR_INTERSECTION => {
+            let left = load_nfa(input_index, errors, references, grammar, node.child(0));
+            let right = load_nfa(input_index, errors, references, grammar, node.child(1));
+            left.into_intersection(&right)
+        }
+        hime_grammar::ID_TERMINAL_OPERATOR_COMPLEMENT => {
+            let inner = load_nfa(input_index, errors, references, grammar, node.child(0));
+            inner.into_complement()
+        }
+        hime_grammar::ID_TERMINAL_OPERATOR_TRAILING => {
+            let left = load_nfa(input_index, errors, references, grammar, node.child(0));
+            let right = load_nfa(input_index, errors, references, grammar, node.child(1));
+            left.into_trailing_context(&right)
+        }
         hime_grammar::ID_VIRTUAL_RANGE => {
             let inner = load_nfa(input_index, errors, references, grammar, node.child(0));
             let min = node.child(1).get_value().unwrap().parse::<usize>().unwrap();
@@ -685,19 +777,6 @@ fn load_nfa_unicode_span(input_index: usize, errors: &mut Vec<Error>, node: AstN
     nfa
 }
 
-/// Builds a NFA that matches everything (a single character)
-fn load_nfa_any() -> NFA {
-    let mut nfa = NFA::new_minimal();
-    // plane 0 transitions
-    nfa.add_transition(nfa.entry, CharSpan::new(0x0000, 0xD7FF), nfa.exit);
-    nfa.add_transition(nfa.entry, CharSpan::new(0xE000, 0xFFFF), nfa.exit);
-    // surrogate pairs
-    let intermediate = nfa.add_state().id;
-    nfa.add_transition(nfa.entry, CharSpan::new(0xD800, 0xDBFF), intermediate);
-    nfa.add_transition(intermediate, CharSpan::new(0xDC00, 0xDFFF), nfa.exit);
-    nfa
-}
-
 /// Builds a NFA from a referenced terminal
 fn load_nfa_reference(
     input_index: usize,
@@ -708,6 +787,13 @@ fn load_nfa_reference(
 ) -> NFA {
     let value = node.get_value().unwrap();
     if let Some(terminal) = grammar.get_terminal_for_name(value) {
+        if terminal.is_external {
+            errors.push(Error::ExternalTerminalReferenced(
+                InputReference::from(input_index, &node),
+                value.to_string(),
+            ));
+            return NFA::new_minimal();
+        }
         references.push((terminal.id, InputReference::from(input_index, &node)));
         terminal.nfa.clone_no_finals()
     } else {
@@ -817,6 +903,12 @@ fn load_simple_rule_definitions(
         }
         hime_grammar::ID_VIRTUAL_CONCAT => load_simple_rule_concat(input_index, errors, grammar, head_sid, node),
         hime_grammar::ID_VIRTUAL_EMPTYPART => load_simple_rule_empty_part(),
+        hime_grammar::ID_VARIABLE_RULE_SYM_ANNOTATION => BodySet {
+            bodies: load_rule_annotations(input_index, errors, node)
+                .map(RuleBody::annotated)
+                .into_iter()
+                .collect(),
+        },
         _ => load_simple_rule_atomic(input_index, errors, grammar, node),
     }
 }
@@ -1061,6 +1153,7 @@ fn load_simple_rule_atomic(
         hime_grammar::ID_VARIABLE_RULE_SYM_REF_TEMPLATE => {
             load_simple_rule_atomic_template_ref(input_index, errors, grammar, node)
         }
+        hime_grammar::ID_VARIABLE_RULE_SYM_LABEL => load_simple_rule_atomic_label(input_index, errors, grammar, node),
         hime_grammar::ID_TERMINAL_LITERAL_TEXT => load_simple_rule_atomic_inline_text(input_index, grammar, node),
         _ => {
             panic!("Unrecognized symbol: {}", node.get_symbol().name)
@@ -1068,6 +1161,31 @@ fn load_simple_rule_atomic(
     }
 }
 
+/// Loads the disambiguation annotations represented by the given AST
+fn load_rule_annotations(input_index: usize, errors: &mut Vec<Error>, node: AstNode) -> Option<RuleAnnotations> {
+    let name = node.child(0).get_value().unwrap();
+    let value = if node.children_count() > 1 {
+        Some(node.child(1).get_value().unwrap())
+    } else {
+        None
+    };
+    let mut annotations = RuleAnnotations::default();
+    match (name, value.map(str::parse::<u16>)) {
+        ("prefer", None) => annotations.preference = RulePreference::Prefer,
+        ("avoid", None) => annotations.preference = RulePreference::Avoid,
+        ("priority", Some(Ok(priority))) => annotations.priority = priority,
+        _ => {
+            let text = match value {
+                Some(value) => format!("{name}:{value}"),
+                None => name.to_string(),
+            };
+            errors.push(Error::InvalidAnnotation(InputReference::from(input_index, &node), text));
+            return None;
+        }
+    }
+    Some(annotations)
+}
+
 /// Builds the set of rule definitions that represents a single semantic action
 fn load_simple_rule_atomic_action(input_index: usize, grammar: &mut Grammar, node: AstNode) -> BodySet<RuleBody> {
     let name = node.child(0).get_value().unwrap();
@@ -1140,6 +1258,19 @@ fn load_simple_rule_atomic_template_ref(
     }
 }
 
+/// Builds the set of rule definitions that represents a labelled element
+fn load_simple_rule_atomic_label(
+    input_index: usize,
+    errors: &mut Vec<Error>,
+    grammar: &mut Grammar,
+    node: AstNode,
+) -> BodySet<RuleBody> {
+    let label = grammar.resolve_label(node.child(0).get_value().unwrap());
+    let mut set_inner = load_simple_rule_atomic(input_index, errors, grammar, node.child(1));
+    set_inner.apply_label(label);
+    set_inner
+}
+
 /// Builds the set of rule definitions that represents a single inline piece of text
 fn load_simple_rule_atomic_inline_text(input_index: usize, grammar: &mut Grammar, node: AstNode) -> BodySet<RuleBody> {
     // Construct the terminal name
@@ -1166,6 +1297,38 @@ fn load_simple_rule_atomic_inline_text(input_index: usize, grammar: &mut Grammar
     }
 }
 
+/// Loads the precedence block of a grammar
+fn load_precedences(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Grammar, node: AstNode) {
+    for child in node {
+        load_precedence_level(input_index, errors, grammar, child);
+    }
+}
+
+/// Loads a precedence level in the given AST
+fn load_precedence_level(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Grammar, node: AstNode) {
+    let associativity = match node.child(0).get_symbol().id {
+        hime_grammar::ID_TERMINAL_PRECEDENCE_LEFT => Associativity::Left,
+        hime_grammar::ID_TERMINAL_PRECEDENCE_RIGHT => Associativity::Right,
+        _ => Associativity::NonAssoc,
+    };
+    let mut symbols = Vec::new();
+    for child in node.children().iter().skip(1) {
+        if child.get_symbol().id == hime_grammar::ID_TERMINAL_LITERAL_TEXT {
+            symbols.push(load_simple_rule_atomic_inline_text(input_index, grammar, child).bodies[0].elements[0].symbol);
+        } else {
+            let name = child.get_value().unwrap();
+            match grammar.get_symbol(name) {
+                Some(symbol @ (SymbolRef::Terminal(_) | SymbolRef::Variable(_))) => symbols.push(symbol),
+                _ => errors.push(Error::SymbolNotFound(
+                    InputReference::from(input_index, &child),
+                    name.to_string(),
+                )),
+            }
+        }
+    }
+    grammar.add_precedence_level(InputReference::from(input_index, &node), associativity, symbols);
+}
+
 /// Loads the syntactic rule in the given AST
 fn load_template_rule(input_index: usize, errors: &mut Vec<Error>, grammar: &mut Grammar, node: AstNode) {
     let name = node.child(0).get_value().unwrap();
@@ -1206,6 +1369,12 @@ fn load_template_rule_definitions(
         }
         hime_grammar::ID_VIRTUAL_CONCAT => load_template_rule_concat(input_index, errors, grammar, parameters, node),
         hime_grammar::ID_VIRTUAL_EMPTYPART => load_template_rule_empty_part(),
+        hime_grammar::ID_VARIABLE_RULE_SYM_ANNOTATION => BodySet {
+            bodies: load_rule_annotations(input_index, errors, node)
+                .map(TemplateRuleBody::annotated)
+                .into_iter()
+                .collect(),
+        },
         _ => load_template_rule_atomic(input_index, errors, grammar, parameters, node),
     }
 }
@@ -1456,6 +1625,9 @@ fn load_template_rule_atomic(
         hime_grammar::ID_VARIABLE_RULE_SYM_REF_TEMPLATE => {
             load_template_rule_atomic_template_ref(input_index, errors, grammar, parameters, node)
         }
+        hime_grammar::ID_VARIABLE_RULE_SYM_LABEL => {
+            load_template_rule_atomic_label(input_index, errors, grammar, parameters, node)
+        }
         hime_grammar::ID_TERMINAL_LITERAL_TEXT => load_template_rule_atomic_inline_text(input_index, grammar, node),
         _ => {
             panic!("Unrecognized symbol: {}", node.get_symbol().name);
@@ -1566,6 +1738,20 @@ fn load_template_rule_atomic_template_ref(
     }
 }
 
+/// Builds the set of rule definitions that represents a labelled element
+fn load_template_rule_atomic_label(
+    input_index: usize,
+    errors: &mut Vec<Error>,
+    grammar: &mut Grammar,
+    parameters: &[TemplateRuleParam],
+    node: AstNode,
+) -> BodySet<TemplateRuleBody> {
+    let label = grammar.resolve_label(node.child(0).get_value().unwrap());
+    let mut set_inner = load_template_rule_atomic(input_index, errors, grammar, parameters, node.child(1));
+    set_inner.apply_label(label);
+    set_inner
+}
+
 /// Builds the set of rule definitions that represents a single inline piece of text
 fn load_template_rule_atomic_inline_text(
     input_index: usize,
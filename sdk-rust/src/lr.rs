@@ -17,15 +17,16 @@
 
 //! Module for LR automata
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 
-use hime_redist::parsers::{LRActionCode, LR_ACTION_CODE_REDUCE, LR_ACTION_CODE_SHIFT};
+use hime_redist::parsers::{LRActionCode, LR_ACTION_CODE_NONE, LR_ACTION_CODE_REDUCE, LR_ACTION_CODE_SHIFT};
 
 use crate::errors::{Error, UnmatchableTokenError};
 use crate::finite::DFA;
 use crate::grammars::{
-    Grammar, RuleChoice, RuleChoiceRef, RuleRef, SymbolRef, Terminal, TerminalRef, TerminalSet, GENERATED_AXIOM,
+    Associativity, Grammar, RuleChoice, RuleChoiceRef, RuleRef, SymbolRef, Terminal, TerminalRef, TerminalSet,
 };
 use crate::ParsingMethod;
 
@@ -172,6 +173,17 @@ impl Item {
         }
     }
 
+    /// Gets whether this item leads to a reduction in a RNGLR automaton,
+    /// i.e. whether the part of its rule after the dot is nullable
+    fn is_reducing_rnglr(&self, grammar: &Grammar) -> bool {
+        let rule = self.rule.get_rule_in(grammar);
+        self.get_action(grammar) == LR_ACTION_CODE_REDUCE
+            || rule.body.choices[self.position]
+                .firsts
+                .content
+                .contains(&TerminalRef::Epsilon)
+    }
+
     /// Gets the symbol following the dot in this item
     #[must_use]
     pub fn get_next_symbol(&self, grammar: &Grammar) -> Option<SymbolRef> {
@@ -228,67 +240,60 @@ impl Item {
     /// A panic is raised when the symbols in the rule cannot be found
     /// in their respective grammar (which should not happen).
     pub fn close_to(&self, grammar: &Grammar, closure: &mut Vec<Item>, mode: LookaheadMode) {
-        if let Some(SymbolRef::Variable(sid)) = self.get_next_symbol(grammar) {
-            // Here the item is of the form [Var -> alpha . next beta]
-            // next is a variable
-            // Firsts is a copy of the Firsts set for beta (next choice)
-            // Firsts will contains symbols that may follow Next
-            // Firsts will therefore be the lookahead for child items
-            let mut firsts = Lookaheads::from_firsts(
-                &self.get_next_choice(grammar).unwrap().firsts,
-                RuleChoiceRef {
-                    rule: self.rule,
-                    position: self.position + 1,
-                },
-            );
-            // If beta is nullifiable (contains ε) :
-            if firsts.contains(TerminalRef::Epsilon) {
-                // Remove ε
-                firsts.remove(TerminalRef::Epsilon);
-                // Add the item's lookaheads
-                firsts.add_others(&self.lookaheads);
-            }
-            let variable = grammar.get_variable(sid).unwrap();
-            // For each rule that has Next as a head variable :
-            for index in 0..variable.rules.len() {
-                match mode {
-                    LookaheadMode::LR0 => {
-                        let candidate = Item {
-                            rule: RuleRef::new(sid, index),
-                            position: 0,
-                            lookaheads: Lookaheads::default(),
-                        };
-                        if !closure.contains(&candidate) {
-                            closure.push(candidate);
-                        }
-                    }
-                    LookaheadMode::LR1 => {
-                        for lookahead in firsts.clone().0 {
-                            let candidate = Item {
-                                rule: RuleRef::new(sid, index),
-                                position: 0,
-                                lookaheads: Lookaheads::from_single(lookahead),
-                            };
-                            if !closure.contains(&candidate) {
-                                closure.push(candidate);
-                            }
-                        }
+        if let Some(expansion) = Expansion::of(self, grammar) {
+            for candidate in self.get_expanded(&expansion, mode) {
+                if mode == LookaheadMode::LALR1 {
+                    if let Some(other) = closure.iter_mut().find(|item| item.same_base(&candidate)) {
+                        other.lookaheads.add_others(&candidate.lookaheads);
+                    } else {
+                        closure.push(candidate);
                     }
-                    LookaheadMode::LALR1 => {
-                        let candidate = Item {
-                            rule: RuleRef::new(sid, index),
+                } else if !closure.contains(&candidate) {
+                    closure.push(candidate);
+                }
+            }
+        }
+    }
+
+    /// Gets the items produced by the expansion of this item in a closure
+    fn get_expanded(&self, expansion: &Expansion, mode: LookaheadMode) -> Vec<Item> {
+        // Firsts will contains symbols that may follow the next variable
+        // Firsts will therefore be the lookahead for child items
+        let mut firsts = expansion.firsts.clone();
+        if expansion.nullable {
+            // Add the item's lookaheads
+            firsts.add_others(&self.lookaheads);
+        }
+        let mut result = Vec::new();
+        // For each rule that has Next as a head variable :
+        for &rule in &expansion.rules {
+            match mode {
+                LookaheadMode::LR0 => {
+                    result.push(Item {
+                        rule,
+                        position: 0,
+                        lookaheads: Lookaheads::default(),
+                    });
+                }
+                LookaheadMode::LR1 => {
+                    for lookahead in &firsts.0 {
+                        result.push(Item {
+                            rule,
                             position: 0,
-                            lookaheads: firsts.clone(),
-                        };
-                        if let Some(other) = closure.iter_mut().find(|item| item.same_base(&candidate)) {
-                            other.lookaheads.add_others(&candidate.lookaheads);
-                        } else {
-                            closure.push(candidate);
-                        }
+                            lookaheads: Lookaheads::from_single(lookahead.clone()),
+                        });
                     }
                 }
+                LookaheadMode::LALR1 => {
+                    result.push(Item {
+                        rule,
+                        position: 0,
+                        lookaheads: firsts.clone(),
+                    });
+                }
             }
         }
+        result
     }
 
     /// Gets whether the two items have the same base
@@ -357,6 +362,195 @@ impl Item {
     }
 }
 
+/// The expansion of an item of the form [Var -> alpha . next beta] where next is a variable
+/// This does not depend on the item's lookaheads so that it can be reused across states
+#[derive(Debug, Clone)]
+struct Expansion {
+    /// The rules that have next as a head variable
+    rules: Vec<RuleRef>,
+    /// The FIRSTS set of beta, without ε
+    firsts: Lookaheads,
+    /// Whether beta is nullable
+    nullable: bool,
+}
+
+impl Expansion {
+    /// Gets the expansion of an item, if any
+    fn of(item: &Item, grammar: &Grammar) -> Option<Expansion> {
+        let Some(SymbolRef::Variable(sid)) = item.get_next_symbol(grammar) else {
+            return None;
+        };
+        let mut firsts = Lookaheads::from_firsts(
+            &item.get_next_choice(grammar).unwrap().firsts,
+            RuleChoiceRef {
+                rule: item.rule,
+                position: item.position + 1,
+            },
+        );
+        let nullable = firsts.contains(TerminalRef::Epsilon);
+        firsts.remove(TerminalRef::Epsilon);
+        let variable = grammar.get_variable(sid).unwrap();
+        Some(Expansion {
+            rules: (0..variable.rules.len()).map(|index| RuleRef::new(sid, index)).collect(),
+            firsts,
+            nullable,
+        })
+    }
+}
+
+/// The data shared by all the items with the same rule and position
+#[derive(Debug, Clone)]
+struct ItemData {
+    /// The symbol following the dot, if any
+    next: Option<SymbolRef>,
+    /// The expansion of the item when the next symbol is a variable
+    expansion: Option<Expansion>,
+}
+
+/// A cache of the data for the items, shared by the closures of all the states in a graph
+#[derive(Debug, Default)]
+struct ClosureCache {
+    /// The data for each rule and position
+    items: HashMap<(RuleRef, usize), ItemData>,
+}
+
+impl ClosureCache {
+    /// Gets the data for an item
+    fn get_data(&mut self, item: &Item, grammar: &Grammar) -> &ItemData {
+        self.items.entry((item.rule, item.position)).or_insert_with(|| ItemData {
+            next: item.get_next_symbol(grammar),
+            expansion: Expansion::of(item, grammar),
+        })
+    }
+
+    /// Gets the symbol following the dot in an item
+    fn get_next_symbol(&mut self, item: &Item, grammar: &Grammar) -> Option<SymbolRef> {
+        self.get_data(item, grammar).next
+    }
+
+    /// Gets the expansion of an item, if any
+    fn get_expansion(&mut self, item: &Item, grammar: &Grammar) -> Option<&Expansion> {
+        self.get_data(item, grammar).expansion.as_ref()
+    }
+
+    /// Gets the closure of a set of kernel items
+    fn close(&mut self, grammar: &Grammar, kernel: &[Item], mode: LookaheadMode) -> Vec<Item> {
+        let mut closure = Closure::new(kernel, mode);
+        closure.expand(grammar, self);
+        closure.items
+    }
+}
+
+/// The key identifying an item: its rule, position and lookahead
+type ItemKey = (RuleRef, usize, Option<TerminalRef>);
+
+/// Gets the key for an item
+/// LR(1) items have at most one lookahead,
+/// while LALR(1) items with the same base are merged so that the lookaheads are not part of the key
+fn get_item_key(item: &Item, mode: LookaheadMode) -> ItemKey {
+    let lookahead = match mode {
+        LookaheadMode::LR1 => item.lookaheads.0.first().map(|lookahead| lookahead.terminal),
+        _ => None,
+    };
+    (item.rule, item.position, lookahead)
+}
+
+/// A closure of items being built, indexed by the items' keys
+struct Closure {
+    /// The lookahead mode
+    mode: LookaheadMode,
+    /// The items in the closure
+    items: Vec<Item>,
+    /// The index of the items by key
+    index: HashMap<ItemKey, usize>,
+}
+
+impl Closure {
+    /// Initializes a closure with kernel items
+    fn new(kernel: &[Item], mode: LookaheadMode) -> Closure {
+        let mut index = HashMap::new();
+        for (i, item) in kernel.iter().enumerate() {
+            index.entry(get_item_key(item, mode)).or_insert(i);
+        }
+        Closure {
+            mode,
+            items: kernel.to_vec(),
+            index,
+        }
+    }
+
+    /// Adds an item at the start of a rule, unless it is already in the closure
+    fn add(&mut self, rule: RuleRef, lookahead: Option<TerminalRef>, lookaheads: impl FnOnce() -> Lookaheads) {
+        let key = (rule, 0, lookahead);
+        if let Some(&previous) = self.index.get(&key) {
+            if self.mode == LookaheadMode::LALR1 {
+                self.items[previous].lookaheads.add_others(&lookaheads());
+            }
+        } else {
+            self.index.insert(key, self.items.len());
+            self.items.push(Item {
+                rule,
+                position: 0,
+                lookaheads: lookaheads(),
+            });
+        }
+    }
+
+    /// Expands all the items in the closure
+    fn expand(&mut self, grammar: &Grammar, cache: &mut ClosureCache) {
+        let mut i = 0;
+        while i < self.items.len() {
+            if let Some(expansion) = cache.get_expansion(&self.items[i], grammar) {
+                // Firsts will contains symbols that may follow the next variable
+                let merged = expansion.nullable.then(|| {
+                    let mut firsts = expansion.firsts.clone();
+                    firsts.add_others(&self.items[i].lookaheads);
+                    firsts
+                });
+                let firsts = merged.as_ref().unwrap_or(&expansion.firsts);
+                for &rule in &expansion.rules {
+                    match self.mode {
+                        LookaheadMode::LR0 => self.add(rule, None, Lookaheads::default),
+                        LookaheadMode::LR1 => {
+                            for lookahead in &firsts.0 {
+                                self.add(rule, Some(lookahead.terminal), || Lookaheads::from_single(lookahead.clone()));
+                            }
+                        }
+                        LookaheadMode::LALR1 => self.add(rule, None, || firsts.clone()),
+                    }
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+/// The table of interned items, giving a unique identifier to each item
+#[derive(Debug, Default)]
+struct ItemTable {
+    /// The identifiers of the known items
+    ids: HashMap<ItemKey, usize>,
+}
+
+impl ItemTable {
+    /// Gets the unique identifier of an item
+    fn intern(&mut self, key: ItemKey) -> usize {
+        let next = self.ids.len();
+        *self.ids.entry(key).or_insert(next)
+    }
+
+    /// Gets the key of a kernel, i.e. the sorted identifiers of its items
+    fn get_kernel_key(&mut self, kernel: &StateKernel, mode: LookaheadMode) -> Vec<usize> {
+        let mut key: Vec<usize> = kernel
+            .items
+            .iter()
+            .map(|item| self.intern(get_item_key(item, mode)))
+            .collect();
+        key.sort_unstable();
+        key
+    }
+}
+
 /// The graph along with the associated grammar
 pub struct GraphWithGrammar<'a> {
     /// The grammar
@@ -388,18 +582,19 @@ impl StateKernel {
     /// Gets the closure of this kernel
     #[must_use]
     pub fn into_state(self, grammar: &Grammar, mode: LookaheadMode) -> State {
-        let mut items = self.items.clone();
-        let mut i = 0;
-        while i < items.len() {
-            items[i].clone().close_to(grammar, &mut items, mode);
-            i += 1;
-        }
+        self.into_state_with(grammar, mode, &mut ClosureCache::default())
+    }
+
+    /// Gets the closure of this kernel, reusing the expansions in the cache
+    fn into_state_with(self, grammar: &Grammar, mode: LookaheadMode, cache: &mut ClosureCache) -> State {
+        let items = cache.close(grammar, &self.items, mode);
         State {
             kernel: self,
             items,
             children: HashMap::new(),
             opening_contexts: HashMap::new(),
             reductions: Vec::new(),
+            resolved: Vec::new(),
         }
     }
 
@@ -422,6 +617,17 @@ pub struct Reduction {
     pub length: usize,
 }
 
+/// Represents a shift/reduce conflict resolved using the grammar's precedences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedConflict {
+    /// The lookahead for the conflict
+    pub lookahead: TerminalRef,
+    /// The reducing item in the conflict
+    pub item: Item,
+    /// The retained action, `LR_ACTION_CODE_NONE` when the lookahead is an error
+    pub action: LRActionCode,
+}
+
 /// Represents a LR state
 #[derive(Debug, Clone)]
 pub struct State {
@@ -435,6 +641,8 @@ pub struct State {
     pub opening_contexts: HashMap<TerminalRef, Vec<usize>>,
     /// The reductions on this state
     pub reductions: Vec<Reduction>,
+    /// The shift/reduce conflicts resolved using precedences
+    pub resolved: Vec<ResolvedConflict>,
 }
 
 impl State {
@@ -470,11 +678,19 @@ impl State {
     pub fn build_reductions_lr1(&mut self, id: usize, grammar: &Grammar) -> Conflicts {
         let mut conflicts = Conflicts::default();
         let mut reductions: HashMap<TerminalRef, usize> = HashMap::new();
+        let resolutions = self.resolve_precedences(grammar, |item| item.get_action(grammar) == LR_ACTION_CODE_REDUCE);
         for (index, item) in self.items.iter().enumerate() {
             if item.get_action(grammar) != LR_ACTION_CODE_REDUCE {
                 continue;
             }
             for lookahead in &item.lookaheads.0 {
+                if resolutions
+                    .get(&(index, lookahead.terminal))
+                    .is_some_and(|action| *action != LR_ACTION_CODE_REDUCE)
+                {
+                    // The conflict is solved by the precedences against this reduction
+                    continue;
+                }
                 let symbol_ref: SymbolRef = lookahead.terminal.into();
                 if self.children.contains_key(&symbol_ref) {
                     // There is already a shift action for the lookahead => conflict
@@ -501,18 +717,19 @@ impl State {
     pub fn build_reductions_rnglr1(&mut self, id: usize, grammar: &Grammar) -> Conflicts {
         let mut conflicts = Conflicts::default();
         let mut reductions: HashMap<TerminalRef, usize> = HashMap::new();
+        let resolutions = self.resolve_precedences(grammar, |item| item.is_reducing_rnglr(grammar));
         for (index, item) in self.items.iter().enumerate() {
-            let rule = item.rule.get_rule_in(grammar);
-            if item.get_action(grammar) == LR_ACTION_CODE_SHIFT
-                && !rule.body.choices[item.position]
-                    .firsts
-                    .content
-                    .contains(&TerminalRef::Epsilon)
-            {
-                // item is shift action and is not nullable after the dot
+            if !item.is_reducing_rnglr(grammar) {
                 continue;
             }
             for lookahead in &item.lookaheads.0 {
+                if resolutions
+                    .get(&(index, lookahead.terminal))
+                    .is_some_and(|action| *action != LR_ACTION_CODE_REDUCE)
+                {
+                    // The conflict is solved by the precedences against this reduction
+                    continue;
+                }
                 let symbol_ref: SymbolRef = lookahead.terminal.into();
                 if self.children.contains_key(&symbol_ref) {
                     // There is already a shift action for the lookahead => conflict
@@ -533,6 +750,86 @@ impl State {
         conflicts
     }
 
+    /// Resolves the shift/reduce conflicts of the reducing items using the grammar's precedences
+    /// All the resolutions are decided before being applied, so that they do not depend on the order of the items.
+    /// The shift on a lookahead is removed when any reduction on it is retained, or when the lookahead becomes an error.
+    /// Returns the retained action for each resolved pair of an item index and a lookahead.
+    fn resolve_precedences(
+        &mut self,
+        grammar: &Grammar,
+        is_reducing: impl Fn(&Item) -> bool,
+    ) -> HashMap<(usize, TerminalRef), LRActionCode> {
+        let mut resolutions = HashMap::new();
+        let mut removed = Vec::new();
+        for (index, item) in self.items.iter().enumerate() {
+            if !is_reducing(item) {
+                continue;
+            }
+            for lookahead in &item.lookaheads.0 {
+                let symbol_ref: SymbolRef = lookahead.terminal.into();
+                if !self.children.contains_key(&symbol_ref) {
+                    continue;
+                }
+                if let Some(action) = resolve_shift_reduce(grammar, item, lookahead.terminal) {
+                    self.resolved.push(ResolvedConflict {
+                        lookahead: lookahead.terminal,
+                        item: item.clone(),
+                        action,
+                    });
+                    resolutions.insert((index, lookahead.terminal), action);
+                    if action != LR_ACTION_CODE_SHIFT && !removed.contains(&symbol_ref) {
+                        removed.push(symbol_ref);
+                    }
+                }
+            }
+        }
+        for symbol_ref in &removed {
+            self.children.remove(symbol_ref);
+        }
+        resolutions
+    }
+
+    /// Builds the contexts opened by transitions from this state
+    ///
+    /// # Panics
+    ///
+    /// A panic is raised when the symbols in the items cannot be found in the grammar
+    pub fn build_opening_contexts(&mut self, grammar: &Grammar) {
+        for item in &self.items {
+            if let Some(context) = item.get_opened_context(grammar) {
+                let mut opening_terminals = TerminalSet::default();
+                match item.get_next_symbol(grammar) {
+                    Some(SymbolRef::Variable(sid)) => {
+                        let variable = &grammar.get_variable(sid).unwrap();
+                        opening_terminals.add_others(&variable.firsts);
+                    }
+                    Some(SymbolRef::Epsilon) => {
+                        opening_terminals.add(TerminalRef::Epsilon);
+                    }
+                    Some(SymbolRef::Dollar) => {
+                        opening_terminals.add(TerminalRef::Dollar);
+                    }
+                    Some(SymbolRef::Dummy) => {
+                        opening_terminals.add(TerminalRef::Dummy);
+                    }
+                    Some(SymbolRef::NullTerminal) => {
+                        opening_terminals.add(TerminalRef::NullTerminal);
+                    }
+                    Some(SymbolRef::Terminal(sid)) => {
+                        opening_terminals.add(TerminalRef::Terminal(sid));
+                    }
+                    _ => {}
+                }
+                for terminal in opening_terminals.content {
+                    let contexts = self.opening_contexts.entry(terminal).or_default();
+                    if !contexts.contains(&context) {
+                        contexts.push(context);
+                    }
+                }
+            }
+        }
+    }
+
     /// Gets the reduction for the specified terminal
     #[must_use]
     pub fn get_reduction_for(&self, terminal: TerminalRef) -> Option<&Reduction> {
@@ -563,6 +860,23 @@ impl State {
             )?;
         }
         writeln!(f, "  }}")?;
+        if !self.resolved.is_empty() {
+            writeln!(f, "  resolved {{")?;
+            for resolved in &self.resolved {
+                let action = match resolved.action {
+                    LR_ACTION_CODE_SHIFT => "shift",
+                    LR_ACTION_CODE_REDUCE => "reduce",
+                    _ => "error",
+                };
+                write!(
+                    f,
+                    "    on {} {action} over ",
+                    grammar.get_symbol_value(resolved.lookahead.into())
+                )?;
+                resolved.item.format(f, grammar)?;
+            }
+            writeln!(f, "  }}")?;
+        }
         writeln!(f, "  items {{")?;
         for item in &self.items {
             write!(f, "    ")?;
@@ -574,93 +888,87 @@ impl State {
     }
 }
 
+/// Attempts to resolve a shift/reduce conflict using the grammar's precedences
+/// Returns the action to retain, `LR_ACTION_CODE_NONE` meaning a syntax error
+fn resolve_shift_reduce(grammar: &Grammar, reducing: &Item, lookahead: TerminalRef) -> Option<LRActionCode> {
+    let (terminal_level, associativity) = grammar.get_symbol_precedence(lookahead.into())?;
+    let (rule_level, _) = grammar.get_rule_precedence(reducing.rule.get_rule_in(grammar))?;
+    Some(match rule_level.cmp(&terminal_level) {
+        Ordering::Greater => LR_ACTION_CODE_REDUCE,
+        Ordering::Less => LR_ACTION_CODE_SHIFT,
+        Ordering::Equal => match associativity {
+            Associativity::Left => LR_ACTION_CODE_REDUCE,
+            Associativity::Right => LR_ACTION_CODE_SHIFT,
+            Associativity::NonAssoc => LR_ACTION_CODE_NONE,
+        },
+    })
+}
+
 /// Represents a LR graph
 #[derive(Debug, Clone, Default)]
 pub struct Graph {
-    /// The states in this graph
-    pub states: Vec<State>,
+    /// The states in this graph, only added through `add_state` so that they are indexed
+    states: Vec<State>,
+    /// The index of the states by the cores of their kernels
+    cores: HashMap<Vec<(usize, usize, usize)>, Vec<usize>>,
 }
 
 impl Graph {
-    /// Initializes a graph from the given state
+    /// Initializes a graph from the given initial states
     #[must_use]
-    pub fn from(state: State, grammar: &Grammar, mode: LookaheadMode) -> Graph {
-        let mut graph = Graph::default();
-        graph.states.push(state);
+    pub fn from(states: Vec<State>, grammar: &Grammar, mode: LookaheadMode) -> Graph {
+        let mut builder = GraphBuilder {
+            grammar,
+            mode,
+            graph: Graph::default(),
+            table: ItemTable::default(),
+            kernels: HashMap::new(),
+            cache: ClosureCache::default(),
+        };
+        for state in states {
+            let key = builder.table.get_kernel_key(&state.kernel, mode);
+            builder.kernels.insert(key, builder.graph.states.len());
+            builder.graph.add_state(state);
+        }
         let mut i = 0;
-        while i < graph.states.len() {
-            graph.build_at_state(grammar, i, mode);
+        while i < builder.graph.states.len() {
+            builder.build_at_state(i);
             i += 1;
         }
-        graph
+        builder.graph
     }
 
-    /// Build this graph at the given state
-    fn build_at_state(&mut self, grammar: &Grammar, state_id: usize, mode: LookaheadMode) {
-        // Shift dictionnary for the current set
-        let mut shifts: HashMap<SymbolRef, StateKernel> = HashMap::new();
-        // Build the children kernels from the shift actions
-        for item in &self.states[state_id].items {
-            if let Some(next) = item.get_next_symbol(grammar) {
-                shifts.entry(next).or_default().add_item(item.get_child());
-            }
-        }
-        // Close the children and add them to the graph
-        let mut shifts: Vec<(SymbolRef, StateKernel)> = shifts.into_iter().collect();
-        shifts.sort_by_key(|(s, _)| *s);
-        for (next, kernel) in shifts {
-            let child_index = match self.get_state_for(&kernel) {
-                Some(child_index) => child_index,
-                None => self.add_state(kernel.into_state(grammar, mode)),
-            };
-            self.states[state_id].children.insert(next, child_index);
-        }
-        // Build the context data
-        let state = &mut self.states[state_id];
-        for item in &state.items {
-            if let Some(context) = item.get_opened_context(grammar) {
-                let mut opening_terminals = TerminalSet::default();
-                match item.get_next_symbol(grammar) {
-                    Some(SymbolRef::Variable(sid)) => {
-                        let variable = &grammar.get_variable(sid).unwrap();
-                        opening_terminals.add_others(&variable.firsts);
-                    }
-                    Some(SymbolRef::Epsilon) => {
-                        opening_terminals.add(TerminalRef::Epsilon);
-                    }
-                    Some(SymbolRef::Dollar) => {
-                        opening_terminals.add(TerminalRef::Dollar);
-                    }
-                    Some(SymbolRef::Dummy) => {
-                        opening_terminals.add(TerminalRef::Dummy);
-                    }
-                    Some(SymbolRef::NullTerminal) => {
-                        opening_terminals.add(TerminalRef::NullTerminal);
-                    }
-                    Some(SymbolRef::Terminal(sid)) => {
-                        opening_terminals.add(TerminalRef::Terminal(sid));
-                    }
-                    _ => {}
-                }
-                for terminal in opening_terminals.content {
-                    let contexts = state.opening_contexts.entry(terminal).or_default();
-                    if !contexts.contains(&context) {
-                        contexts.push(context);
-                    }
-                }
-            }
+    /// Initializes a graph with the given states
+    #[must_use]
+    pub fn from_states(states: Vec<State>) -> Graph {
+        let mut graph = Graph::default();
+        for state in states {
+            graph.add_state(state);
         }
+        graph
     }
 
     /// Determines whether the given state (as a kernel) is already in this graph
+    /// Only the states added through `add_state` are found.
     #[must_use]
     pub fn get_state_for(&self, kernel: &StateKernel) -> Option<usize> {
-        self.states.iter().position(|state| &state.kernel == kernel)
+        self.cores
+            .get(&get_kernel_core(kernel))?
+            .iter()
+            .copied()
+            .find(|&index| &self.states[index].kernel == kernel)
+    }
+
+    /// Gets the states in this graph
+    #[must_use]
+    pub fn get_states(&self) -> &[State] {
+        &self.states
     }
 
     /// Adds a state to this graph
     pub fn add_state(&mut self, state: State) -> usize {
         let index = self.states.len();
+        self.cores.entry(get_kernel_core(&state.kernel)).or_default().push(index);
         self.states.push(state);
         index
     }
@@ -711,6 +1019,57 @@ impl Graph {
     }
 }
 
+/// The data for building a LR graph from its initial state
+struct GraphBuilder<'g> {
+    /// The associated grammar
+    grammar: &'g Grammar,
+    /// The lookahead mode
+    mode: LookaheadMode,
+    /// The graph being built
+    graph: Graph,
+    /// The table of interned items
+    table: ItemTable,
+    /// The index of the states by the key of their kernel
+    kernels: HashMap<Vec<usize>, usize>,
+    /// The cache of expansions for the closures
+    cache: ClosureCache,
+}
+
+impl GraphBuilder<'_> {
+    /// Build the graph at the given state
+    fn build_at_state(&mut self, state_id: usize) {
+        // Shift dictionnary for the current set
+        // The items in a closure are unique, so are their children
+        let mut shifts: HashMap<SymbolRef, (StateKernel, Vec<usize>)> = HashMap::new();
+        // Build the children kernels from the shift actions
+        for item in &self.graph.states[state_id].items {
+            if let Some(next) = self.cache.get_next_symbol(item, self.grammar) {
+                let (rule, position, lookahead) = get_item_key(item, self.mode);
+                let (kernel, key) = shifts.entry(next).or_default();
+                key.push(self.table.intern((rule, position + 1, lookahead)));
+                kernel.items.push(item.get_child());
+            }
+        }
+        // Close the children and add them to the graph
+        let mut shifts: Vec<(SymbolRef, (StateKernel, Vec<usize>))> = shifts.into_iter().collect();
+        shifts.sort_by_key(|(s, _)| *s);
+        for (next, (kernel, mut key)) in shifts {
+            key.sort_unstable();
+            let child_index = if let Some(&child_index) = self.kernels.get(&key) {
+                child_index
+            } else {
+                let state = kernel.into_state_with(self.grammar, self.mode, &mut self.cache);
+                let child_index = self.graph.add_state(state);
+                self.kernels.insert(key, child_index);
+                child_index
+            };
+            self.graph.states[state_id].children.insert(next, child_index);
+        }
+        // Build the context data
+        self.graph.states[state_id].build_opening_contexts(self.grammar);
+    }
+}
+
 /// An inverse LR graph
 #[derive(Debug, Clone, Default)]
 pub struct InverseGraph(HashMap<usize, HashMap<SymbolRef, Vec<usize>>>);
@@ -785,13 +1144,14 @@ impl InverseGraph {
         InverseGraph(transitions)
     }
 
-    /// Gets all the paths from state 0 to the specified one
+    /// Gets all the paths from a start state to the specified one
+    /// The start states are the ones without incoming transitions, i.e. state 0 and those of the entry points.
     #[must_use]
     pub fn get_paths_to(&self, target: usize) -> Vec<Path> {
-        if target == 0 {
-            // for the first state, a single path that is empty
+        if !self.0.contains_key(&target) {
+            // for a start state, a single path that is empty
             return vec![Path(vec![PathElem {
-                state: 0,
+                state: target,
                 transition: None,
             }])];
         }
@@ -811,10 +1171,10 @@ impl InverseGraph {
                         visited_with.push(*symbol);
                         let index = elements.len();
                         elements.push(PNode::new(*previous, Some(*symbol), Some(current)));
-                        if *previous == 0 {
-                            goals.push(index);
-                        } else {
+                        if self.0.contains_key(previous) {
                             queue.push(index);
+                        } else {
+                            goals.push(index);
                         }
                     }
                 }
@@ -838,7 +1198,7 @@ impl InverseGraph {
             .collect()
     }
 
-    /// Gets possible inputs that allows for reaching the specified state from state 0
+    /// Gets possible inputs that allows for reaching the specified state from a start state
     #[must_use]
     pub fn get_inputs_for(&self, state: usize, grammar: &Grammar) -> Vec<Phrase> {
         self.get_paths_to(state)
@@ -940,6 +1300,8 @@ pub struct Conflict {
     pub lookahead: Lookahead,
     /// Example phrases for the conflict
     pub phrases: Vec<Phrase>,
+    /// A counterexample explaining the conflict
+    pub counterexample: Option<Counterexample>,
 }
 
 impl PartialEq for Conflict {
@@ -989,6 +1351,7 @@ impl Conflicts {
             reduce_items: vec![reducing],
             lookahead,
             phrases: Vec::new(),
+            counterexample: None,
         });
     }
 
@@ -1007,6 +1370,7 @@ impl Conflicts {
             reduce_items: vec![previous, reducing],
             lookahead,
             phrases: Vec::new(),
+            counterexample: None,
         });
     }
 
@@ -1031,6 +1395,395 @@ impl Conflicts {
     }
 }
 
+/// The maximum number of configurations to explore when looking for a unifying counterexample
+const MAX_UNIFYING_CONFIGURATIONS: usize = 5000;
+/// The maximum number of symbols after the conflict point in a unifying counterexample
+const MAX_UNIFYING_LENGTH: usize = 16;
+/// The maximum number of derivations to make the lookahead appear after the conflict point
+const MAX_LOOKAHEAD_EXPANSIONS: usize = 32;
+
+/// A derivation of a sentential form that explains a conflict
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Derivation {
+    /// A symbol that is not derived any further
+    Symbol(SymbolRef),
+    /// The point of conflict within the derivation
+    Dot,
+    /// A variable derived with one of its rules
+    Rule(RuleRef, Vec<Derivation>),
+}
+
+impl Derivation {
+    /// Builds the derivation for a chain of items, from the axiom down to the conflicting one
+    fn from_chain(grammar: &Grammar, chain: &[(RuleRef, usize)]) -> Option<Derivation> {
+        let mut result = None;
+        for &(rule_ref, position) in chain.iter().rev() {
+            let mut children = Derivation::get_leaves(grammar, rule_ref);
+            match result.take() {
+                None => children.insert(position, Derivation::Dot),
+                Some(child) => children[position] = child,
+            }
+            result = Some(Derivation::Rule(rule_ref, children));
+        }
+        result
+    }
+
+    /// Gets the symbols in the body of a rule, not derived any further
+    fn get_leaves(grammar: &Grammar, rule_ref: RuleRef) -> Vec<Derivation> {
+        rule_ref.get_rule_in(grammar).body.choices[0]
+            .elements
+            .iter()
+            .map(|element| Derivation::Symbol(element.symbol))
+            .collect()
+    }
+
+    /// Gets the symbols produced by this derivation and the position of the conflict point within them
+    #[must_use]
+    pub fn get_frontier(&self) -> (Vec<SymbolRef>, usize) {
+        let mut symbols = Vec::new();
+        let mut dot = 0;
+        self.build_frontier(&mut symbols, &mut dot);
+        (symbols, dot)
+    }
+
+    /// Accumulates the symbols produced by this derivation
+    fn build_frontier(&self, symbols: &mut Vec<SymbolRef>, dot: &mut usize) {
+        match self {
+            Derivation::Symbol(symbol) => symbols.push(*symbol),
+            Derivation::Dot => *dot = symbols.len(),
+            Derivation::Rule(_, children) => {
+                for child in children {
+                    child.build_frontier(symbols, dot);
+                }
+            }
+        }
+    }
+
+    /// Derives the symbol at the given index in the frontier using a rule
+    fn derive(&mut self, grammar: &Grammar, index: &mut usize, rule_ref: RuleRef) -> bool {
+        match self {
+            Derivation::Symbol(_) => {
+                if *index == 0 {
+                    *self = Derivation::Rule(rule_ref, Derivation::get_leaves(grammar, rule_ref));
+                    return true;
+                }
+                *index -= 1;
+                false
+            }
+            Derivation::Dot => false,
+            Derivation::Rule(_, children) => children.iter_mut().any(|child| child.derive(grammar, index, rule_ref)),
+        }
+    }
+
+    /// Derives the symbols following the conflict point until the lookahead appears right after it
+    fn derive_to_lookahead(&mut self, grammar: &Grammar, lookahead: TerminalRef) {
+        for _ in 0..MAX_LOOKAHEAD_EXPANSIONS {
+            let (symbols, dot) = self.get_frontier();
+            let Some(&SymbolRef::Variable(sid)) = symbols.get(dot) else {
+                return;
+            };
+            let Some(variable) = grammar.get_variable(sid) else {
+                return;
+            };
+            // derive to the lookahead if possible, otherwise to nothing
+            let target = if variable.firsts.content.contains(&lookahead) {
+                lookahead
+            } else {
+                TerminalRef::Epsilon
+            };
+            let candidate = variable
+                .rules
+                .iter()
+                .enumerate()
+                .filter(|(_, rule)| rule.body.choices[0].firsts.content.contains(&target))
+                .min_by_key(|(_, rule)| {
+                    let elements = &rule.body.choices[0].elements;
+                    let recursive = elements.first().map(|element| element.symbol) == Some(SymbolRef::Variable(sid));
+                    (recursive, elements.len())
+                });
+            let Some((index, _)) = candidate else {
+                return;
+            };
+            self.derive(grammar, &mut dot.clone(), RuleRef::new(sid, index));
+        }
+    }
+}
+
+/// A counterexample that explains a LR conflict
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    /// Whether both derivations produce the same input, i.e. the grammar is ambiguous
+    pub unifying: bool,
+    /// The derivation for the first item, the reducing one for shift/reduce conflicts
+    pub first: Derivation,
+    /// The derivation for the second item, the shifting one for shift/reduce conflicts
+    pub second: Derivation,
+}
+
+/// A node when looking for the chain of items leading to a conflicting one
+struct ChainNode {
+    /// The index of the item's state in the followed path, or the state itself when no path is followed
+    index: usize,
+    /// The item's rule
+    rule: RuleRef,
+    /// The item's position
+    position: usize,
+    /// The terminal that must be expected after the item's rule, if any
+    lookahead: Option<TerminalRef>,
+    /// Whether the item derives the variable of the next node
+    derives: bool,
+    /// The next node towards the conflicting item
+    next: Option<usize>,
+}
+
+/// The chain of items that leads from the axiom to a conflicting item
+struct ItemChain {
+    /// The items where a variable is derived, followed by the conflicting item
+    items: Vec<(RuleRef, usize)>,
+    /// The path in the LR graph that corresponds to the chain
+    path: Path,
+}
+
+/// Finds the chain of items that leads from the axiom, or an entry point, to an item in a state
+/// When a path is given, the chain follows its transitions, otherwise the shortest one is found.
+fn find_item_chain(
+    graph: &Graph,
+    inverse: &InverseGraph,
+    grammar: &Grammar,
+    (state, path): (usize, Option<&Path>),
+    item: &Item,
+    lookahead: Option<TerminalRef>,
+) -> Option<ItemChain> {
+    // the rules of the generated axioms, at the index of their start state
+    let axioms: Vec<RuleRef> = grammar
+        .get_generated_axioms()
+        .iter()
+        .map(|axiom| RuleRef::new(axiom.id, 0))
+        .collect();
+    let get_state = |index: usize| path.map_or(index, |path| path.0[index].state);
+    let start = path.map_or(state, |path| path.0.len() - 1);
+    let mut nodes = vec![ChainNode {
+        index: start,
+        rule: item.rule,
+        position: item.position,
+        lookahead,
+        derives: false,
+        next: None,
+    }];
+    let mut visited = HashSet::new();
+    visited.insert((start, item.rule, item.position, lookahead));
+    let mut current = 0;
+    while current < nodes.len() {
+        let ChainNode {
+            index,
+            rule: rule_ref,
+            position,
+            lookahead,
+            ..
+        } = nodes[current];
+        if axioms.get(get_state(index)) == Some(&rule_ref) && position == 0 && lookahead.is_none() {
+            return Some(ItemChain::rebuild(grammar, &nodes, current, get_state));
+        }
+        let mut predecessors = Vec::new();
+        if position > 0 {
+            // the symbol before the dot is the transition into this state
+            let symbol = rule_ref.get_rule_in(grammar).body.choices[0].elements[position - 1].symbol;
+            let candidates = match path {
+                Some(path) if index > 0 && path.0[index - 1].transition == Some(symbol) => vec![index - 1],
+                Some(_) => Vec::new(),
+                None => inverse
+                    .0
+                    .get(&index)
+                    .and_then(|transitions| transitions.get(&symbol))
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            for previous in candidates {
+                if graph.states[get_state(previous)]
+                    .items
+                    .iter()
+                    .any(|candidate| candidate.rule == rule_ref && candidate.position == position - 1)
+                {
+                    predecessors.push((previous, rule_ref, position - 1, lookahead, false));
+                }
+            }
+        } else {
+            // look for the items deriving the rule's head in the same state
+            for parent in &graph.states[get_state(index)].items {
+                if parent.get_next_symbol(grammar) != Some(SymbolRef::Variable(rule_ref.variable)) {
+                    continue;
+                }
+                let firsts = &parent.rule.get_rule_in(grammar).body.choices[parent.position + 1].firsts;
+                let parent_lookahead = match lookahead {
+                    Some(terminal) if !firsts.content.contains(&terminal) => {
+                        if !firsts.content.contains(&TerminalRef::Epsilon) {
+                            continue;
+                        }
+                        Some(terminal)
+                    }
+                    _ => None,
+                };
+                predecessors.push((index, parent.rule, parent.position, parent_lookahead, true));
+            }
+        }
+        for (index, rule, position, lookahead, derives) in predecessors {
+            if visited.insert((index, rule, position, lookahead)) {
+                nodes.push(ChainNode {
+                    index,
+                    rule,
+                    position,
+                    lookahead,
+                    derives,
+                    next: Some(current),
+                });
+            }
+        }
+        current += 1;
+    }
+    None
+}
+
+impl ItemChain {
+    /// Rebuilds the chain from the node for the axiom item
+    fn rebuild(grammar: &Grammar, nodes: &[ChainNode], goal: usize, get_state: impl Fn(usize) -> usize) -> ItemChain {
+        let mut items = Vec::new();
+        let mut path = Vec::new();
+        let mut cursor = Some(goal);
+        while let Some(node_id) = cursor {
+            let node = &nodes[node_id];
+            match node.next {
+                None => {
+                    items.push((node.rule, node.position));
+                    path.push(PathElem {
+                        state: get_state(node.index),
+                        transition: None,
+                    });
+                }
+                Some(_) if node.derives => items.push((node.rule, node.position)),
+                Some(_) => path.push(PathElem {
+                    state: get_state(node.index),
+                    transition: Some(node.rule.get_rule_in(grammar).body.choices[0].elements[node.position].symbol),
+                }),
+            }
+            cursor = node.next;
+        }
+        ItemChain { items, path: Path(path) }
+    }
+}
+
+/// Looks for two distinct derivations that produce the same symbols from the given ones
+fn unify(grammar: &Grammar, first: &Derivation, second: &Derivation) -> Option<(Derivation, Derivation)> {
+    let mut queue = VecDeque::new();
+    queue.push_back((first.clone(), second.clone()));
+    let mut visited = HashSet::new();
+    let mut explored = 0;
+    while let Some((first, second)) = queue.pop_front() {
+        explored += 1;
+        if explored > MAX_UNIFYING_CONFIGURATIONS {
+            return None;
+        }
+        let (symbols1, dot1) = first.get_frontier();
+        let (symbols2, dot2) = second.get_frontier();
+        if dot1 != dot2
+            || symbols1[..dot1] != symbols2[..dot2]
+            || symbols1.len() - dot1 > MAX_UNIFYING_LENGTH
+            || symbols2.len() - dot2 > MAX_UNIFYING_LENGTH
+        {
+            continue;
+        }
+        let Some(index) = (dot1..symbols1.len().max(symbols2.len())).find(|&i| symbols1.get(i) != symbols2.get(i)) else {
+            if first != second {
+                return Some((first, second));
+            }
+            continue;
+        };
+        if !visited.insert((symbols1[dot1..].to_vec(), symbols2[dot2..].to_vec())) {
+            continue;
+        }
+        // derive the first different symbol on both sides
+        for (is_first, symbols) in [(true, &symbols1), (false, &symbols2)] {
+            let Some(&SymbolRef::Variable(sid)) = symbols.get(index) else {
+                continue;
+            };
+            let Some(variable) = grammar.get_variable(sid) else {
+                continue;
+            };
+            for rule_index in 0..variable.rules.len() {
+                let mut pair = (first.clone(), second.clone());
+                let target = if is_first { &mut pair.0 } else { &mut pair.1 };
+                target.derive(grammar, &mut index.clone(), RuleRef::new(sid, rule_index));
+                queue.push_back(pair);
+            }
+        }
+    }
+    None
+}
+
+/// Builds a counterexample for a conflict
+fn build_counterexample(
+    graph: &Graph,
+    inverse: &InverseGraph,
+    grammar: &Grammar,
+    conflict: &Conflict,
+) -> Option<Counterexample> {
+    let lookahead = match conflict.lookahead.terminal {
+        TerminalRef::NullTerminal => None,
+        terminal => Some(terminal),
+    };
+    let (others, others_lookahead) = match conflict.kind {
+        ConflictKind::ShiftReduce => (&conflict.shift_items[..], None),
+        ConflictKind::ReduceReduce => (&conflict.reduce_items[1..], lookahead),
+    };
+    let first_chain = find_item_chain(
+        graph,
+        inverse,
+        grammar,
+        (conflict.state, None),
+        conflict.reduce_items.first()?,
+        lookahead,
+    )?;
+    let mut first = Derivation::from_chain(grammar, &first_chain.items)?;
+    if let Some(lookahead) = lookahead {
+        first.derive_to_lookahead(grammar, lookahead);
+    }
+    let mut result = None;
+    for other in others {
+        // try to follow the same path as the first item, so that both derivations share the same prefix
+        let Some(chain) = find_item_chain(
+            graph,
+            inverse,
+            grammar,
+            (conflict.state, Some(&first_chain.path)),
+            other,
+            others_lookahead,
+        )
+        .or_else(|| find_item_chain(graph, inverse, grammar, (conflict.state, None), other, others_lookahead)) else {
+            continue;
+        };
+        let Some(mut second) = Derivation::from_chain(grammar, &chain.items) else {
+            continue;
+        };
+        if let Some(lookahead) = lookahead {
+            second.derive_to_lookahead(grammar, lookahead);
+        }
+        if let Some((first, second)) = unify(grammar, &first, &second) {
+            return Some(Counterexample {
+                unifying: true,
+                first,
+                second,
+            });
+        }
+        if result.is_none() {
+            result = Some(Counterexample {
+                unifying: false,
+                first: first.clone(),
+                second,
+            });
+        }
+    }
+    result
+}
+
 /// Represents an error where a contextual terminal is expected but its context cannot be available at this point
 #[derive(Debug, Clone, Eq)]
 pub struct ContextError {
@@ -1052,18 +1805,30 @@ impl PartialEq for ContextError {
     }
 }
 
+/// Gets the kernels of the initial states, one for each generated axiom, with the given lookaheads
+/// The first one is for the grammar's axiom, the others for the additional entry points.
+fn get_initial_kernels(grammar: &Grammar, lookaheads: &Lookaheads) -> Vec<StateKernel> {
+    grammar
+        .get_generated_axioms()
+        .into_iter()
+        .map(|axiom| StateKernel {
+            items: vec![Item {
+                rule: RuleRef::new(axiom.id, 0),
+                position: 0,
+                lookaheads: lookaheads.clone(),
+            }],
+        })
+        .collect()
+}
+
 /// Gets the LR(0) graph
 fn get_graph_lr0(grammar: &Grammar) -> Graph {
     // Create the base LR(0) graph
-    let axiom = grammar.get_variable_for_name(GENERATED_AXIOM).unwrap();
-    let item = Item {
-        rule: RuleRef::new(axiom.id, 0),
-        position: 0,
-        lookaheads: Lookaheads::default(),
-    };
-    let kernel = StateKernel { items: vec![item] };
-    let state0 = kernel.into_state(grammar, LookaheadMode::LR0);
-    Graph::from(state0, grammar, LookaheadMode::LR0)
+    let states = get_initial_kernels(grammar, &Lookaheads::default())
+        .into_iter()
+        .map(|kernel| kernel.into_state(grammar, LookaheadMode::LR0))
+        .collect();
+    Graph::from(states, grammar, LookaheadMode::LR0)
 }
 
 /// Builds a LR(0) graph
@@ -1077,15 +1842,11 @@ pub fn build_graph_lr0(grammar: &Grammar) -> (Graph, Conflicts) {
 /// Gets the LR(1) graph
 fn get_graph_lr1(grammar: &Grammar) -> Graph {
     // Create the base LR(0) graph
-    let axiom = grammar.get_variable_for_name(GENERATED_AXIOM).unwrap();
-    let item = Item {
-        rule: RuleRef::new(axiom.id, 0),
-        position: 0,
-        lookaheads: Lookaheads::default(),
-    };
-    let kernel = StateKernel { items: vec![item] };
-    let state0 = kernel.into_state(grammar, LookaheadMode::LR1);
-    Graph::from(state0, grammar, LookaheadMode::LR1)
+    let states = get_initial_kernels(grammar, &Lookaheads::default())
+        .into_iter()
+        .map(|kernel| kernel.into_state(grammar, LookaheadMode::LR1))
+        .collect();
+    Graph::from(states, grammar, LookaheadMode::LR1)
 }
 
 /// Builds a LR(1) graph
@@ -1105,12 +1866,15 @@ pub fn build_graph_rnglr1(grammar: &Grammar) -> (Graph, Conflicts) {
 }
 
 /// Builds the kernels for a LALR(1) graph
-fn build_graph_lalr1_kernels(graph0: &Graph) -> Vec<StateKernel> {
+fn build_graph_lalr1_kernels(graph0: &Graph, grammar: &Grammar) -> Vec<StateKernel> {
     // copy kernel without the lookaheads
     let mut kernels: Vec<StateKernel> = graph0.states.iter().map(|state| state.kernel.clone()).collect();
-    // set epsilon as lookahead on all items in kernel 0
-    for item in &mut kernels[0].items {
-        item.lookaheads.add(Lookahead::from(TerminalRef::Epsilon));
+    // set epsilon as lookahead on all items in the initial kernels
+    let starts = grammar.get_generated_axioms().len();
+    for kernel in &mut kernels[..starts] {
+        for item in &mut kernel.items {
+            item.lookaheads.add(Lookahead::from(TerminalRef::Epsilon));
+        }
     }
     kernels
 }
@@ -1127,6 +1891,7 @@ struct Propagation {
 /// Builds the propagation table for a LALR(1) graph
 fn build_graph_lalr1_propagation_table(graph0: &Graph, grammar: &Grammar, kernels: &mut [StateKernel]) -> Vec<Propagation> {
     let mut propagation = Vec::new();
+    let mut cache = ClosureCache::default();
     for i in 0..kernels.len() {
         // For each LALR(1) item in the kernel
         // Only the kernel needs to be examined as the other items will be discovered and treated
@@ -1148,7 +1913,7 @@ fn build_graph_lalr1_propagation_table(graph0: &Graph, grammar: &Grammar, kernel
                     lookaheads: Lookaheads::from_single(Lookahead::from(TerminalRef::Dummy)),
                 }],
             }
-            .into_state(grammar, LookaheadMode::LR1);
+            .into_state_with(grammar, LookaheadMode::LR1, &mut cache);
             // For each item in the closure of the dummy item
             for dummy_item in &dummy_state.items {
                 if let Some(next_symbol) = dummy_item.get_next_symbol(grammar) {
@@ -1205,22 +1970,23 @@ fn build_graph_lalr1_propagate(kernels: &mut [StateKernel], table: &[Propagation
 /// Builds the complete LALR(1) graph
 fn build_graph_lalr1_graph(kernels: Vec<StateKernel>, graph0: &Graph, grammar: &Grammar) -> Graph {
     // Build states
+    let mut cache = ClosureCache::default();
     let mut states: Vec<State> = kernels
         .into_iter()
-        .map(|kernel| kernel.into_state(grammar, LookaheadMode::LALR1))
+        .map(|kernel| kernel.into_state_with(grammar, LookaheadMode::LALR1, &mut cache))
         .collect();
     // Link for each LALR(1) set
     for (state0, state1) in graph0.states.iter().zip(states.iter_mut()) {
         state1.children.clone_from(&state0.children);
         state1.opening_contexts.clone_from(&state0.opening_contexts);
     }
-    Graph { states }
+    Graph::from_states(states)
 }
 
 /// Gets the LALR(1) graph
 fn get_graph_lalr1(grammar: &Grammar) -> Graph {
     let graph0 = get_graph_lr0(grammar);
-    let mut kernels = build_graph_lalr1_kernels(&graph0);
+    let mut kernels = build_graph_lalr1_kernels(&graph0, grammar);
     let propagation = build_graph_lalr1_propagation_table(&graph0, grammar, &mut kernels);
     build_graph_lalr1_propagate(&mut kernels, &propagation);
     build_graph_lalr1_graph(kernels, &graph0, grammar)
@@ -1242,6 +2008,216 @@ pub fn build_graph_rnglalr1(grammar: &Grammar) -> (Graph, Conflicts) {
     (graph, conflicts)
 }
 
+/// Gets the closure of a kernel with lookahead sets
+/// The lookaheads are propagated until a fixpoint is reached.
+fn close_kernel(grammar: &Grammar, kernel: &StateKernel, cache: &mut ClosureCache) -> Vec<Item> {
+    let count = |items: &[Item]| items.len() + items.iter().map(|item| item.lookaheads.0.len()).sum::<usize>();
+    let mut closure = Closure::new(&kernel.items, LookaheadMode::LALR1);
+    loop {
+        let before = count(&closure.items);
+        closure.expand(grammar, cache);
+        if count(&closure.items) == before {
+            return closure.items;
+        }
+    }
+}
+
+/// Gets the core of a kernel, i.e. its LR(0) items
+fn get_kernel_core(kernel: &StateKernel) -> Vec<(usize, usize, usize)> {
+    let mut core: Vec<(usize, usize, usize)> = kernel
+        .items
+        .iter()
+        .map(|item| (item.rule.variable, item.rule.index, item.position))
+        .collect();
+    core.sort_unstable();
+    core
+}
+
+/// Gets whether two sets of lookaheads have terminals in common
+fn lookaheads_intersect(left: &Lookaheads, right: &Lookaheads) -> bool {
+    left.0.iter().any(|lookahead| right.contains(lookahead.terminal))
+}
+
+/// Determines whether two kernels with the same core are weakly compatible, as defined by Pager
+/// Merging weakly compatible kernels does not introduce new conflicts.
+fn are_weakly_compatible(left: &StateKernel, right: &StateKernel) -> bool {
+    let pairs: Vec<(&Lookaheads, &Lookaheads)> = left
+        .items
+        .iter()
+        .filter_map(|item| {
+            right
+                .items
+                .iter()
+                .find(|other| other.same_base(item))
+                .map(|other| (&item.lookaheads, &other.lookaheads))
+        })
+        .collect();
+    for (i, (left_i, right_i)) in pairs.iter().enumerate() {
+        for (left_j, right_j) in &pairs[(i + 1)..] {
+            if (lookaheads_intersect(left_i, right_j) || lookaheads_intersect(left_j, right_i))
+                && !lookaheads_intersect(left_i, left_j)
+                && !lookaheads_intersect(right_i, right_j)
+            {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Gets whether a kernel already contains all the lookaheads of another one with the same core
+fn kernel_covers(kernel: &StateKernel, other: &StateKernel) -> bool {
+    other.items.iter().all(|item| {
+        kernel
+            .items
+            .iter()
+            .find(|candidate| candidate.same_base(item))
+            .is_some_and(|candidate| item.lookaheads.0.iter().all(|la| candidate.lookaheads.contains(la.terminal)))
+    })
+}
+
+/// Builds the states of a minimal LR(1) graph from the kernels and transitions
+/// Only the states that are still reachable after the merges are kept, the initial states first
+fn build_graph_minlr1_states(
+    grammar: &Grammar,
+    kernels: &[StateKernel],
+    transitions: &[HashMap<SymbolRef, usize>],
+    starts: usize,
+    cache: &mut ClosureCache,
+) -> Vec<State> {
+    let mut mapping: Vec<Option<usize>> = vec![None; kernels.len()];
+    for (index, target) in mapping.iter_mut().take(starts).enumerate() {
+        *target = Some(index);
+    }
+    let mut reachable: Vec<usize> = (0..starts).collect();
+    let mut i = 0;
+    while i < reachable.len() {
+        let mut targets: Vec<usize> = transitions[reachable[i]].values().copied().collect();
+        targets.sort_unstable();
+        for target in targets {
+            if mapping[target].is_none() {
+                mapping[target] = Some(reachable.len());
+                reachable.push(target);
+            }
+        }
+        i += 1;
+    }
+    reachable
+        .into_iter()
+        .map(|old_id| {
+            let kernel = kernels[old_id].clone();
+            let mut state = State {
+                items: close_kernel(grammar, &kernel, cache),
+                kernel,
+                children: transitions[old_id]
+                    .iter()
+                    .map(|(&symbol, &target)| (symbol, mapping[target].unwrap()))
+                    .collect(),
+                opening_contexts: HashMap::new(),
+                reductions: Vec::new(),
+                resolved: Vec::new(),
+            };
+            state.build_opening_contexts(grammar);
+            state
+        })
+        .collect()
+}
+
+/// Gets the minimal LR(1) graph
+/// This uses Pager's method, where the states of the canonical LR(1) graph are merged on the fly
+/// when they are weakly compatible. This gives the same power as LR(1) with a number of states close to LALR(1).
+fn get_graph_minlr1(grammar: &Grammar) -> Graph {
+    // set epsilon as lookahead on the initial items, as for LALR(1)
+    let mut kernels = get_initial_kernels(grammar, &Lookaheads::from_single(Lookahead::from(TerminalRef::Epsilon)));
+    let mut cores: HashMap<Vec<(usize, usize, usize)>, Vec<usize>> = HashMap::new();
+    for (index, kernel) in kernels.iter().enumerate() {
+        cores.insert(get_kernel_core(kernel), vec![index]);
+    }
+    let starts = kernels.len();
+    let mut transitions: Vec<HashMap<SymbolRef, usize>> = vec![HashMap::new(); starts];
+    let mut queued = vec![true; starts];
+    let mut queue: VecDeque<usize> = (0..starts).collect();
+    let mut cache = ClosureCache::default();
+    while let Some(state_id) = queue.pop_front() {
+        queued[state_id] = false;
+        // Build the children kernels from the shift actions
+        let mut shifts: HashMap<SymbolRef, StateKernel> = HashMap::new();
+        for item in close_kernel(grammar, &kernels[state_id], &mut cache) {
+            if let Some(next) = item.get_next_symbol(grammar) {
+                let child = item.get_child();
+                let kernel = shifts.entry(next).or_default();
+                if let Some(previous) = kernel.items.iter_mut().find(|candidate| candidate.same_base(&child)) {
+                    previous.lookaheads.add_others(&child.lookaheads);
+                } else {
+                    kernel.items.push(child);
+                }
+            }
+        }
+        let mut shifts: Vec<(SymbolRef, StateKernel)> = shifts.into_iter().collect();
+        shifts.sort_by_key(|(s, _)| *s);
+        let mut children = HashMap::new();
+        for (next, kernel) in shifts {
+            let core = get_kernel_core(&kernel);
+            let candidates = cores.entry(core).or_default();
+            let compatible: Vec<usize> = candidates
+                .iter()
+                .copied()
+                .filter(|&candidate| are_weakly_compatible(&kernels[candidate], &kernel))
+                .collect();
+            let child_id = if let Some(&child_id) = compatible
+                .iter()
+                .find(|&&candidate| kernel_covers(&kernels[candidate], &kernel))
+            {
+                // an existing state already has all the lookaheads
+                child_id
+            } else if let Some(&child_id) = compatible.first() {
+                // merge the lookaheads into an existing state and propagate them
+                for item in kernel.items {
+                    let target = kernels[child_id]
+                        .items
+                        .iter_mut()
+                        .find(|candidate| candidate.same_base(&item))
+                        .unwrap();
+                    target.lookaheads.add_others(&item.lookaheads);
+                }
+                if !queued[child_id] {
+                    queued[child_id] = true;
+                    queue.push_back(child_id);
+                }
+                child_id
+            } else {
+                // no compatible state, create a new one
+                let child_id = kernels.len();
+                candidates.push(child_id);
+                kernels.push(kernel);
+                transitions.push(HashMap::new());
+                queued.push(true);
+                queue.push_back(child_id);
+                child_id
+            };
+            children.insert(next, child_id);
+        }
+        transitions[state_id] = children;
+    }
+    Graph::from_states(build_graph_minlr1_states(grammar, &kernels, &transitions, starts, &mut cache))
+}
+
+/// Builds a minimal LR(1) graph
+#[must_use]
+pub fn build_graph_minlr1(grammar: &Grammar) -> (Graph, Conflicts) {
+    let mut graph = get_graph_minlr1(grammar);
+    let conflicts = graph.build_reductions_lr1(grammar);
+    (graph, conflicts)
+}
+
+/// Builds a RNGLR graph based on a minimal LR(1) graph
+#[must_use]
+pub fn build_graph_rngminlr1(grammar: &Grammar) -> (Graph, Conflicts) {
+    let mut graph = get_graph_minlr1(grammar);
+    let conflicts = graph.build_reductions_rnglr1(grammar);
+    (graph, conflicts)
+}
+
 /// Find the potential context errors in the graph
 fn find_context_errors(graph: &Graph, inverse: &InverseGraph, grammar: &Grammar) -> Vec<ContextError> {
     let mut errors = Vec::new();
@@ -1249,7 +2225,8 @@ fn find_context_errors(graph: &Graph, inverse: &InverseGraph, grammar: &Grammar)
         for (symbol, to_state) in &state.children {
             if let SymbolRef::Terminal(tid) = *symbol {
                 let terminal = grammar.get_terminal(tid).unwrap();
-                if terminal.context == 0 {
+                if terminal.context == 0 || grammar.is_lexer_mode(terminal.context) {
+                    // the contexts that are lexer modes are opened by the lexer itself
                     continue;
                 }
                 // this is a contextual terminal, can we reach this state without the right context being available
@@ -1358,15 +2335,22 @@ pub fn build_graph(
         ParsingMethod::LALR1 => build_graph_lalr1(grammar),
         ParsingMethod::RNGLR1 => build_graph_rnglr1(grammar),
         ParsingMethod::RNGLALR1 => build_graph_rnglalr1(grammar),
+        ParsingMethod::MinLR1 => build_graph_minlr1(grammar),
+        ParsingMethod::RNGMinLR1 => build_graph_rngminlr1(grammar),
     };
     let inverse = graph.inverse();
     let mut errors = Vec::new();
+    if method == ParsingMethod::LR0 && !grammar.precedences.is_empty() {
+        // LR(0) reductions have no lookahead to compare against
+        errors.push(Error::PrecedencesNotSupported(grammar_index));
+    }
     if method.raise_conflict() {
         for mut conflict in conflicts.0 {
             conflict.phrases = inverse.get_inputs_for(conflict.state, grammar);
             for phrase in &mut conflict.phrases {
                 phrase.append(conflict.lookahead.terminal);
             }
+            conflict.counterexample = build_counterexample(&graph, &inverse, grammar, &conflict);
             errors.push(Error::LrConflict(grammar_index, Box::new(conflict)));
         }
     }
@@ -21,11 +21,11 @@ use std::fs::File;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
-use hime_redist::lexers::automaton::DEAD_STATE;
+use hime_redist::lexers::automaton::{DEAD_STATE, SECTION_CONTEXTS, SECTION_HEADS, SECTION_MODES};
 
 use crate::errors::Error;
 use crate::finite::{DFAState, DFA};
-use crate::grammars::{Grammar, TerminalRef, TerminalSet};
+use crate::grammars::{Grammar, ModeAction, TerminalRef, TerminalSet};
 use crate::output::helper::{write_u16, write_u32};
 use crate::CharSpan;
 
@@ -49,11 +49,17 @@ pub fn write_lexer_data_file(
 
 /// Writes the lexer's data
 pub fn write_lexer_data(writer: &mut dyn Write, grammar: &Grammar, dfa: &DFA, expected: &TerminalSet) -> Result<(), Error> {
+    let contexts = build_trailing_contexts(grammar, dfa.len(), expected);
+    let states: Vec<&DFAState> = dfa
+        .states
+        .iter()
+        .chain(contexts.iter().flat_map(|(_, context)| context.states.iter()))
+        .collect();
     // write number of states
-    write_u32(writer, dfa.len() as u32)?;
+    write_u32(writer, states.len() as u32)?;
     // write the offsets to all the states
     let mut offset: u32 = 0;
-    for state in &dfa.states {
+    for state in &states {
         write_u32(writer, offset)?;
         // adds the length required by this state
         offset += 3 + 256; // header + transitions for [0-255] characters
@@ -74,9 +80,103 @@ pub fn write_lexer_data(writer: &mut dyn Write, grammar: &Grammar, dfa: &DFA, ex
         }
     }
     // write each state
-    for state in &dfa.states {
+    for state in &states {
         write_lexer_data_state(writer, grammar, expected, state)?;
     }
+    if grammar.has_lexer_modes() {
+        write_lexer_data_modes(writer, grammar, expected)?;
+    }
+    if dfa.states.iter().any(|state| !state.heads.is_empty()) {
+        write_lexer_data_heads(writer, dfa, expected)?;
+    }
+    if !contexts.is_empty() {
+        write_u16(writer, SECTION_CONTEXTS)?;
+        write_u16(writer, contexts.len() as u16)?;
+        for (terminal, context) in &contexts {
+            write_u16(writer, *terminal as u16)?;
+            write_u16(writer, context.states[0].id as u16)?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds the automata matching the trailing contexts of the expected terminals, by terminal index
+/// Their states are numbered after the states of the main automaton.
+fn build_trailing_contexts(grammar: &Grammar, mut offset: usize, expected: &TerminalSet) -> Vec<(usize, DFA)> {
+    let mut contexts = Vec::new();
+    for (index, terminal_ref) in expected.content.iter().enumerate() {
+        let Some(nfa) = grammar
+            .get_terminal(terminal_ref.sid())
+            .and_then(|terminal| terminal.nfa.get_trailing_context())
+        else {
+            continue;
+        };
+        let mut context = DFA::from_nfa(nfa).minimize();
+        for state in &mut context.states {
+            state.id += offset;
+            for next in state.transitions.values_mut() {
+                *next += offset;
+            }
+        }
+        offset += context.len();
+        contexts.push((index, context));
+    }
+    contexts
+}
+
+/// Writes the actions on the stack of lexer modes for the expected terminals
+/// Each action is of the form:
+/// u16: 0 for no action, 1 for a push, 2 for a pop
+/// u16: the context pushed as the new mode, 0 otherwise
+fn write_lexer_data_modes(writer: &mut dyn Write, grammar: &Grammar, expected: &TerminalSet) -> Result<(), Error> {
+    write_u16(writer, SECTION_MODES)?;
+    write_u16(writer, expected.content.len() as u16)?;
+    for terminal_ref in &expected.content {
+        let action = match terminal_ref {
+            TerminalRef::Terminal(id) => grammar.get_terminal(*id).map_or(ModeAction::None, |t| t.mode_action),
+            _ => ModeAction::None,
+        };
+        match action {
+            ModeAction::None => {
+                write_u16(writer, 0)?;
+                write_u16(writer, 0)?;
+            }
+            ModeAction::Push(mode) => {
+                write_u16(writer, 1)?;
+                write_u16(writer, mode as u16)?;
+            }
+            ModeAction::Pop => {
+                write_u16(writer, 2)?;
+                write_u16(writer, 0)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the ends of the heads of the terminals with a trailing context, in the order of the states
+/// Each end is of the form:
+/// u16: the state at the end of the head
+/// u16: the index of the terminal
+fn write_lexer_data_heads(writer: &mut dyn Write, dfa: &DFA, expected: &TerminalSet) -> Result<(), Error> {
+    let heads: Vec<(usize, usize)> = dfa
+        .states
+        .iter()
+        .flat_map(|state| {
+            // the terminals that are never matched are not expected
+            state.heads.iter().filter_map(move |item| {
+                let terminal_ref = TerminalRef::from(*item);
+                let index = expected.content.iter().position(|t| t == &terminal_ref)?;
+                Some((state.id, index))
+            })
+        })
+        .collect();
+    write_u16(writer, SECTION_HEADS)?;
+    write_u16(writer, heads.len() as u16)?;
+    for (state, terminal) in heads {
+        write_u16(writer, state as u16)?;
+        write_u16(writer, terminal as u16)?;
+    }
     Ok(())
 }
 
@@ -21,6 +21,8 @@ use std::fs::File;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
+use hime_redist::lexers::Indentation;
+
 use crate::errors::Error;
 use crate::grammars::{Grammar, TerminalRef, TerminalSet, PREFIX_GENERATED_TERMINAL};
 use crate::output::get_lexer_bin_name_rust;
@@ -35,6 +37,7 @@ pub fn write(
     grammar: &Grammar,
     expected: &TerminalSet,
     separator: Option<TerminalRef>,
+    indentation: Option<Indentation>,
     is_rnglr: bool,
     with_std: bool,
     suppress_module_doc: bool,
@@ -75,6 +78,12 @@ pub fn write(
     writeln!(writer, "use hime_redist::errors::ParseErrors;")?;
     writeln!(writer, "use hime_redist::lexers::automaton::Automaton;")?;
     writeln!(writer, "use hime_redist::lexers::impls::{base_lexer}Lexer;")?;
+    if grammar.has_external_terminals() {
+        writeln!(writer, "use hime_redist::lexers::ExternalScanner;")?;
+    }
+    if indentation.is_some() {
+        writeln!(writer, "use hime_redist::lexers::Indentation;")?;
+    }
     writeln!(writer, "use hime_redist::lexers::Lexer;")?;
     if is_rnglr {
         writeln!(writer, "use hime_redist::parsers::rnglr::RNGLRAutomaton;")?;
@@ -82,7 +91,12 @@ pub fn write(
     } else {
         writeln!(writer, "use hime_redist::parsers::lrk::LRkAutomaton;")?;
         writeln!(writer, "use hime_redist::parsers::lrk::LRkParser;")?;
+        writeln!(writer, "use hime_redist::parsers::push::PushParser;")?;
+    }
+    if !is_rnglr && !grammar.actions.is_empty() {
+        writeln!(writer, "use hime_redist::parsers::values::SemanticValues;")?;
     }
+    writeln!(writer, "use hime_redist::parsers::LRCompletion;")?;
     writeln!(writer, "use hime_redist::parsers::Parser;")?;
     writeln!(
         writer,
@@ -95,7 +109,7 @@ pub fn write(
     writeln!(writer, "use hime_redist::symbols::SemanticBody;")?;
     writeln!(writer, "use hime_redist::symbols::SemanticElementTrait;")?;
     writeln!(writer, "use hime_redist::symbols::Symbol;")?;
-    writeln!(writer, "use hime_redist::text::Text;")?;
+    writeln!(writer, "use hime_redist::text::{{Text, TextEdit, TextEditError}};")?;
     writeln!(writer, "use hime_redist::tokens::TokenRepository;")?;
     writeln!(writer)?;
 
@@ -170,10 +184,24 @@ pub fn write(
         "    let automaton = Automaton::new(LEXER_AUTOMATON{});",
         if compress_automata { ".as_ref()" } else { "" }
     )?;
-    writeln!(
-        writer,
-        "    Lexer::{base_lexer}({base_lexer}Lexer::new(repository, errors, automaton, 0x{separator:04X}))"
-    )?;
+    if let Some(indentation) = indentation {
+        writeln!(
+            writer,
+            "    let mut lexer = Lexer::{base_lexer}({base_lexer}Lexer::new(repository, errors, automaton, 0x{separator:04X}));"
+        )?;
+        writeln!(writer, "    lexer.set_indentation(Indentation {{")?;
+        writeln!(writer, "        indent_id: 0x{:04X},", indentation.indent_id)?;
+        writeln!(writer, "        dedent_id: 0x{:04X},", indentation.dedent_id)?;
+        writeln!(writer, "        newline_id: 0x{:04X},", indentation.newline_id)?;
+        writeln!(writer, "        tab_width: {},", indentation.tab_width)?;
+        writeln!(writer, "    }});")?;
+        writeln!(writer, "    lexer")?;
+    } else {
+        writeln!(
+            writer,
+            "    Lexer::{base_lexer}({base_lexer}Lexer::new(repository, errors, automaton, 0x{separator:04X}))"
+        )?;
+    }
     writeln!(writer, "}}")?;
     writeln!(writer)?;
     Ok(())
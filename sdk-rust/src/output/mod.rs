@@ -51,7 +51,7 @@ use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
 use crate::errors::Error;
-use crate::grammars::{BuildData, Grammar};
+use crate::grammars::{BuildData, Grammar, ModeAction, TerminalRef, OPTION_AXIOM, OPTION_INDENTATION};
 use crate::sdk::{InMemoryParser, ParserAutomaton};
 use crate::{CompilationTask, ParsingMethod, Runtime};
 
@@ -76,6 +76,12 @@ pub fn output_grammar_artifacts(
         Ok(runtime) => runtime,
         Err(error) => return Err(vec![error]),
     };
+    if runtime != Runtime::Rust {
+        let errors = get_rust_only_features(grammar, grammar_index);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+    }
     let nmspace = match task.get_output_namespace(grammar) {
         Some(nmspace) => nmspace,
         None => grammar.name.clone(),
@@ -104,20 +110,24 @@ pub fn output_grammar_artifacts(
         return Err(vec![error]);
     }
     if let Err(error) = match data.method {
-        ParsingMethod::LR0 | ParsingMethod::LR1 | ParsingMethod::LALR1 => parser_data::write_parser_lrk_data_file(
-            output_path.as_ref(),
-            get_parser_bin_name(grammar, runtime),
-            grammar,
-            &data.expected,
-            &data.graph,
-        ),
-        ParsingMethod::RNGLR1 | ParsingMethod::RNGLALR1 => parser_data::write_parser_rnglr_data_file(
-            output_path.as_ref(),
-            get_parser_bin_name(grammar, runtime),
-            grammar,
-            &data.expected,
-            &data.graph,
-        ),
+        ParsingMethod::LR0 | ParsingMethod::LR1 | ParsingMethod::LALR1 | ParsingMethod::MinLR1 => {
+            parser_data::write_parser_lrk_data_file(
+                output_path.as_ref(),
+                get_parser_bin_name(grammar, runtime),
+                grammar,
+                &data.expected,
+                &data.graph,
+            )
+        }
+        ParsingMethod::RNGLR1 | ParsingMethod::RNGLALR1 | ParsingMethod::RNGMinLR1 => {
+            parser_data::write_parser_rnglr_data_file(
+                output_path.as_ref(),
+                get_parser_bin_name(grammar, runtime),
+                grammar,
+                &data.expected,
+                &data.graph,
+            )
+        }
     } {
         return Err(vec![error]);
     }
@@ -199,12 +209,14 @@ pub fn output_grammar_artifacts(
             let with_std = task.get_rust_use_std();
             let suppress_module_doc = task.get_rust_suppress_module_doc();
             let compress_automata = task.get_rust_compress_automata();
+            let typed_ast = task.get_rust_typed_ast();
             if let Err(error) = lexer_rust::write(
                 output_path.as_ref(),
                 format!("{}.rs", helper::to_snake_case(&grammar.name)),
                 grammar,
                 &data.expected,
                 data.separator,
+                data.indentation,
                 data.method.is_rnglr(),
                 with_std,
                 suppress_module_doc,
@@ -222,6 +234,7 @@ pub fn output_grammar_artifacts(
                 mode.output_assembly(),
                 with_std,
                 compress_automata,
+                typed_ast,
             ) {
                 return Err(vec![error]);
             }
@@ -254,6 +267,32 @@ pub fn output_grammar_artifacts(
     Ok(())
 }
 
+/// Gets the errors for the features of a grammar that are only supported by the Rust runtime
+fn get_rust_only_features(grammar: &Grammar, grammar_index: usize) -> Vec<Error> {
+    let mut errors = Vec::new();
+    if grammar.get_option(OPTION_INDENTATION).is_some() {
+        // the other runtimes never insert the indentation tokens
+        errors.push(Error::IndentationNotSupported(grammar_index));
+    }
+    let indentation = grammar.get_indentation_terminals();
+    for terminal in &grammar.terminals {
+        let terminal_ref = TerminalRef::Terminal(terminal.id);
+        if terminal.nfa.head.is_some() {
+            // the other runtimes ignore the ends of the heads in the lexer's data
+            errors.push(Error::TrailingContextNotSupported(grammar_index, terminal_ref));
+        }
+        if terminal.mode_action != ModeAction::None {
+            // the other runtimes ignore the actions on the lexer modes in the lexer's data
+            errors.push(Error::ModesNotSupported(grammar_index, terminal_ref));
+        }
+        if terminal.is_external && !indentation.contains(&terminal.name.as_str()) {
+            // the other runtimes have no scanner to match the external terminals
+            errors.push(Error::ExternalTerminalNotSupported(grammar_index, terminal_ref));
+        }
+    }
+    errors
+}
+
 /// Builds the in-memory parser for a grammar
 ///
 /// # Errors
@@ -306,12 +345,19 @@ pub fn build_in_memory_grammar<'a>(grammar: &'a Grammar, data: &BuildData) -> Re
         terminals,
         variables,
         virtuals,
+        entries: grammar
+            .get_option(OPTION_AXIOM)
+            .map(|option| option.value.as_str())
+            .into_iter()
+            .chain(grammar.get_entries())
+            .collect(),
         separator: match data.separator {
             None => 0xFFFF,
             Some(terminal_ref) => terminal_ref.sid() as u32,
         },
         lexer_automaton: Automaton::new(&lexer_automaton),
         lexer_is_context_sensitive: grammar.contexts.len() > 1,
+        lexer_indentation: data.indentation,
         parser_automaton: if data.method.is_rnglr() {
             ParserAutomaton::Rnglr(RNGLRAutomaton::new(&parser_automaton))
         } else {
@@ -516,7 +562,7 @@ fn str_to_pathbuf(s: &str, sep: char) -> PathBuf {
 
 #[cfg(not(target_os = "windows"))]
 pub fn from_slashed_path(p: &Path) -> PathBuf {
-    PathBuf::from(p.as_ref())
+    p.to_path_buf()
 }
 
 #[cfg(target_os = "windows")]
@@ -29,7 +29,9 @@ use hime_redist::parsers::{
 };
 
 use crate::errors::Error;
-use crate::grammars::{Grammar, Rule, RuleRef, SymbolRef, TerminalRef, TerminalSet, GENERATED_AXIOM};
+use crate::grammars::{
+    Grammar, Rule, RuleAnnotations, RulePreference, RuleRef, SymbolRef, TerminalRef, TerminalSet, GENERATED_AXIOM,
+};
 use crate::lr::{Graph, State};
 use crate::output::helper::{write_u16, write_u32, write_u8};
 
@@ -67,7 +69,7 @@ pub fn write_parser_lrk_data(
     // number of columns
     write_u16(writer, (expected.len() + grammar.variables.len()) as u16)?;
     // number of states
-    write_u16(writer, graph.states.len() as u16)?;
+    write_u16(writer, graph.get_states().len() as u16)?;
     // number of rules
     write_u16(writer, rules.len() as u16)?;
 
@@ -75,7 +77,7 @@ pub fn write_parser_lrk_data(
     write_parser_opening_contexts(writer, graph)?;
 
     // write the LR table
-    for state in &graph.states {
+    for state in graph.get_states() {
         write_parser_lrk_data_state(writer, grammar, expected, &rules, state)?;
     }
     // write production rules
@@ -84,6 +86,55 @@ pub fn write_parser_lrk_data(
             write_parser_lrk_data_rule(writer, grammar, rule)?;
         }
     }
+    // write the labels, if any
+    if !grammar.labels.is_empty() {
+        let productions = grammar
+            .variables
+            .iter()
+            .flat_map(|variable| variable.rules.iter().map(|rule| (rule, rule.body.choices[0].len())));
+        write_parser_labels(writer, grammar, productions)?;
+    }
+    Ok(())
+}
+
+/// Writes the labels of the rules' elements for a parser data
+/// The labels are written as an optional trailing section:
+/// u16: number of labels, then for each label its length in bytes as u16 and its UTF-8 content,
+/// then for each production, the number of labelled op-codes as u16,
+/// followed by the index of each labelled op-code in the bytecode as u16 and the index of its label as u16.
+fn write_parser_labels<'a>(
+    writer: &mut dyn Write,
+    grammar: &Grammar,
+    productions: impl Iterator<Item = (&'a Rule, usize)>,
+) -> Result<(), Error> {
+    write_u16(writer, grammar.labels.len() as u16)?;
+    for label in &grammar.labels {
+        write_u16(writer, label.len() as u16)?;
+        writer.write_all(label.as_bytes())?;
+    }
+    for (rule, length) in productions {
+        let mut labelled = Vec::new();
+        let mut op_index: usize = 0;
+        let mut pop = 0;
+        for element in &rule.body.elements {
+            if let Some(label) = element.label {
+                labelled.push((op_index, label));
+            }
+            match element.symbol {
+                SymbolRef::Virtual(_) | SymbolRef::Action(_) => op_index += 2,
+                _ if pop >= length => op_index += 2,
+                _ => {
+                    op_index += 1;
+                    pop += 1;
+                }
+            }
+        }
+        write_u16(writer, labelled.len() as u16)?;
+        for (op_index, label) in labelled {
+            write_u16(writer, op_index as u16)?;
+            write_u16(writer, label as u16)?;
+        }
+    }
     Ok(())
 }
 
@@ -102,7 +153,7 @@ fn write_parser_column_headers(writer: &mut dyn Write, grammar: &Grammar, expect
 /// Write the opening context informations for each state
 fn write_parser_opening_contexts(writer: &mut dyn Write, graph: &Graph) -> Result<(), Error> {
     // write context openings for each state
-    for state in &graph.states {
+    for state in graph.get_states() {
         let count: usize = state.opening_contexts.values().map(std::vec::Vec::len).sum();
         write_u16(writer, count as u16)?;
         for (terminal, contexts) in &state.opening_contexts {
@@ -274,7 +325,7 @@ pub fn write_parser_rnglr_data(
     let mut total: u32 = 0;
     let mut offsets: Vec<u32> = Vec::new(); // for each state, the offset in the action table
     let mut counts: Vec<u16> = Vec::new(); // for each state, the number of actions
-    for state in &graph.states {
+    for state in graph.get_states() {
         total = write_parser_rnglr_data_generate_offset(expected, grammar, &mut offsets, &mut counts, total, state);
     }
     let axiom_index = grammar
@@ -288,7 +339,7 @@ pub fn write_parser_rnglr_data(
     // nb of colimns
     write_u16(writer, (expected.len() + grammar.variables.len()) as u16)?;
     // nb of rows
-    write_u16(writer, graph.states.len() as u16)?;
+    write_u16(writer, graph.get_states().len() as u16)?;
     // nb of actions
     write_u32(writer, total)?;
     // nb of rules
@@ -305,12 +356,12 @@ pub fn write_parser_rnglr_data(
         write_u32(writer, offset)?;
     }
 
-    for state in &graph.states {
+    for state in graph.get_states() {
         write_parser_rnglr_data_action_table(writer, expected, grammar, &rules, state)?;
     }
 
-    for (rule_ref, length) in rules {
-        write_parser_rnglr_data_rule(writer, grammar, rule_ref.get_rule_in(grammar), length)?;
+    for (rule_ref, length) in &rules {
+        write_parser_rnglr_data_rule(writer, grammar, rule_ref.get_rule_in(grammar), *length)?;
     }
 
     // write the indexes for nullables production
@@ -318,6 +369,31 @@ pub fn write_parser_rnglr_data(
         write_u16(writer, index)?;
     }
 
+    // write the labels and the annotations, if any
+    let annotated = rules
+        .iter()
+        .any(|(rule_ref, _)| rule_ref.get_rule_in(grammar).body.annotations != RuleAnnotations::default());
+    if !grammar.labels.is_empty() || annotated {
+        let productions = rules
+            .iter()
+            .map(|(rule_ref, length)| (rule_ref.get_rule_in(grammar), *length));
+        write_parser_labels(writer, grammar, productions)?;
+    }
+    if annotated {
+        for (rule_ref, _) in &rules {
+            let annotations = rule_ref.get_rule_in(grammar).body.annotations;
+            write_u16(writer, annotations.priority)?;
+            write_u8(
+                writer,
+                match annotations.preference {
+                    RulePreference::None => 0,
+                    RulePreference::Prefer => 1,
+                    RulePreference::Avoid => 2,
+                },
+            )?;
+        }
+    }
+
     Ok(())
 }
 
@@ -92,6 +92,7 @@ pub fn write(
     )?;
 
     write_code_symbols(&mut writer, grammar)?;
+    write_code_labels(&mut writer, grammar)?;
     write_code_variables(&mut writer, grammar)?;
     write_code_virtuals(&mut writer, grammar)?;
     write_code_actions(&mut writer, grammar)?;
@@ -141,6 +142,32 @@ fn write_code_symbols(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), E
     Ok(())
 }
 
+/// Generates the code for the labels
+fn write_code_labels(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
+    if grammar.labels.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "    /**")?;
+    writeln!(
+        writer,
+        "     * Contains the constant names for the labels of the children of nodes"
+    )?;
+    writeln!(writer, "     */")?;
+    writeln!(writer, "    public static class Label {{")?;
+    for label in &grammar.labels {
+        writeln!(writer, "        /**")?;
+        writeln!(writer, "         * The label {label}")?;
+        writeln!(writer, "         */")?;
+        writeln!(
+            writer,
+            "        public static final String {} = \"{label}\";",
+            to_upper_case(label)
+        )?;
+    }
+    writeln!(writer, "    }}")?;
+    Ok(())
+}
+
 /// Generates the code for the variables
 fn write_code_variables(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
     writeln!(writer, "    /**")?;
@@ -21,14 +21,18 @@ use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
 use std::path::PathBuf;
 
+use hime_redist::parsers::{TREE_ACTION_DROP, TREE_ACTION_PROMOTE, TREE_ACTION_REPLACE_BY_CHILDREN};
+
 use crate::errors::Error;
-use crate::grammars::{Grammar, TerminalSet, PREFIX_GENERATED_TERMINAL, PREFIX_GENERATED_VARIABLE};
+use crate::grammars::{
+    Grammar, RuleBodyElement, SymbolRef, TerminalSet, Variable, PREFIX_GENERATED_TERMINAL, PREFIX_GENERATED_VARIABLE,
+};
 use crate::output::get_parser_bin_name_rust;
-use crate::output::helper::{to_snake_case, to_upper_case};
+use crate::output::helper::{to_snake_case, to_upper_camel_case, to_upper_case};
 use crate::ParsingMethod;
 
 /// Generates code for the specified file
-#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
 pub fn write(
     path: Option<&String>,
     file_name: String,
@@ -39,6 +43,7 @@ pub fn write(
     output_assembly: bool,
     with_std: bool,
     compress_automata: bool,
+    typed_ast: bool,
 ) -> Result<(), Error> {
     let mut final_path = PathBuf::new();
     if let Some(path) = path {
@@ -69,7 +74,7 @@ pub fn write(
     write_code_symbols(&mut writer, grammar)?;
     write_code_variables(&mut writer, grammar)?;
     write_code_virtuals(&mut writer, grammar)?;
-    write_code_actions(&mut writer, grammar)?;
+    write_code_actions(&mut writer, grammar, !method.is_rnglr())?;
     write_code_constructors(
         &mut writer,
         grammar,
@@ -84,6 +89,9 @@ pub fn write(
         with_std,
         compress_automata,
     )?;
+    if !method.is_rnglr() {
+        write_code_values_constructors(&mut writer, grammar, compress_automata)?;
+    }
     if method.is_rnglr() {
         writeln!(writer)?;
         write_code_constructors(
@@ -102,6 +110,9 @@ pub fn write(
         )?;
     }
     write_code_visitor(&mut writer, grammar, expected)?;
+    if typed_ast {
+        write_code_typed_ast(&mut writer, grammar)?;
+    }
     Ok(())
 }
 
@@ -131,6 +142,13 @@ fn write_code_symbols(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), E
         )?;
     }
     writeln!(writer)?;
+    if !grammar.labels.is_empty() {
+        for label in &grammar.labels {
+            writeln!(writer, "/// The label `{label}` for the children of nodes")?;
+            writeln!(writer, "pub const LABEL_{}: &str = \"{label}\";", to_upper_case(label))?;
+        }
+        writeln!(writer)?;
+    }
     Ok(())
 }
 
@@ -183,7 +201,8 @@ fn write_code_virtuals(writer: &mut dyn Write, grammar: &Grammar) -> Result<(),
 }
 
 /// Generates the code for the semantic actions
-fn write_code_actions(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
+/// The semantic actions that produce values are only supported by LR(k) parsers.
+fn write_code_actions(writer: &mut dyn Write, grammar: &Grammar, with_values: bool) -> Result<(), Error> {
     if grammar.actions.is_empty() {
         return Ok(());
     }
@@ -205,6 +224,32 @@ fn write_code_actions(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), E
     writeln!(writer)?;
     writeln!(writer, "impl Actions for NoActions {{}}")?;
     writeln!(writer)?;
+    if with_values {
+        writeln!(
+            writer,
+            "/// Represents a set of semantic actions in this parser that produce values"
+        )?;
+        writeln!(
+            writer,
+            "/// The values of the elements of a rule that have been popped so far are given in the order of the rule,"
+        )?;
+        writeln!(writer, "/// excluding the virtual symbols and the semantic actions.")?;
+        writeln!(writer, "/// Tokens have no value.")?;
+        writeln!(writer, "pub trait ValueActions {{")?;
+        writeln!(writer, "    /// The type of the values produced by the semantic actions")?;
+        writeln!(writer, "    type Value;")?;
+        for action in &grammar.actions {
+            writeln!(writer)?;
+            writeln!(writer, "    /// The {} semantic action", &action.name)?;
+            writeln!(
+                writer,
+                "    fn {}(&mut self, head: Symbol, body: &dyn SemanticBody, values: &mut [Option<Self::Value>]) -> Self::Value;",
+                to_snake_case(&action.name)
+            )?;
+        }
+        writeln!(writer, "}}")?;
+        writeln!(writer)?;
+    }
     Ok(())
 }
 
@@ -333,6 +378,196 @@ fn write_code_constructors(
         }
     }
 
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/// Parses again the input of a previous result after some edits, reusing what the edits do not affect"
+    )?;
+    writeln!(
+        writer,
+        "/// The spans of the edits refer to the previous input and must not overlap."
+    )?;
+    writeln!(writer, "///")?;
+    writeln!(writer, "/// # Errors")?;
+    writeln!(writer, "///")?;
+    writeln!(
+        writer,
+        "/// Return a `TextEditError` when the edits are not valid for the previous input"
+    )?;
+    if output_assembly {
+        writeln!(writer, "#[no_mangle]")?;
+        writeln!(writer, "#[export_name = \"{nmespace}_reparse{fn_suffix}\"]")?;
+    }
+    writeln!(
+        writer,
+        "pub fn reparse{fn_suffix}(previous: ParseResult<'static, '_, 'static, {tree_type}>, edits: &[TextEdit]) -> Result<{parse_result_type}, TextEditError> {{"
+    )?;
+    writeln!(
+        writer,
+        "    Ok(parse_into{fn_suffix}(previous.apply_edits(edits)?, 0{}))",
+        if has_actions { ", &mut NoActions {}" } else { "" }
+    )?;
+    writeln!(writer, "}}")?;
+
+    if grammar.has_external_terminals() {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "/// Parses the specified string with this parser, matching the external terminals with the specified scanner"
+        )?;
+        writeln!(writer, "#[must_use]")?;
+        writeln!(
+            writer,
+            "pub fn parse_str_with_scanner{fn_suffix}<'t>(input: &'t str, scanner: &mut dyn ExternalScanner) -> ParseResult<'static, 't, 'static, {tree_type}> {{"
+        )?;
+        writeln!(
+            writer,
+            "    let mut result = ParseResult::<{tree_type}>::new(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));"
+        )?;
+        writeln!(
+            writer,
+            "    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| {{}};"
+        )?;
+        writeln!(writer, "    {{")?;
+        writeln!(writer, "        let data = result.get_parsing_data();")?;
+        writeln!(writer, "        let mut lexer = new_lexer(data.0, data.1);")?;
+        writeln!(writer, "        lexer.set_scanner(scanner);")?;
+        writeln!(
+            writer,
+            "        let automaton = {automaton_type}::new(PARSER_AUTOMATON{});",
+            if compress_automata { ".as_ref()" } else { "" }
+        )?;
+        writeln!(
+            writer,
+            "        let mut parser = {parser_type}::{parser_ctor}(&mut lexer, VARIABLES, VIRTUALS, automaton, data.2, &mut my_actions);"
+        )?;
+        writeln!(writer, "        parser.parse();")?;
+        writeln!(writer, "    }}")?;
+        writeln!(writer, "    result")?;
+        writeln!(writer, "}}")?;
+    }
+
+    for (index, entry) in grammar.get_entries().into_iter().enumerate() {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "/// Parses the specified string with this parser, as a `{entry}` instead of the axiom"
+        )?;
+        if output_assembly {
+            writeln!(writer, "#[no_mangle]")?;
+            writeln!(
+                writer,
+                "#[export_name = \"{nmespace}_parse_str_from_{}{fn_suffix}\"]",
+                to_snake_case(entry)
+            )?;
+        }
+        writeln!(writer, "#[must_use]")?;
+        writeln!(
+            writer,
+            "pub fn parse_str_from_{}{fn_suffix}(input: &str) -> ParseResult<'static, '_, 'static, {tree_type}> {{",
+            to_snake_case(entry)
+        )?;
+        writeln!(
+            writer,
+            "    let result = ParseResult::<{tree_type}>::new(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));"
+        )?;
+        writeln!(
+            writer,
+            "    parse_into{fn_suffix}(result, {}{})",
+            index + 1,
+            if has_actions { ", &mut NoActions {}" } else { "" }
+        )?;
+        writeln!(writer, "}}")?;
+    }
+
+    if tree_type == "AstImpl" {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "/// Gets the symbols expected at the specified index in an input, for code completion"
+        )?;
+        writeln!(writer, "/// Only the tokens that end at or before the index are parsed.")?;
+        if output_assembly {
+            writeln!(writer, "#[no_mangle]")?;
+            writeln!(writer, "#[export_name = \"{nmespace}_get_expected_at\"]")?;
+        }
+        writeln!(writer, "#[must_use]")?;
+        writeln!(
+            writer,
+            "pub fn get_expected_at(input: &str, index: usize) -> LRCompletion<'static> {{"
+        )?;
+        writeln!(
+            writer,
+            "    let mut result = ParseResult::<AstImpl>::new(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));"
+        )?;
+        writeln!(
+            writer,
+            "    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| {{}};"
+        )?;
+        writeln!(writer, "    let data = result.get_parsing_data();")?;
+        writeln!(writer, "    let mut lexer = new_lexer(data.0, data.1);")?;
+        writeln!(
+            writer,
+            "    let automaton = {automaton_type}::new(PARSER_AUTOMATON{});",
+            if compress_automata { ".as_ref()" } else { "" }
+        )?;
+        writeln!(
+            writer,
+            "    let mut parser = {parser_type}::{parser_ctor}(&mut lexer, VARIABLES, VIRTUALS, automaton, data.2, &mut my_actions);"
+        )?;
+        writeln!(writer, "    parser.get_expected_at(index)")?;
+        writeln!(writer, "}}")?;
+    }
+
+    if parser_type == "LRkParser" {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "/// Parses the specified string with this parser, keeping the separators as trivia and building the concrete syntax tree"
+        )?;
+        if output_assembly {
+            writeln!(writer, "#[no_mangle]")?;
+            writeln!(writer, "#[export_name = \"{nmespace}_parse_str_lossless{fn_suffix}\"]")?;
+        }
+        writeln!(writer, "#[must_use]")?;
+        writeln!(
+            writer,
+            "pub fn parse_str_lossless{fn_suffix}(input: &str) -> ParseResult<'static, '_, 'static, {tree_type}> {{"
+        )?;
+        writeln!(
+            writer,
+            "    let result = ParseResult::<{tree_type}>::new_lossless(TERMINALS, VARIABLES, VIRTUALS, Text::from_str(input));"
+        )?;
+        writeln!(
+            writer,
+            "    parse_into{fn_suffix}(result, 0{})",
+            if has_actions { ", &mut NoActions {}" } else { "" }
+        )?;
+        writeln!(writer, "}}")?;
+        writeln!(writer)?;
+        writeln!(writer, "/// Creates a push parser, to be fed with the input piece by piece")?;
+        if output_assembly {
+            writeln!(writer, "#[no_mangle]")?;
+            writeln!(writer, "#[export_name = \"{nmespace}_new_push_parser{fn_suffix}\"]")?;
+        }
+        writeln!(writer, "#[must_use]")?;
+        writeln!(
+            writer,
+            "pub fn new_push_parser{fn_suffix}() -> PushParser<'static, 'static> {{"
+        )?;
+        writeln!(
+            writer,
+            "    let automaton = Automaton::new(LEXER_AUTOMATON{});",
+            if compress_automata { ".as_ref()" } else { "" }
+        )?;
+        writeln!(
+            writer,
+            "    PushParser::new(TERMINALS, VARIABLES, VIRTUALS, automaton, |result| parse_into{fn_suffix}(result, 0{}))",
+            if has_actions { ", &mut NoActions {}" } else { "" }
+        )?;
+        writeln!(writer, "}}")?;
+    }
+
     writeln!(writer)?;
     writeln!(writer, "/// Parses the specified text with this parser")?;
     writeln!(
@@ -357,6 +592,28 @@ fn write_code_constructors(
         writeln!(writer, "    actions: &mut dyn Actions")?;
     }
     writeln!(writer, ") -> ParseResult<'s, 't, 'a, {tree_type}> {{")?;
+    writeln!(
+        writer,
+        "    let result = ParseResult::<{tree_type}>::new(terminals, variables, virtuals, text);"
+    )?;
+    writeln!(
+        writer,
+        "    parse_into{fn_suffix}(result, 0{})",
+        if has_actions { ", actions" } else { "" }
+    )?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/// Parses the input of the specified result into it with this parser, from the specified entry point"
+    )?;
+    writeln!(writer, "fn parse_into{fn_suffix}<'s, 't, 'a>(")?;
+    writeln!(writer, "    mut result: ParseResult<'s, 't, 'a, {tree_type}>,")?;
+    writeln!(writer, "    entry: usize,")?;
+    if has_actions {
+        writeln!(writer, "    actions: &mut dyn Actions")?;
+    }
+    writeln!(writer, ") -> ParseResult<'s, 't, 'a, {tree_type}> {{")?;
     if has_actions {
         writeln!(
             writer,
@@ -379,25 +636,119 @@ fn write_code_constructors(
             "    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| {{}};"
         )?;
     }
+    writeln!(writer, "    let variables = result.variables;")?;
+    writeln!(writer, "    let virtuals = result.virtuals;")?;
+    writeln!(writer, "    {{")?;
+    writeln!(writer, "        let data = result.get_parsing_data();")?;
+    writeln!(writer, "        let mut lexer = new_lexer(data.0, data.1);")?;
+    writeln!(
+        writer,
+        "        let automaton = {automaton_type}::new(PARSER_AUTOMATON{});",
+        if compress_automata { ".as_ref()" } else { "" }
+    )?;
+    writeln!(
+        writer,
+        "        let parser = {parser_type}::{parser_ctor}(&mut lexer, variables, virtuals, automaton, data.2, &mut my_actions).with_entry(entry);"
+    )?;
+    writeln!(writer, "        if let Some(mut parser) = parser {{")?;
+    writeln!(writer, "            parser.parse();")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "    result")?;
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// Generates the constructors for the parsing with semantic actions that produce values
+fn write_code_values_constructors(writer: &mut dyn Write, grammar: &Grammar, compress_automata: bool) -> Result<(), Error> {
+    if grammar.actions.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/// Parses the specified string with this parser, synthesizing values with the semantic actions"
+    )?;
+    writeln!(
+        writer,
+        "/// The value synthesized for the axiom is returned when the parsing is successful."
+    )?;
+    writeln!(
+        writer,
+        "pub fn parse_str_with_values<'t, A: ValueActions>(input: &'t str, actions: &mut A) -> (ParseResult<'static, 't, 'static, AstImpl>, Option<A::Value>) {{"
+    )?;
+    writeln!(writer, "    let text = Text::from_str(input);")?;
+    writeln!(writer, "    parse_text_with_values(text, actions)")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/// Parses the specified string with this parser, synthesizing values with the semantic actions"
+    )?;
+    writeln!(
+        writer,
+        "/// The value synthesized for the axiom is returned when the parsing is successful."
+    )?;
     writeln!(
         writer,
-        "    let mut result = ParseResult::<{tree_type}>::new(terminals, variables, virtuals, text);"
+        "pub fn parse_string_with_values<A: ValueActions>(input: String, actions: &mut A) -> (ParseResultAst, Option<A::Value>) {{"
     )?;
+    writeln!(writer, "    let text = Text::from_string(input);")?;
+    writeln!(writer, "    parse_text_with_values(text, actions)")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "/// Parses the specified text with this parser, synthesizing values with the semantic actions"
+    )?;
+    writeln!(
+        writer,
+        "fn parse_text_with_values<'t, A: ValueActions>(text: Text<'t>, actions: &mut A) -> (ParseResult<'static, 't, 'static, AstImpl>, Option<A::Value>) {{"
+    )?;
+    writeln!(
+        writer,
+        "    let mut result = ParseResult::<AstImpl>::new(TERMINALS, VARIABLES, VIRTUALS, text);"
+    )?;
+    writeln!(
+        writer,
+        "    let mut my_actions = |index: usize, head: Symbol, body: &dyn SemanticBody, values: &mut [Option<A::Value>]| match index {{"
+    )?;
+    for (index, action) in grammar.actions.iter().enumerate() {
+        writeln!(
+            writer,
+            "        {} => Some(actions.{}(head, body, values)),",
+            index,
+            to_snake_case(&action.name)
+        )?;
+    }
+    writeln!(writer, "        _ => None")?;
+    writeln!(writer, "    }};")?;
+    writeln!(writer, "    let mut values = SemanticValues::new(&mut my_actions);")?;
+    writeln!(writer, "    let variables = result.variables;")?;
+    writeln!(writer, "    let virtuals = result.virtuals;")?;
     writeln!(writer, "    {{")?;
     writeln!(writer, "        let data = result.get_parsing_data();")?;
     writeln!(writer, "        let mut lexer = new_lexer(data.0, data.1);")?;
     writeln!(
         writer,
-        "        let automaton = {automaton_type}::new(PARSER_AUTOMATON{});",
+        "        let automaton = LRkAutomaton::new(PARSER_AUTOMATON{});",
         if compress_automata { ".as_ref()" } else { "" }
     )?;
     writeln!(
         writer,
-        "        let mut parser = {parser_type}::{parser_ctor}(&mut lexer, variables, virtuals, automaton, data.2, &mut my_actions);"
+        "        let mut no_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| {{}};"
+    )?;
+    writeln!(
+        writer,
+        "        let mut parser = LRkParser::new(&mut lexer, variables, virtuals, automaton, data.2, &mut no_actions).with_values(&mut values);"
     )?;
     writeln!(writer, "        parser.parse();")?;
     writeln!(writer, "    }}")?;
-    writeln!(writer, "    result")?;
+    writeln!(
+        writer,
+        "    let value = if result.is_success() {{ values.into_value() }} else {{ None }};"
+    )?;
+    writeln!(writer, "    (result, value)")?;
     writeln!(writer, "}}")?;
     Ok(())
 }
@@ -495,3 +846,523 @@ fn write_code_visitor(writer: &mut dyn Write, grammar: &Grammar, expected: &Term
     writeln!(writer, "}}")?;
     Ok(())
 }
+
+/// The upper bound of the number of occurrences of an unbounded child
+const UNBOUNDED: usize = usize::MAX;
+
+/// A kind of child for a typed AST node, after the tree actions are applied
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct TypedChild {
+    /// The symbol of the child
+    symbol: SymbolRef,
+    /// The minimum number of occurrences
+    min: usize,
+    /// The maximum number of occurrences
+    max: usize,
+}
+
+/// Adds the children of a sequence to those of another
+fn typed_children_concat(children: &mut Vec<TypedChild>, others: &[TypedChild]) {
+    for other in others {
+        match children.iter_mut().find(|child| child.symbol == other.symbol) {
+            Some(child) => {
+                child.min += other.min;
+                child.max = child.max.saturating_add(other.max);
+            }
+            None => children.push(*other),
+        }
+    }
+}
+
+/// Merges the children of alternative sequences
+fn typed_children_merge(alternatives: &[Vec<TypedChild>]) -> Vec<TypedChild> {
+    let mut result: Vec<TypedChild> = Vec::new();
+    for alternative in alternatives {
+        for child in alternative {
+            if !result.iter().any(|c| c.symbol == child.symbol) {
+                result.push(*child);
+            }
+        }
+    }
+    for child in &mut result {
+        for alternative in alternatives {
+            match alternative.iter().find(|c| c.symbol == child.symbol) {
+                Some(other) => {
+                    child.min = child.min.min(other.min);
+                    child.max = child.max.max(other.max);
+                }
+                None => child.min = 0,
+            }
+        }
+    }
+    result
+}
+
+/// Gets whether a variable is generated and replaced by its children in the AST
+fn is_inlined_variable(grammar: &Grammar, sid: usize) -> bool {
+    grammar.get_variable(sid).is_some_and(|variable| {
+        variable.name.starts_with(PREFIX_GENERATED_VARIABLE)
+            && variable
+                .rules
+                .iter()
+                .all(|rule| rule.head_action == TREE_ACTION_REPLACE_BY_CHILDREN)
+    })
+}
+
+/// Gets the children produced by the elements of a rule body, after the tree actions are applied
+/// Generated variables replaced by their children are inlined, the other generated variables are ignored.
+fn typed_children_of(
+    grammar: &Grammar,
+    elements: &[RuleBodyElement],
+    stack: &mut Vec<usize>,
+    recursive: &mut Vec<usize>,
+) -> Vec<TypedChild> {
+    let mut children = Vec::new();
+    for element in elements {
+        if element.action == TREE_ACTION_DROP {
+            continue;
+        }
+        match element.symbol {
+            SymbolRef::Variable(sid) if is_inlined_variable(grammar, sid) => {
+                if stack.contains(&sid) {
+                    // the generated variable is a repetition
+                    if !recursive.contains(&sid) {
+                        recursive.push(sid);
+                    }
+                    continue;
+                }
+                stack.push(sid);
+                let alternatives: Vec<Vec<TypedChild>> = grammar
+                    .get_variable(sid)
+                    .unwrap()
+                    .rules
+                    .iter()
+                    .map(|rule| typed_children_of(grammar, &rule.body.elements, stack, recursive))
+                    .collect();
+                stack.pop();
+                let mut inlined = typed_children_merge(&alternatives);
+                if recursive.contains(&sid) {
+                    for child in &mut inlined {
+                        child.max = UNBOUNDED;
+                    }
+                }
+                typed_children_concat(&mut children, &inlined);
+            }
+            symbol if is_typed_accessible(grammar, symbol) => {
+                typed_children_concat(&mut children, &[TypedChild { symbol, min: 1, max: 1 }]);
+            }
+            _ => {}
+        }
+    }
+    children
+}
+
+/// Gets the exact sequence of the children produced by the elements of a rule body, after the tree actions are applied
+/// There is no exact sequence when the body contains a repetition or an inlined choice.
+fn typed_sequence_of(grammar: &Grammar, elements: &[RuleBodyElement], stack: &mut Vec<usize>) -> Option<Vec<SymbolRef>> {
+    let mut sequence = Vec::new();
+    for element in elements {
+        if element.action == TREE_ACTION_DROP {
+            continue;
+        }
+        match element.symbol {
+            SymbolRef::Variable(sid) if is_inlined_variable(grammar, sid) => {
+                let variable = grammar.get_variable(sid).unwrap();
+                if stack.contains(&sid) || variable.rules.len() != 1 {
+                    return None;
+                }
+                stack.push(sid);
+                let inlined = typed_sequence_of(grammar, &variable.rules[0].body.elements, stack);
+                stack.pop();
+                sequence.extend(inlined?);
+            }
+            SymbolRef::Terminal(_) | SymbolRef::Variable(_) | SymbolRef::Virtual(_) => sequence.push(element.symbol),
+            _ => {}
+        }
+    }
+    Some(sequence)
+}
+
+/// Gets the common exact sequence of children for alternative rule bodies, if any
+fn typed_sequence_merge(alternatives: &[Option<Vec<SymbolRef>>]) -> Option<Vec<SymbolRef>> {
+    let first = alternatives.first()?.as_ref()?;
+    alternatives
+        .iter()
+        .all(|alternative| alternative.as_ref() == Some(first))
+        .then(|| first.clone())
+}
+
+/// Gets whether a symbol has accessors in the typed AST
+fn is_typed_accessible(grammar: &Grammar, symbol: SymbolRef) -> bool {
+    match symbol {
+        SymbolRef::Terminal(_) => !get_symbol_name(grammar, symbol).starts_with(PREFIX_GENERATED_TERMINAL),
+        SymbolRef::Variable(_) => !get_symbol_name(grammar, symbol).starts_with(PREFIX_GENERATED_VARIABLE),
+        SymbolRef::Virtual(_) => true,
+        _ => false,
+    }
+}
+
+/// The alternative rule bodies for a typed AST node, as their children and exact sequences
+type TypedAlternatives = (Vec<Vec<TypedChild>>, Vec<Option<Vec<SymbolRef>>>);
+
+/// Gets the children and exact sequence for the elements of a rule body and adds them to alternatives
+fn typed_alternatives_add(grammar: &Grammar, alternatives: &mut TypedAlternatives, elements: &[RuleBodyElement]) {
+    alternatives
+        .0
+        .push(typed_children_of(grammar, elements, &mut Vec::new(), &mut Vec::new()));
+    alternatives.1.push(typed_sequence_of(grammar, elements, &mut Vec::new()));
+}
+
+/// A form of typed AST node, i.e. a node with a specific symbol
+#[derive(Debug, Clone)]
+struct TypedForm {
+    /// The name of the generated type
+    name: String,
+    /// The symbol of the node
+    symbol: SymbolRef,
+    /// The children of the node
+    children: Vec<TypedChild>,
+    /// The exact sequence of the children of the node, if any
+    sequence: Option<Vec<SymbolRef>>,
+}
+
+impl TypedForm {
+    /// Creates a form from its alternative rule bodies
+    fn new(name: String, symbol: SymbolRef, alternatives: &TypedAlternatives) -> TypedForm {
+        TypedForm {
+            name,
+            symbol,
+            children: typed_children_merge(&alternatives.0),
+            sequence: typed_sequence_merge(&alternatives.1),
+        }
+    }
+}
+
+/// Gets the name of a symbol in the grammar
+fn get_symbol_name(grammar: &Grammar, symbol: SymbolRef) -> &str {
+    match symbol {
+        SymbolRef::Terminal(sid) => grammar.get_terminal(sid).map_or("", |terminal| &terminal.name),
+        SymbolRef::Variable(sid) => grammar.get_variable(sid).map_or("", |variable| &variable.name),
+        SymbolRef::Virtual(sid) => grammar.get_virtual(sid).map_or("", |symbol| &symbol.name),
+        _ => "",
+    }
+}
+
+/// Gets the name of a symbol for the documentation of the typed AST
+fn get_symbol_doc_name(grammar: &Grammar, symbol: SymbolRef) -> &str {
+    match symbol {
+        SymbolRef::Terminal(sid) => grammar.get_terminal(sid).map_or("", |terminal| {
+            if terminal.name.starts_with(PREFIX_GENERATED_TERMINAL) {
+                &terminal.value
+            } else {
+                &terminal.name
+            }
+        }),
+        _ => get_symbol_name(grammar, symbol),
+    }
+}
+
+/// Gets the name of a typed form for a terminal promoted in the rules of a variable
+fn get_promoted_terminal_name(grammar: &Grammar, sid: usize) -> String {
+    let Some(terminal) = grammar.get_terminal(sid) else {
+        return format!("T{sid}");
+    };
+    if !terminal.name.starts_with(PREFIX_GENERATED_TERMINAL) {
+        return to_upper_camel_case(&terminal.name);
+    }
+    // name the anonymous terminal after its value, e.g. `'+='` becomes `PlusEq`
+    let value = terminal.value.trim_matches('\'');
+    if value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        let name = to_upper_camel_case(value);
+        if !name.is_empty() {
+            return name;
+        }
+    }
+    let mut name = String::new();
+    for c in value.chars() {
+        name.push_str(match c {
+            '+' => "Plus",
+            '-' => "Minus",
+            '*' => "Star",
+            '/' => "Slash",
+            '%' => "Percent",
+            '=' => "Eq",
+            '<' => "Lt",
+            '>' => "Gt",
+            '!' => "Bang",
+            '&' => "Amp",
+            '|' => "Pipe",
+            '^' => "Caret",
+            '~' => "Tilde",
+            '?' => "Question",
+            ':' => "Colon",
+            ';' => "Semi",
+            ',' => "Comma",
+            '.' => "Dot",
+            '(' => "LParen",
+            ')' => "RParen",
+            '[' => "LBracket",
+            ']' => "RBracket",
+            '{' => "LBrace",
+            '}' => "RBrace",
+            '@' => "At",
+            '#' => "Pound",
+            '$' => "Dollar",
+            _ if c.is_ascii_alphanumeric() => {
+                name.push(c.to_ascii_uppercase());
+                continue;
+            }
+            _ => "T",
+        });
+    }
+    name
+}
+
+/// Gets the name of the generated type for a variable
+fn get_typed_name(grammar: &Grammar, sid: usize) -> String {
+    to_upper_camel_case(get_symbol_name(grammar, SymbolRef::Variable(sid)))
+}
+
+/// Gets the typed forms of the nodes that can be produced for a variable
+/// The forms of promoted variables are flattened into those of the variable.
+fn get_typed_forms(grammar: &Grammar, sid: usize, visited: &mut Vec<usize>) -> Vec<TypedForm> {
+    let mut forms: Vec<TypedForm> = Vec::new();
+    if visited.contains(&sid) {
+        return forms;
+    }
+    visited.push(sid);
+    let variable = grammar.get_variable(sid).unwrap();
+    let mut plain = TypedAlternatives::default();
+    let mut promoted_terminals: Vec<(SymbolRef, TypedAlternatives)> = Vec::new();
+    let mut promoted_variables = Vec::new();
+    for rule in &variable.rules {
+        let elements = &rule.body.elements;
+        let Some(index) = elements.iter().rposition(|element| element.action == TREE_ACTION_PROMOTE) else {
+            typed_alternatives_add(grammar, &mut plain, elements);
+            continue;
+        };
+        match elements[index].symbol {
+            symbol @ (SymbolRef::Terminal(_) | SymbolRef::Virtual(_)) => {
+                let mut others = elements.clone();
+                others.remove(index);
+                let index = if let Some(index) = promoted_terminals.iter().position(|(s, _)| *s == symbol) {
+                    index
+                } else {
+                    promoted_terminals.push((symbol, TypedAlternatives::default()));
+                    promoted_terminals.len() - 1
+                };
+                typed_alternatives_add(grammar, &mut promoted_terminals[index].1, &others);
+            }
+            symbol @ SymbolRef::Variable(promoted)
+                if is_typed_accessible(grammar, symbol) && !promoted_variables.contains(&promoted) =>
+            {
+                promoted_variables.push(promoted);
+            }
+            _ => {}
+        }
+    }
+    let name = get_typed_name(grammar, sid);
+    let is_plain = promoted_terminals.is_empty() && promoted_variables.is_empty();
+    if !plain.0.is_empty() || is_plain {
+        forms.push(TypedForm::new(
+            if is_plain { name.clone() } else { format!("{name}Node") },
+            SymbolRef::Variable(sid),
+            &plain,
+        ));
+    }
+    for (symbol, alternatives) in &promoted_terminals {
+        let (SymbolRef::Terminal(terminal) | SymbolRef::Virtual(terminal)) = *symbol else {
+            continue;
+        };
+        let suffix = if matches!(symbol, SymbolRef::Terminal(_)) {
+            get_promoted_terminal_name(grammar, terminal)
+        } else {
+            to_upper_camel_case(get_symbol_name(grammar, *symbol))
+        };
+        forms.push(TypedForm::new(format!("{name}{suffix}"), *symbol, alternatives));
+    }
+    for promoted in promoted_variables {
+        for form in get_typed_forms(grammar, promoted, visited) {
+            if !forms.iter().any(|f| f.name == form.name) {
+                forms.push(form);
+            }
+        }
+    }
+    forms
+}
+
+/// Generates the code for the strongly typed AST
+fn write_code_typed_ast(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
+    let variables: Vec<&Variable> = grammar
+        .variables
+        .iter()
+        .filter(|variable| !variable.name.starts_with(PREFIX_GENERATED_VARIABLE))
+        .collect();
+    let mut structs: Vec<TypedForm> = Vec::new();
+    writeln!(writer)?;
+    writeln!(writer, "/// Strongly typed wrappers for the nodes of the AST")?;
+    writeln!(writer, "pub mod typed {{")?;
+    writeln!(writer, "    use hime_redist::ast::AstNode;")?;
+    writeln!(writer, "    use hime_redist::symbols::SemanticElementTrait;")?;
+    for variable in &variables {
+        let forms = get_typed_forms(grammar, variable.id, &mut Vec::new());
+        let name = get_typed_name(grammar, variable.id);
+        if forms.len() == 1 && forms[0].name == name {
+            structs.push(forms[0].clone());
+            continue;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "    /// The possible AST nodes for variable `{}`", &variable.name)?;
+        writeln!(writer, "    #[derive(Copy, Clone)]")?;
+        writeln!(writer, "    pub enum {name}<'s, 't, 'a> {{")?;
+        for form in &forms {
+            writeln!(writer, "        /// A `{}` node", get_symbol_doc_name(grammar, form.symbol))?;
+            writeln!(writer, "        {}({}<'s, 't, 'a>),", form.name, form.name)?;
+        }
+        writeln!(writer, "    }}")?;
+        writeln!(writer)?;
+        writeln!(writer, "    impl<'s, 't, 'a> {name}<'s, 't, 'a> {{")?;
+        writeln!(
+            writer,
+            "        /// Casts an AST node to this type, if it is one of the possible nodes for `{}`",
+            &variable.name
+        )?;
+        writeln!(writer, "        #[must_use]")?;
+        writeln!(writer, "        pub fn cast(node: AstNode<'s, 't, 'a>) -> Option<Self> {{")?;
+        for form in &forms {
+            writeln!(writer, "            if let Some(inner) = {}::cast(node) {{", form.name)?;
+            writeln!(writer, "                return Some({name}::{}(inner));", form.name)?;
+            writeln!(writer, "            }}")?;
+        }
+        writeln!(writer, "            None")?;
+        writeln!(writer, "        }}")?;
+        writeln!(writer)?;
+        writeln!(writer, "        /// Gets the underlying AST node")?;
+        writeln!(writer, "        #[must_use]")?;
+        writeln!(writer, "        pub fn node(&self) -> AstNode<'s, 't, 'a> {{")?;
+        writeln!(writer, "            match self {{")?;
+        for form in &forms {
+            writeln!(writer, "                {name}::{}(inner) => inner.node(),", form.name)?;
+        }
+        writeln!(writer, "            }}")?;
+        writeln!(writer, "        }}")?;
+        writeln!(writer, "    }}")?;
+        for form in forms {
+            if !structs.iter().any(|f| f.name == form.name)
+                && !variables.iter().any(|v| get_typed_name(grammar, v.id) == form.name)
+            {
+                structs.push(form);
+            }
+        }
+    }
+    for form in &structs {
+        write_code_typed_struct(writer, grammar, form)?;
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// Generates the code for a typed AST node
+fn write_code_typed_struct(writer: &mut dyn Write, grammar: &Grammar, form: &TypedForm) -> Result<(), Error> {
+    let name = &form.name;
+    let (SymbolRef::Terminal(sid) | SymbolRef::Variable(sid) | SymbolRef::Virtual(sid)) = form.symbol else {
+        return Ok(());
+    };
+    let symbol_name = get_symbol_doc_name(grammar, form.symbol);
+    writeln!(writer)?;
+    writeln!(writer, "    /// An AST node for `{symbol_name}`")?;
+    writeln!(writer, "    #[derive(Copy, Clone)]")?;
+    writeln!(writer, "    pub struct {name}<'s, 't, 'a>(AstNode<'s, 't, 'a>);")?;
+    writeln!(writer)?;
+    writeln!(writer, "    impl<'s, 't, 'a> {name}<'s, 't, 'a> {{")?;
+    writeln!(
+        writer,
+        "        /// Casts an AST node to this type, if it is a `{symbol_name}` node"
+    )?;
+    writeln!(writer, "        #[must_use]")?;
+    writeln!(writer, "        pub fn cast(node: AstNode<'s, 't, 'a>) -> Option<Self> {{")?;
+    writeln!(writer, "            if node.get_symbol().id == 0x{sid:04X} {{")?;
+    writeln!(writer, "                Some({name}(node))")?;
+    writeln!(writer, "            }} else {{")?;
+    writeln!(writer, "                None")?;
+    writeln!(writer, "            }}")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer)?;
+    writeln!(writer, "        /// Gets the underlying AST node")?;
+    writeln!(writer, "        #[must_use]")?;
+    writeln!(writer, "        pub fn node(&self) -> AstNode<'s, 't, 'a> {{")?;
+    writeln!(writer, "            self.0")?;
+    writeln!(writer, "        }}")?;
+    if let Some(sequence) = &form.sequence {
+        // the children are at fixed positions
+        for (index, &symbol) in sequence.iter().enumerate() {
+            if !is_typed_accessible(grammar, symbol) {
+                continue;
+            }
+            let child_name = get_symbol_name(grammar, symbol);
+            let occurrences = sequence.iter().filter(|&&s| s == symbol).count();
+            let accessor = if occurrences > 1 {
+                let rank = sequence[..=index].iter().filter(|&&s| s == symbol).count();
+                format!("get_{}_{rank}", to_snake_case(child_name))
+            } else {
+                format!("get_{}", to_snake_case(child_name))
+            };
+            let (child_type, cast) = get_typed_child_code(grammar, symbol, "and_then", "filter");
+            writeln!(writer)?;
+            writeln!(writer, "        /// Gets the `{child_name}` child at index {index}")?;
+            writeln!(writer, "        #[must_use]")?;
+            writeln!(writer, "        pub fn {accessor}(&self) -> Option<{child_type}> {{")?;
+            writeln!(writer, "            self.0.children().into_iter().nth({index}).{cast}")?;
+            writeln!(writer, "        }}")?;
+        }
+    } else {
+        // the children are found by their types
+        for child in &form.children {
+            if !is_typed_accessible(grammar, child.symbol) {
+                continue;
+            }
+            let child_name = get_symbol_name(grammar, child.symbol);
+            writeln!(writer)?;
+            if child.max <= 1 {
+                let (child_type, find) = get_typed_child_code(grammar, child.symbol, "find_map", "find");
+                writeln!(writer, "        /// Gets the `{child_name}` child, if any")?;
+                writeln!(writer, "        #[must_use]")?;
+                writeln!(
+                    writer,
+                    "        pub fn get_{}(&self) -> Option<{child_type}> {{",
+                    to_snake_case(child_name)
+                )?;
+                writeln!(writer, "            self.0.children().into_iter().{find}")?;
+            } else {
+                let (child_type, filter) = get_typed_child_code(grammar, child.symbol, "filter_map", "filter");
+                writeln!(writer, "        /// Gets the `{child_name}` children")?;
+                writeln!(
+                    writer,
+                    "        pub fn get_all_{}(&self) -> impl Iterator<Item = {child_type}> {{",
+                    to_snake_case(child_name)
+                )?;
+                writeln!(writer, "            self.0.children().into_iter().{filter}")?;
+            }
+            writeln!(writer, "        }}")?;
+        }
+    }
+    writeln!(writer, "    }}")?;
+    Ok(())
+}
+
+/// Gets the type of a typed AST child and the code to select it
+/// Variables are selected with the `on_typed` method and their cast function,
+/// other symbols with the `on_node` method and a predicate on their identifier.
+fn get_typed_child_code(grammar: &Grammar, symbol: SymbolRef, on_typed: &str, on_node: &str) -> (String, String) {
+    match symbol {
+        SymbolRef::Variable(sid) => {
+            let child_type = get_typed_name(grammar, sid);
+            (format!("{child_type}<'s, 't, 'a>"), format!("{on_typed}({child_type}::cast)"))
+        }
+        SymbolRef::Terminal(sid) | SymbolRef::Virtual(sid) => (
+            String::from("AstNode<'s, 't, 'a>"),
+            format!("{on_node}(|child| child.get_symbol().id == 0x{sid:04X})"),
+        ),
+        _ => (String::new(), String::new()),
+    }
+}
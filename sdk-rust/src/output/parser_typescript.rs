@@ -106,6 +106,7 @@ pub fn write(
     writeln!(writer, "export namespace {}Parser {{", &name)?;
 
     write_code_symbols(&mut writer, grammar)?;
+    write_code_labels(&mut writer, grammar)?;
     write_code_actions(&mut writer, grammar)?;
     write_code_visitor(&mut writer, grammar, expected)?;
 
@@ -158,6 +159,30 @@ fn write_code_symbols(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), E
     Ok(())
 }
 
+/// Generates the code for the labels
+fn write_code_labels(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
+    if grammar.labels.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "  /**")?;
+    writeln!(
+        writer,
+        "   * Contains the constant names for the labels of the children of nodes"
+    )?;
+    writeln!(writer, "   *")?;
+    writeln!(writer, "   * Hime.SDK {CRATE_VERSION}")?;
+    writeln!(writer, "   */")?;
+    writeln!(writer, "  export enum Label {{")?;
+    for label in &grammar.labels {
+        writeln!(writer, "    /**")?;
+        writeln!(writer, "     * The label {label}")?;
+        writeln!(writer, "     */")?;
+        writeln!(writer, "    {} = \"{label}\",", to_upper_camel_case(label))?;
+    }
+    writeln!(writer, "  }}")?;
+    Ok(())
+}
+
 /// Generates the code for the variables
 fn write_code_variables(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
     writeln!(writer, "  /**")?;
@@ -231,7 +256,12 @@ fn write_code_get_actions(writer: &mut dyn Write, grammar: &Grammar) -> Result<(
         &grammar.actions.len()
     )?;
     for (index, action) in grammar.actions.iter().enumerate() {
-        writeln!(writer, "    result[{}] = input.{}.bind(input)", index, to_lower_camel_case(&action.name))?;
+        writeln!(
+            writer,
+            "    result[{}] = input.{}.bind(input)",
+            index,
+            to_lower_camel_case(&action.name)
+        )?;
     }
     writeln!(writer, "    return result")?;
     writeln!(writer, "  }}")?;
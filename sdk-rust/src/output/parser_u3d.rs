@@ -96,6 +96,7 @@ pub fn write(
     writeln!(writer)?;
 
     write_code_symbols(&mut writer, grammar)?;
+    write_code_labels(&mut writer, grammar)?;
     write_code_variables(&mut writer, grammar)?;
     write_code_virtuals(&mut writer, grammar)?;
     write_code_actions(&mut writer, grammar)?;
@@ -148,6 +149,34 @@ fn write_code_symbols(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), E
     Ok(())
 }
 
+/// Generates the code for the labels
+fn write_code_labels(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
+    if grammar.labels.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "\t\t/// <summary>")?;
+    writeln!(
+        writer,
+        "\t\t/// Contains the constant names for the labels of the children of nodes"
+    )?;
+    writeln!(writer, "\t\t/// </summary>")?;
+    writeln!(writer, "\t\t[GeneratedCodeAttribute(\"Hime.SDK\", \"{CRATE_VERSION}\")]")?;
+    writeln!(writer, "\t\tpublic class Label")?;
+    writeln!(writer, "\t\t{{")?;
+    for label in &grammar.labels {
+        writeln!(writer, "\t\t\t/// <summary>")?;
+        writeln!(writer, "\t\t\t/// The label {label}")?;
+        writeln!(writer, "\t\t\t/// </summary>")?;
+        writeln!(
+            writer,
+            "\t\t\tpublic const string {} = \"{label}\";",
+            to_upper_camel_case(label)
+        )?;
+    }
+    writeln!(writer, "\t\t}}")?;
+    Ok(())
+}
+
 /// Generates the code for the variables
 fn write_code_variables(writer: &mut dyn Write, grammar: &Grammar) -> Result<(), Error> {
     writeln!(writer, "\t\t/// <summary>")?;
@@ -18,16 +18,20 @@
 //! Module for SDK utilities
 
 use hime_redist::ast::AstImpl;
+use hime_redist::budget::ParseBudget;
 use hime_redist::errors::ParseErrors;
 use hime_redist::lexers::automaton::Automaton;
 use hime_redist::lexers::impls::{ContextFreeLexer, ContextSensitiveLexer};
-use hime_redist::lexers::Lexer;
+use hime_redist::lexers::{ExternalScanner, Indentation, Lexer};
 use hime_redist::parsers::lrk::{LRkAutomaton, LRkParser};
+use hime_redist::parsers::push::PushParser;
 use hime_redist::parsers::rnglr::{RNGLRAutomaton, RNGLRParser};
-use hime_redist::parsers::Parser;
+use hime_redist::parsers::values::{SemanticValueAction, SemanticValues};
+use hime_redist::parsers::{LRCompletion, Parser};
 use hime_redist::result::ParseResult;
+use hime_redist::sppf::{SppfImpl, SppfImplNodeRef};
 use hime_redist::symbols::{SemanticBody, Symbol};
-use hime_redist::text::Text;
+use hime_redist::text::{Text, TextEdit, TextEditError};
 use hime_redist::tokens::TokenRepository;
 
 /// The automaton for a parser
@@ -50,12 +54,16 @@ pub struct InMemoryParser<'s> {
     pub variables: Vec<Symbol<'s>>,
     /// The virtuals
     pub virtuals: Vec<Symbol<'s>>,
+    /// The names of the entry points, the axiom first, then the additional ones
+    pub entries: Vec<&'s str>,
     /// The identifier of the separator terminal, if any
     pub separator: u32,
     /// The lexer's automaton
     pub lexer_automaton: Automaton,
     /// Whether the lexer is context-sensitive
     pub lexer_is_context_sensitive: bool,
+    /// The configuration of the indentation-sensitive lexer, if any
+    pub lexer_indentation: Option<Indentation>,
     /// The parser's automaton
     pub parser_automaton: ParserAutomaton,
 }
@@ -64,43 +72,251 @@ impl<'s> InMemoryParser<'s> {
     /// Parses an input parser
     #[must_use]
     pub fn parse<'a, 't>(&'a self, input: &'t str) -> ParseResult<'s, 't, 'a, AstImpl> {
+        let text = Text::from_str(input);
+        let result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        self.parse_into(result, 0)
+    }
+
+    /// Parses an input within a budget of steps, GSS nodes or SPPF nodes, or until it is cancelled
+    /// When the budget is exhausted, the parsing stops with an error and the result holds what was done so far.
+    #[must_use]
+    pub fn parse_with_budget<'a, 't>(&'a self, input: &'t str, budget: ParseBudget) -> ParseResult<'s, 't, 'a, AstImpl> {
         let text = Text::from_str(input);
         let mut result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
         let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
         {
             let data = result.get_parsing_data();
             let mut lexer = self.new_lexer(data.0, data.1);
-            self.do_parse(&mut lexer, data.2, &mut my_actions);
+            lexer.set_budget(budget);
+            self.do_parse(&mut lexer, data.2, &mut my_actions, 0);
         }
         result
     }
 
-    /// Execute the parser
-    fn do_parse<'a, 't>(
+    /// Parses an input, matching the external terminals with the specified scanner
+    #[must_use]
+    pub fn parse_with_scanner<'a, 't>(
         &'a self,
-        lexer: &'a mut Lexer<'s, 't, 'a>,
-        ast: &'a mut AstImpl,
-        actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
-    ) {
-        let mut parser: Box<dyn Parser> = match &self.parser_automaton {
-            ParserAutomaton::Lrk(automaton) => Box::new(LRkParser::new(
-                lexer,
+        input: &'t str,
+        scanner: &mut dyn ExternalScanner,
+    ) -> ParseResult<'s, 't, 'a, AstImpl> {
+        let text = Text::from_str(input);
+        let mut result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        {
+            let data = result.get_parsing_data();
+            let mut lexer = self.new_lexer(data.0, data.1);
+            lexer.set_scanner(scanner);
+            self.do_parse(&mut lexer, data.2, &mut my_actions, 0);
+        }
+        result
+    }
+
+    /// Parses an input from the specified entry point (variable) instead of the axiom
+    /// Returns `None` when the variable is not an entry point of the grammar.
+    #[must_use]
+    pub fn parse_from<'a, 't>(&'a self, variable: &str, input: &'t str) -> Option<ParseResult<'s, 't, 'a, AstImpl>> {
+        let entry = self.entries.iter().position(|name| *name == variable)?;
+        let text = Text::from_str(input);
+        let result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        Some(self.parse_into(result, entry))
+    }
+
+    /// Parses the specified input, keeping the separators as trivia
+    /// For LR(k) parsers, the concrete syntax tree of the input is also built.
+    #[must_use]
+    pub fn parse_lossless<'a, 't>(&'a self, input: &'t str) -> ParseResult<'s, 't, 'a, AstImpl> {
+        let text = Text::from_str(input);
+        let result = ParseResult::<AstImpl>::new_lossless(&self.terminals, &self.variables, &self.virtuals, text);
+        self.parse_into(result, 0)
+    }
+
+    /// Parses again the input of a previous result after some edits, reusing what the edits do not affect
+    /// The spans of the edits refer to the previous input and must not overlap.
+    ///
+    /// # Errors
+    ///
+    /// Return a `TextEditError` when the edits are not valid for the previous input
+    pub fn reparse<'a>(
+        &'a self,
+        previous: ParseResult<'s, '_, 'a, AstImpl>,
+        edits: &[TextEdit],
+    ) -> Result<ParseResult<'s, 'static, 'a, AstImpl>, TextEditError> {
+        Ok(self.parse_into(previous.apply_edits(edits)?, 0))
+    }
+
+    /// Gets the symbols expected at the specified index in an input, for code completion
+    /// Only the tokens that end at or before the index are parsed.
+    #[must_use]
+    pub fn get_expected_at(&self, input: &str, index: usize) -> LRCompletion<'s> {
+        let text = Text::from_str(input);
+        let mut result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        let data = result.get_parsing_data();
+        let mut lexer = self.new_lexer(data.0, data.1);
+        match &self.parser_automaton {
+            ParserAutomaton::Lrk(automaton) => LRkParser::new(
+                &mut lexer,
                 &self.variables,
                 &self.virtuals,
                 automaton.clone(),
-                ast,
-                actions,
-            )),
-            ParserAutomaton::Rnglr(automaton) => Box::new(RNGLRParser::new_with_ast(
-                lexer,
+                data.2,
+                &mut my_actions,
+            )
+            .get_expected_at(index),
+            ParserAutomaton::Rnglr(automaton) => RNGLRParser::new_with_ast(
+                &mut lexer,
+                &self.variables,
+                &self.virtuals,
+                automaton.clone(),
+                data.2,
+                &mut my_actions,
+            )
+            .get_expected_at(index),
+        }
+    }
+
+    /// Creates a push parser, to be fed with the input piece by piece
+    #[must_use]
+    pub fn push_parser<'a>(&'a self) -> PushParser<'s, 'a> {
+        PushParser::new(
+            &self.terminals,
+            &self.variables,
+            &self.virtuals,
+            self.lexer_automaton.clone(),
+            |result| self.parse_into(result, 0),
+        )
+    }
+
+    /// Parses an input, synthesizing values with the given semantic actions
+    /// The value synthesized for the axiom is returned when the parsing is successful.
+    /// Only LR(k) parsers synthesize values, RNGLR parsers execute no semantic action in this case.
+    pub fn parse_with_values<'a, 't, V>(
+        &'a self,
+        input: &'t str,
+        actions: &mut SemanticValueAction<V>,
+    ) -> (ParseResult<'s, 't, 'a, AstImpl>, Option<V>) {
+        let text = Text::from_str(input);
+        let mut result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        let mut values = SemanticValues::new(actions);
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        {
+            let data = result.get_parsing_data();
+            let mut lexer = self.new_lexer(data.0, data.1);
+            match &self.parser_automaton {
+                ParserAutomaton::Lrk(automaton) => LRkParser::new(
+                    &mut lexer,
+                    &self.variables,
+                    &self.virtuals,
+                    automaton.clone(),
+                    data.2,
+                    &mut my_actions,
+                )
+                .with_values(&mut values)
+                .parse(),
+                ParserAutomaton::Rnglr(_) => self.do_parse(&mut lexer, data.2, &mut my_actions, 0),
+            }
+        }
+        let value = if result.is_success() { values.into_value() } else { None };
+        (result, value)
+    }
+
+    /// Parses an input, resolving the ambiguities left by the productions' annotations with the given function
+    /// The function is given the SPPF, the ambiguous node and the indices of its remaining versions,
+    /// and returns the index of the chosen version.
+    /// Only RNGLR parsers produce ambiguities, LR(k) parsers never call the function.
+    pub fn parse_with_resolver<'a, 't>(
+        &'a self,
+        input: &'t str,
+        resolver: impl FnMut(&SppfImpl, SppfImplNodeRef, &[usize]) -> usize,
+    ) -> ParseResult<'s, 't, 'a, AstImpl> {
+        let text = Text::from_str(input);
+        let mut result = ParseResult::<AstImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        {
+            let data = result.get_parsing_data();
+            let mut lexer = self.new_lexer(data.0, data.1);
+            match &self.parser_automaton {
+                ParserAutomaton::Lrk(_) => self.do_parse(&mut lexer, data.2, &mut my_actions, 0),
+                ParserAutomaton::Rnglr(automaton) => RNGLRParser::new_with_ast(
+                    &mut lexer,
+                    &self.variables,
+                    &self.virtuals,
+                    automaton.clone(),
+                    data.2,
+                    &mut my_actions,
+                )
+                .with_resolver(resolver)
+                .parse(),
+            }
+        }
+        result
+    }
+
+    /// Parses an input into a SPPF keeping all the possible trees
+    /// Returns `None` for LR(k) parsers that do not produce a SPPF.
+    #[must_use]
+    pub fn parse_to_sppf<'a, 't>(&'a self, input: &'t str) -> Option<ParseResult<'s, 't, 'a, SppfImpl>> {
+        let ParserAutomaton::Rnglr(automaton) = &self.parser_automaton else {
+            return None;
+        };
+        let text = Text::from_str(input);
+        let mut result = ParseResult::<SppfImpl>::new(&self.terminals, &self.variables, &self.virtuals, text);
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        {
+            let data = result.get_parsing_data();
+            let mut lexer = self.new_lexer(data.0, data.1);
+            RNGLRParser::new_with_sppf(
+                &mut lexer,
                 &self.variables,
                 &self.virtuals,
                 automaton.clone(),
-                ast,
-                actions,
-            )),
+                data.2,
+                &mut my_actions,
+            )
+            .parse();
+        }
+        Some(result)
+    }
+
+    /// Parses the input of the specified result into it, from the specified entry point
+    fn parse_into<'a, 't>(
+        &'a self,
+        mut result: ParseResult<'s, 't, 'a, AstImpl>,
+        entry: usize,
+    ) -> ParseResult<'s, 't, 'a, AstImpl> {
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        {
+            let data = result.get_parsing_data();
+            let mut lexer = self.new_lexer(data.0, data.1);
+            self.do_parse(&mut lexer, data.2, &mut my_actions, entry);
+        }
+        result
+    }
+
+    /// Execute the parser from the specified entry point
+    fn do_parse<'a, 't>(
+        &'a self,
+        lexer: &'a mut Lexer<'s, 't, 'a>,
+        ast: &'a mut AstImpl,
+        actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+        entry: usize,
+    ) {
+        let parser: Option<Box<dyn Parser>> = match &self.parser_automaton {
+            ParserAutomaton::Lrk(automaton) => {
+                LRkParser::new(lexer, &self.variables, &self.virtuals, automaton.clone(), ast, actions)
+                    .with_entry(entry)
+                    .map(|parser| Box::new(parser) as Box<dyn Parser>)
+            }
+            ParserAutomaton::Rnglr(automaton) => {
+                RNGLRParser::new_with_ast(lexer, &self.variables, &self.virtuals, automaton.clone(), ast, actions)
+                    .with_entry(entry)
+                    .map(|parser| Box::new(parser) as Box<dyn Parser>)
+            }
         };
-        parser.parse();
+        if let Some(mut parser) = parser {
+            parser.parse();
+        }
     }
 
     /// Creates a new lexer
@@ -109,7 +325,7 @@ impl<'s> InMemoryParser<'s> {
         repository: TokenRepository<'s, 't, 'a>,
         errors: &'a mut ParseErrors<'s>,
     ) -> Lexer<'s, 't, 'a> {
-        if self.lexer_is_context_sensitive {
+        let mut lexer = if self.lexer_is_context_sensitive {
             Lexer::ContextSensitive(ContextSensitiveLexer::new(
                 repository,
                 errors,
@@ -123,6 +339,10 @@ impl<'s> InMemoryParser<'s> {
                 self.lexer_automaton.clone(),
                 self.separator,
             ))
+        };
+        if let Some(indentation) = self.lexer_indentation {
+            lexer.set_indentation(indentation);
         }
+        lexer
     }
 }
@@ -1,8 +1,111 @@
 use std::borrow::BorrowMut;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
+use hime_redist::ast::{AstImpl, AstNode};
+use hime_redist::budget::{BudgetExhaustion, ParseBudget};
+use hime_redist::errors::{ParseError, ParseErrorDataTrait};
+use hime_redist::lexers::{ExternalMatch, ExternalScanner};
+use hime_redist::parsers::push::PushStatus;
+use hime_redist::result::ParseResult;
+use hime_redist::symbols::{SemanticBody, SemanticElementTrait, Symbol};
+use hime_redist::text::{Text, TextEdit, TextEditError, TextPosition, TextSpan};
+use hime_redist::tokens::TokenRepository;
+use hime_sdk::errors::{Error, Warning};
+use hime_sdk::grammars::Grammar;
+use hime_sdk::lr::{ConflictKind, Counterexample, Derivation};
 use hime_sdk::output::helper::{get_namespace_java, get_namespace_net, get_namespace_rust};
+use hime_sdk::sdk::InMemoryParser;
 use hime_sdk::ParsingMethod;
 
+/// Loads a single grammar from its text
+fn load_grammar(text_grammar: &str) -> Grammar {
+    let inputs = vec![hime_sdk::Input::Raw(text_grammar)];
+    let mut data = hime_sdk::loaders::load_inputs(&inputs).expect("Failed to load the grammar");
+    data.grammars.remove(0)
+}
+
+/// Builds a single grammar with a parsing method and its in-memory parser
+/// The grammar is leaked so that the parser may borrow it for the rest of the test.
+fn build_grammar_and_parser(text_grammar: &str, method: ParsingMethod) -> (&'static Grammar, InMemoryParser<'static>) {
+    let grammar = Box::leak(Box::new(load_grammar(text_grammar)));
+    let data = grammar.build(Some(method), 0).expect("Failed to build the grammar");
+    let grammar: &'static Grammar = grammar;
+    let parser = hime_sdk::output::build_in_memory_grammar(grammar, &data).expect("Failed to build the parser");
+    (grammar, parser)
+}
+
+/// Builds a single grammar with a parsing method and gets its in-memory parser
+fn build_parser(text_grammar: &str, method: ParsingMethod) -> InMemoryParser<'static> {
+    build_grammar_and_parser(text_grammar, method).1
+}
+
+/// A temporary directory for the outputs of a test
+/// It is removed when dropped, so that it is also removed when the test fails.
+struct TempDir {
+    /// The path to the directory
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new temporary directory, unique to the test and its process
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("hime-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    /// Gets the number of entries in the directory
+    fn count_entries(&self) -> usize {
+        std::fs::read_dir(&self.path).unwrap().count()
+    }
+
+    /// Reads a file in the directory
+    fn read(&self, name: &str) -> String {
+        std::fs::read_to_string(self.path.join(name)).unwrap()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Executes a compilation task with its outputs in a new temporary directory
+/// Gets the directory, with the errors of the compilation, if any.
+fn execute_in_temp_dir(name: &str, task: &mut hime_sdk::CompilationTask) -> (TempDir, Result<(), Vec<Error>>) {
+    let output = TempDir::new(name);
+    task.output_path = Some(output.path.to_str().unwrap().to_string());
+    let result = task.execute().map(|_| ()).map_err(|errors| errors.errors);
+    (output, result)
+}
+
+/// A grammar for a simple list of statements
+const GRAMMAR_STATEMENTS: &str = r#"
+grammar Statements
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> (' ' | '\n')+;
+        ID -> [a-z]+;
+    }
+    rules
+    {
+        file -> stmt*;
+        stmt -> ID '=' ID ';'
+              | '{' stmt* '}';
+    }
+}
+"#;
+
 /// [Github issue #79](https://github.com/cenotelie/hime/issues/79)
 #[test]
 fn test_namespace_transformation() {
@@ -52,3 +155,1880 @@ pub fn test_in_memory_parser() {
         }
     }
 }
+
+/// Recovery of multiple errors in a LR(k) parser
+#[test]
+fn test_lrk_error_recovery() {
+    let mut grammar = load_grammar(GRAMMAR_STATEMENTS);
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+
+    // a missing terminal is inserted, an unexpected one is deleted
+    let result = parser.parse("a = b; c = ; d = e f = g; h = = i;");
+    assert_eq!(result.errors.errors.len(), 3);
+    assert!(!result.is_success());
+    assert!(result.has_tree());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.children_count(), 5);
+    let inserted = root.child(1).child(2);
+    assert_eq!(inserted.get_symbol().name, "ID");
+    assert_eq!(inserted.get_value(), None);
+
+    // missing terminals at the end of the input are completed
+    let result = parser.parse("a = b; { { c = d;");
+    assert_eq!(result.errors.errors.len(), 1);
+    assert!(!result.is_success());
+    assert!(result.has_tree());
+    let ast = result.get_ast();
+    let block = ast.get_root().child(1);
+    assert_eq!(block.child(2).get_symbol().name, "}");
+    assert_eq!(block.child(1).child(2).get_symbol().name, "}");
+}
+
+/// Recovery of multiple errors in a RNGLR parser, with error nodes
+#[test]
+fn test_rnglr_error_recovery() {
+    let mut grammar = load_grammar(GRAMMAR_STATEMENTS);
+    let data = grammar
+        .build(Some(ParsingMethod::RNGLALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+
+    // a missing terminal is inserted, an unexpected one is deleted
+    let result = parser.parse("a = b; c = ; d = e f = g; h = = i;");
+    assert_eq!(result.errors.errors.len(), 3);
+    assert!(!result.is_success());
+    assert!(result.has_tree());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.children_count(), 5);
+    let inserted = root.child(1).child(2);
+    assert_eq!(inserted.get_symbol().name, "ID");
+    assert_eq!(inserted.get_value(), None);
+    let error = root.child(4).child(2);
+    assert_eq!(error.get_symbol().name, "error");
+    assert_eq!(error.children_count(), 1);
+    assert_eq!(error.child(0).get_value(), Some("="));
+
+    // skipped tokens are kept in error nodes
+    let result = parser.parse("a = b; c d e f; g = h;");
+    assert_eq!(result.errors.errors.len(), 2);
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.children_count(), 3);
+    let stmt = root.child(1);
+    assert_eq!(stmt.child(1).get_symbol().name, "error");
+    assert_eq!(stmt.child(1).child(0).get_value(), Some("d"));
+    assert_eq!(stmt.child(4).get_symbol().name, "error");
+    assert_eq!(stmt.child(4).child(0).get_value(), Some("f"));
+    let span = stmt.child(4).get_total_span().unwrap();
+    assert_eq!((span.index, span.length), (13, 1));
+
+    // missing terminals at the end of the input are completed
+    let result = parser.parse("a = b; { { c = d;");
+    assert_eq!(result.errors.errors.len(), 1);
+    assert!(!result.is_success());
+    assert!(result.has_tree());
+    let ast = result.get_ast();
+    let block = ast.get_root().child(1);
+    assert_eq!(block.child(2).get_symbol().name, "}");
+    assert_eq!(block.child(1).child(2).get_symbol().name, "}");
+}
+
+/// A grammar for arithmetic expressions using precedences
+const GRAMMAR_PRECEDENCES: &str = r#"
+grammar Expressions
+{
+    options
+    {
+        Axiom = "exp";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        NUM -> [0-9]+;
+    }
+    precedence
+    {
+        nonassoc '<';
+        left '+' '-';
+        left '*' '/';
+        right '^';
+        right neg;
+    }
+    rules
+    {
+        exp -> exp '<' exp
+             | exp '+' exp
+             | exp '-' exp
+             | exp '*' exp
+             | exp '/' exp
+             | exp '^' exp
+             | neg
+             | '(' exp ')'
+             | NUM;
+        neg -> '-' exp;
+    }
+}
+"#;
+
+/// Resolution of shift/reduce conflicts with precedences and associativities
+#[test]
+fn test_precedences() {
+    for method in [ParsingMethod::LALR1, ParsingMethod::RNGLALR1] {
+        let mut grammar = load_grammar(GRAMMAR_PRECEDENCES);
+        let data = grammar.build(Some(method), 0).expect("Failed to build the grammar");
+        assert!(data.graph.get_states().iter().any(|state| !state.resolved.is_empty()));
+        let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+
+        // higher precedence binds tighter
+        let result = parser.parse("1 + 2 * 3");
+        assert!(result.errors.errors.is_empty());
+        let ast = result.get_ast();
+        let root = ast.get_root();
+        assert_eq!(root.child(1).get_value(), Some("+"));
+        assert_eq!(root.child(2).child(1).get_value(), Some("*"));
+
+        // left associativity
+        let result = parser.parse("1 - 2 - 3");
+        assert!(result.errors.errors.is_empty());
+        let ast = result.get_ast();
+        let root = ast.get_root();
+        assert_eq!(root.child(0).child(1).get_value(), Some("-"));
+        assert_eq!(root.child(2).child(0).get_value(), Some("3"));
+
+        // right associativity
+        let result = parser.parse("1 ^ 2 ^ 3");
+        assert!(result.errors.errors.is_empty());
+        let ast = result.get_ast();
+        let root = ast.get_root();
+        assert_eq!(root.child(0).child(0).get_value(), Some("1"));
+        assert_eq!(root.child(2).child(1).get_value(), Some("^"));
+
+        // precedence of a rule given by its head
+        let result = parser.parse("- 1 + 2");
+        assert!(result.errors.errors.is_empty());
+        let ast = result.get_ast();
+        let root = ast.get_root();
+        assert_eq!(root.child(0).child(0).get_symbol().name, "neg");
+        assert_eq!(root.child(1).get_value(), Some("+"));
+
+        // non-associative operators cannot be chained
+        assert!(parser.parse("1 < 2").errors.errors.is_empty());
+        assert!(!parser.parse("1 < 2 < 3").errors.errors.is_empty());
+    }
+}
+
+/// Precedences cannot resolve the conflicts of LR(0) reductions, which have no lookahead
+#[test]
+fn test_precedences_lr0() {
+    let mut grammar = load_grammar(GRAMMAR_PRECEDENCES);
+    let errors = grammar.build(Some(ParsingMethod::LR0), 0).err().unwrap();
+    assert!(errors.iter().any(|error| matches!(error, Error::PrecedencesNotSupported(_))));
+}
+
+/// Undeclared symbols in precedence levels are reported
+#[test]
+fn test_precedences_unknown_symbol() {
+    let text = GRAMMAR_PRECEDENCES.replace("right neg;", "right unknown;");
+    let inputs = vec![hime_sdk::Input::Raw(&text)];
+    assert!(hime_sdk::loaders::load_inputs(&inputs).is_err());
+}
+
+/// The precedence keyword remains usable as a symbol name
+#[test]
+fn test_precedence_as_name() {
+    let text = GRAMMAR_PRECEDENCES
+        .replace("right neg;", "right precedence;")
+        .replace("| neg", "| precedence")
+        .replace("neg ->", "precedence ->");
+    let mut grammar = load_grammar(&text);
+    assert!(grammar.build(Some(ParsingMethod::LALR1), 0).is_ok());
+}
+
+/// Gets the counterexamples for the conflicts when building a grammar
+fn get_counterexamples(text_grammar: &str) -> (Grammar, Vec<(ConflictKind, Counterexample)>) {
+    let mut grammar = load_grammar(text_grammar);
+    let errors = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect_err("The grammar should have conflicts");
+    let counterexamples = errors
+        .into_iter()
+        .filter_map(|error| match error {
+            Error::LrConflict(_, conflict) => Some((conflict.kind, conflict.counterexample.unwrap())),
+            _ => None,
+        })
+        .collect();
+    (grammar, counterexamples)
+}
+
+/// Gets the symbols produced by a derivation
+fn get_example(grammar: &Grammar, derivation: &Derivation) -> String {
+    let (symbols, dot) = derivation.get_frontier();
+    let mut parts: Vec<&str> = symbols.iter().map(|symbol| grammar.get_symbol_value(*symbol)).collect();
+    parts.insert(dot, "•");
+    parts.join(" ")
+}
+
+/// Unifying counterexamples for ambiguous grammars
+#[test]
+fn test_counterexamples_unifying() {
+    let (grammar, counterexamples) = get_counterexamples(
+        r#"
+        grammar Test
+        {
+            options { Axiom = "s"; Separator = "SEPARATOR"; }
+            terminals { SEPARATOR -> ' '+; ID -> [a-z]+; }
+            rules
+            {
+                s -> 'if' ID 'then' s | 'if' ID 'then' s 'else' s | ID;
+            }
+        }
+        "#,
+    );
+    assert_eq!(counterexamples.len(), 1);
+    let (kind, counterexample) = &counterexamples[0];
+    assert_eq!(*kind, ConflictKind::ShiftReduce);
+    assert!(counterexample.unifying);
+    assert_ne!(counterexample.first, counterexample.second);
+    let example = get_example(&grammar, &counterexample.first);
+    assert_eq!(example, "if ID then if ID then s • else s $");
+    assert_eq!(get_example(&grammar, &counterexample.second), example);
+}
+
+/// Non-unifying counterexamples for grammars that are not ambiguous
+#[test]
+fn test_counterexamples_non_unifying() {
+    let (grammar, counterexamples) = get_counterexamples(
+        r#"
+        grammar Test
+        {
+            options { Axiom = "s"; Separator = "SEPARATOR"; }
+            terminals { SEPARATOR -> ' '+; }
+            rules
+            {
+                s -> a 'x' 'y' | b 'x' 'z';
+                a -> '0';
+                b -> '0';
+            }
+        }
+        "#,
+    );
+    assert_eq!(counterexamples.len(), 1);
+    let (kind, counterexample) = &counterexamples[0];
+    assert_eq!(*kind, ConflictKind::ReduceReduce);
+    assert!(!counterexample.unifying);
+    assert_eq!(get_example(&grammar, &counterexample.first), "0 • x y $");
+    assert_eq!(get_example(&grammar, &counterexample.second), "0 • x z $");
+}
+
+/// A grammar that is LR(1) but not LALR(1)
+/// The canonical LR(1) states for `g` are duplicated for each lookahead, but they can be merged without conflict.
+const GRAMMAR_LR1: &str = r#"
+grammar Lr1
+{
+    options
+    {
+        Axiom = "s";
+    }
+    terminals {}
+    rules
+    {
+        s -> 'a' e 'c' | 'a' f 'd' | 'b' f 'c' | 'b' e 'd' | 'x' g 'c' | 'y' g 'd';
+        e -> 'e';
+        f -> 'e';
+        g -> 'g' 'g';
+    }
+}
+"#;
+
+/// Minimal LR(1) graphs keep the LR(1) power with fewer states
+#[test]
+fn test_minimal_lr1() {
+    let mut grammar = load_grammar(GRAMMAR_LR1);
+    assert!(grammar.build(Some(ParsingMethod::LALR1), 0).is_err());
+    let lr1 = grammar
+        .build(Some(ParsingMethod::LR1), 0)
+        .expect("Failed to build the grammar");
+    let data = grammar
+        .build(Some(ParsingMethod::MinLR1), 0)
+        .expect("Failed to build the grammar");
+    assert!(data.graph.get_states().len() < lr1.graph.get_states().len());
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    for input in ["aec", "aed", "bec", "bed", "xggc", "yggd"] {
+        assert!(parser.parse(input).errors.errors.is_empty());
+    }
+    assert!(!parser.parse("aee").errors.errors.is_empty());
+
+    let data = grammar
+        .build(Some(ParsingMethod::RNGMinLR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    assert!(parser.parse("bed").errors.errors.is_empty());
+    assert!(!parser.parse("bee").errors.errors.is_empty());
+}
+
+/// Generates a large expression grammar where each kind of statement has its own terminator
+/// The canonical LR(1) graph duplicates the states for the expressions for each terminator
+fn generate_large_grammar(levels: usize, statements: usize) -> String {
+    let mut rules = vec![String::from("file -> stmt* ;")];
+    let choices: Vec<String> = (0..statements).map(|i| format!("'k{i}' e0 't{i}'")).collect();
+    rules.push(format!("stmt -> {} ;", choices.join(" | ")));
+    for i in 0..levels {
+        rules.push(format!("e{i} -> e{i} 'o{i}' e{} | e{} ;", i + 1, i + 1));
+    }
+    rules.push(format!("e{levels} -> ID | NUM | '(' e0 ')' ;"));
+    format!(
+        "grammar Large {{
+            options {{ Axiom = \"file\"; Separator = \"SEPARATOR\"; }}
+            terminals {{ SEPARATOR -> ' '+; ID -> [a-z]+; NUM -> [0-9]+; }}
+            rules {{ {} }}
+        }}",
+        rules.join("\n")
+    )
+}
+
+/// Builds the LR(1) and RNGLR(1) graphs for a large grammar
+/// Returns the number of states
+fn build_large_grammar(levels: usize, statements: usize) -> usize {
+    let mut grammar = load_grammar(&generate_large_grammar(levels, statements));
+    let data = grammar
+        .build(Some(ParsingMethod::LR1), 0)
+        .expect("Failed to build the grammar");
+    let count = data.graph.get_states().len();
+    // the states are indexed by their kernels
+    for (index, state) in data.graph.get_states().iter().enumerate() {
+        assert_eq!(data.graph.get_state_for(&state.kernel), Some(index));
+    }
+    let data = grammar
+        .build(Some(ParsingMethod::RNGLR1), 0)
+        .expect("Failed to build the grammar");
+    assert_eq!(data.graph.get_states().len(), count);
+    count
+}
+
+/// Construction of the canonical and minimal LR(1) graphs for a large grammar
+#[test]
+fn test_large_grammar_lr1() {
+    assert_eq!(build_large_grammar(10, 20), 801);
+    // the minimal LR(1) graph merges the states duplicated for each terminator, as the LALR(1) graph does
+    let mut grammar = load_grammar(&generate_large_grammar(10, 20));
+    for method in [ParsingMethod::MinLR1, ParsingMethod::LALR1] {
+        let data = grammar.build(Some(method), 0).expect("Failed to build the grammar");
+        assert_eq!(data.graph.get_states().len(), 101);
+    }
+}
+
+/// Benchmark of the construction of the canonical LR(1) graph for large grammars, run with `--ignored`
+/// The time of the construction grows almost linearly with the number of states, about 6 times for 4 times the states,
+/// where it used to be quadratic, about 20 times.
+#[test]
+#[ignore]
+fn bench_large_grammar_lr1() {
+    let time = |statements: usize| {
+        let mut grammar = load_grammar(&generate_large_grammar(10, statements));
+        let start = std::time::Instant::now();
+        let data = grammar
+            .build(Some(ParsingMethod::LR1), 0)
+            .expect("Failed to build the grammar");
+        let elapsed = start.elapsed();
+        println!("{} states in {elapsed:?}", data.graph.get_states().len());
+        elapsed
+    };
+    let small = time(40);
+    // four times as many states
+    let large = time(160);
+    assert!(large < small * 10, "{large:?} for 4 times the states built in {small:?}");
+}
+
+/// Warnings are reported for unused terminals, unreachable and unproductive variables
+#[test]
+fn test_grammar_warnings() {
+    let mut grammar = load_grammar(
+        r#"
+        grammar Warnings
+        {
+            options
+            {
+                Axiom = "s";
+                Separator = "SEPARATOR";
+            }
+            terminals
+            {
+                SEPARATOR -> ' '+;
+                fragment DIGIT -> [0-9];
+                NUMBER -> DIGIT+;
+                UNUSED -> 'u';
+            }
+            rules
+            {
+                s -> NUMBER | looping;
+                looping -> '(' looping ')';
+                orphan -> NUMBER;
+            }
+        }
+        "#,
+    );
+    let data = grammar.build(None, 0).expect("Failed to build the grammar");
+    let names: Vec<String> = data
+        .warnings
+        .iter()
+        .map(|warning| match warning {
+            Warning::UnusedTerminal(_, terminal) => format!("unused {}", grammar.get_terminal(terminal.sid()).unwrap().name),
+            Warning::UnreachableVariable(_, id) => format!("unreachable {}", grammar.get_variable(*id).unwrap().name),
+            Warning::UnproductiveVariable(_, id) => format!("unproductive {}", grammar.get_variable(*id).unwrap().name),
+        })
+        .collect();
+    assert_eq!(names, vec!["unused UNUSED", "unreachable orphan", "unproductive looping"]);
+}
+
+/// Denied warnings fail the compilation before any output is written
+#[test]
+fn test_deny_warnings() {
+    let mut task = hime_sdk::CompilationTask {
+        inputs: vec![hime_sdk::Input::Raw(
+            r#"
+            grammar Warnings
+            {
+                options { Axiom = "s"; Separator = "SEPARATOR"; }
+                terminals { SEPARATOR -> ' '+; NUMBER -> [0-9]+; UNUSED -> 'u'; }
+                rules { s -> NUMBER; }
+            }
+            "#,
+        )],
+        output_target: Some(hime_sdk::Runtime::Rust),
+        deny_warnings: Some(true),
+        ..Default::default()
+    };
+    let (output, result) = execute_in_temp_dir("deny-warnings", &mut task);
+    assert!(result.is_err());
+    assert_eq!(output.count_entries(), 0);
+    // in-memory parsers are denied as well
+    let mut data = task.load().expect("Failed to load the grammar");
+    assert!(task.generate_in_memory(&mut data.grammars[0], 0).is_err());
+    task.deny_warnings = None;
+    assert!(task.generate_in_memory(&mut data.grammars[0], 0).is_ok());
+}
+
+/// Gets a textual representation of a parse result with its tokens, tree and errors
+fn dump_result(result: &ParseResult<AstImpl>) -> String {
+    fn dump_node(node: AstNode, output: &mut String) {
+        write!(output, "{}", node.get_symbol().name).unwrap();
+        if let (Some(value), Some(span)) = (node.get_value(), node.get_span()) {
+            write!(output, "={value:?}{span}").unwrap();
+        }
+        if node.children_count() > 0 {
+            output.push('(');
+            for child in node.children() {
+                dump_node(child, output);
+                output.push(' ');
+            }
+            output.push(')');
+        }
+    }
+
+    let mut output = String::new();
+    for token in &result.get_tokens() {
+        write!(output, "{}{} ", token.get_symbol().name, token.get_span().unwrap()).unwrap();
+    }
+    output.push('\n');
+    if result.has_tree() {
+        dump_node(result.get_ast().get_root(), &mut output);
+    }
+    output.push('\n');
+    for error in &result.errors.errors {
+        writeln!(output, "{error}").unwrap();
+    }
+    output
+}
+
+/// Gets the names of the terminals and the values of the tokens in a parse result, the final `$` included
+fn token_pairs<'r>(result: &'r ParseResult<AstImpl>) -> Vec<(&'r str, &'r str)> {
+    let tokens = result.get_tokens();
+    (0..tokens.get_tokens_count())
+        .map(|index| {
+            let token = tokens.get_token(index);
+            let span = token.get_span().unwrap();
+            (token.get_symbol().name, result.text.get_value(span.index, span.length))
+        })
+        .collect()
+}
+
+/// Checks that parsing again after each batch of edits yields the same result as a full parse
+/// Each edit replaces the first occurrence of a piece of text in the current input.
+/// Gets the numbers of reused tokens and sub-trees for each batch.
+fn check_reparse(parser: &InMemoryParser, input: &str, batches: &[&[(&str, &str)]]) -> Vec<(usize, usize)> {
+    let mut text = input.to_string();
+    let mut result = parser.reparse(parser.parse(input), &[]).unwrap();
+    assert_eq!(dump_result(&result), dump_result(&parser.parse(&text)));
+    let mut reused = Vec::new();
+    for &batch in batches {
+        let edits: Vec<TextEdit> = batch
+            .iter()
+            .map(|(old, new)| TextEdit::new(text.find(old).unwrap(), old.len(), new))
+            .collect();
+        let mut sorted = edits.clone();
+        sorted.sort_by_key(|edit| edit.span);
+        for edit in sorted.iter().rev() {
+            text.replace_range(edit.span.index..edit.span.index + edit.span.length, &edit.value);
+        }
+        result = parser.reparse(result, &edits).unwrap();
+        assert_eq!(result.text.get_value(0, result.text.len()), text);
+        assert_eq!(dump_result(&result), dump_result(&parser.parse(&text)));
+        reused.push((result.get_tokens().get_reused_count(), result.get_reused_subtrees_count()));
+    }
+    reused
+}
+
+/// An input for the statements grammar
+const STATEMENTS_INPUT: &str = "a = b;\n{ c = d; { e = f; } }\ng = h;\n{ i = j; }\nk = l;\n";
+
+/// Batches of edits for the input of the statements grammar
+const STATEMENTS_EDITS: &[&[(&str, &str)]] = &[
+    // in the middle, at the start, at the end
+    &[("= d;", "= xyz;")],
+    &[("a", "aa")],
+    &[("k = l;\n", "k = l;\nm = n;")],
+    // removal of a statement
+    &[("g = h;\n", "")],
+    // syntax error and its fix
+    &[("e = f;", "e = ;")],
+    &[("e = ;", "e = f;")],
+    // lexical error and its fix
+    &[("i = j", "i = #j")],
+    &[("#", "")],
+    // several edits at once
+    &[("aa", "z"), ("{ e", "{ y = y; e"), ("m = n;", "{ }")],
+];
+
+/// Parsing again after edits with a LR(k) parser gives the same result as a full parse
+#[test]
+fn test_lrk_reparse() {
+    let mut grammar = load_grammar(GRAMMAR_STATEMENTS);
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    // the tokens and the sub-trees are reused, except after a lexical error
+    assert_eq!(
+        check_reparse(&parser, STATEMENTS_INPUT, STATEMENTS_EDITS),
+        vec![(27, 5), (30, 4), (27, 1), (31, 3), (27, 5), (27, 5), (28, 3), (0, 0), (1, 0)]
+    );
+}
+
+/// Parsing again after edits with a RNGLR parser gives the same result as a full parse
+#[test]
+fn test_rnglr_reparse() {
+    let mut grammar = load_grammar(GRAMMAR_STATEMENTS);
+    let data = grammar
+        .build(Some(ParsingMethod::RNGLALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    // only the tokens are reused
+    assert_eq!(
+        check_reparse(&parser, STATEMENTS_INPUT, STATEMENTS_EDITS),
+        vec![(27, 0), (30, 0), (27, 0), (31, 0), (27, 0), (27, 0), (28, 0), (0, 0), (1, 0)]
+    );
+}
+
+/// Parsing again after edits with a context-sensitive lexer gives the same result as a full parse
+#[test]
+fn test_context_sensitive_reparse() {
+    let mut grammar = load_grammar(
+        r#"
+        grammar Contexts
+        {
+            options
+            {
+                Axiom = "e";
+                Separator = "SEPARATOR";
+            }
+            terminals
+            {
+                SEPARATOR -> ' '+;
+                X0 -> 'x';
+                context inner { X1 -> 'x'; }
+            }
+            rules
+            {
+                sub -> '(' #inner{ X1* } ')';
+                e -> (X0 | sub)*;
+            }
+        }
+        "#,
+    );
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    // only the tokens before the edits are reused
+    let reused = check_reparse(
+        &parser,
+        "x (x x) x (x) x",
+        &[&[("(x)", "(x x x)")], &[("x (", "x x (")], &[(") x", ")")]],
+    );
+    assert_eq!(reused, vec![(6, 0), (0, 0), (4, 0)]);
+}
+
+/// Parsing again after invalid edits fails without parsing
+#[test]
+fn test_reparse_invalid_edits() {
+    let mut grammar = load_grammar(GRAMMAR_STATEMENTS);
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    let input = "a = \u{e9};";
+    let overlapping = [TextEdit::new(0, 3, "b"), TextEdit::new(2, 1, "c")];
+    assert_eq!(
+        parser.reparse(parser.parse(input), &overlapping).err(),
+        Some(TextEditError::Overlapping(
+            TextSpan { index: 0, length: 3 },
+            TextSpan { index: 2, length: 1 }
+        ))
+    );
+    let out_of_bounds = [TextEdit::new(6, 2, "b")];
+    assert_eq!(
+        parser.reparse(parser.parse(input), &out_of_bounds).err(),
+        Some(TextEditError::OutOfBounds(TextSpan { index: 6, length: 2 }))
+    );
+    let not_char_boundary = [TextEdit::new(5, 0, "b")];
+    assert_eq!(
+        parser.reparse(parser.parse(input), &not_char_boundary).err(),
+        Some(TextEditError::NotCharBoundary(TextSpan { index: 5, length: 0 }))
+    );
+}
+
+/// Concatenates the leading trivia and the values of the leaves of a concrete syntax tree
+fn concat_leaves(node: AstNode, tokens: &TokenRepository, output: &mut String) {
+    if let Some(index) = node.get_token_index() {
+        let token = tokens.get_token(index);
+        for trivia in token.get_leading_trivia() {
+            output.push_str(trivia.get_value().unwrap());
+        }
+        output.push_str(token.get_value().unwrap_or_default());
+    }
+    for child in node.children() {
+        concat_leaves(child, tokens, output);
+    }
+}
+
+/// The concrete syntax tree of a lossless parse reproduces the input
+#[test]
+fn test_lossless_cst() {
+    let mut grammar = load_grammar(
+        r#"
+        grammar Lossless
+        {
+            options
+            {
+                Axiom = "file";
+                Separator = "SEPARATOR";
+            }
+            terminals
+            {
+                SEPARATOR -> (' ' | '\n' | '/*' .* '*/')+;
+                ID -> [a-z]+;
+            }
+            rules
+            {
+                file -> stmt*;
+                stmt -> ID! '='! ID^ ';'!
+                      | '{'! stmt* '}'!;
+            }
+        }
+        "#,
+    );
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    for input in [
+        "",
+        "  /* empty */ \n",
+        "a = b;",
+        " a /* x */ = b;\n{ c = d; { } }\n/* end */ ",
+        "a = b; # c = d;\n",
+    ] {
+        let result = parser.parse_lossless(input);
+        assert!(result.get_tokens().is_lossless());
+        let cst = result.get_cst().expect("Expected a concrete syntax tree");
+        let root = cst.get_root();
+        assert_eq!(root.get_symbol().name, "__VAxiom");
+        let mut output = String::new();
+        concat_leaves(root, &result.get_tokens(), &mut output);
+        assert_eq!(output, input);
+        // the abstract syntax tree is the same as for a normal parse
+        let normal = parser.parse(input);
+        assert_eq!(dump_result(&result), dump_result(&normal));
+        assert!(normal.get_cst().is_none());
+    }
+}
+
+/// The grammar for the tests of the labels
+const GRAMMAR_LABELS: &str = r#"
+    grammar Labels
+    {
+        options { Axiom = "file"; Separator = "SEPARATOR"; }
+        terminals
+        {
+            SEPARATOR -> ' '+;
+            ID -> [a-z]+;
+            NUMBER -> [0-9]+;
+        }
+        rules
+        {
+            file -> stmt*;
+            stmt -> 'if'! cond:expr 'then'! body:stmt
+                  | name:ID '='! value:expr ';'!
+                  | 'call'! target:ID '('! args:list<expr>? ')'! ';'!;
+            list<x> -> item:x (','! item:x)*;
+            expr -> left:expr op:'+'^ right:term
+                  | term^;
+            term -> NUMBER | ID | '('! inner:expr^ ')'!;
+        }
+    }
+"#;
+
+/// Checks the labels given to the children of nodes for the input `if a + (1) then x = 2; call f(a, b + c);`
+fn check_labels(result: &ParseResult<AstImpl>) {
+    assert!(result.errors.errors.is_empty());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.get_label(), None);
+    let stmt_if = root.child(0);
+    let cond = stmt_if.child_by_label("cond").expect("Expected the condition");
+    assert_eq!(cond.get_value(), Some("+"));
+    assert_eq!(cond.get_label(), Some("cond"));
+    let left = cond.child_by_label("left").unwrap();
+    assert_eq!(left.child(0).get_value(), Some("a"));
+    let right = cond.child_by_label("right").unwrap();
+    assert_eq!(right.child(0).get_value(), Some("1"));
+    assert!(cond.child_by_label("op").is_none());
+    let body = stmt_if.child_by_label("body").unwrap();
+    assert_eq!(body.child_by_label("name").unwrap().get_value(), Some("x"));
+    assert_eq!(body.child_by_label("value").unwrap().get_symbol().name, "term");
+    assert!(stmt_if.child_by_label("name").is_none());
+    let stmt_call = root.child(1);
+    assert_eq!(stmt_call.child_by_label("target").unwrap().get_value(), Some("f"));
+    let args = stmt_call.child_by_label("args").unwrap();
+    assert_eq!(args.children_count(), 2);
+    let items: Vec<_> = args.children_by_label("item").map(|item| item.get_symbol().name).collect();
+    assert_eq!(items, vec!["term", "+"]);
+}
+
+/// The labels given to elements in rules are found on the nodes produced by LR(k) parsers
+#[test]
+fn test_lrk_labels() {
+    let mut grammar = load_grammar(GRAMMAR_LABELS);
+    // the template rules are loaded first
+    assert_eq!(
+        grammar.labels,
+        vec!["item", "cond", "body", "name", "value", "target", "args", "left", "op", "right", "inner"]
+    );
+    let data = grammar
+        .build(Some(ParsingMethod::LALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    check_labels(&parser.parse("if a + (1) then x = 2; call f(a, b + c);"));
+}
+
+/// The labels given to elements in rules are found on the nodes produced by RNGLR parsers
+#[test]
+fn test_rnglr_labels() {
+    let mut grammar = load_grammar(GRAMMAR_LABELS);
+    let data = grammar
+        .build(Some(ParsingMethod::RNGLALR1), 0)
+        .expect("Failed to build the grammar");
+    let parser = hime_sdk::output::build_in_memory_grammar(&grammar, &data).expect("Failed to build the parser");
+    check_labels(&parser.parse("if a + (1) then x = 2; call f(a, b + c);"));
+}
+
+/// The generated typed AST has accessors derived from the rules after the tree actions are applied
+#[test]
+fn test_rust_typed_ast() {
+    let mut task = hime_sdk::CompilationTask {
+        inputs: vec![hime_sdk::Input::Raw(
+            r#"
+            grammar Typed
+            {
+                options { Axiom = "file"; Separator = "SEPARATOR"; }
+                terminals { SEPARATOR -> ' '+; ID -> [a-z]+; NUM -> [0-9]+; }
+                rules
+                {
+                    file -> stmt*;
+                    stmt -> ID '='! exp ';'!
+                          | '{'! stmt* '}'! "block";
+                    exp -> atom^ | exp '+'^ atom;
+                    atom -> NUM^ | ID^ | '('! exp^ ')'!;
+                }
+            }
+            "#,
+        )],
+        output_target: Some(hime_sdk::Runtime::Rust),
+        rust_typed_ast: Some(true),
+        ..Default::default()
+    };
+    let (output, result) = execute_in_temp_dir("typed-ast", &mut task);
+    result.expect("Failed to compile the grammar");
+    let code = output.read("typed.rs");
+    for expected in [
+        "pub struct File<'s, 't, 'a>(AstNode<'s, 't, 'a>);",
+        "pub fn get_all_stmt(&self) -> impl Iterator<Item = Stmt<'s, 't, 'a>> {",
+        "pub fn get_id(&self) -> Option<AstNode<'s, 't, 'a>> {",
+        "pub fn get_block(&self) -> Option<AstNode<'s, 't, 'a>> {",
+        // promotions produce an enum of the possible nodes
+        "pub enum Exp<'s, 't, 'a> {",
+        "ExpPlus(ExpPlus<'s, 't, 'a>),",
+        "AtomNum(AtomNum<'s, 't, 'a>),",
+        // the children of a promoted terminal are at fixed positions
+        "pub fn get_exp(&self) -> Option<Exp<'s, 't, 'a>> {\n            self.0.children().into_iter().nth(0).and_then(Exp::cast)",
+        "pub fn get_atom(&self) -> Option<Atom<'s, 't, 'a>> {\n            self.0.children().into_iter().nth(1).and_then(Atom::cast)",
+    ] {
+        assert!(code.contains(expected), "missing `{expected}`");
+    }
+}
+
+/// A grammar with multiple alternatives for the same variables
+const GRAMMAR_ALTERNATIVES: &str = r#"
+grammar Alternatives
+{
+    options
+    {
+        Axiom = "exp";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        ID -> [a-z]+;
+        NUM -> [0-9]+;
+    }
+    rules
+    {
+        exp -> exp '+' term
+            |  exp '-' term
+            |  term ;
+        term -> ID | NUM | '(' exp ')' ;
+    }
+}
+"#;
+
+/// Gets the index of the production for the i-th rule of a variable
+fn get_production_index(grammar: &Grammar, variable: &str, rule: usize) -> usize {
+    let mut offset = 0;
+    for candidate in &grammar.variables {
+        if candidate.name == variable {
+            return offset + rule;
+        }
+        offset += candidate.rules.len();
+    }
+    panic!("Unknown variable {variable}");
+}
+
+/// Checks the productions of the nodes for the input `a - 1 + (b)`
+fn check_productions(grammar: &Grammar, result: &ParseResult<AstImpl>) {
+    assert!(result.errors.errors.is_empty());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.get_production(), Some(get_production_index(grammar, "exp", 0)));
+    let minus = root.child(0);
+    assert_eq!(minus.get_production(), Some(get_production_index(grammar, "exp", 1)));
+    assert_eq!(minus.child(0).get_production(), Some(get_production_index(grammar, "exp", 2)));
+    assert_eq!(
+        minus.child(0).child(0).get_production(),
+        Some(get_production_index(grammar, "term", 0))
+    );
+    assert_eq!(
+        minus.child(2).get_production(),
+        Some(get_production_index(grammar, "term", 1))
+    );
+    assert_eq!(minus.child(1).get_production(), None);
+    assert_eq!(root.child(2).get_production(), Some(get_production_index(grammar, "term", 2)));
+}
+
+/// The nodes produced by LR(k) parsers record the production of their variable
+#[test]
+fn test_lrk_productions() {
+    let (grammar, parser) = build_grammar_and_parser(GRAMMAR_ALTERNATIVES, ParsingMethod::LALR1);
+    check_productions(grammar, &parser.parse("a - 1 + (b)"));
+}
+
+/// The nodes produced by RNGLR parsers record the production of their variable
+#[test]
+fn test_rnglr_productions() {
+    let (grammar, parser) = build_grammar_and_parser(GRAMMAR_ALTERNATIVES, ParsingMethod::RNGLALR1);
+    check_productions(grammar, &parser.parse("a - 1 + (b)"));
+}
+
+/// The grammars whose generated Rust code is checked to compile
+const GENERATED_RUST_GRAMMARS: &[&str] = &[
+    GRAMMAR_ALTERNATIVES,
+    GRAMMAR_ENTRIES,
+    GRAMMAR_DISAMBIGUATED,
+    GRAMMAR_INTERPOLATION,
+    GRAMMAR_EXTERNAL,
+    GRAMMAR_INDENTED,
+    GRAMMAR_TRAILING,
+];
+
+/// The generated Rust code, with the typed AST, compiles in a crate depending on the local runtime
+#[test]
+fn test_generated_rust_compiles() {
+    let mut task = hime_sdk::CompilationTask {
+        inputs: GENERATED_RUST_GRAMMARS
+            .iter()
+            .map(|text| hime_sdk::Input::Raw(text))
+            .collect(),
+        output_target: Some(hime_sdk::Runtime::Rust),
+        output_modifier: Some(hime_sdk::Modifier::Public),
+        rust_typed_ast: Some(true),
+        ..Default::default()
+    };
+    let (output, result) = execute_in_temp_dir("generated", &mut task);
+    result.expect("Failed to compile the grammars");
+    // the generated modules are the sources of a crate in the same directory
+    let mut lib = String::new();
+    for entry in std::fs::read_dir(&output.path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "rs") {
+            writeln!(lib, "pub mod {};", path.file_stem().unwrap().to_str().unwrap()).unwrap();
+        }
+    }
+    std::fs::write(output.path.join("lib.rs"), lib).unwrap();
+    let runtime = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../runtime-rust");
+    std::fs::write(
+        output.path.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"hime_generated\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [lib]\npath = \"lib.rs\"\n\n\
+             [dependencies]\nhime_redist = {{ path = {:?} }}\n\n[workspace]\n",
+            runtime.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    // the compiled dependencies are kept between the runs
+    let result = std::process::Command::new(env!("CARGO"))
+        .args(["check", "--offline", "--quiet"])
+        .current_dir(&output.path)
+        .env("CARGO_TARGET_DIR", std::env::temp_dir().join("hime-generated-target"))
+        .output()
+        .unwrap();
+    assert!(
+        result.status.success(),
+        "The generated Rust code does not compile:\n{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+}
+
+/// A grammar for arithmetic expressions with semantic actions
+const GRAMMAR_VALUES: &str = r#"
+grammar Values
+{
+    options
+    {
+        Axiom = "exp";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        NUM -> [0-9]+;
+    }
+    rules
+    {
+        exp -> exp '+' term @add
+            |  exp '-' term @sub
+            |  term ;
+        term -> term '*' atom @mul
+            |  atom ;
+        atom -> NUM @num
+            |  '(' exp ')' @inner
+            |  '-' @neg atom ;
+    }
+}
+"#;
+
+/// The semantic actions of LR(k) parsers produce the values of the variables, the one of the axiom is returned
+#[test]
+fn test_lrk_values() {
+    let (grammar, parser) = build_grammar_and_parser(GRAMMAR_VALUES, ParsingMethod::LALR1);
+    let names: Vec<String> = grammar.actions.iter().map(|action| action.name.clone()).collect();
+    let mut negations = 0;
+    let mut actions = |index: usize, _head: Symbol, body: &dyn SemanticBody, values: &mut [Option<i64>]| {
+        match names[index].as_str() {
+            "add" => Some(values[0]? + values[2]?),
+            "sub" => Some(values[0]? - values[2]?),
+            "mul" => Some(values[0]? * values[2]?),
+            "num" => body.get_element_at(0).get_value().and_then(|value| value.parse().ok()),
+            "inner" => values[1].take(),
+            "neg" => {
+                // executed in the middle of the rule, only the leading operator is popped
+                assert_eq!(values.len(), 1);
+                negations += 1;
+                None
+            }
+            _ => None,
+        }
+    };
+    let (result, value) = parser.parse_with_values("10 - 2 - 3 * (1 + 1) + -4", &mut actions);
+    assert!(result.errors.errors.is_empty());
+    // the value of `atom -> '-' atom` is the one of its last semantic action, executed in the middle of the rule
+    assert_eq!(value, None);
+    let (result, value) = parser.parse_with_values("10 - 2 - 3 * (1 + 1)", &mut actions);
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(value, Some(2));
+    let (result, value) = parser.parse_with_values("10 -", &mut actions);
+    assert!(!result.errors.errors.is_empty());
+    assert_eq!(value, None);
+    assert_eq!(negations, 1);
+}
+
+/// The push parser reports whether the chunks of input so far are complete, a prefix or invalid,
+/// with the chunks being either text or tokens that have already been lexed
+#[test]
+fn test_push_parser() {
+    let parser = build_parser(GRAMMAR_VALUES, ParsingMethod::LALR1);
+    let mut push = parser.push_parser();
+    assert_eq!(push.get_status(), PushStatus::Prefix);
+    assert_eq!(push.push_str("1"), PushStatus::Complete);
+    // the number is extended by the next chunk
+    assert_eq!(push.push_str("0 - ("), PushStatus::Prefix);
+    assert_eq!(push.push_str("2 * 3"), PushStatus::Prefix);
+    assert_eq!(push.push_str(")"), PushStatus::Complete);
+    // a stray closing parenthesis cannot be fixed by more input
+    assert_eq!(push.push_str(" )"), PushStatus::Invalid);
+    assert_eq!(push.push_str(" "), PushStatus::Invalid);
+    assert_eq!(push.push_str("1"), PushStatus::Invalid);
+    let result = push.finish();
+    assert_eq!(result.text.get_value(0, result.text.len()), "10 - (2 * 3) ) 1");
+    assert!(!result.errors.errors.is_empty());
+    let mut push = parser.push_parser();
+    for chunk in ["10 - (2", " * 3)", " - 1"] {
+        push.push_str(chunk);
+    }
+    let result = push.finish();
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(dump_result(&result), dump_result(&parser.parse("10 - (2 * 3) - 1")));
+    // the parsing resumes before the last tokens, whatever the chunks
+    let input = "(1 + 23) * 4 - 567 * (8 - 9) + 10";
+    let mut push = parser.push_parser();
+    for (index, c) in input.char_indices() {
+        push.push_str(&input[index..index + c.len_utf8()]);
+    }
+    assert_eq!(push.get_status(), PushStatus::Complete);
+    assert_eq!(dump_result(push.get_result()), dump_result(&parser.parse(input)));
+
+    // the tokens are pushed one by one, without separators
+    let terminal = |name: &str| parser.terminals.iter().find(|symbol| symbol.name == name).unwrap().id;
+    let mut push = parser.push_parser();
+    assert_eq!(push.push_token(terminal("NUM"), "10"), PushStatus::Complete);
+    assert_eq!(push.push_token(terminal("-"), "-"), PushStatus::Prefix);
+    assert_eq!(push.push_token(terminal("("), "("), PushStatus::Prefix);
+    assert_eq!(push.push_token(terminal("NUM"), "2"), PushStatus::Prefix);
+    assert_eq!(push.push_token(terminal(")"), ")"), PushStatus::Complete);
+    // an unknown terminal is rejected without changing the input
+    assert_eq!(push.push_token(u32::MAX, "?"), PushStatus::Invalid);
+    assert_eq!(push.get_result().text.get_value(0, push.get_result().text.len()), "10-(2)");
+    assert_eq!(dump_result(push.get_result()), dump_result(&parser.parse("10-(2)")));
+    assert_eq!(push.push_token(terminal(")"), ")"), PushStatus::Invalid);
+}
+
+/// The terminals and variables expected at an index of the input are found by both the LR(k) and RNGLR parsers
+#[test]
+fn test_expected_at() {
+    fn names(symbols: &[Symbol]) -> Vec<String> {
+        let mut names: Vec<String> = symbols.iter().map(|symbol| symbol.name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    for method in [ParsingMethod::LALR1, ParsingMethod::RNGLALR1] {
+        let parser = build_parser(GRAMMAR_VALUES, method);
+        // an operand is expected after the opening parenthesis
+        let completion = parser.get_expected_at("10 - (2 ", 6);
+        assert_eq!(names(&completion.terminals), ["(", "-", "NUM"]);
+        assert_eq!(names(&completion.variables), ["atom", "exp", "term"]);
+        assert!(!completion.truncated);
+        // the operand ends at the index, an operator is expected
+        let completion = parser.get_expected_at("10 - (2 ", 7);
+        assert_eq!(names(&completion.terminals), [")", "*", "+", "-"]);
+        assert!(completion.variables.is_empty());
+        // the error before the index is recovered
+        let completion = parser.get_expected_at("10 - ) * ", 9);
+        assert_eq!(names(&completion.terminals), ["(", "-", "NUM"]);
+        assert_eq!(names(&completion.variables), ["atom"]);
+    }
+}
+
+/// The API for the semantic values is generated only for the LR(k) parsers
+#[test]
+fn test_rust_values_api() {
+    for (method, expected) in [(ParsingMethod::LALR1, true), (ParsingMethod::RNGLALR1, false)] {
+        let mut task = hime_sdk::CompilationTask {
+            inputs: vec![hime_sdk::Input::Raw(GRAMMAR_VALUES)],
+            output_target: Some(hime_sdk::Runtime::Rust),
+            method: Some(method),
+            ..Default::default()
+        };
+        let (output, result) = execute_in_temp_dir(&format!("values-{expected}"), &mut task);
+        result.expect("Failed to compile the grammar");
+        let code = output.read("values.rs");
+        // the semantic actions producing values are only supported by LR(k) parsers
+        for api in [
+            "pub trait ValueActions {",
+            "fn add(&mut self, head: Symbol, body: &dyn SemanticBody, values: &mut [Option<Self::Value>]) -> Self::Value;",
+            "pub fn parse_str_with_values<'t, A: ValueActions>(",
+        ] {
+            assert_eq!(code.contains(api), expected, "unexpected presence of `{api}`");
+        }
+    }
+}
+
+/// A grammar for declarations, with expressions and types as additional entry points
+const GRAMMAR_ENTRIES: &str = r#"
+grammar Entries
+{
+    options
+    {
+        Axiom = "program";
+        Entries = "exp, type";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        ID -> [a-z]+;
+        NUM -> [0-9]+;
+    }
+    rules
+    {
+        program -> decl* ;
+        decl -> 'let' ID ':' type '=' exp ';' ;
+        type -> ID
+            |  type '[' ']' ;
+        exp -> exp '+' atom
+            |  atom ;
+        atom -> NUM
+            |  ID
+            |  '(' exp ')' ;
+    }
+}
+"#;
+
+/// The additional entry points are parsed from by all the parsing methods, the other variables are not
+#[test]
+fn test_entries() {
+    for method in [
+        ParsingMethod::LALR1,
+        ParsingMethod::LR1,
+        ParsingMethod::MinLR1,
+        ParsingMethod::RNGLALR1,
+    ] {
+        let parser = build_parser(GRAMMAR_ENTRIES, method);
+        assert!(parser.parse("let x : a[] = 1 + b; let y : c = (x);").errors.errors.is_empty());
+        assert!(!parser.parse("1 + b").errors.errors.is_empty());
+        let result = parser.parse_from("exp", "1 + (b)").unwrap();
+        assert!(result.errors.errors.is_empty());
+        assert_eq!(result.get_ast().get_root().get_symbol().name, "exp");
+        let result = parser.parse_from("type", "a[][]").unwrap();
+        assert!(result.errors.errors.is_empty());
+        assert_eq!(result.get_ast().get_root().get_symbol().name, "type");
+        assert!(!parser.parse_from("type", "1 + b").unwrap().errors.errors.is_empty());
+        assert!(parser
+            .parse_from("program", "let x : a = 1;")
+            .unwrap()
+            .errors
+            .errors
+            .is_empty());
+        // only the declared entry points can be parsed from
+        assert!(parser.parse_from("atom", "1").is_none());
+    }
+}
+
+/// An entry point that is not a variable of the grammar is reported
+#[test]
+fn test_entries_not_defined() {
+    let mut grammar = load_grammar(&GRAMMAR_ENTRIES.replace("\"exp, type\"", "\"exp, unknown\""));
+    let errors = grammar.build(Some(ParsingMethod::LALR1), 0).err().unwrap();
+    assert!(matches!(&errors[..], [Error::EntryNotDefined(0, name)] if name == "unknown"));
+}
+
+/// A variable named like a generated axiom is rejected, it would be taken for an entry point
+#[test]
+fn test_entries_reserved_name() {
+    let mut grammar = load_grammar(&GRAMMAR_ENTRIES.replace("type -> ID", "__VAxiom_x -> ID ; type -> ID"));
+    let errors = grammar.build(Some(ParsingMethod::LALR1), 0).err().unwrap();
+    assert!(matches!(&errors[..], [Error::VariableNameReserved(0, name)] if name == "__VAxiom_x"));
+    // The generated axioms of a previous build are not rejected
+    let mut grammar = load_grammar(GRAMMAR_ENTRIES);
+    assert!(grammar.build(Some(ParsingMethod::LALR1), 0).is_ok());
+    assert!(grammar.build(Some(ParsingMethod::LALR1), 0).is_ok());
+}
+
+/// A grammar where an identifier is an item in three different ways
+const GRAMMAR_AMBIGUOUS: &str = r#"
+grammar Ambiguous
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        ID -> [a-z]+;
+    }
+    rules
+    {
+        file -> item ;
+        item -> call | cast | index ;
+        call -> ID ;
+        cast -> ID ;
+        index -> ID ;
+    }
+}
+"#;
+
+/// The ambiguous grammar, with annotations to disambiguate the item for a RNGLR parser
+const GRAMMAR_DISAMBIGUATED: &str = r#"
+grammar Disambiguated
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+        Method = "rnglalr1";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        ID -> [a-z]+;
+    }
+    rules
+    {
+        file -> item ;
+        item -> call %avoid | cast %prefer | index %priority:1 ;
+        call -> ID ;
+        cast -> ID ;
+        index -> ID ;
+    }
+}
+"#;
+
+/// Gets the name of the derivation chosen for the item in a result of the ambiguous grammar
+fn get_chosen_derivation(result: &ParseResult<'_, '_, '_, AstImpl>) -> String {
+    let ast = result.get_ast();
+    ast.get_root().child(0).child(0).get_symbol().name.to_string()
+}
+
+/// The annotations of the alternatives choose the derivation of an ambiguous item, a priority over a preference
+#[test]
+fn test_disambiguation() {
+    for (alternatives, expected) in [
+        ("call | cast %prefer | index", "cast"),
+        ("call %avoid | cast %avoid | index", "index"),
+        ("call %prefer | cast %priority:1 | index", "cast"),
+    ] {
+        let parser = build_parser(
+            &GRAMMAR_AMBIGUOUS.replace("call | cast | index", alternatives),
+            ParsingMethod::RNGLALR1,
+        );
+        let result = parser.parse("x");
+        assert!(result.errors.errors.is_empty());
+        assert_eq!(get_chosen_derivation(&result), expected, "for `{alternatives}`");
+    }
+}
+
+/// The resolver chooses among the derivations left by the annotations
+#[test]
+fn test_disambiguation_resolver() {
+    let parser = build_parser(
+        &GRAMMAR_AMBIGUOUS.replace("call | cast | index", "call | cast | index %avoid"),
+        ParsingMethod::RNGLALR1,
+    );
+    let mut remaining = Vec::new();
+    let result = parser.parse_with_resolver("x", |sppf, node, candidates| {
+        let versions = &sppf.get_node(node).versions;
+        let names: Vec<&str> = candidates
+            .iter()
+            .map(|&index| {
+                let child = sppf.get_node(versions[index].children[0]).first_version().label;
+                parser.variables[child.index()].name
+            })
+            .collect();
+        remaining.extend(names.iter().map(ToString::to_string));
+        candidates[names.iter().position(|name| *name == "cast").unwrap()]
+    });
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(get_chosen_derivation(&result), "cast");
+    // the avoided derivation is not given to the resolver
+    remaining.sort();
+    assert_eq!(remaining, vec!["call", "cast"]);
+}
+
+/// The unknown annotations, or the ones with an invalid value, are reported by the loader
+#[test]
+fn test_disambiguation_invalid_annotation() {
+    let text = GRAMMAR_AMBIGUOUS.replace("call | cast | index", "call %unknown | cast %prefer:1 | index");
+    let inputs = vec![hime_sdk::Input::Raw(&text)];
+    let errors = hime_sdk::loaders::load_inputs(&inputs).err().unwrap().errors;
+    assert!(
+        matches!(&errors[..], [Error::InvalidAnnotation(_, first), Error::InvalidAnnotation(_, second)] if first == "unknown" && second == "prefer:1")
+    );
+}
+
+/// The ambiguities of a RNGLR parse are reported with their symbol and position, even when resolved by the annotations
+#[test]
+fn test_ambiguities() {
+    let parser = build_parser(GRAMMAR_AMBIGUOUS, ParsingMethod::RNGLALR1);
+    let result = parser.parse("  x");
+    assert!(result.errors.errors.is_empty());
+    let ambiguities = result.get_ambiguities();
+    assert_eq!(ambiguities.len(), 1);
+    assert_eq!(ambiguities[0].symbol.name, "item");
+    assert_eq!(ambiguities[0].span, Some(TextSpan { index: 2, length: 1 }));
+    assert_eq!(ambiguities[0].position, Some(TextPosition { line: 1, column: 3 }));
+    assert_eq!(ambiguities[0].versions, 3);
+    assert_eq!(ambiguities[0].trees, 3);
+    assert_eq!(
+        ambiguities[0].to_string(),
+        "`item` at (1, 3) has 3 competing versions, for 3 distinct trees"
+    );
+    // the ambiguities of the SPPF refer to its nodes
+    let result = parser.parse_to_sppf("  x").expect("Expected a SPPF");
+    let ambiguities = result.get_ambiguities();
+    assert_eq!(ambiguities.len(), 1);
+    let sppf = result.get_ast();
+    for version in &ambiguities[0].versions {
+        assert_eq!(sppf.get_version(*version).get_symbol().name, "item");
+    }
+    // the ambiguities are still reported when resolved by the annotations
+    let parser = build_parser(GRAMMAR_DISAMBIGUATED, ParsingMethod::RNGLALR1);
+    assert_eq!(parser.parse("x").get_ambiguities().len(), 1);
+    // an unambiguous grammar has no ambiguity
+    let parser = build_parser(
+        &GRAMMAR_AMBIGUOUS.replace("call | cast | index", "call"),
+        ParsingMethod::RNGLALR1,
+    );
+    assert!(parser.parse("x").get_ambiguities().is_empty());
+}
+
+/// The distinct trees of a SPPF are enumerated up to a limit, and the SPPF is exported as a graph
+#[test]
+fn test_sppf_trees() {
+    let parser = build_parser(
+        &GRAMMAR_AMBIGUOUS.replace("file -> item ;", "file -> item item ;"),
+        ParsingMethod::RNGLALR1,
+    );
+    let result = parser.parse_to_sppf("x y").expect("Expected a SPPF");
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(result.get_trees_count(), 9);
+    let trees: Vec<_> = result.get_trees(usize::MAX).collect();
+    assert_eq!(trees.len(), 9);
+    let sppf = result.get_ast();
+    let mut derivations: Vec<String> = trees
+        .iter()
+        .map(|tree| {
+            tree.children
+                .iter()
+                .map(|item| sppf.get_version(item.children[0].version).get_symbol().name)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    derivations.sort();
+    derivations.dedup();
+    assert_eq!(derivations.len(), 9);
+    // the trees are bounded by the limit
+    assert_eq!(result.get_trees(4).count(), 4);
+    // the versions of the ambiguous nodes are exported as packed nodes
+    let graph = sppf.get_graph();
+    assert_eq!(graph.nodes.iter().filter(|node| node.versions.len() == 3).count(), 2);
+    let mut dot = String::new();
+    sppf.write_dot(&mut dot).unwrap();
+    assert!(dot.starts_with("digraph sppf {"));
+    assert_eq!(dot.matches("[shape=circle").count(), 6);
+    assert!(dot.contains("label=\"ID = y\""));
+}
+
+/// Gets the reasons of the exhaustion of the budget reported in a result
+fn get_budget_exhaustions(result: &ParseResult<'_, '_, '_, AstImpl>) -> Vec<BudgetExhaustion> {
+    result
+        .errors
+        .errors
+        .iter()
+        .filter_map(|error| match error {
+            ParseError::BudgetExhausted(error) => Some(error.get_reason()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The budget stops the parsing when its steps are exhausted, when it is cancelled or when the RNGLR structures grow too large
+#[test]
+fn test_parse_budget() {
+    let input = "a = b;\n".repeat(50);
+    for method in [ParsingMethod::LALR1, ParsingMethod::RNGLALR1] {
+        let parser = build_parser(GRAMMAR_STATEMENTS, method);
+        // a large enough budget does not change the result
+        let result = parser.parse_with_budget(&input, ParseBudget::new().with_max_steps(10_000));
+        assert!(result.errors.errors.is_empty());
+        // the parsing stops when the budget is exhausted, the partial result stays usable
+        let result = parser.parse_with_budget(&input, ParseBudget::new().with_max_steps(100));
+        assert_eq!(result.errors.errors.len(), 1);
+        assert_eq!(get_budget_exhaustions(&result), vec![BudgetExhaustion::Steps]);
+        assert!(!result.has_tree());
+        let count = result.get_tokens().get_tokens_count();
+        assert!(count > 0 && count < 200);
+        // the parsing stops when it is cancelled
+        let flag = Arc::new(AtomicBool::new(true));
+        let result = parser.parse_with_budget(&input, ParseBudget::new().with_cancellation(flag));
+        assert_eq!(get_budget_exhaustions(&result), vec![BudgetExhaustion::Cancelled]);
+        assert_eq!(result.errors.errors[0].get_position(), TextPosition { line: 1, column: 1 });
+    }
+    // the sizes of the GSS and of the SPPF are bounded for RNGLR parsers
+    let parser = build_parser(GRAMMAR_STATEMENTS, ParsingMethod::RNGLALR1);
+    let result = parser.parse_with_budget(&input, ParseBudget::new().with_max_gss_nodes(50));
+    assert_eq!(get_budget_exhaustions(&result), vec![BudgetExhaustion::GssNodes]);
+    assert!(!result.has_tree());
+    let result = parser.parse_with_budget(&input, ParseBudget::new().with_max_sppf_nodes(50));
+    assert_eq!(get_budget_exhaustions(&result), vec![BudgetExhaustion::SppfNodes]);
+}
+
+/// A grammar for strings with interpolated expressions, lexed in their own mode
+const GRAMMAR_INTERPOLATION: &str = r#"
+grammar Interpolation
+{
+    options
+    {
+        Axiom = "expr";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        NAME -> [a-z]+;
+        QUOTE -> '"' -> push(string);
+        CLOSE -> '}' -> pop;
+        context string
+        {
+            TEXT -> [^"$}]+;
+            OPEN -> '${' -> push(default);
+            END -> '"' -> pop;
+        }
+    }
+    rules
+    {
+        expr -> NAME^ | string^;
+        string -> QUOTE! part* END!;
+        part -> TEXT^ | OPEN! expr^ CLOSE!;
+    }
+}
+"#;
+
+/// Lexer modes are pushed and popped by the terminals, independently of the parser's contexts
+#[test]
+fn test_lexer_modes() {
+    let parser = build_parser(GRAMMAR_INTERPOLATION, ParsingMethod::LALR1);
+    let result = parser.parse(r#""a b ${x}!""#);
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(
+        dump_result(&result),
+        "QUOTE@0+1 TEXT@1+4 OPEN@5+2 NAME@7+1 CLOSE@8+1 TEXT@9+1 END@10+1 $@11+0 \n\
+         string(TEXT=\"a b \"@1+4 NAME=\"x\"@7+1 TEXT=\"!\"@9+1 )\n"
+    );
+    // the modes nest
+    let result = parser.parse(r#""a ${"b ${c}"} d""#);
+    assert!(result.errors.errors.is_empty());
+    assert_eq!(
+        dump_result(&result).lines().nth(1),
+        Some(r#"string(TEXT="a "@1+2 string(TEXT="b "@6+2 NAME="c"@10+1 ) TEXT=" d"@14+2 )"#)
+    );
+    // the terminals of a mode do not hide the shorter matches outside of it
+    assert!(parser.parse(r#"x "#).errors.errors.is_empty());
+    assert!(parser.parse(r#""a ${ x }""#).errors.errors.is_empty());
+    // the modes at the end of the reused tokens are unknown, nothing is reused
+    let reused = check_reparse(&parser, r#""a ${"b ${c}"} d""#, &[&[("c", "\"e\"")], &[("a ", "")]]);
+    assert_eq!(reused, vec![(0, 0), (0, 0)]);
+}
+
+/// A terminal pushing a mode that is not defined is reported by the loader
+#[test]
+fn test_lexer_modes_not_defined() {
+    let text = GRAMMAR_INTERPOLATION.replace("push(string)", "push(strings)");
+    let inputs = vec![hime_sdk::Input::Raw(&text)];
+    let errors = hime_sdk::loaders::load_inputs(&inputs).err().unwrap().errors;
+    assert!(matches!(&errors[..], [Error::ModeNotDefined(_, name)] if name == "strings"));
+}
+
+/// A scanner for nested comments, for the tests of the external terminals
+struct NestedCommentScanner {
+    /// The identifier of the terminal for the comments
+    terminal_id: u32,
+}
+
+impl ExternalScanner for NestedCommentScanner {
+    fn scan(&mut self, text: &Text, index: usize, acceptable: &[u32]) -> Option<ExternalMatch> {
+        if !acceptable.contains(&self.terminal_id) {
+            return None;
+        }
+        let input = text.get_value(index, text.len() - index).as_bytes();
+        let mut depth = 0;
+        let mut length = 0;
+        while length + 1 < input.len() {
+            match &input[length..length + 2] {
+                b"/*" => depth += 1,
+                b"*/" if depth > 0 => depth -= 1,
+                _ if depth == 0 => return None,
+                _ => {
+                    length += 1;
+                    continue;
+                }
+            }
+            length += 2;
+            if depth == 0 {
+                return Some(ExternalMatch {
+                    terminal_id: self.terminal_id,
+                    length,
+                    reach: index + length,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// A grammar with an external terminal for the comments
+const GRAMMAR_EXTERNAL: &str = r#"
+grammar External
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        NAME -> [a-z]+;
+        external COMMENT;
+    }
+    rules
+    {
+        file -> (NAME | COMMENT)*;
+    }
+}
+"#;
+
+/// External terminals are matched by a scanner supplied at runtime
+#[test]
+fn test_external_terminals() {
+    for method in [ParsingMethod::LALR1, ParsingMethod::RNGLALR1] {
+        let parser = build_parser(GRAMMAR_EXTERNAL, method);
+        let terminal_id = parser.terminals.iter().find(|t| t.name == "COMMENT").unwrap().id;
+        let mut scanner = NestedCommentScanner { terminal_id };
+        let input = "a /* b /* c */ d */ e";
+        let result = parser.parse_with_scanner(input, &mut scanner);
+        assert!(result.errors.errors.is_empty());
+        assert_eq!(
+            token_pairs(&result),
+            vec![("NAME", "a"), ("COMMENT", "/* b /* c */ d */"), ("NAME", "e"), ("$", "")]
+        );
+        // without the scanner, the external terminal is never matched
+        assert!(!parser.parse(input).errors.errors.is_empty());
+    }
+}
+
+/// External terminals cannot be used in the definition of other terminals
+#[test]
+fn test_external_terminals_referenced() {
+    let text = r#"
+        grammar External
+        {
+            options { Axiom = "file"; }
+            terminals
+            {
+                external COMMENT;
+                NAME -> [a-z]+ COMMENT?;
+            }
+            rules { file -> NAME*; }
+        }
+        "#;
+    let inputs = vec![hime_sdk::Input::Raw(text)];
+    let errors = hime_sdk::loaders::load_inputs(&inputs).err().unwrap().errors;
+    assert!(matches!(&errors[..], [Error::ExternalTerminalReferenced(_, name)] if name == "COMMENT"));
+}
+
+/// A grammar for nested blocks of statements delimited by their indentation
+const GRAMMAR_INDENTED: &str = r#"
+grammar Indented
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+        Indentation = "INDENT,DEDENT,NEWLINE";
+        TabWidth = "4";
+    }
+    terminals
+    {
+        SEPARATOR -> (' ' | '\t' | '\n' | '#' [^\n]* | '/*' [^*]* '*/')+;
+        NAME -> [a-z]+;
+    }
+    rules
+    {
+        file -> statement*;
+        statement -> NAME ':'! NEWLINE! INDENT! statement+ DEDENT!
+                  |  NAME NEWLINE!;
+    }
+}
+"#;
+
+/// An indentation-sensitive lexer inserts the tokens for the ends of lines and the changes of indentation
+#[test]
+fn test_indentation() {
+    // the second grammar has a lexical context, so that its lexer is context-sensitive
+    for context in ["", "context value { NUMBER -> [0-9]+; }"] {
+        for method in [ParsingMethod::LALR1, ParsingMethod::RNGLALR1] {
+            let parser = build_parser(
+                &GRAMMAR_INDENTED.replace("NAME -> [a-z]+;", &format!("NAME -> [a-z]+; {context}")),
+                method,
+            );
+            let get_names = |input: &str| {
+                let result = parser.parse(input);
+                let names: Vec<&str> = token_pairs(&result).into_iter().map(|(name, _)| name).collect();
+                (names.join(" "), result.errors.errors)
+            };
+            // blank and comment-only lines are skipped, a tab goes to the next multiple of the tab width
+            let (names, errors) = get_names("a:\n  b\n\n    # comment\n  c:\n\td\ne");
+            assert!(errors.is_empty());
+            assert_eq!(
+                names,
+                "NAME : NEWLINE INDENT NAME NEWLINE NAME : NEWLINE INDENT NAME NEWLINE DEDENT DEDENT NAME NEWLINE $"
+            );
+            let (names, errors) = get_names("a:\n\tb\n    c\n");
+            assert!(errors.is_empty());
+            assert_eq!(names, "NAME : NEWLINE INDENT NAME NEWLINE NAME NEWLINE DEDENT $");
+            // only the leading whitespace is measured, not the comments before the first token
+            let (names, errors) = get_names("a:\n  /* b */ c\n  d\n");
+            assert!(errors.is_empty());
+            assert_eq!(names, "NAME : NEWLINE INDENT NAME NEWLINE NAME NEWLINE DEDENT $");
+            // the dedent does not match an outer level, the column replaces the inner one
+            let (names, errors) = get_names("a:\n    b\n  c\n  d\ne\n");
+            assert!(matches!(
+                &errors[..],
+                [ParseError::InconsistentDedent(error)] if error.get_position() == TextPosition { line: 3, column: 3 }
+            ));
+            assert_eq!(
+                names,
+                "NAME : NEWLINE INDENT NAME NEWLINE NAME NEWLINE NAME NEWLINE DEDENT NAME NEWLINE $"
+            );
+        }
+    }
+}
+
+/// A grammar with terminals that require a trailing context after them
+const GRAMMAR_TRAILING: &str = r#"
+grammar Trailing
+{
+    options
+    {
+        Axiom = "file";
+        Separator = "SEPARATOR";
+    }
+    terminals
+    {
+        SEPARATOR -> ' '+;
+        INTEGER -> [0-9]+;
+        FLOAT -> [0-9]+ '.' [0-9]*;
+        RANGE_START -> [0-9]+ / '..';
+        NAME -> [a-z]+;
+        CALLEE -> [a-z]+ / ' '* '(';
+    }
+    rules
+    {
+        expr -> RANGE_START '..' INTEGER
+             |  FLOAT
+             |  INTEGER
+             |  NAME
+             |  CALLEE '(' ')';
+        file -> expr*;
+    }
+}
+"#;
+
+/// The trailing context of a terminal is required after it, but is not part of its tokens
+#[test]
+fn test_trailing_context() {
+    let parser = build_parser(GRAMMAR_TRAILING, ParsingMethod::LALR1);
+    let result = parser.parse("1..2 1. 3 f () g");
+    assert!(result.errors.errors.is_empty(), "{}", dump_result(&result));
+    assert_eq!(
+        token_pairs(&result),
+        vec![
+            ("RANGE_START", "1"),
+            ("..", ".."),
+            ("INTEGER", "2"),
+            ("FLOAT", "1."),
+            ("INTEGER", "3"),
+            ("CALLEE", "f"),
+            ("(", "("),
+            (")", ")"),
+            ("NAME", "g"),
+            ("$", ""),
+        ]
+    );
+
+    // the head may overlap its trailing context, the head ends where the trailing context matches the rest
+    let parser = build_parser(
+        r#"
+        grammar Overlap
+        {
+            options { Axiom = "file"; }
+            terminals { A -> 'a'+ / 'a'; B -> 'a'; }
+            rules { file -> A B; }
+        }
+        "#,
+        ParsingMethod::LALR1,
+    );
+    for (input, head) in [("aa", "a"), ("aaa", "aa")] {
+        let result = parser.parse(input);
+        assert!(result.errors.errors.is_empty(), "{}", dump_result(&result));
+        assert_eq!(token_pairs(&result), vec![("A", head), ("B", "a"), ("$", "")]);
+    }
+
+    // only the Rust runtime can match the trailing contexts
+    let mut task = hime_sdk::CompilationTask {
+        inputs: vec![hime_sdk::Input::Raw(GRAMMAR_TRAILING)],
+        output_target: Some(hime_sdk::Runtime::Net),
+        ..Default::default()
+    };
+    let (output, result) = execute_in_temp_dir("trailing", &mut task);
+    let errors = result.unwrap_err();
+    assert_eq!(output.count_entries(), 0);
+    assert!(matches!(
+        &errors[..],
+        [
+            Error::TrailingContextNotSupported(_, _),
+            Error::TrailingContextNotSupported(_, _)
+        ]
+    ));
+
+    // the head before the trailing context cannot match the empty string
+    let mut grammar = load_grammar(
+        r#"
+        grammar Trailing
+        {
+            options { Axiom = "file"; }
+            terminals { A -> 'a'* / 'b'; }
+            rules { file -> A*; }
+        }
+        "#,
+    );
+    let errors = grammar.build(Some(ParsingMethod::LALR1), 0).err().unwrap();
+    assert!(matches!(&errors[..], [Error::TerminalMatchesEmpty(_, _)]));
+}
+
+/// Only the Rust runtime follows the lexer modes, matches the external terminals and inserts the indentation tokens
+#[test]
+fn test_rust_only_features() {
+    for runtime in [hime_sdk::Runtime::Net, hime_sdk::Runtime::Java, hime_sdk::Runtime::TypeScript] {
+        let get_errors = |grammar: &str| {
+            let mut task = hime_sdk::CompilationTask {
+                inputs: vec![hime_sdk::Input::Raw(grammar)],
+                output_target: Some(runtime),
+                ..Default::default()
+            };
+            let (output, result) = execute_in_temp_dir("rust-only", &mut task);
+            assert_eq!(output.count_entries(), 0);
+            result.unwrap_err()
+        };
+        // the four terminals acting on the lexer modes
+        let errors = get_errors(GRAMMAR_INTERPOLATION);
+        assert_eq!(errors.len(), 4);
+        assert!(errors.iter().all(|error| matches!(error, Error::ModesNotSupported(_, _))));
+        let errors = get_errors(GRAMMAR_EXTERNAL);
+        assert!(matches!(&errors[..], [Error::ExternalTerminalNotSupported(_, _)]));
+        // the indentation terminals are reported with the option
+        let errors = get_errors(GRAMMAR_INDENTED);
+        assert!(matches!(&errors[..], [Error::IndentationNotSupported(_)]));
+    }
+}
+
+/// The intersection and the complement of terminal definitions
+#[test]
+fn test_intersection_complement() {
+    let parser = build_parser(
+        r#"
+        grammar Operators
+        {
+            options
+            {
+                Axiom = "file";
+                Separator = "SEPARATOR";
+            }
+            terminals
+            {
+                SEPARATOR -> ' '+;
+                KEYWORD -> 'if' | 'else';
+                IDENTIFIER -> [a-z]+ & ~KEYWORD;
+                NUMBER -> [0-9]+ & ~('0' [0-9]+);
+                COMMENT -> '/*' ~(.* '*/' .*) '*/';
+            }
+            rules
+            {
+                file -> (KEYWORD | IDENTIFIER | NUMBER | COMMENT)*;
+            }
+        }
+        "#,
+        ParsingMethod::LALR1,
+    );
+    let result = parser.parse("if ifx /* a * b / c */ else 0 10");
+    assert!(result.errors.errors.is_empty(), "{}", dump_result(&result));
+    assert_eq!(
+        token_pairs(&result),
+        vec![
+            ("KEYWORD", "if"),
+            ("IDENTIFIER", "ifx"),
+            ("COMMENT", "/* a * b / c */"),
+            ("KEYWORD", "else"),
+            ("NUMBER", "0"),
+            ("NUMBER", "10"),
+            ("$", ""),
+        ]
+    );
+
+    // the complement alone matches the empty string
+    let mut grammar = load_grammar(
+        r#"
+        grammar Operators
+        {
+            options { Axiom = "file"; }
+            terminals { X -> 'x'; A -> ~X; }
+            rules { file -> X A; }
+        }
+        "#,
+    );
+    let errors = grammar.build(Some(ParsingMethod::LALR1), 0).err().unwrap();
+    assert!(matches!(&errors[..], [Error::TerminalMatchesEmpty(_, _)]));
+
+    // the intersection of disjoint terminals matches nothing
+    let mut grammar = load_grammar(
+        r#"
+        grammar Operators
+        {
+            options { Axiom = "file"; }
+            terminals { A -> [a-z]+ & [0-9]+; }
+            rules { file -> A*; }
+        }
+        "#,
+    );
+    let errors = grammar.build(Some(ParsingMethod::LALR1), 0).err().unwrap();
+    assert!(matches!(&errors[..], [Error::TerminalCannotBeMatched(_, _)]));
+}
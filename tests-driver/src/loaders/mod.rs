@@ -72,6 +72,7 @@ pub fn parse_fixtures(fixtures: &[FixtureDef]) -> Result<Fixtures, Errors> {
                     })
                     .collect(),
                 grammars: Vec::new(),
+                warnings: Vec::new(),
             },
             errors,
         })
@@ -296,6 +296,7 @@ impl Fixtures {
             context: LoadedData {
                 inputs: self.get_loaded_inputs(),
                 grammars,
+                warnings: Vec::new(),
             },
             errors,
         }